@@ -5,6 +5,7 @@ use data_types::database_rules::{DatabaseRules, PartitionTemplate, TemplatePart}
 use generated_types::wal as wb;
 use influxdb_line_protocol::{parse_lines, ParsedLine};
 use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
 use std::fmt;
 use std::time::Duration;
 
@@ -160,7 +161,7 @@ struct Db {
 
 impl Db {
     fn deserialize_write(&mut self, data: &[u8]) {
-        let write = ReplicatedWrite::from(data);
+        let write = ReplicatedWrite::try_from(data).unwrap();
 
         if let Some(batch) = write.write_buffer_batch() {
             if let Some(entries) = batch.entries() {
@@ -0,0 +1,213 @@
+//! Module contains a representation of chunk metadata
+use std::convert::TryFrom;
+
+use generated_types::{google::FieldViolation, influxdata::iox::management::v1 as management};
+use serde::{Deserialize, Serialize};
+
+use crate::{field_validation::FromField, partition_metadata::TableSummary};
+
+/// Which storage system a chunk is stored in
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkStorage {
+    /// The chunk is the open chunk of its partition in the mutable buffer,
+    /// and still accepts writes
+    OpenMutableBuffer,
+
+    /// The chunk is a closed chunk in the mutable buffer
+    #[serde(alias = "mutable_buffer")]
+    ClosedMutableBuffer,
+
+    /// The chunk is in the read buffer
+    ReadBuffer,
+
+    /// The chunk was persisted to Parquet files in object storage and is
+    /// read from there
+    ObjectStore,
+}
+
+impl ChunkStorage {
+    /// Returns true if the chunk is in the mutable buffer, open or closed
+    pub fn is_mutable_buffer(&self) -> bool {
+        matches!(self, Self::OpenMutableBuffer | Self::ClosedMutableBuffer)
+    }
+}
+
+impl From<ChunkStorage> for management::ChunkStorage {
+    fn from(storage: ChunkStorage) -> Self {
+        match storage {
+            ChunkStorage::OpenMutableBuffer => Self::OpenMutableBuffer,
+            ChunkStorage::ClosedMutableBuffer => Self::ClosedMutableBuffer,
+            ChunkStorage::ReadBuffer => Self::ReadBuffer,
+            ChunkStorage::ObjectStore => Self::ObjectStore,
+        }
+    }
+}
+
+impl TryFrom<management::ChunkStorage> for ChunkStorage {
+    type Error = FieldViolation;
+
+    fn try_from(proto: management::ChunkStorage) -> Result<Self, Self::Error> {
+        Ok(match proto {
+            management::ChunkStorage::Unspecified => return Err(FieldViolation::required("")),
+            management::ChunkStorage::OpenMutableBuffer => Self::OpenMutableBuffer,
+            management::ChunkStorage::ClosedMutableBuffer => Self::ClosedMutableBuffer,
+            management::ChunkStorage::ReadBuffer => Self::ReadBuffer,
+            management::ChunkStorage::ObjectStore => Self::ObjectStore,
+        })
+    }
+}
+
+/// Summary of a chunk of one of a database's partitions. A chunk that is
+/// being moved to the read buffer is listed once for each storage system
+/// it is in.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ChunkSummary {
+    /// The partition key of the chunk's partition
+    pub partition_key: String,
+
+    /// The id of the chunk, which is unique within its partition
+    pub id: u32,
+
+    /// Where the chunk is stored
+    pub storage: ChunkStorage,
+
+    /// The estimated size in bytes of the chunk's data in memory. Chunks
+    /// that are only in object storage report 0.
+    pub estimated_bytes: usize,
+
+    /// The number of rows in the chunk
+    pub row_count: usize,
+
+    /// The minimum and maximum timestamps of the chunk's rows, or `None` if
+    /// it has no rows
+    pub time_range: Option<(i64, i64)>,
+}
+
+impl ChunkSummary {
+    /// Creates a summary of a chunk, taking its row count and time range
+    /// from the summaries of its tables
+    pub fn from_table_summaries(
+        partition_key: impl Into<String>,
+        id: u32,
+        storage: ChunkStorage,
+        estimated_bytes: usize,
+        tables: &[TableSummary],
+    ) -> Self {
+        let row_count = tables.iter().map(TableSummary::row_count).sum();
+        let time_range =
+            tables
+                .iter()
+                .filter_map(TableSummary::time_range)
+                .fold(None, |range, (min, max)| match range {
+                    Some((range_min, range_max)) => {
+                        Some((i64::min(range_min, min), i64::max(range_max, max)))
+                    }
+                    None => Some((min, max)),
+                });
+
+        Self {
+            partition_key: partition_key.into(),
+            id,
+            storage,
+            estimated_bytes,
+            row_count,
+            time_range,
+        }
+    }
+}
+
+impl From<ChunkSummary> for management::Chunk {
+    fn from(summary: ChunkSummary) -> Self {
+        let storage: management::ChunkStorage = summary.storage.into();
+
+        Self {
+            partition_key: summary.partition_key,
+            id: summary.id,
+            storage: storage as _,
+            estimated_bytes: summary.estimated_bytes as u64,
+            row_count: summary.row_count as u64,
+            time_range: summary
+                .time_range
+                .map(|(min, max)| management::TimeRange { min, max }),
+        }
+    }
+}
+
+impl TryFrom<management::Chunk> for ChunkSummary {
+    type Error = FieldViolation;
+
+    fn try_from(proto: management::Chunk) -> Result<Self, Self::Error> {
+        let storage = proto.storage().scope("storage")?;
+
+        Ok(Self {
+            partition_key: proto.partition_key,
+            id: proto.id,
+            storage,
+            estimated_bytes: proto.estimated_bytes as usize,
+            row_count: proto.row_count as usize,
+            time_range: proto.time_range.map(|range| (range.min, range.max)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::partition_metadata::{ColumnSummary, StatValues, Statistics};
+
+    fn table(name: &str, times: &[i64]) -> TableSummary {
+        let mut stats = StatValues::new(times[0]);
+        for &time in &times[1..] {
+            stats.update(time);
+        }
+
+        TableSummary {
+            name: name.to_string(),
+            columns: vec![ColumnSummary {
+                name: "time".to_string(),
+                stats: Statistics::I64(stats),
+            }],
+        }
+    }
+
+    #[test]
+    fn summary_from_tables() {
+        let tables = vec![table("cpu", &[20, 10]), table("mem", &[30])];
+        let summary =
+            ChunkSummary::from_table_summaries("p1", 3, ChunkStorage::ReadBuffer, 100, &tables);
+
+        assert_eq!(summary.row_count, 3);
+        assert_eq!(summary.time_range, Some((10, 30)));
+
+        let empty =
+            ChunkSummary::from_table_summaries("p1", 4, ChunkStorage::OpenMutableBuffer, 0, &[]);
+        assert_eq!(empty.row_count, 0);
+        assert_eq!(empty.time_range, None);
+    }
+
+    #[test]
+    fn proto_round_trip() {
+        let summary = ChunkSummary {
+            partition_key: "p1".to_string(),
+            id: 3,
+            storage: ChunkStorage::ClosedMutableBuffer,
+            estimated_bytes: 100,
+            row_count: 2,
+            time_range: Some((10, 20)),
+        };
+
+        let proto: management::Chunk = summary.clone().into();
+        assert_eq!(
+            proto.storage,
+            management::ChunkStorage::ClosedMutableBuffer as i32
+        );
+        assert_eq!(ChunkSummary::try_from(proto).unwrap(), summary);
+
+        let proto = management::Chunk {
+            storage: 42,
+            ..Default::default()
+        };
+        assert_eq!(ChunkSummary::try_from(proto).unwrap_err().field, "storage");
+    }
+}
@@ -1,14 +1,19 @@
 //! This module contains helper methods for constructing replicated writes
 //! based on `DatabaseRules`.
 
-use crate::database_rules::Partitioner;
+use crate::database_rules::{PartitionRow, Partitioner};
+use crate::schema::{InfluxColumnType, InfluxFieldType};
 use crate::TIME_COLUMN_NAME;
+use arrow_deps::arrow::{
+    array::{Array, ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray, UInt64Array},
+    record_batch::RecordBatch,
+};
 use generated_types::wal as wb;
-use influxdb_line_protocol::{FieldValue, ParsedLine};
+use influxdb_line_protocol::{EscapedStr, FieldValue, ParsedLine};
 
-use std::{collections::BTreeMap, fmt};
+use std::{collections::BTreeMap, convert::TryFrom, fmt};
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use crc32fast::Hasher;
 use flatbuffers::FlatBufferBuilder;
 
@@ -26,25 +31,28 @@ pub fn type_description(value: wb::ColumnValue) -> &'static str {
     }
 }
 
-/// A friendlier wrapper to help deal with the Flatbuffers write data
-#[derive(Debug, Default, Clone, PartialEq)]
+/// A friendlier wrapper to help deal with the Flatbuffers write data. Bytes
+/// from elsewhere are verified to hold a valid `ReplicatedWrite` and payload
+/// when they are converted with `TryFrom`.
+#[derive(Debug, Clone, PartialEq)]
 pub struct ReplicatedWrite {
-    pub data: Vec<u8>,
+    data: Vec<u8>,
 }
 
 impl ReplicatedWrite {
     /// Returns the Flatbuffers struct represented by the raw bytes.
     pub fn to_fb(&self) -> wb::ReplicatedWrite<'_> {
-        flatbuffers::get_root::<wb::ReplicatedWrite<'_>>(&self.data)
+        flatbuffers::root::<wb::ReplicatedWrite<'_>>(&self.data)
+            .expect("replicated write verified when created")
     }
 
     /// Returns the Flatbuffers struct for the WriteBufferBatch in the raw bytes
     /// of the payload of the ReplicatedWrite.
     pub fn write_buffer_batch(&self) -> Option<wb::WriteBufferBatch<'_>> {
-        match self.to_fb().payload() {
-            Some(d) => Some(flatbuffers::get_root::<wb::WriteBufferBatch<'_>>(&d)),
-            None => None,
-        }
+        self.to_fb().payload().map(|d| {
+            flatbuffers::root::<wb::WriteBufferBatch<'_>>(d)
+                .expect("write buffer batch verified when created")
+        })
     }
 
     /// Returns true if this replicated write matches the writer and sequence.
@@ -59,11 +67,16 @@ impl ReplicatedWrite {
         (fb.writer(), fb.sequence())
     }
 
-    /// Returns the serialized bytes for the write. (used for benchmarking)
+    /// Returns the serialized bytes for the write
     pub fn bytes(&self) -> &Vec<u8> {
         &self.data
     }
 
+    /// Returns the serialized bytes for the write, consuming it
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+
     /// Returns the number of write buffer entries in this replicated write
     pub fn entry_count(&self) -> usize {
         if let Some(batch) = self.write_buffer_batch() {
@@ -76,11 +89,26 @@ impl ReplicatedWrite {
     }
 }
 
-impl From<&[u8]> for ReplicatedWrite {
-    fn from(data: &[u8]) -> Self {
-        Self {
-            data: Vec::from(data),
+impl TryFrom<Vec<u8>> for ReplicatedWrite {
+    type Error = flatbuffers::InvalidFlatbuffer;
+
+    /// Verifies that the bytes hold a `ReplicatedWrite` whose payload, if it
+    /// has one, is a `WriteBufferBatch`
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        let write = flatbuffers::root::<wb::ReplicatedWrite<'_>>(&data)?;
+        if let Some(payload) = write.payload() {
+            flatbuffers::root::<wb::WriteBufferBatch<'_>>(payload)?;
         }
+
+        Ok(Self { data })
+    }
+}
+
+impl TryFrom<&[u8]> for ReplicatedWrite {
+    type Error = flatbuffers::InvalidFlatbuffer;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        Self::try_from(Vec::from(data))
     }
 }
 
@@ -172,12 +200,288 @@ pub fn lines_to_replicated_write(
         lines,
     );
 
+    replicated_write_from_batch_bytes(writer, sequence, &entry_bytes)
+}
+
+/// Creates a `ReplicatedWrite` of the rows of a record batch of a table, as
+/// `lines_to_replicated_write` does for lines, reading the values straight
+/// from the columns of the batch. `columns` holds the name and IOx column
+/// type of each column of the batch, whose arrays must have the arrow data
+/// types of these column types. Null values are left out of a row, and rows
+/// with a null time are written with the current time.
+pub fn record_batch_to_replicated_write(
+    writer: u32,
+    sequence: u64,
+    table_name: &str,
+    columns: &[(String, InfluxColumnType)],
+    batch: &RecordBatch,
+    partitioner: &impl Partitioner,
+) -> ReplicatedWrite {
+    let default_time = Utc::now();
+    let columns: Vec<_> = columns
+        .iter()
+        .zip(batch.columns())
+        .map(|((name, column_type), array)| BatchColumn {
+            name,
+            column_type: *column_type,
+            array,
+        })
+        .collect();
+
+    // split the rows into the partitions they go into
+    let mut partition_rows = BTreeMap::new();
+    for row in 0..batch.num_rows() {
+        let row = BatchRow {
+            table_name,
+            columns: &columns,
+            row,
+        };
+        let key = partitioner.partition_key(&row, &default_time).unwrap();
+
+        partition_rows.entry(key).or_insert_with(Vec::new).push(row);
+    }
+
+    // create a WALEntry with a single table batch for each partition
+    let mut fbb = flatbuffers::FlatBufferBuilder::new_with_capacity(1024);
+    let entries = partition_rows
+        .into_iter()
+        .map(|(key, rows)| {
+            let rows = rows
+                .iter()
+                .map(|row| add_batch_row(&mut fbb, row, &default_time))
+                .collect::<Vec<_>>();
+
+            let name = fbb.create_string(table_name);
+            let rows = fbb.create_vector(&rows);
+            let table_batch = wb::TableWriteBatch::create(
+                &mut fbb,
+                &wb::TableWriteBatchArgs {
+                    name: Some(name),
+                    rows: Some(rows),
+                },
+            );
+
+            let table_batches = fbb.create_vector(&[table_batch]);
+            let partition_key = fbb.create_string(&key);
+            wb::WriteBufferEntry::create(
+                &mut fbb,
+                &wb::WriteBufferEntryArgs {
+                    partition_key: Some(partition_key),
+                    table_batches: Some(table_batches),
+                    ..Default::default()
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let entry_bytes = finish_write_buffer_batch(fbb, &entries);
+
+    replicated_write_from_batch_bytes(writer, sequence, &entry_bytes)
+}
+
+/// A column of a record batch that is written as a tag, field or time column
+struct BatchColumn<'a> {
+    name: &'a str,
+    column_type: InfluxColumnType,
+    array: &'a ArrayRef,
+}
+
+/// A row of a record batch that is written to a table
+struct BatchRow<'a> {
+    table_name: &'a str,
+    columns: &'a [BatchColumn<'a>],
+    row: usize,
+}
+
+impl PartitionRow for BatchRow<'_> {
+    fn table_name(&self) -> &str {
+        self.table_name
+    }
+
+    fn column_value(&self, column: &str) -> Option<String> {
+        let BatchColumn {
+            column_type, array, ..
+        } = self.columns.iter().find(|c| c.name == column)?;
+        let row = self.row;
+        if array.is_null(row) {
+            return None;
+        }
+
+        let value = match column_type {
+            InfluxColumnType::Tag => downcast::<StringArray>(array).value(row).to_string(),
+            InfluxColumnType::Field(InfluxFieldType::Float) => {
+                FieldValue::F64(downcast::<Float64Array>(array).value(row)).to_string()
+            }
+            InfluxColumnType::Field(InfluxFieldType::Integer) => {
+                FieldValue::I64(downcast::<Int64Array>(array).value(row)).to_string()
+            }
+            InfluxColumnType::Field(InfluxFieldType::UInteger) => {
+                format!("{}u", downcast::<UInt64Array>(array).value(row))
+            }
+            InfluxColumnType::Field(InfluxFieldType::String) => {
+                let value = downcast::<StringArray>(array).value(row);
+                FieldValue::String(EscapedStr::from(value)).to_string()
+            }
+            InfluxColumnType::Field(InfluxFieldType::Boolean) => {
+                FieldValue::Boolean(downcast::<BooleanArray>(array).value(row)).to_string()
+            }
+            InfluxColumnType::Timestamp => return None,
+        };
+
+        Some(value)
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        let BatchColumn { array, .. } = self
+            .columns
+            .iter()
+            .find(|c| matches!(c.column_type, InfluxColumnType::Timestamp))?;
+
+        if array.is_null(self.row) {
+            None
+        } else {
+            Some(downcast::<Int64Array>(array).value(self.row))
+        }
+    }
+}
+
+/// The arrays of a record batch must have the arrow data types of the IOx
+/// column types they are written as
+fn downcast<T: 'static>(array: &ArrayRef) -> &T {
+    array
+        .as_any()
+        .downcast_ref::<T>()
+        .expect("arrow data type of column matches its IOx column type")
+}
+
+/// Creates a new `ReplicatedWrite` for the writer and sequence number that
+/// contains copies of the passed write buffer entries. This is used to split
+/// up an existing write (for example, by the host that should receive each
+/// partition) while keeping the identity of the original write.
+pub fn replicated_write_from_entries<'a>(
+    writer: u32,
+    sequence: u64,
+    entries: impl IntoIterator<Item = wb::WriteBufferEntry<'a>>,
+) -> ReplicatedWrite {
+    let mut fbb = flatbuffers::FlatBufferBuilder::new_with_capacity(1024);
+
+    let entries = entries
+        .into_iter()
+        .map(|entry| copy_write_entry(&mut fbb, &entry))
+        .collect::<Vec<_>>();
+
+    let entry_bytes = finish_write_buffer_batch(fbb, &entries);
+
+    replicated_write_from_batch_bytes(writer, sequence, &entry_bytes)
+}
+
+/// Creates a new `ReplicatedWrite` with the writer and sequence number of
+/// `write` that only contains the tables and rows of `write` that pass the
+/// filters. Tables and entries left without any rows are omitted, as are
+/// deletes. `None` is returned if no rows pass the filters.
+pub fn filter_replicated_write(
+    write: &ReplicatedWrite,
+    table_filter: impl Fn(&str) -> bool,
+    row_filter: impl Fn(&wb::Row<'_>) -> bool,
+) -> Option<ReplicatedWrite> {
+    let mut fbb = flatbuffers::FlatBufferBuilder::new_with_capacity(1024);
+    let mut entries = vec![];
+
+    for entry in write.write_buffer_batch()?.entries().into_iter().flatten() {
+        let mut tables = vec![];
+
+        for table in entry.table_batches().into_iter().flatten() {
+            let name = table.name().unwrap_or("");
+            if !table_filter(name) {
+                continue;
+            }
+
+            let rows = table
+                .rows()
+                .into_iter()
+                .flatten()
+                .filter(&row_filter)
+                .map(|row| copy_row(&mut fbb, &row))
+                .collect::<Vec<_>>();
+
+            if rows.is_empty() {
+                continue;
+            }
+
+            let name = fbb.create_string(name);
+            let rows = fbb.create_vector(&rows);
+            tables.push(wb::TableWriteBatch::create(
+                &mut fbb,
+                &wb::TableWriteBatchArgs {
+                    name: Some(name),
+                    rows: Some(rows),
+                },
+            ));
+        }
+
+        if tables.is_empty() {
+            continue;
+        }
+
+        let partition_key = entry.partition_key().map(|key| fbb.create_string(key));
+        let table_batches = fbb.create_vector(&tables);
+        entries.push(wb::WriteBufferEntry::create(
+            &mut fbb,
+            &wb::WriteBufferEntryArgs {
+                partition_key,
+                table_batches: Some(table_batches),
+                ..Default::default()
+            },
+        ));
+    }
+
+    if entries.is_empty() {
+        return None;
+    }
+
+    let entry_bytes = finish_write_buffer_batch(fbb, &entries);
+    let (writer, sequence) = write.writer_and_sequence();
+
+    Some(replicated_write_from_batch_bytes(
+        writer,
+        sequence,
+        &entry_bytes,
+    ))
+}
+
+/// Finishes a `WriteBufferBatch` of the entries and returns its bytes
+fn finish_write_buffer_batch<'a>(
+    mut fbb: FlatBufferBuilder<'a>,
+    entries: &[flatbuffers::WIPOffset<wb::WriteBufferEntry<'a>>],
+) -> Vec<u8> {
+    let entries_vec = fbb.create_vector(entries);
+
+    let batch = wb::WriteBufferBatch::create(
+        &mut fbb,
+        &wb::WriteBufferBatchArgs {
+            entries: Some(entries_vec),
+        },
+    );
+
+    fbb.finish(batch, None);
+
+    let (mut data, idx) = fbb.collapse();
+    data.split_off(idx)
+}
+
+/// Wraps the bytes of a `WriteBufferBatch` into a `ReplicatedWrite`,
+/// computing the checksum of the payload.
+fn replicated_write_from_batch_bytes(
+    writer: u32,
+    sequence: u64,
+    entry_bytes: &[u8],
+) -> ReplicatedWrite {
     let mut hasher = Hasher::new();
-    hasher.update(&entry_bytes);
+    hasher.update(entry_bytes);
     let checksum = hasher.finalize();
 
     let mut fbb = flatbuffers::FlatBufferBuilder::new_with_capacity(1024);
-    let payload = fbb.create_vector_direct(&entry_bytes);
+    let payload = fbb.create_vector_direct(entry_bytes);
 
     let write = wb::ReplicatedWrite::create(
         &mut fbb,
@@ -340,6 +644,194 @@ fn add_line<'a>(
     )
 }
 
+fn add_batch_row<'a>(
+    fbb: &mut FlatBufferBuilder<'a>,
+    row: &BatchRow<'_>,
+    default_time: &DateTime<Utc>,
+) -> flatbuffers::WIPOffset<wb::Row<'a>> {
+    let mut row_values = Vec::with_capacity(row.columns.len());
+
+    for BatchColumn {
+        name,
+        column_type,
+        array,
+    } in row.columns
+    {
+        let i = row.row;
+        if array.is_null(i) {
+            continue;
+        }
+
+        let val = match column_type {
+            InfluxColumnType::Tag => {
+                add_tag_value(fbb, name, downcast::<StringArray>(array).value(i))
+            }
+            InfluxColumnType::Field(InfluxFieldType::Float) => {
+                add_f64_value(fbb, name, downcast::<Float64Array>(array).value(i))
+            }
+            InfluxColumnType::Field(InfluxFieldType::Integer) => {
+                add_i64_value(fbb, name, downcast::<Int64Array>(array).value(i))
+            }
+            InfluxColumnType::Field(InfluxFieldType::UInteger) => {
+                add_u64_value(fbb, name, downcast::<UInt64Array>(array).value(i))
+            }
+            InfluxColumnType::Field(InfluxFieldType::String) => {
+                add_string_value(fbb, name, downcast::<StringArray>(array).value(i))
+            }
+            InfluxColumnType::Field(InfluxFieldType::Boolean) => {
+                add_bool_value(fbb, name, downcast::<BooleanArray>(array).value(i))
+            }
+            // the time is always written to the time column, after the
+            // other values
+            InfluxColumnType::Timestamp => continue,
+        };
+
+        row_values.push(val);
+    }
+
+    let time = row
+        .timestamp()
+        .unwrap_or_else(|| default_time.timestamp_nanos());
+    row_values.push(add_i64_value(fbb, TIME_COLUMN_NAME, time));
+
+    let row_values = fbb.create_vector(&row_values);
+
+    wb::Row::create(
+        fbb,
+        &wb::RowArgs {
+            values: Some(row_values),
+        },
+    )
+}
+
+fn copy_write_entry<'a>(
+    fbb: &mut FlatBufferBuilder<'a>,
+    entry: &wb::WriteBufferEntry<'_>,
+) -> flatbuffers::WIPOffset<wb::WriteBufferEntry<'a>> {
+    let table_batches = entry.table_batches().map(|batches| {
+        let batches = batches
+            .into_iter()
+            .map(|batch| copy_table_batch(fbb, &batch))
+            .collect::<Vec<_>>();
+        fbb.create_vector(&batches)
+    });
+
+    let delete = entry.delete().map(|delete| {
+        let table_name = delete.table_name().map(|name| fbb.create_string(name));
+        let predicate = delete.predicate().map(|pred| fbb.create_string(pred));
+        wb::WriteBufferDelete::create(
+            fbb,
+            &wb::WriteBufferDeleteArgs {
+                table_name,
+                predicate,
+            },
+        )
+    });
+
+    let partition_key = entry.partition_key().map(|key| fbb.create_string(key));
+
+    wb::WriteBufferEntry::create(
+        fbb,
+        &wb::WriteBufferEntryArgs {
+            partition_key,
+            table_batches,
+            delete,
+        },
+    )
+}
+
+fn copy_table_batch<'a>(
+    fbb: &mut FlatBufferBuilder<'a>,
+    batch: &wb::TableWriteBatch<'_>,
+) -> flatbuffers::WIPOffset<wb::TableWriteBatch<'a>> {
+    let rows = batch.rows().map(|rows| {
+        let rows = rows
+            .into_iter()
+            .map(|row| copy_row(fbb, &row))
+            .collect::<Vec<_>>();
+        fbb.create_vector(&rows)
+    });
+
+    let name = batch.name().map(|name| fbb.create_string(name));
+
+    wb::TableWriteBatch::create(fbb, &wb::TableWriteBatchArgs { name, rows })
+}
+
+fn copy_row<'a>(
+    fbb: &mut FlatBufferBuilder<'a>,
+    row: &wb::Row<'_>,
+) -> flatbuffers::WIPOffset<wb::Row<'a>> {
+    let values = row.values().map(|values| {
+        let values = values
+            .into_iter()
+            .map(|value| copy_value(fbb, &value))
+            .collect::<Vec<_>>();
+        fbb.create_vector(&values)
+    });
+
+    wb::Row::create(fbb, &wb::RowArgs { values })
+}
+
+fn copy_value<'a>(
+    fbb: &mut FlatBufferBuilder<'a>,
+    value: &wb::Value<'_>,
+) -> flatbuffers::WIPOffset<wb::Value<'a>> {
+    let column = value.column().unwrap_or("");
+
+    match value.value_type() {
+        wb::ColumnValue::TagValue => add_tag_value(
+            fbb,
+            column,
+            value
+                .value_as_tag_value()
+                .and_then(|v| v.value())
+                .unwrap_or(""),
+        ),
+        wb::ColumnValue::StringValue => add_string_value(
+            fbb,
+            column,
+            value
+                .value_as_string_value()
+                .and_then(|v| v.value())
+                .unwrap_or(""),
+        ),
+        wb::ColumnValue::I64Value => add_i64_value(
+            fbb,
+            column,
+            value.value_as_i64value().map(|v| v.value()).unwrap_or(0),
+        ),
+        wb::ColumnValue::U64Value => add_u64_value(
+            fbb,
+            column,
+            value.value_as_u64value().map(|v| v.value()).unwrap_or(0),
+        ),
+        wb::ColumnValue::F64Value => add_f64_value(
+            fbb,
+            column,
+            value.value_as_f64value().map(|v| v.value()).unwrap_or(0.0),
+        ),
+        wb::ColumnValue::BoolValue => add_bool_value(
+            fbb,
+            column,
+            value
+                .value_as_bool_value()
+                .map(|v| v.value())
+                .unwrap_or(false),
+        ),
+        wb::ColumnValue::NONE => {
+            let column = fbb.create_string(column);
+            wb::Value::create(
+                fbb,
+                &wb::ValueArgs {
+                    column: Some(column),
+                    value_type: wb::ColumnValue::NONE,
+                    value: None,
+                },
+            )
+        }
+    }
+}
+
 fn add_tag_value<'a>(
     fbb: &mut FlatBufferBuilder<'a>,
     column: &str,
@@ -393,6 +885,16 @@ fn add_i64_value<'a>(
     add_value(fbb, column, wb::ColumnValue::I64Value, iv.as_union_value())
 }
 
+fn add_u64_value<'a>(
+    fbb: &mut FlatBufferBuilder<'a>,
+    column: &str,
+    value: u64,
+) -> flatbuffers::WIPOffset<wb::Value<'a>> {
+    let uv = wb::U64Value::create(fbb, &wb::U64ValueArgs { value });
+
+    add_value(fbb, column, wb::ColumnValue::U64Value, uv.as_union_value())
+}
+
 fn add_bool_value<'a>(
     fbb: &mut FlatBufferBuilder<'a>,
     column: &str,
@@ -420,3 +922,102 @@ fn add_value<'a>(
         },
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database_rules::{DatabaseRules, PartitionTemplate, TemplatePart};
+    use crate::schema::builder::SchemaBuilder;
+    use arrow_deps::arrow::datatypes::DataType;
+    use influxdb_line_protocol::parse_lines;
+    use std::sync::Arc;
+
+    #[test]
+    fn verifies_replicated_write_bytes() {
+        let lines: Vec<_> = parse_lines("cpu,host=a usage=1.5 10")
+            .map(|l| l.unwrap())
+            .collect();
+        let write = lines_to_replicated_write(1, 2, &lines, &DatabaseRules::new());
+
+        let verified = ReplicatedWrite::try_from(write.bytes().as_slice()).unwrap();
+        assert_eq!(verified, write);
+        assert_eq!(verified.writer_and_sequence(), (1, 2));
+        assert_eq!(verified.entry_count(), 1);
+
+        ReplicatedWrite::try_from(&b"not a flatbuffer"[..]).unwrap_err();
+
+        // a valid write whose payload isn't a write buffer batch
+        let mut fbb = flatbuffers::FlatBufferBuilder::new();
+        let payload = fbb.create_vector_direct(&[0xff_u8; 3]);
+        let bad_payload = wb::ReplicatedWrite::create(
+            &mut fbb,
+            &wb::ReplicatedWriteArgs {
+                writer: 1,
+                sequence: 2,
+                checksum: 0,
+                payload: Some(payload),
+            },
+        );
+        fbb.finish(bad_payload, None);
+        ReplicatedWrite::try_from(fbb.finished_data()).unwrap_err();
+    }
+
+    #[test]
+    fn record_batch_to_replicated_write_like_lines() {
+        let schema = SchemaBuilder::new()
+            .tag("host")
+            .field("usage", DataType::Float64)
+            .field("count", DataType::Int64)
+            .field("status", DataType::Utf8)
+            .timestamp()
+            .build()
+            .unwrap();
+        let columns: Vec<_> = schema
+            .iter()
+            .map(|(column_type, field)| (field.name().clone(), column_type.unwrap()))
+            .collect();
+        let batch = RecordBatch::try_new(
+            schema.into(),
+            vec![
+                Arc::new(StringArray::from(vec![Some("a"), None, Some("b")])),
+                Arc::new(Float64Array::from(vec![Some(1.5), None, None])),
+                Arc::new(Int64Array::from(vec![None, Some(2), None])),
+                Arc::new(StringArray::from(vec![None, None, Some("ok")])),
+                Arc::new(Int64Array::from(vec![10, 20, 30])),
+            ],
+        )
+        .unwrap();
+
+        let rules = DatabaseRules {
+            partition_template: PartitionTemplate {
+                parts: vec![
+                    TemplatePart::Table,
+                    TemplatePart::Column("host".to_string()),
+                    TemplatePart::Column("count".to_string()),
+                ],
+            },
+            ..DatabaseRules::new()
+        };
+        let write = record_batch_to_replicated_write(1, 2, "cpu", &columns, &batch, &rules);
+
+        let lines: Vec<_> =
+            parse_lines("cpu,host=a usage=1.5 10\ncpu count=2i 20\ncpu,host=b status=\"ok\" 30")
+                .map(|l| l.unwrap())
+                .collect();
+        let expected = lines_to_replicated_write(1, 2, &lines, &rules);
+
+        // the same rows are written to the same partitions, leaving out the
+        // header with the checksum of the bytes
+        let rows = |write: &ReplicatedWrite| {
+            write
+                .to_string()
+                .lines()
+                .skip(2)
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rows(&write), rows(&expected));
+        assert_eq!(write.writer_and_sequence(), (1, 2));
+        assert_eq!(write.entry_count(), 3);
+    }
+}
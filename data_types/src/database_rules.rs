@@ -27,7 +27,7 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 /// DatabaseRules contains the rules for replicating data, sending data to
 /// subscribers, and querying data for a single database.
-#[derive(Debug, Serialize, Deserialize, Default, Eq, PartialEq, Clone)]
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
 pub struct DatabaseRules {
     /// The unencoded name of the database. This gets put in by the create
     /// database call, so an empty default is fine.
@@ -58,9 +58,14 @@ pub struct DatabaseRules {
     /// How long the replication queue can get before either rejecting writes or
     /// dropping missed writes. The queue is kept in memory on a
     /// per-database basis. A queue size of zero means it will only try to
-    /// replicate synchronously and drop any failures.
-    #[serde(default)]
+    /// replicate synchronously and drop any failures. Rules that don't set it
+    /// get a queue of 10,000 writes.
+    #[serde(default = "default_replication_queue_max_size")]
     pub replication_queue_max_size: usize,
+    /// What to do with a write that needs to be queued once the replication
+    /// queue holds `replication_queue_max_size` writes.
+    #[serde(default)]
+    pub replication_queue_overflow: ReplicationQueueOverflow,
     /// `subscriptions` are used for query servers to get data via either push
     /// or pull as it arrives. They are separate from replication as they
     /// have a different purpose. They're for query servers or other clients
@@ -115,13 +120,39 @@ pub struct DatabaseRules {
     pub mutable_buffer_config: Option<MutableBufferConfig>,
 }
 
+const DEFAULT_REPLICATION_QUEUE_MAX_SIZE: usize = 10_000;
+
+fn default_replication_queue_max_size() -> usize {
+    DEFAULT_REPLICATION_QUEUE_MAX_SIZE
+}
+
+impl Default for DatabaseRules {
+    fn default() -> Self {
+        Self {
+            name: Default::default(),
+            partition_template: Default::default(),
+            replication: Default::default(),
+            replication_count: Default::default(),
+            replication_queue_max_size: DEFAULT_REPLICATION_QUEUE_MAX_SIZE,
+            replication_queue_overflow: Default::default(),
+            subscriptions: Default::default(),
+            query_local: Default::default(),
+            primary_query_group: Default::default(),
+            secondary_query_groups: Default::default(),
+            read_only_partitions: Default::default(),
+            wal_buffer_config: Default::default(),
+            mutable_buffer_config: Default::default(),
+        }
+    }
+}
+
 impl DatabaseRules {
     pub fn partition_key(
         &self,
-        line: &ParsedLine<'_>,
+        row: &dyn PartitionRow,
         default_time: &DateTime<Utc>,
     ) -> Result<String> {
-        self.partition_template.partition_key(line, default_time)
+        self.partition_template.partition_key(row, default_time)
     }
 
     pub fn new() -> Self {
@@ -132,18 +163,54 @@ impl DatabaseRules {
     }
 }
 
-/// Generates a partition key based on the line and the default time.
+/// Generates a partition key based on the row and the default time.
 pub trait Partitioner {
     fn partition_key(
         &self,
-        _line: &ParsedLine<'_>,
+        _row: &dyn PartitionRow,
         _default_time: &DateTime<Utc>,
     ) -> Result<String>;
 }
 
 impl Partitioner for DatabaseRules {
-    fn partition_key(&self, line: &ParsedLine<'_>, default_time: &DateTime<Utc>) -> Result<String> {
-        self.partition_key(&line, &default_time)
+    fn partition_key(
+        &self,
+        row: &dyn PartitionRow,
+        default_time: &DateTime<Utc>,
+    ) -> Result<String> {
+        self.partition_key(row, &default_time)
+    }
+}
+
+/// A row that gets written, such as a line of line protocol or a row of a
+/// record batch, which a partition key is computed from
+pub trait PartitionRow {
+    /// The name of the table the row is written to
+    fn table_name(&self) -> &str;
+
+    /// The value of the tag or field column of this name, formatted as in
+    /// line protocol, or `None` if the row has no value for it
+    fn column_value(&self, column: &str) -> Option<String>;
+
+    /// The time of the row in nanoseconds since the epoch, or `None` if it
+    /// is written with the default time
+    fn timestamp(&self) -> Option<i64>;
+}
+
+impl PartitionRow for ParsedLine<'_> {
+    fn table_name(&self) -> &str {
+        self.series.measurement.as_str()
+    }
+
+    fn column_value(&self, column: &str) -> Option<String> {
+        match self.tag_value(column) {
+            Some(v) => Some(v.to_string()),
+            None => self.field_value(column).map(ToString::to_string),
+        }
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        self.timestamp
     }
 }
 
@@ -152,10 +219,14 @@ impl From<DatabaseRules> for management::DatabaseRules {
         let subscriptions: Vec<management::subscription_config::Subscription> =
             rules.subscriptions.into_iter().map(Into::into).collect();
 
+        let replication_queue_overflow: management::replication_config::Overflow =
+            rules.replication_queue_overflow.into();
+
         let replication_config = management::ReplicationConfig {
             replications: rules.replication,
             replication_count: rules.replication_count as _,
             replication_queue_max_size: rules.replication_queue_max_size as _,
+            replication_queue_overflow: replication_queue_overflow as _,
         };
 
         let query_config = management::QueryConfig {
@@ -206,12 +277,17 @@ impl TryFrom<management::DatabaseRules> for DatabaseRules {
         let query = proto.query_config.unwrap_or_default();
         let replication = proto.replication_config.unwrap_or_default();
 
+        let replication_queue_overflow = replication
+            .replication_queue_overflow()
+            .scope("replication_config.replication_queue_overflow")?;
+
         Ok(Self {
             name: proto.name,
             partition_template,
             replication: replication.replications,
             replication_count: replication.replication_count as _,
             replication_queue_max_size: replication.replication_queue_max_size as _,
+            replication_queue_overflow,
             subscriptions,
             query_local: query.query_local,
             primary_query_group: query.primary.optional(),
@@ -223,6 +299,47 @@ impl TryFrom<management::DatabaseRules> for DatabaseRules {
     }
 }
 
+/// ReplicationQueueOverflow defines what happens to a write that needs to be
+/// queued for replication to a host group when the database's replication
+/// queue is full.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Copy)]
+pub enum ReplicationQueueOverflow {
+    /// Drop the oldest write in the queue to make room for the new one. The
+    /// dropped write is logged and counted in the replication status.
+    DropOldest,
+    /// Reject the incoming write and return an error. The client may retry the
+    /// request, which will succeed once the queue has drained.
+    ReturnError,
+}
+
+impl Default for ReplicationQueueOverflow {
+    fn default() -> Self {
+        Self::ReturnError
+    }
+}
+
+impl From<ReplicationQueueOverflow> for management::replication_config::Overflow {
+    fn from(overflow: ReplicationQueueOverflow) -> Self {
+        match overflow {
+            ReplicationQueueOverflow::DropOldest => Self::DropOldest,
+            ReplicationQueueOverflow::ReturnError => Self::ReturnError,
+        }
+    }
+}
+
+impl TryFrom<management::replication_config::Overflow> for ReplicationQueueOverflow {
+    type Error = FieldViolation;
+
+    fn try_from(proto: management::replication_config::Overflow) -> Result<Self, Self::Error> {
+        use management::replication_config::Overflow;
+        Ok(match proto {
+            Overflow::Unspecified => Self::default(),
+            Overflow::DropOldest => Self::DropOldest,
+            Overflow::ReturnError => Self::ReturnError,
+        })
+    }
+}
+
 /// MutableBufferConfig defines the configuration for the in-memory database
 /// that is hot for writes as they arrive. Operators can define rules for
 /// evicting data once the mutable buffer passes a set memory threshold.
@@ -670,22 +787,19 @@ pub struct PartitionTemplate {
 impl PartitionTemplate {
     pub fn partition_key(
         &self,
-        line: &ParsedLine<'_>,
+        row: &dyn PartitionRow,
         default_time: &DateTime<Utc>,
     ) -> Result<String> {
         let parts: Vec<_> = self
             .parts
             .iter()
             .map(|p| match p {
-                TemplatePart::Table => line.series.measurement.to_string(),
-                TemplatePart::Column(column) => match line.tag_value(&column) {
+                TemplatePart::Table => row.table_name().to_string(),
+                TemplatePart::Column(column) => match row.column_value(&column) {
                     Some(v) => format!("{}_{}", column, v),
-                    None => match line.field_value(&column) {
-                        Some(v) => format!("{}_{}", column, v),
-                        None => "".to_string(),
-                    },
+                    None => "".to_string(),
                 },
-                TemplatePart::TimeFormat(format) => match line.timestamp {
+                TemplatePart::TimeFormat(format) => match row.timestamp() {
                     Some(t) => Utc.timestamp_nanos(t).format(&format).to_string(),
                     None => default_time.format(&format).to_string(),
                 },
@@ -852,7 +966,9 @@ impl TryFrom<management::subscription_config::Subscription> for Subscription {
 #[derive(Debug, Default, Serialize, Deserialize, Eq, PartialEq, Clone)]
 pub struct Matcher {
     pub tables: MatchTables,
-    // TODO: make this work with query::Predicate
+    /// An optional predicate that rows must match, a SQL boolean expression
+    /// such as `host = 'server01' AND usage_user > 90`. It is validated when
+    /// the server compiles the matcher.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub predicate: Option<String>,
 }
@@ -1093,6 +1209,13 @@ mod tests {
         parsed_lines(line).pop().unwrap()
     }
 
+    fn default_replication_config() -> management::ReplicationConfig {
+        management::ReplicationConfig {
+            replication_queue_overflow: management::replication_config::Overflow::ReturnError as _,
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn test_database_rules_defaults() {
         let protobuf = management::DatabaseRules {
@@ -1114,7 +1237,7 @@ mod tests {
 
         // These will be defaulted as optionality not preserved on non-protobuf
         // DatabaseRules
-        assert_eq!(back.replication_config, Some(Default::default()));
+        assert_eq!(back.replication_config, Some(default_replication_config()));
         assert_eq!(back.subscription_config, Some(Default::default()));
         assert_eq!(back.query_config, Some(Default::default()));
         assert_eq!(back.partition_template, Some(Default::default()));
@@ -1159,11 +1282,58 @@ mod tests {
 
         // These will be defaulted as optionality not preserved on non-protobuf
         // DatabaseRules
-        assert_eq!(back.replication_config, Some(Default::default()));
+        assert_eq!(back.replication_config, Some(default_replication_config()));
         assert_eq!(back.subscription_config, Some(Default::default()));
         assert_eq!(back.partition_template, Some(Default::default()));
     }
 
+    #[test]
+    fn test_replication_config() {
+        let protobuf = management::DatabaseRules {
+            name: "database".to_string(),
+            replication_config: Some(management::ReplicationConfig {
+                replications: vec!["az1".to_string()],
+                replication_count: 1,
+                replication_queue_max_size: 5,
+                replication_queue_overflow: management::replication_config::Overflow::DropOldest
+                    as _,
+            }),
+            ..Default::default()
+        };
+
+        let rules: DatabaseRules = protobuf.clone().try_into().unwrap();
+        let back: management::DatabaseRules = rules.clone().into();
+
+        assert_eq!(rules.replication_queue_max_size, 5);
+        assert_eq!(
+            rules.replication_queue_overflow,
+            ReplicationQueueOverflow::DropOldest
+        );
+        assert_eq!(back.replication_config, protobuf.replication_config);
+
+        let protobuf = management::DatabaseRules {
+            name: "database".to_string(),
+            replication_config: Some(Default::default()),
+            ..Default::default()
+        };
+
+        // a queue size of zero disables the queue, as it does in stored rules
+        let rules: DatabaseRules = protobuf.try_into().unwrap();
+        assert_eq!(rules.replication_queue_max_size, 0);
+        assert_eq!(
+            rules.replication_queue_overflow,
+            ReplicationQueueOverflow::ReturnError
+        );
+        assert_eq!(
+            rules,
+            DatabaseRules {
+                name: "database".to_string(),
+                replication_queue_max_size: 0,
+                ..Default::default()
+            }
+        );
+    }
+
     #[test]
     fn test_query_config_default() {
         let protobuf = management::DatabaseRules {
@@ -1345,7 +1515,7 @@ mod tests {
     #[test]
     fn test_subscription_default() {
         let pb_matcher = Some(management::Matcher {
-            predicate: "predicate1".to_string(),
+            predicate: "host = 'server01'".to_string(),
             table_matcher: Some(management::matcher::TableMatcher::Table(
                 "table".to_string(),
             )),
@@ -1353,7 +1523,7 @@ mod tests {
 
         let matcher = Matcher {
             tables: MatchTables::Table("table".to_string()),
-            predicate: Some("predicate1".to_string()),
+            predicate: Some("host = 'server01'".to_string()),
         };
 
         let subscription_config = management::SubscriptionConfig {
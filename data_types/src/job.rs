@@ -0,0 +1,224 @@
+//! Module contains a representation of the long-running jobs of a server
+use std::convert::TryFrom;
+
+use generated_types::{
+    google::FieldViolation,
+    influxdata::iox::management::v1::{self as management, job},
+};
+use serde::{Deserialize, Serialize};
+
+/// A long-running job of a server, which is registered so that it can be
+/// listed, followed and cancelled as an operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Job {
+    /// Writes a closed WAL segment to object storage
+    PersistSegment { db_name: String, segment_id: u64 },
+
+    /// Moves a closed mutable buffer chunk into the read buffer
+    LoadReadBuffer {
+        db_name: String,
+        partition_key: String,
+        chunk_id: u32,
+    },
+
+    /// Writes a chunk to Parquet files in object storage
+    SnapshotChunk {
+        db_name: String,
+        partition_key: String,
+        chunk_id: u32,
+    },
+
+    /// Moves the closed chunks of a cold partition to the read buffer,
+    /// snapshots them and drops them from the mutable buffer
+    PersistPartition {
+        db_name: String,
+        partition_key: String,
+    },
+
+    /// Merges chunks of a partition into a single read buffer chunk
+    CompactChunks {
+        db_name: String,
+        partition_key: String,
+        chunk_ids: Vec<u32>,
+    },
+}
+
+impl Job {
+    /// Returns the name of the database the job works on
+    pub fn db_name(&self) -> &str {
+        match self {
+            Self::PersistSegment { db_name, .. }
+            | Self::LoadReadBuffer { db_name, .. }
+            | Self::SnapshotChunk { db_name, .. }
+            | Self::PersistPartition { db_name, .. }
+            | Self::CompactChunks { db_name, .. } => db_name,
+        }
+    }
+
+    /// Returns a human readable description of the job
+    pub fn description(&self) -> String {
+        match self {
+            Self::PersistSegment {
+                db_name,
+                segment_id,
+            } => format!("Persist WAL segment {} of database {}", segment_id, db_name),
+            Self::LoadReadBuffer {
+                db_name,
+                partition_key,
+                chunk_id,
+            } => format!(
+                "Load chunk {} of partition '{}' in database {} to the read buffer",
+                chunk_id, partition_key, db_name
+            ),
+            Self::SnapshotChunk {
+                db_name,
+                partition_key,
+                chunk_id,
+            } => format!(
+                "Snapshot chunk {} of partition '{}' in database {}",
+                chunk_id, partition_key, db_name
+            ),
+            Self::PersistPartition {
+                db_name,
+                partition_key,
+            } => format!(
+                "Persist partition '{}' of database {}",
+                partition_key, db_name
+            ),
+            Self::CompactChunks {
+                db_name,
+                partition_key,
+                chunk_ids,
+            } => format!(
+                "Compact chunks {:?} of partition '{}' in database {}",
+                chunk_ids, partition_key, db_name
+            ),
+        }
+    }
+}
+
+impl From<Job> for management::Job {
+    fn from(job: Job) -> Self {
+        let job = match job {
+            Job::PersistSegment {
+                db_name,
+                segment_id,
+            } => job::Job::PersistSegment(job::PersistSegment {
+                db_name,
+                segment_id,
+            }),
+            Job::LoadReadBuffer {
+                db_name,
+                partition_key,
+                chunk_id,
+            } => job::Job::LoadReadBuffer(job::LoadReadBuffer {
+                db_name,
+                partition_key,
+                chunk_id,
+            }),
+            Job::SnapshotChunk {
+                db_name,
+                partition_key,
+                chunk_id,
+            } => job::Job::SnapshotChunk(job::SnapshotChunk {
+                db_name,
+                partition_key,
+                chunk_id,
+            }),
+            Job::PersistPartition {
+                db_name,
+                partition_key,
+            } => job::Job::PersistPartition(job::PersistPartition {
+                db_name,
+                partition_key,
+            }),
+            Job::CompactChunks {
+                db_name,
+                partition_key,
+                chunk_ids,
+            } => job::Job::CompactChunks(job::CompactChunks {
+                db_name,
+                partition_key,
+                chunk_ids,
+            }),
+        };
+
+        Self { job: Some(job) }
+    }
+}
+
+impl TryFrom<management::Job> for Job {
+    type Error = FieldViolation;
+
+    fn try_from(proto: management::Job) -> Result<Self, Self::Error> {
+        let job = proto.job.ok_or_else(|| FieldViolation::required("job"))?;
+
+        Ok(match job {
+            job::Job::PersistSegment(job::PersistSegment {
+                db_name,
+                segment_id,
+            }) => Self::PersistSegment {
+                db_name,
+                segment_id,
+            },
+            job::Job::LoadReadBuffer(job::LoadReadBuffer {
+                db_name,
+                partition_key,
+                chunk_id,
+            }) => Self::LoadReadBuffer {
+                db_name,
+                partition_key,
+                chunk_id,
+            },
+            job::Job::SnapshotChunk(job::SnapshotChunk {
+                db_name,
+                partition_key,
+                chunk_id,
+            }) => Self::SnapshotChunk {
+                db_name,
+                partition_key,
+                chunk_id,
+            },
+            job::Job::PersistPartition(job::PersistPartition {
+                db_name,
+                partition_key,
+            }) => Self::PersistPartition {
+                db_name,
+                partition_key,
+            },
+            job::Job::CompactChunks(job::CompactChunks {
+                db_name,
+                partition_key,
+                chunk_ids,
+            }) => Self::CompactChunks {
+                db_name,
+                partition_key,
+                chunk_ids,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proto_round_trip() {
+        let job = Job::SnapshotChunk {
+            db_name: "my_db".to_string(),
+            partition_key: "p1".to_string(),
+            chunk_id: 3,
+        };
+        assert_eq!(
+            job.description(),
+            "Snapshot chunk 3 of partition 'p1' in database my_db"
+        );
+
+        let proto: management::Job = job.clone().into();
+        assert_eq!(Job::try_from(proto).unwrap(), job);
+
+        let err = Job::try_from(management::Job::default()).unwrap_err();
+        assert_eq!(err.field, "job");
+    }
+}
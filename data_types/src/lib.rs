@@ -20,10 +20,12 @@ pub const TABLE_NAMES_COLUMN_NAME: &str = "table";
 /// `column_names`.
 pub const COLUMN_NAMES_COLUMN_NAME: &str = "column";
 
+pub mod chunk;
 pub mod data;
 pub mod database_rules;
 pub mod error;
 pub mod http;
+pub mod job;
 pub mod names;
 pub mod partition_metadata;
 pub mod schema;
@@ -5,6 +5,8 @@ use std::fmt::{Debug, Display};
 
 use serde::{Deserialize, Serialize};
 
+use crate::TIME_COLUMN_NAME;
+
 /// Describes the schema, summary statistics for each column in each table and
 /// the location of the partition in storage.
 #[derive(Debug, Deserialize, Serialize, PartialEq)]
@@ -91,6 +93,22 @@ impl TableSummary {
     pub fn column(&self, name: &str) -> Option<&ColumnSummary> {
         self.columns.iter().find(|c| c.name == name)
     }
+
+    /// Returns the number of rows in the table, which all have a value in
+    /// the time column
+    pub fn row_count(&self) -> usize {
+        self.column(TIME_COLUMN_NAME)
+            .map_or(0, |column| column.count() as usize)
+    }
+
+    /// Returns the minimum and maximum timestamps of the table's rows, or
+    /// `None` if it has no rows
+    pub fn time_range(&self) -> Option<(i64, i64)> {
+        match self.column(TIME_COLUMN_NAME).map(|column| &column.stats) {
+            Some(Statistics::I64(stats)) if stats.count > 0 => Some((stats.min, stats.max)),
+            _ => None,
+        }
+    }
 }
 
 /// Column name, statistics which encode type information
@@ -289,6 +307,27 @@ mod tests {
         assert_eq!(stat.count, 4);
     }
 
+    #[test]
+    fn table_rows_and_time_range() {
+        let mut table = TableSummary::new("cpu");
+        assert_eq!(table.row_count(), 0);
+        assert_eq!(table.time_range(), None);
+
+        let mut time = StatValues::new(20);
+        time.update(10);
+        table.columns.push(ColumnSummary {
+            name: "time".to_string(),
+            stats: Statistics::I64(time),
+        });
+        table.columns.push(ColumnSummary {
+            name: "usage".to_string(),
+            stats: Statistics::F64(StatValues::new(1.0)),
+        });
+
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(table.time_range(), Some((10, 20)));
+    }
+
     #[test]
     fn update_string() {
         let mut stat = StatValues::new("bbb".to_string());
@@ -33,6 +33,21 @@ mod pb {
                     include!(concat!(env!("OUT_DIR"), "/influxdata.iox.management.v1.rs"));
                 }
             }
+
+            pub mod write {
+                pub mod v1 {
+                    include!(concat!(env!("OUT_DIR"), "/influxdata.iox.write.v1.rs"));
+                }
+            }
+
+            pub mod subscription {
+                pub mod v1 {
+                    include!(concat!(
+                        env!("OUT_DIR"),
+                        "/influxdata.iox.subscription.v1.rs"
+                    ));
+                }
+            }
         }
     }
 
@@ -71,6 +86,10 @@ pub const STORAGE_SERVICE: &str = "influxdata.platform.storage.Storage";
 pub const IOX_TESTING_SERVICE: &str = "influxdata.platform.storage.IOxTesting";
 /// gRPC Arrow Flight Service
 pub const ARROW_SERVICE: &str = "arrow.flight.protocol.FlightService";
+/// gRPC IOx Write Service
+pub const WRITE_SERVICE: &str = "influxdata.iox.write.v1.WriteService";
+/// gRPC IOx Subscription Service
+pub const SUBSCRIPTION_SERVICE: &str = "influxdata.iox.subscription.v1.SubscriptionService";
 
 pub use pb::com::github::influxdata::idpe::storage::read::*;
 pub use pb::influxdata::platform::storage::*;
@@ -4,6 +4,15 @@ pub mod health;
 /// Client for the management API
 pub mod management;
 
+/// Client for the operations API
+pub mod operations;
+
+/// Client for the write API
+pub mod write;
+
+/// Client for the subscription API
+pub mod subscription;
+
 #[cfg(feature = "flight")]
 /// Client for the flight API
 pub mod flight;
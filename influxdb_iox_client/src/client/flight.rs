@@ -9,12 +9,16 @@ use arrow_deps::{
     arrow::{
         array::Array,
         datatypes::Schema,
-        ipc::{self, reader},
+        ipc::{self, reader, writer::IpcWriteOptions},
         record_batch::RecordBatch,
     },
     arrow_flight::{
-        flight_service_client::FlightServiceClient, utils::flight_data_to_arrow_batch, FlightData,
-        Ticket,
+        flight_descriptor::DescriptorType,
+        flight_service_client::FlightServiceClient,
+        utils::{
+            flight_data_from_arrow_batch, flight_data_from_arrow_schema, flight_data_to_arrow_batch,
+        },
+        Action, ActionType, Empty, FlightData, FlightDescriptor, SchemaResult, Ticket,
     },
 };
 
@@ -101,6 +105,127 @@ impl Client {
     ) -> Result<PerformQuery, Error> {
         PerformQuery::new(self, database_name.into(), sql_query.into()).await
     }
+
+    /// Plan the given SQL query against the database without running it, and
+    /// return the schema of its results.
+    pub async fn query_schema(
+        &mut self,
+        database_name: impl Into<String>,
+        sql_query: impl Into<String>,
+    ) -> Result<Arc<Schema>, Error> {
+        let descriptor = query_descriptor(database_name.into(), sql_query.into())?;
+        let schema_result = self.inner.get_schema(descriptor).await?.into_inner();
+
+        Ok(Arc::new(Schema::try_from(&schema_result)?))
+    }
+
+    /// Plan the given SQL query against the database without running it, and
+    /// return the schema of its results along with an estimate of the rows
+    /// it reads.
+    pub async fn query_info(
+        &mut self,
+        database_name: impl Into<String>,
+        sql_query: impl Into<String>,
+    ) -> Result<QueryInfo, Error> {
+        let descriptor = query_descriptor(database_name.into(), sql_query.into())?;
+        let flight_info = self.inner.get_flight_info(descriptor).await?.into_inner();
+
+        let schema_result = SchemaResult {
+            schema: flight_info.schema,
+        };
+        let schema = Arc::new(Schema::try_from(&schema_result)?);
+
+        // a negative count means the server couldn't estimate the rows
+        let estimated_rows = if flight_info.total_records < 0 {
+            None
+        } else {
+            Some(flight_info.total_records as u64)
+        };
+
+        Ok(QueryInfo {
+            schema,
+            estimated_rows,
+        })
+    }
+
+    /// Write the given Arrow `RecordBatch`es to a table of the database using
+    /// the Flight `DoPut` API. All batches must have the same schema, whose
+    /// columns carry the IOx column type metadata of a tag, field or
+    /// timestamp column. The server rejects batches larger than 10MB once
+    /// encoded, so large writes should be split into several batches.
+    pub async fn write_record_batches(
+        &mut self,
+        database_name: impl Into<String>,
+        table_name: impl Into<String>,
+        batches: &[RecordBatch],
+    ) -> Result<(), Error> {
+        let schema = match batches.first() {
+            Some(batch) => batch.schema(),
+            None => return Ok(()),
+        };
+
+        let options = IpcWriteOptions::default();
+
+        let mut schema_flight_data = flight_data_from_arrow_schema(&schema, &options);
+        schema_flight_data.flight_descriptor = Some(FlightDescriptor {
+            r#type: DescriptorType::Path as i32,
+            cmd: vec![],
+            path: vec![database_name.into(), table_name.into()],
+        });
+
+        let mut flights = vec![schema_flight_data];
+        for batch in batches {
+            let (flight_dictionaries, flight_batch) = flight_data_from_arrow_batch(batch, &options);
+            flights.extend(flight_dictionaries);
+            flights.push(flight_batch);
+        }
+
+        let mut response = self
+            .inner
+            .do_put(futures_util::stream::iter(flights))
+            .await?
+            .into_inner();
+
+        // one result is returned for each batch once it was written
+        while response.message().await?.is_some() {}
+
+        Ok(())
+    }
+
+    /// Run the Flight action of the given type with a JSON body, such as
+    /// `rollover_partition` or `list_chunks`, and return the JSON bodies of
+    /// its results. [`Client::list_actions`] describes the supported actions.
+    pub async fn do_action(
+        &mut self,
+        action_type: impl Into<String>,
+        body: Vec<u8>,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let action = Action {
+            r#type: action_type.into(),
+            body,
+        };
+        let mut response = self.inner.do_action(action).await?.into_inner();
+
+        let mut results = vec![];
+        while let Some(result) = response.message().await? {
+            results.push(result.body);
+        }
+
+        Ok(results)
+    }
+
+    /// List the types and descriptions of the Flight actions supported by
+    /// the server.
+    pub async fn list_actions(&mut self) -> Result<Vec<ActionType>, Error> {
+        let mut response = self.inner.list_actions(Empty {}).await?.into_inner();
+
+        let mut actions = vec![];
+        while let Some(action) = response.message().await? {
+            actions.push(action);
+        }
+
+        Ok(actions)
+    }
 }
 
 // TODO: this should be shared
@@ -110,6 +235,31 @@ struct ReadInfo {
     sql_query: String,
 }
 
+/// The descriptor of a query, which carries the same JSON as the ticket used
+/// to run it
+fn query_descriptor(database_name: String, sql_query: String) -> Result<FlightDescriptor, Error> {
+    let query = ReadInfo {
+        database_name,
+        sql_query,
+    };
+
+    Ok(FlightDescriptor {
+        r#type: DescriptorType::Cmd as i32,
+        cmd: serde_json::to_string(&query)?.into(),
+        path: vec![],
+    })
+}
+
+/// The result of planning a query with [`Client::query_info`]
+#[derive(Debug, Clone)]
+pub struct QueryInfo {
+    /// The schema of the query results
+    pub schema: Arc<Schema>,
+    /// The number of rows the query reads before any predicates are applied,
+    /// if the server could estimate it
+    pub estimated_rows: Option<u64>,
+}
+
 /// A struct that manages the stream of Arrow `RecordBatch` results from an
 /// Arrow Flight query. Created by calling the `perform_query` method on a
 /// [`FlightClient`].
@@ -148,6 +298,12 @@ impl PerformQuery {
         })
     }
 
+    /// Returns the schema of the query results, which is sent before any
+    /// `RecordBatch`.
+    pub fn schema(&self) -> Arc<Schema> {
+        Arc::clone(&self.schema)
+    }
+
     /// Returns the next `RecordBatch` available for this query, or `None` if
     /// there are no further results available.
     pub async fn next(&mut self) -> Result<Option<RecordBatch>, Error> {
@@ -66,4 +66,14 @@ impl Client {
     pub async fn check_storage(&mut self) -> Result<()> {
         self.check(generated_types::STORAGE_SERVICE).await
     }
+
+    /// Returns `Ok()` if the write service is serving
+    pub async fn check_write(&mut self) -> Result<()> {
+        self.check(generated_types::WRITE_SERVICE).await
+    }
+
+    /// Returns `Ok()` if the subscription service is serving
+    pub async fn check_subscription(&mut self) -> Result<()> {
+        self.check(generated_types::SUBSCRIPTION_SERVICE).await
+    }
 }
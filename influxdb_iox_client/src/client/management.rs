@@ -80,6 +80,199 @@ pub enum GetDatabaseError {
     ServerError(tonic::Status),
 }
 
+/// Errors returned by Client::update_database
+#[derive(Debug, Error)]
+pub enum UpdateDatabaseError {
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Writer ID is not set, or the database can't switch to the new rules
+    /// while it is loaded
+    #[error("Failed precondition: {}", .0.message())]
+    FailedPrecondition(tonic::Status),
+
+    /// Server returned an invalid argument error
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    InvalidArgument(tonic::Status),
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::delete_database
+#[derive(Debug, Error)]
+pub enum DeleteDatabaseError {
+    /// Writer ID is not set
+    #[error("Writer ID not set")]
+    NoWriterId,
+
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Server returned an invalid argument error
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    InvalidArgument(tonic::Status),
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::create_host_group
+#[derive(Debug, Error)]
+pub enum CreateHostGroupError {
+    /// Writer ID is not set
+    #[error("Writer ID not set")]
+    NoWriterId,
+
+    /// Server returned an invalid argument error
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    InvalidArgument(tonic::Status),
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::get_replication_status
+#[derive(Debug, Error)]
+pub enum GetReplicationStatusError {
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::list_partitions
+#[derive(Debug, Error)]
+pub enum ListPartitionsError {
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::list_chunks and Client::list_partition_chunks
+#[derive(Debug, Error)]
+pub enum ListChunksError {
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::rollover_partition
+#[derive(Debug, Error)]
+pub enum RolloverPartitionError {
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Database has no mutable buffer
+    #[error("Database has no mutable buffer")]
+    NotWriteable,
+
+    /// Response contained no payload
+    #[error("Server returned an empty response")]
+    EmptyResponse,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::load_chunk_to_read_buffer
+#[derive(Debug, Error)]
+pub enum LoadChunkError {
+    /// Database or chunk not found
+    #[error("Not found: {}", .0.message())]
+    NotFound(tonic::Status),
+
+    /// Database has no mutable buffer to load the chunk from
+    #[error("Database has no mutable buffer")]
+    NotWriteable,
+
+    /// Response contained no payload
+    #[error("Server returned an empty response")]
+    EmptyResponse,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::drop_chunk
+#[derive(Debug, Error)]
+pub enum DropChunkError {
+    /// Database or chunk not found
+    #[error("Not found: {}", .0.message())]
+    NotFound(tonic::Status),
+
+    /// Server returned an invalid argument error
+    #[error("Invalid argument: {}", .0.message())]
+    InvalidArgument(tonic::Status),
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::snapshot_partition
+#[derive(Debug, Error)]
+pub enum SnapshotPartitionError {
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Writer ID is not set, or the database has no mutable buffer
+    #[error("Failed precondition: {}", .0.message())]
+    FailedPrecondition(tonic::Status),
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::compact_chunks
+#[derive(Debug, Error)]
+pub enum CompactChunksError {
+    /// Database or chunk not found
+    #[error("Not found: {}", .0.message())]
+    NotFound(tonic::Status),
+
+    /// Fewer than two chunks were given
+    #[error("Invalid argument: {}", .0.message())]
+    InvalidArgument(tonic::Status),
+
+    /// A chunk is still in the mutable buffer
+    #[error("Failed precondition: {}", .0.message())]
+    FailedPrecondition(tonic::Status),
+
+    /// The compaction was cancelled
+    #[error("Compaction cancelled")]
+    Cancelled,
+
+    /// Response contained no payload
+    #[error("Server returned an empty response")]
+    EmptyResponse,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
 /// An IOx Management API client.
 ///
 /// ```no_run
@@ -198,4 +391,280 @@ impl Client {
             .ok_or(GetDatabaseError::EmptyResponse)?;
         Ok(rules)
     }
+
+    /// Replace the rules of the database named in `rules`. Its partition
+    /// template, replication and buffer settings change without a restart,
+    /// but a mutable buffer or WAL buffer can't be added or removed.
+    pub async fn update_database(
+        &mut self,
+        rules: DatabaseRules,
+    ) -> Result<(), UpdateDatabaseError> {
+        self.inner
+            .update_database(UpdateDatabaseRequest { rules: Some(rules) })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => UpdateDatabaseError::DatabaseNotFound,
+                tonic::Code::FailedPrecondition => UpdateDatabaseError::FailedPrecondition(status),
+                tonic::Code::InvalidArgument => UpdateDatabaseError::InvalidArgument(status),
+                _ => UpdateDatabaseError::ServerError(status),
+            })?;
+
+        Ok(())
+    }
+
+    /// Delete a database. Its snapshots and WAL segments are only removed
+    /// from object storage if `delete_data` is set.
+    pub async fn delete_database(
+        &mut self,
+        name: impl Into<String>,
+        delete_data: bool,
+    ) -> Result<(), DeleteDatabaseError> {
+        self.inner
+            .delete_database(DeleteDatabaseRequest {
+                name: name.into(),
+                delete_data,
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => DeleteDatabaseError::DatabaseNotFound,
+                tonic::Code::FailedPrecondition => DeleteDatabaseError::NoWriterId,
+                tonic::Code::InvalidArgument => DeleteDatabaseError::InvalidArgument(status),
+                _ => DeleteDatabaseError::ServerError(status),
+            })?;
+
+        Ok(())
+    }
+
+    /// Creates or replaces a host group, which databases can then use as a
+    /// replication or subscription target.
+    pub async fn create_host_group(
+        &mut self,
+        host_group: HostGroup,
+    ) -> Result<(), CreateHostGroupError> {
+        self.inner
+            .create_host_group(CreateHostGroupRequest {
+                host_group: Some(host_group),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::FailedPrecondition => CreateHostGroupError::NoWriterId,
+                tonic::Code::InvalidArgument => CreateHostGroupError::InvalidArgument(status),
+                _ => CreateHostGroupError::ServerError(status),
+            })?;
+
+        Ok(())
+    }
+
+    /// Get the depth and lag of the database's background replication queue
+    pub async fn get_replication_status(
+        &mut self,
+        db_name: impl Into<String>,
+    ) -> Result<GetReplicationStatusResponse, GetReplicationStatusError> {
+        let response = self
+            .inner
+            .get_replication_status(GetReplicationStatusRequest {
+                db_name: db_name.into(),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => GetReplicationStatusError::DatabaseNotFound,
+                _ => GetReplicationStatusError::ServerError(status),
+            })?;
+
+        Ok(response.into_inner())
+    }
+
+    /// List the partitions of a database, ordered by key
+    pub async fn list_partitions(
+        &mut self,
+        db_name: impl Into<String>,
+    ) -> Result<Vec<Partition>, ListPartitionsError> {
+        let response = self
+            .inner
+            .list_partitions(ListPartitionsRequest {
+                db_name: db_name.into(),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => ListPartitionsError::DatabaseNotFound,
+                _ => ListPartitionsError::ServerError(status),
+            })?;
+
+        Ok(response.into_inner().partitions)
+    }
+
+    /// List the chunks of all partitions of a database, with where they are
+    /// stored, their size, row count and time range
+    pub async fn list_chunks(
+        &mut self,
+        db_name: impl Into<String>,
+    ) -> Result<Vec<Chunk>, ListChunksError> {
+        let response = self
+            .inner
+            .list_chunks(ListChunksRequest {
+                db_name: db_name.into(),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => ListChunksError::DatabaseNotFound,
+                _ => ListChunksError::ServerError(status),
+            })?;
+
+        Ok(response.into_inner().chunks)
+    }
+
+    /// List the chunks of a single partition of a database
+    pub async fn list_partition_chunks(
+        &mut self,
+        db_name: impl Into<String>,
+        partition_key: impl Into<String>,
+    ) -> Result<Vec<Chunk>, ListChunksError> {
+        let response = self
+            .inner
+            .list_partition_chunks(ListPartitionChunksRequest {
+                db_name: db_name.into(),
+                partition_key: partition_key.into(),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => ListChunksError::DatabaseNotFound,
+                _ => ListChunksError::ServerError(status),
+            })?;
+
+        Ok(response.into_inner().chunks)
+    }
+
+    /// Close the open mutable buffer chunk of a partition and start a new
+    /// one, returning the closed chunk
+    pub async fn rollover_partition(
+        &mut self,
+        db_name: impl Into<String>,
+        partition_key: impl Into<String>,
+    ) -> Result<Chunk, RolloverPartitionError> {
+        let response = self
+            .inner
+            .rollover_partition(RolloverPartitionRequest {
+                db_name: db_name.into(),
+                partition_key: partition_key.into(),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => RolloverPartitionError::DatabaseNotFound,
+                tonic::Code::FailedPrecondition => RolloverPartitionError::NotWriteable,
+                _ => RolloverPartitionError::ServerError(status),
+            })?;
+
+        response
+            .into_inner()
+            .chunk
+            .ok_or(RolloverPartitionError::EmptyResponse)
+    }
+
+    /// Load a closed mutable buffer chunk into the read buffer, returning
+    /// the chunk in the read buffer
+    pub async fn load_chunk_to_read_buffer(
+        &mut self,
+        db_name: impl Into<String>,
+        partition_key: impl Into<String>,
+        chunk_id: u32,
+    ) -> Result<Chunk, LoadChunkError> {
+        let response = self
+            .inner
+            .load_chunk_to_read_buffer(LoadChunkToReadBufferRequest {
+                db_name: db_name.into(),
+                partition_key: partition_key.into(),
+                chunk_id,
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => LoadChunkError::NotFound(status),
+                tonic::Code::FailedPrecondition => LoadChunkError::NotWriteable,
+                _ => LoadChunkError::ServerError(status),
+            })?;
+
+        response
+            .into_inner()
+            .chunk
+            .ok_or(LoadChunkError::EmptyResponse)
+    }
+
+    /// Drop a chunk from the given storage
+    pub async fn drop_chunk(
+        &mut self,
+        db_name: impl Into<String>,
+        partition_key: impl Into<String>,
+        chunk_id: u32,
+        storage: ChunkStorage,
+    ) -> Result<(), DropChunkError> {
+        self.inner
+            .drop_chunk(DropChunkRequest {
+                db_name: db_name.into(),
+                partition_key: partition_key.into(),
+                chunk_id,
+                storage: storage as _,
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => DropChunkError::NotFound(status),
+                tonic::Code::InvalidArgument => DropChunkError::InvalidArgument(status),
+                _ => DropChunkError::ServerError(status),
+            })?;
+
+        Ok(())
+    }
+
+    /// Close the open mutable buffer chunk of a partition and write it to
+    /// object storage. The snapshot runs in the background.
+    pub async fn snapshot_partition(
+        &mut self,
+        db_name: impl Into<String>,
+        partition_key: impl Into<String>,
+    ) -> Result<SnapshotPartitionResponse, SnapshotPartitionError> {
+        let response = self
+            .inner
+            .snapshot_partition(SnapshotPartitionRequest {
+                db_name: db_name.into(),
+                partition_key: partition_key.into(),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => SnapshotPartitionError::DatabaseNotFound,
+                tonic::Code::FailedPrecondition => {
+                    SnapshotPartitionError::FailedPrecondition(status)
+                }
+                _ => SnapshotPartitionError::ServerError(status),
+            })?;
+
+        Ok(response.into_inner())
+    }
+
+    /// Merge read buffer or persisted chunks of a partition into a single
+    /// read buffer chunk, returning the compacted chunk
+    pub async fn compact_chunks(
+        &mut self,
+        db_name: impl Into<String>,
+        partition_key: impl Into<String>,
+        chunk_ids: Vec<u32>,
+    ) -> Result<Chunk, CompactChunksError> {
+        let response = self
+            .inner
+            .compact_chunks(CompactChunksRequest {
+                db_name: db_name.into(),
+                partition_key: partition_key.into(),
+                chunk_ids,
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => CompactChunksError::NotFound(status),
+                tonic::Code::InvalidArgument => CompactChunksError::InvalidArgument(status),
+                tonic::Code::FailedPrecondition => CompactChunksError::FailedPrecondition(status),
+                tonic::Code::Cancelled => CompactChunksError::Cancelled,
+                _ => CompactChunksError::ServerError(status),
+            })?;
+
+        response
+            .into_inner()
+            .chunk
+            .ok_or(CompactChunksError::EmptyResponse)
+    }
 }
@@ -0,0 +1,132 @@
+use thiserror::Error;
+
+use self::generated_types::{operations_service_client::OperationsServiceClient, *};
+
+use crate::connection::Connection;
+
+/// Re-export generated_types
+pub mod generated_types {
+    pub use generated_types::influxdata::iox::management::v1::*;
+}
+
+/// Errors returned by Client::list_operations
+#[derive(Debug, Error)]
+pub enum ListOperationsError {
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::get_operation
+#[derive(Debug, Error)]
+pub enum GetOperationError {
+    /// The operation isn't running, because it has finished or it never
+    /// existed
+    #[error("Operation not found")]
+    OperationNotFound,
+
+    /// Response contained no payload
+    #[error("Server returned an empty response")]
+    EmptyResponse,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// Errors returned by Client::cancel_operation
+#[derive(Debug, Error)]
+pub enum CancelOperationError {
+    /// The operation isn't running, because it has finished or it never
+    /// existed
+    #[error("Operation not found")]
+    OperationNotFound,
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// An IOx Operations API client, to follow and cancel the long-running
+/// operations of a server, such as snapshots.
+///
+/// ```no_run
+/// #[tokio::main]
+/// # async fn main() {
+/// use influxdb_iox_client::{
+///     operations::Client,
+///     connection::Builder,
+/// };
+///
+/// let mut connection = Builder::default()
+///     .build("http://127.0.0.1:8082")
+///     .await
+///     .unwrap();
+///
+/// let mut client = Client::new(connection);
+///
+/// // List the running operations
+/// for operation in client
+///     .list_operations()
+///     .await
+///     .expect("failed to list operations")
+/// {
+///     println!("{}: {}", operation.id, operation.description);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: OperationsServiceClient<Connection>,
+}
+
+impl Client {
+    /// Creates a new client with the provided connection
+    pub fn new(channel: tonic::transport::Channel) -> Self {
+        Self {
+            inner: OperationsServiceClient::new(channel),
+        }
+    }
+
+    /// List the running operations, ordered by id
+    pub async fn list_operations(&mut self) -> Result<Vec<Operation>, ListOperationsError> {
+        let response = self
+            .inner
+            .list_operations(ListOperationsRequest {})
+            .await
+            .map_err(ListOperationsError::ServerError)?;
+
+        Ok(response.into_inner().operations)
+    }
+
+    /// Get a running operation
+    pub async fn get_operation(&mut self, id: u64) -> Result<Operation, GetOperationError> {
+        let response = self
+            .inner
+            .get_operation(GetOperationRequest { id })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => GetOperationError::OperationNotFound,
+                _ => GetOperationError::ServerError(status),
+            })?;
+
+        response
+            .into_inner()
+            .operation
+            .ok_or(GetOperationError::EmptyResponse)
+    }
+
+    /// Cancel a running operation. An operation that is executing stops the
+    /// next time it yields.
+    pub async fn cancel_operation(&mut self, id: u64) -> Result<(), CancelOperationError> {
+        self.inner
+            .cancel_operation(CancelOperationRequest { id })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => CancelOperationError::OperationNotFound,
+                _ => CancelOperationError::ServerError(status),
+            })?;
+
+        Ok(())
+    }
+}
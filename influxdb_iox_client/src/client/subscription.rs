@@ -0,0 +1,134 @@
+use thiserror::Error;
+use tonic::Streaming;
+
+use self::generated_types::{subscription_service_client::SubscriptionServiceClient, *};
+
+use crate::connection::Connection;
+
+/// Re-export generated_types
+pub mod generated_types {
+    pub use generated_types::influxdata::iox::management::v1::Matcher;
+    pub use generated_types::influxdata::iox::subscription::v1::*;
+}
+
+/// Errors returned by Client::subscribe and Subscription::next
+#[derive(Debug, Error)]
+pub enum SubscribeError {
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// The database doesn't have a WAL buffer to serve writes from
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    NoWalBuffer(tonic::Status),
+
+    /// Server returned an invalid argument error
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    InvalidArgument(tonic::Status),
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+impl From<tonic::Status> for SubscribeError {
+    fn from(status: tonic::Status) -> Self {
+        match status.code() {
+            tonic::Code::NotFound => Self::DatabaseNotFound,
+            tonic::Code::FailedPrecondition => Self::NoWalBuffer(status),
+            tonic::Code::InvalidArgument => Self::InvalidArgument(status),
+            _ => Self::ServerError(status),
+        }
+    }
+}
+
+/// An IOx Subscription API client.
+///
+/// This is used to pull the replicated writes of a database from the WAL
+/// buffer of an IOx server, for example by query servers catching up after a
+/// restart.
+///
+/// ```no_run
+/// #[tokio::main]
+/// # async fn main() {
+/// use influxdb_iox_client::{
+///     subscription::{Client, generated_types::WriterSequence},
+///     connection::Builder,
+/// };
+///
+/// let mut connection = Builder::default()
+///     .build("http://127.0.0.1:8082")
+///     .await
+///     .unwrap();
+///
+/// let mut client = Client::new(connection);
+///
+/// // Stream the writes after the last one seen from writer 1
+/// let since = vec![WriterSequence { writer_id: 1, sequence: 42 }];
+/// let mut subscription = client
+///     .subscribe("bananas", since, None)
+///     .await
+///     .expect("failed to subscribe");
+///
+/// while let Some(payload) = subscription.next().await.expect("failed to read") {
+///     // payload holds the bytes of a ReplicatedWrite flatbuffer
+///     println!("received {} bytes", payload.len());
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: SubscriptionServiceClient<Connection>,
+}
+
+impl Client {
+    /// Creates a new client with the provided connection
+    pub fn new(channel: tonic::transport::Channel) -> Self {
+        Self {
+            inner: SubscriptionServiceClient::new(channel),
+        }
+    }
+
+    /// Subscribes to the writes of the named database that come after the
+    /// passed write of each writer. If a matcher is passed only the matching
+    /// tables and rows are sent.
+    pub async fn subscribe(
+        &mut self,
+        db_name: impl Into<String>,
+        since: Vec<WriterSequence>,
+        matcher: Option<Matcher>,
+    ) -> Result<Subscription, SubscribeError> {
+        let response = self
+            .inner
+            .subscribe(SubscribeRequest {
+                db_name: db_name.into(),
+                since,
+                matcher,
+            })
+            .await?;
+
+        Ok(Subscription {
+            response: response.into_inner(),
+        })
+    }
+}
+
+/// The stream of replicated writes of a subscription, created by calling the
+/// `subscribe` method on a [`Client`]. The stream stays open to receive new
+/// writes as they arrive on the server.
+#[derive(Debug)]
+pub struct Subscription {
+    response: Streaming<SubscribeResponse>,
+}
+
+impl Subscription {
+    /// Returns the bytes of the next `ReplicatedWrite` flatbuffer, or `None`
+    /// if the server closed the stream.
+    pub async fn next(&mut self) -> Result<Option<Vec<u8>>, SubscribeError> {
+        Ok(self
+            .response
+            .message()
+            .await?
+            .map(|response| response.payload))
+    }
+}
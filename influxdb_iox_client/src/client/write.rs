@@ -0,0 +1,96 @@
+use thiserror::Error;
+
+use self::generated_types::{write_service_client::WriteServiceClient, *};
+
+use crate::connection::Connection;
+
+/// Re-export generated_types
+pub mod generated_types {
+    pub use generated_types::influxdata::iox::write::v1::*;
+}
+
+/// Errors returned by Client::write_replicated
+#[derive(Debug, Error)]
+pub enum WriteReplicatedError {
+    /// Writer ID is not set
+    #[error("Writer ID not set")]
+    NoWriterId,
+
+    /// Database not found
+    #[error("Database not found")]
+    DatabaseNotFound,
+
+    /// Server returned an invalid argument error
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    InvalidArgument(tonic::Status),
+
+    /// Client received an unexpected error from the server
+    #[error("Unexpected server error: {}: {}", .0.code(), .0.message())]
+    ServerError(tonic::Status),
+}
+
+/// An IOx Write API client.
+///
+/// This is used by IOx servers to send replicated writes to each other.
+///
+/// ```no_run
+/// #[tokio::main]
+/// # async fn main() {
+/// use influxdb_iox_client::{
+///     write::Client,
+///     connection::Builder,
+/// };
+///
+/// let mut connection = Builder::default()
+///     .build("http://127.0.0.1:8082")
+///     .await
+///     .unwrap();
+///
+/// let mut client = Client::new(connection);
+///
+/// // the bytes of a ReplicatedWrite flatbuffer
+/// let payload: Vec<u8> = vec![];
+///
+/// // Send a replicated write to the server
+/// client
+///     .write_replicated("bananas", payload)
+///     .await
+///     .expect("failed to write");
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: WriteServiceClient<Connection>,
+}
+
+impl Client {
+    /// Creates a new client with the provided connection
+    pub fn new(channel: tonic::transport::Channel) -> Self {
+        Self {
+            inner: WriteServiceClient::new(channel),
+        }
+    }
+
+    /// Sends the bytes of a `ReplicatedWrite` flatbuffer to the named
+    /// database on the server.
+    pub async fn write_replicated(
+        &mut self,
+        db_name: impl Into<String>,
+        payload: impl Into<Vec<u8>>,
+    ) -> Result<(), WriteReplicatedError> {
+        self.inner
+            .write_replicated(WriteReplicatedRequest {
+                db_name: db_name.into(),
+                payload: payload.into(),
+            })
+            .await
+            .map_err(|status| match status.code() {
+                tonic::Code::NotFound => WriteReplicatedError::DatabaseNotFound,
+                tonic::Code::FailedPrecondition => WriteReplicatedError::NoWriterId,
+                tonic::Code::InvalidArgument => WriteReplicatedError::InvalidArgument(status),
+                _ => WriteReplicatedError::ServerError(status),
+            })?;
+
+        Ok(())
+    }
+}
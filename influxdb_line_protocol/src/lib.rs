@@ -260,9 +260,20 @@ impl<'a> Display for Series<'a> {
 }
 
 impl<'a> Series<'a> {
+    /// Creates a series from its measurement and tag set, for data that
+    /// wasn't parsed from line protocol text.
+    pub fn new(measurement: EscapedStr<'a>, tag_set: Option<TagSet<'a>>) -> Self {
+        Self {
+            raw_input: "",
+            measurement,
+            tag_set,
+        }
+    }
+
     pub fn generate_base(self) -> Result<Cow<'a, str>> {
         match (!self.is_escaped(), self.is_sorted_and_unique()) {
-            (true, true) => Ok(self.raw_input.into()),
+            // series created with `new` have no raw input to reuse
+            (true, true) if !self.raw_input.is_empty() => Ok(self.raw_input.into()),
             (_, true) => self.generate_base_with_escaping().map(Into::into),
             (_, _) => self
                 .generate_base_with_escaping_sorting_deduplicating()
@@ -1471,6 +1482,20 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn new_series_generate_base() -> Result {
+        let series = Series::new(
+            EscapedStr::from("foo"),
+            Some(smallvec![
+                (EscapedStr::from("tag1"), EscapedStr::from("1")),
+                (EscapedStr::from("tag2"), EscapedStr::from("2")),
+            ]),
+        );
+        assert_eq!(series.generate_base()?, "foo,tag1=1,tag2=2");
+
+        Ok(())
+    }
+
     #[test]
     fn parse_tag_set_duplicate_tags() -> Result {
         let input = "foo,tag=1,tag=2";
@@ -395,6 +395,133 @@ impl Chunk {
         }
     }
 
+    /// Returns the distinct tag sets of the rows of the specified table
+    /// that satisfy the predicate. A tag set holds the names and values of
+    /// the tags that are non-null in a row, ordered by name. If the
+    /// predicate restricts the fields, only rows with a value for one of
+    /// the selected fields are considered.
+    ///
+    /// If the predicate cannot be evaluated entirely with metadata,
+    /// return `Ok(None)`.
+    pub fn tag_sets(
+        &self,
+        table_name: &str,
+        chunk_predicate: &ChunkPredicate,
+    ) -> Result<Option<BTreeSet<Vec<(String, String)>>>> {
+        // No support for general purpose expressions
+        if !chunk_predicate.chunk_exprs.is_empty() {
+            return Ok(None);
+        }
+        let chunk_id = self.id();
+
+        let table_name_id = self.table_name_id(table_name)?;
+
+        // Is this table even in the chunk?
+        let table = self
+            .tables
+            .get(&table_name_id)
+            .context(NamedTableNotFoundInChunk {
+                table_name,
+                chunk_id,
+            })?;
+
+        // See if we can rule out the table entire on metadata
+        let could_match = table
+            .could_match_predicate(chunk_predicate)
+            .context(NamedTablePredicateCheck { table_name })?;
+
+        if !could_match {
+            return Ok(Some(BTreeSet::new()));
+        }
+
+        let mut tag_columns = table
+            .columns
+            .iter()
+            .filter_map(|(&column_id, column)| match column {
+                Column::Tag(values, _) => Some((column_id, values)),
+                _ => None,
+            })
+            .map(|(column_id, values)| {
+                let column_name =
+                    self.dictionary
+                        .lookup_id(column_id)
+                        .context(ColumnIdNotFoundInDictionary {
+                            column_id,
+                            chunk: chunk_id,
+                        })?;
+                Ok((column_name, values))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        tag_columns.sort_by_key(|&(column_name, _)| column_name);
+
+        let time_column = match chunk_predicate.range {
+            Some(_) => Some(
+                table
+                    .column_i64(chunk_predicate.time_column_id)
+                    .context(NamedTableError { table_name })?,
+            ),
+            None => None,
+        };
+
+        let field_columns = chunk_predicate.field_name_predicate.as_ref().map(|_| {
+            table
+                .columns
+                .iter()
+                .filter(|&(&column_id, column)| {
+                    !column.is_tag()
+                        && !chunk_predicate.is_time_column(column_id)
+                        && chunk_predicate.should_include_field(column_id)
+                })
+                .map(|(_, column)| column)
+                .collect::<Vec<_>>()
+        });
+
+        // Collect the tag value ids of matching rows into a BTreeSet to
+        // deduplicate on ids *before* looking up Strings
+        let mut tag_set_ids = BTreeSet::new();
+        for row in 0..table.row_count() {
+            if let (Some(range), Some(time_column)) = (chunk_predicate.range, time_column) {
+                if !range.contains_opt(time_column[row]) {
+                    continue;
+                }
+            }
+
+            if let Some(field_columns) = &field_columns {
+                if !field_columns.iter().any(|column| column.is_non_null(row)) {
+                    continue;
+                }
+            }
+
+            let ids = tag_columns
+                .iter()
+                .map(|(_, values)| values[row])
+                .collect::<Vec<_>>();
+            tag_set_ids.insert(ids);
+        }
+
+        // convert all the (deduplicated) ids to Strings
+        let tag_sets = tag_set_ids
+            .into_iter()
+            .map(|ids| {
+                tag_columns
+                    .iter()
+                    .zip(ids)
+                    .filter_map(|(&(column_name, _), value_id)| {
+                        value_id.map(|value_id| (column_name, value_id))
+                    })
+                    .map(|(column_name, value_id)| {
+                        let value = self.dictionary.lookup_id(value_id).context(
+                            InternalColumnValueIdNotFoundInDictionary { value_id, chunk_id },
+                        )?;
+                        Ok((column_name.to_string(), value.to_string()))
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<BTreeSet<_>>>()?;
+
+        Ok(Some(tag_sets))
+    }
+
     /// Return a builder suitable to create predicates for this Chunk
     pub fn predicate_builder(&self) -> Result<ChunkPredicateBuilder<'_>, crate::pred::Error> {
         ChunkPredicateBuilder::new(&self.dictionary)
@@ -494,4 +621,13 @@ impl Chunk {
     pub fn has_table(&self, table_name: &str) -> bool {
         matches!(self.table(table_name), Ok(Some(_)))
     }
+
+    /// Return the number of rows of the specified table, or None if the
+    /// chunk doesn't have the table
+    pub fn table_rows(&self, table_name: &str) -> Option<usize> {
+        match self.table(table_name) {
+            Ok(Some(table)) => Some(table.row_count()),
+            _ => None,
+        }
+    }
 }
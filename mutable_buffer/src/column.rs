@@ -254,6 +254,17 @@ impl Column {
         matches!(self, Self::Tag(..))
     }
 
+    /// Returns true if the column has a value in the specified row
+    pub fn is_non_null(&self, row: usize) -> bool {
+        match self {
+            Self::F64(v, _) => v[row].is_some(),
+            Self::I64(v, _) => v[row].is_some(),
+            Self::String(v, _) => v[row].is_some(),
+            Self::Bool(v, _) => v[row].is_some(),
+            Self::Tag(v, _) => v[row].is_some(),
+        }
+    }
+
     /// Returns true if there exists at least one row idx where this
     /// self[i] is within the range [min_value, max_value). Inclusive
     /// of `start`, exclusive of `end` and where col[i] is non null
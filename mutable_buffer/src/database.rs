@@ -68,6 +68,10 @@ pub struct MutableBufferDb {
 
     /// Maps partition keys to partitions which hold the actual data
     partitions: RwLock<HashMap<String, Arc<RwLock<Partition>>>>,
+
+    /// The id the first chunk of a partition gets when it is created, for
+    /// partitions whose earlier chunks were dropped or persisted
+    first_chunk_ids: RwLock<HashMap<String, u32>>,
 }
 
 impl MutableBufferDb {
@@ -120,12 +124,30 @@ impl MutableBufferDb {
             .context(DroppingChunk { partition_key })
     }
 
-    /// drop the specified partition
+    /// drop the specified partition. If it is written to again, the ids of
+    /// its new chunks continue after the ones of the dropped partition.
     pub fn drop_partition(&self, partition_key: &str) -> Option<Arc<RwLock<Partition>>> {
-        self.partitions
+        let partition = self
+            .partitions
             .write()
             .expect("mutex poisoned")
-            .remove(partition_key)
+            .remove(partition_key)?;
+
+        let next_chunk_id = partition.read().expect("mutex poisoned").next_chunk_id();
+        self.reserve_chunk_ids(partition_key, next_chunk_id);
+
+        Some(partition)
+    }
+
+    /// Makes the chunks of the partition get ids of at least
+    /// `next_chunk_id` when it is created, because chunks with lower ids
+    /// already exist elsewhere, such as in object storage
+    pub fn reserve_chunk_ids(&self, partition_key: &str, next_chunk_id: u32) {
+        let mut first_chunk_ids = self.first_chunk_ids.write().expect("mutex poisoned");
+        let first_chunk_id = first_chunk_ids
+            .entry(partition_key.to_string())
+            .or_insert(next_chunk_id);
+        *first_chunk_id = (*first_chunk_id).max(next_chunk_id);
     }
 
     /// The approximate size in memory of all data in the mutable buffer, in
@@ -147,6 +169,12 @@ impl MutableBufferDb {
         size
     }
 
+    /// Returns all the partitions in this database
+    pub fn partitions(&self) -> Vec<Arc<RwLock<Partition>>> {
+        let partitions = self.partitions.read().expect("mutex poisoned");
+        partitions.values().map(Arc::clone).collect()
+    }
+
     /// Returns the partitions in the requested sort order
     pub fn partitions_sorted_by(
         &self,
@@ -229,7 +257,17 @@ impl MutableBufferDb {
         if let Some(partition) = partitions.get(partition_key) {
             Arc::clone(&partition)
         } else {
-            let partition = Arc::new(RwLock::new(Partition::new(partition_key)));
+            let first_chunk_id = self
+                .first_chunk_ids
+                .read()
+                .expect("mutex poisoned")
+                .get(partition_key)
+                .copied()
+                .unwrap_or(0);
+            let partition = Arc::new(RwLock::new(Partition::with_first_chunk_id(
+                partition_key,
+                first_chunk_id,
+            )));
             partitions.insert(partition_key.to_string(), Arc::clone(&partition));
             partition
         }
@@ -241,7 +279,9 @@ mod tests {
     use super::*;
     use chrono::{DateTime, Utc};
     use data_types::{
-        data::lines_to_replicated_write, database_rules::Partitioner, selection::Selection,
+        data::lines_to_replicated_write,
+        database_rules::{PartitionRow, Partitioner},
+        selection::Selection,
     };
 
     use arrow_deps::arrow::array::{Array, StringArray};
@@ -351,6 +391,24 @@ mod tests {
         assert_eq!(partitions[1].read().unwrap().key(), "p2");
     }
 
+    #[tokio::test]
+    async fn chunk_ids_continue_after_dropped_and_reserved_ids() {
+        let db = MutableBufferDb::new("foo");
+        write_lines_to_partition(&db, &["cpu val=1 2"], "p1").await;
+        db.rollover_partition("p1").unwrap();
+        db.drop_partition("p1").unwrap();
+
+        write_lines_to_partition(&db, &["cpu val=1 3"], "p1").await;
+        let ids: Vec<_> = db.chunks("p1").iter().map(|c| c.id()).collect();
+        assert_eq!(ids, vec![2]);
+
+        db.reserve_chunk_ids("p2", 5);
+        db.reserve_chunk_ids("p2", 3);
+        write_lines_to_partition(&db, &["cpu val=1 4"], "p2").await;
+        let ids: Vec<_> = db.chunks("p2").iter().map(|c| c.id()).collect();
+        assert_eq!(ids, vec![5]);
+    }
+
     /// write lines into this database
     async fn write_lp(database: &MutableBufferDb, lp: &[ParsedLine<'_>]) {
         write_lp_to_partition(database, lp, "test_partition_key").await
@@ -395,7 +453,7 @@ mod tests {
     impl Partitioner for TestPartitioner {
         fn partition_key(
             &self,
-            _line: &ParsedLine<'_>,
+            _row: &dyn PartitionRow,
             _default_time: &DateTime<Utc>,
         ) -> data_types::database_rules::Result<String> {
             Ok(self.key.clone())
@@ -75,8 +75,8 @@ pub struct Partition {
     /// creation order
     closed_chunks: BTreeMap<u32, Arc<Chunk>>,
 
-    /// Responsible for assigning ids to chunks. Eventually, this might
-    /// need to start at a number other than 0.
+    /// Responsible for assigning ids to chunks. It starts after the ids of
+    /// chunks an earlier incarnation of the partition used, if any.
     id_generator: u32,
 
     /// the instant time this partition was created
@@ -89,8 +89,14 @@ pub struct Partition {
 
 impl Partition {
     pub fn new(key: impl Into<String>) -> Self {
-        // TODO: for existing partitions, does this need to pick up at preexisting ID?
-        let mut id_generator = 0;
+        Self::with_first_chunk_id(key, 0)
+    }
+
+    /// Creates a partition whose chunk ids start at `first_chunk_id`, so that
+    /// they don't collide with the ids of chunks persisted before the
+    /// partition was dropped or the server restarted
+    pub fn with_first_chunk_id(key: impl Into<String>, first_chunk_id: u32) -> Self {
+        let mut id_generator = first_chunk_id;
 
         let key: String = key.into();
         let open_chunk = Chunk::new(id_generator);
@@ -129,14 +135,28 @@ impl Partition {
     /// partition). A Snapshot of the currently active chunk is
     /// returned. The snapshot will not be affected by future inserts
     pub fn chunks(&self) -> Vec<Arc<Chunk>> {
-        let mut chunks: Vec<_> = self
-            .closed_chunks
+        let mut chunks = self.closed_chunks();
+        chunks.push(self.open_chunk_snapshot());
+        chunks
+    }
+
+    /// Return the list of closed chunks, in order of id, in this partition
+    pub fn closed_chunks(&self) -> Vec<Arc<Chunk>> {
+        self.closed_chunks
             .iter()
             .map(|(_, chunk)| Arc::clone(&chunk))
-            .collect::<Vec<_>>();
+            .collect()
+    }
 
-        chunks.push(self.open_chunk_snapshot());
-        chunks
+    /// Returns true if nothing has been written to the open chunk since it
+    /// was created
+    pub fn open_chunk_is_empty(&self) -> bool {
+        self.open_chunk.is_empty()
+    }
+
+    /// Returns true if none of the partition's chunks contain data
+    pub fn is_empty(&self) -> bool {
+        self.iter().all(Chunk::is_empty)
     }
 
     /// return the chunk by id. If the requested chunk is still open,
@@ -208,6 +228,11 @@ impl Partition {
         })
     }
 
+    /// Returns the id the next chunk of the partition will get
+    pub fn next_chunk_id(&self) -> u32 {
+        self.id_generator
+    }
+
     /// Return the partition key shared by all data stored in this
     /// partition
     pub fn key(&self) -> &str {
@@ -683,6 +708,27 @@ mod tests {
         assert_eq!(chunk_ids(&partition), vec![2]);
     }
 
+    #[tokio::test]
+    async fn test_empty_and_closed_chunks() {
+        let mut partition = Partition::new("a_key");
+        assert!(partition.is_empty());
+        assert!(partition.open_chunk_is_empty());
+
+        load_data(&mut partition, &["h2o,state=MA,city=Boston temp=70.4 100"]).await;
+        assert!(!partition.is_empty());
+        assert!(!partition.open_chunk_is_empty());
+        assert!(partition.closed_chunks().is_empty());
+
+        partition.rollover_chunk();
+        assert!(!partition.is_empty());
+        assert!(partition.open_chunk_is_empty());
+        let closed_ids: Vec<_> = partition.closed_chunks().iter().map(|c| c.id()).collect();
+        assert_eq!(closed_ids, vec![0]);
+
+        partition.drop_chunk(0).unwrap();
+        assert!(partition.is_empty());
+    }
+
     #[tokio::test]
     async fn test_get_chunks() {
         // test Create Read Update and Delete for chunks
@@ -924,7 +970,7 @@ mod tests {
         let lines: Vec<_> = parse_lines(&lp_string).map(|l| l.unwrap()).collect();
         let data = split_lines_into_write_entry_partitions(|_| partition.key().into(), &lines);
 
-        let batch = flatbuffers::get_root::<wb::WriteBufferBatch<'_>>(&data);
+        let batch = flatbuffers::root::<wb::WriteBufferBatch<'_>>(&data).unwrap();
 
         let entries = batch.entries().unwrap();
         for entry in entries {
@@ -1,7 +1,6 @@
 //! This module contains the IOx implementation for using S3 as the object
 //! store.
 use crate::{
-    buffer::slurp_stream_tempfile,
     path::{cloud::CloudPath, DELIMITER},
     ListResult, ObjectMeta, ObjectStoreApi,
 };
@@ -15,9 +14,9 @@ use futures::{
 use rusoto_core::ByteStream;
 use rusoto_credential::StaticProvider;
 use rusoto_s3::S3;
-use snafu::{futures::TryStreamExt as _, OptionExt, ResultExt, Snafu};
+use snafu::{ensure, futures::TryStreamExt as _, OptionExt, ResultExt, Snafu};
 use std::convert::TryFrom;
-use std::{fmt, io};
+use std::{fmt, io, ops::Range};
 
 /// A specialized `Result` for object store-related errors
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -96,8 +95,53 @@ pub enum Error {
         bucket: String,
     },
 
-    #[snafu(display("Unable to buffer data into temporary file, Error: {}", source))]
-    UnableToBufferStream { source: std::io::Error },
+    #[snafu(display("Unable to read the data to upload, Error: {}", source))]
+    UnableToReadStream { source: std::io::Error },
+
+    #[snafu(display(
+        "Unable to start multipart upload. Bucket: {}, Location: {}, Error: {}",
+        bucket,
+        location,
+        source,
+    ))]
+    UnableToStartMultipartUpload {
+        source: rusoto_core::RusotoError<rusoto_s3::CreateMultipartUploadError>,
+        bucket: String,
+        location: String,
+    },
+
+    #[snafu(display(
+        "Multipart upload has no upload id. Bucket: {}, Location: {}",
+        bucket,
+        location
+    ))]
+    NoUploadId { bucket: String, location: String },
+
+    #[snafu(display(
+        "Unable to upload part {}. Bucket: {}, Location: {}, Error: {}",
+        part_number,
+        bucket,
+        location,
+        source,
+    ))]
+    UnableToUploadPart {
+        source: rusoto_core::RusotoError<rusoto_s3::UploadPartError>,
+        part_number: i64,
+        bucket: String,
+        location: String,
+    },
+
+    #[snafu(display(
+        "Unable to complete multipart upload. Bucket: {}, Location: {}, Error: {}",
+        bucket,
+        location,
+        source,
+    ))]
+    UnableToCompleteMultipartUpload {
+        source: rusoto_core::RusotoError<rusoto_s3::CompleteMultipartUploadError>,
+        bucket: String,
+        location: String,
+    },
 
     #[snafu(display(
         "Could not parse `{}` as an AWS region. Regions should look like `us-east-2`. {:?}",
@@ -110,6 +154,10 @@ pub enum Error {
     },
 }
 
+/// The size of the parts of multipart uploads. S3 requires every part except
+/// the last one to be at least 5 MiB.
+const MULTIPART_PART_SIZE: usize = 8 * 1024 * 1024;
+
 /// Configuration for connecting to [Amazon S3](https://aws.amazon.com/s3/).
 pub struct AmazonS3 {
     client: rusoto_s3::S3Client,
@@ -138,21 +186,16 @@ impl ObjectStoreApi for AmazonS3 {
     where
         S: Stream<Item = io::Result<Bytes>> + Send + Sync + 'static,
     {
-        let bytes = match length {
-            Some(length) => ByteStream::new_with_size(bytes, length),
-            None => {
-                let bytes = slurp_stream_tempfile(bytes)
-                    .await
-                    .context(UnableToBufferStream)?;
-                let length = bytes.size();
-                ByteStream::new_with_size(bytes, length)
-            }
+        let length = match length {
+            Some(length) => length,
+            // stream data of unknown length without buffering all of it
+            None => return self.put_multipart(location, bytes).await,
         };
 
         let put_request = rusoto_s3::PutObjectRequest {
             bucket: self.bucket_name.clone(),
             key: location.to_raw(),
-            body: Some(bytes),
+            body: Some(ByteStream::new_with_size(bytes, length)),
             ..Default::default()
         };
 
@@ -194,6 +237,52 @@ impl ObjectStoreApi for AmazonS3 {
             .boxed())
     }
 
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        if range.is_empty() {
+            return Ok(Bytes::new());
+        }
+
+        let key = location.to_raw();
+        let get_request = rusoto_s3::GetObjectRequest {
+            bucket: self.bucket_name.clone(),
+            key: key.clone(),
+            // HTTP ranges include their last byte
+            range: Some(format!("bytes={}-{}", range.start, range.end - 1)),
+            ..Default::default()
+        };
+        let data = self
+            .client
+            .get_object(get_request)
+            .await
+            .context(UnableToGetData {
+                bucket: self.bucket_name.to_owned(),
+                location: key.clone(),
+            })?
+            .body
+            .context(NoData {
+                bucket: self.bucket_name.to_owned(),
+                location: key.clone(),
+            })?
+            .map_ok(|b| bytes::BytesMut::from(&b[..]))
+            .try_concat()
+            .await
+            .context(UnableToGetPieceOfData {
+                bucket: self.bucket_name.to_owned(),
+                location: key,
+            })?;
+
+        // a range past the end of the object is cut short
+        ensure!(
+            data.len() == range.len(),
+            DataDoesNotMatchLength {
+                expected: range.len(),
+                actual: data.len(),
+            }
+        );
+
+        Ok(data.freeze())
+    }
+
     async fn delete(&self, location: &Self::Path) -> Result<()> {
         let key = location.to_raw();
         let delete_request = rusoto_s3::DeleteObjectRequest {
@@ -375,6 +464,126 @@ impl AmazonS3 {
 
         Ok(result)
     }
+
+    /// Uploads a stream of unknown length as a multipart upload, holding at
+    /// most one part in memory at a time. The upload is aborted if reading
+    /// the stream or uploading a part fails.
+    async fn put_multipart<S>(&self, location: &CloudPath, bytes: S) -> Result<()>
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + Sync + 'static,
+    {
+        let key = location.to_raw();
+
+        let upload = self
+            .client
+            .create_multipart_upload(rusoto_s3::CreateMultipartUploadRequest {
+                bucket: self.bucket_name.clone(),
+                key: key.clone(),
+                ..Default::default()
+            })
+            .await
+            .context(UnableToStartMultipartUpload {
+                bucket: &self.bucket_name,
+                location: &key,
+            })?;
+        let upload_id = upload.upload_id.context(NoUploadId {
+            bucket: &self.bucket_name,
+            location: &key,
+        })?;
+
+        let parts = match self.upload_parts(&key, &upload_id, bytes).await {
+            Ok(parts) => parts,
+            Err(e) => {
+                // the parts uploaded so far are stored (and billed) until the
+                // upload is aborted
+                let _ = self
+                    .client
+                    .abort_multipart_upload(rusoto_s3::AbortMultipartUploadRequest {
+                        bucket: self.bucket_name.clone(),
+                        key: key.clone(),
+                        upload_id,
+                        ..Default::default()
+                    })
+                    .await;
+                return Err(e);
+            }
+        };
+
+        self.client
+            .complete_multipart_upload(rusoto_s3::CompleteMultipartUploadRequest {
+                bucket: self.bucket_name.clone(),
+                key: key.clone(),
+                upload_id,
+                multipart_upload: Some(rusoto_s3::CompletedMultipartUpload { parts: Some(parts) }),
+                ..Default::default()
+            })
+            .await
+            .context(UnableToCompleteMultipartUpload {
+                bucket: &self.bucket_name,
+                location: &key,
+            })?;
+
+        Ok(())
+    }
+
+    /// Uploads the stream in parts of at least `MULTIPART_PART_SIZE` bytes,
+    /// except for the last one
+    async fn upload_parts<S>(
+        &self,
+        key: &str,
+        upload_id: &str,
+        bytes: S,
+    ) -> Result<Vec<rusoto_s3::CompletedPart>>
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + Sync + 'static,
+    {
+        let mut bytes = Box::pin(bytes);
+        let mut parts = vec![];
+        let mut buffer = Vec::with_capacity(MULTIPART_PART_SIZE);
+
+        loop {
+            let data = bytes.next().await.transpose().context(UnableToReadStream)?;
+            let done = data.is_none();
+            if let Some(data) = data {
+                buffer.extend_from_slice(&data);
+            }
+
+            // an empty stream is uploaded as a single empty part
+            if buffer.len() >= MULTIPART_PART_SIZE
+                || (done && (!buffer.is_empty() || parts.is_empty()))
+            {
+                let part_number = parts.len() as i64 + 1;
+                let body = std::mem::replace(&mut buffer, Vec::with_capacity(MULTIPART_PART_SIZE));
+
+                let output = self
+                    .client
+                    .upload_part(rusoto_s3::UploadPartRequest {
+                        bucket: self.bucket_name.clone(),
+                        key: key.to_string(),
+                        upload_id: upload_id.to_string(),
+                        part_number,
+                        content_length: Some(body.len() as i64),
+                        body: Some(ByteStream::from(body)),
+                        ..Default::default()
+                    })
+                    .await
+                    .context(UnableToUploadPart {
+                        part_number,
+                        bucket: &self.bucket_name,
+                        location: key,
+                    })?;
+
+                parts.push(rusoto_s3::CompletedPart {
+                    e_tag: output.e_tag,
+                    part_number: Some(part_number),
+                });
+            }
+
+            if done {
+                return Ok(parts);
+            }
+        }
+    }
 }
 
 impl Error {
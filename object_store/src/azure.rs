@@ -10,16 +10,16 @@ use azure_storage::{
     clients::{
         AsBlobClient, AsContainerClient, AsStorageClient, ContainerClient, StorageAccountClient,
     },
-    DeleteSnapshotsMethod,
+    BlobBlockType, BlockId, BlockList, DeleteSnapshotsMethod,
 };
 use bytes::Bytes;
 use futures::{
     stream::{self, BoxStream},
-    FutureExt, Stream, StreamExt, TryStreamExt,
+    FutureExt, Stream, StreamExt,
 };
 use snafu::{ensure, ResultExt, Snafu};
 use std::sync::Arc;
-use std::{convert::TryInto, io};
+use std::{convert::TryInto, io, ops::Range};
 
 /// A specialized `Result` for Azure object store-related errors
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -43,6 +43,9 @@ pub enum Error {
         location: String,
     },
 
+    #[snafu(display("Unable to read data to PUT: {}", source))]
+    UnableToReadStream { source: io::Error },
+
     #[snafu(display("Unable to PUT data. Location: {}, Error: {}", location, source,))]
     UnableToPutData {
         source: Box<dyn std::error::Error + Send + Sync>,
@@ -55,6 +58,9 @@ pub enum Error {
     },
 }
 
+/// The size of the blocks that streamed data is uploaded in
+const BLOCK_SIZE: usize = 8 * 1024 * 1024;
+
 /// Configuration for connecting to [Microsoft Azure Blob Storage](https://azure.microsoft.com/en-us/services/storage/blobs/).
 #[derive(Debug)]
 pub struct MicrosoftAzure {
@@ -76,25 +82,56 @@ impl ObjectStoreApi for MicrosoftAzure {
         S: Stream<Item = io::Result<Bytes>> + Send + Sync + 'static,
     {
         let location = location.to_raw();
-        let temporary_non_streaming = bytes
-            .map_ok(|b| bytes::BytesMut::from(&b[..]))
-            .try_concat()
-            .await
-            .expect("Should have been able to collect streaming data");
+        let blob_client = self.container_client.as_blob_client(&location);
+
+        // the stream is staged as uncommitted blocks, which only become the
+        // blob once the block list is committed
+        let mut bytes = Box::pin(bytes);
+        let mut block_list = BlockList::default();
+        let mut buffer = Vec::with_capacity(BLOCK_SIZE);
+        let mut actual = 0;
+
+        loop {
+            let data = bytes.next().await.transpose().context(UnableToReadStream)?;
+            let done = data.is_none();
+            if let Some(data) = data {
+                actual += data.len();
+                buffer.extend_from_slice(&data);
+            }
+
+            if buffer.len() >= BLOCK_SIZE || (done && !buffer.is_empty()) {
+                // block ids of a blob must all have the same length
+                let block_id = BlockId::new(format!("{:08}", block_list.blocks.len()));
+                let body = std::mem::replace(&mut buffer, Vec::with_capacity(BLOCK_SIZE));
+
+                blob_client
+                    .put_block(&block_id, body)
+                    .execute()
+                    .await
+                    .context(UnableToPutData {
+                        location: location.to_owned(),
+                    })?;
+
+                block_list.blocks.push(BlobBlockType::Uncommitted(block_id));
+            }
+
+            if done {
+                break;
+            }
+        }
 
         if let Some(length) = length {
             ensure!(
-                temporary_non_streaming.len() == length,
+                actual == length,
                 DataDoesNotMatchLength {
-                    actual: temporary_non_streaming.len(),
+                    actual,
                     expected: length,
                 }
             );
         }
 
-        self.container_client
-            .as_blob_client(&location)
-            .put_block_blob(temporary_non_streaming)
+        blob_client
+            .put_block_list(&block_list)
             .execute()
             .await
             .context(UnableToPutData {
@@ -122,6 +159,39 @@ impl ObjectStoreApi for MicrosoftAzure {
         .boxed())
     }
 
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        if range.is_empty() {
+            return Ok(Bytes::new());
+        }
+
+        let location = location.to_raw();
+        let data: Bytes = self
+            .container_client
+            .as_blob_client(&location)
+            .get()
+            .range(azure_core::prelude::Range::new(
+                range.start as u64,
+                range.end as u64,
+            ))
+            .execute()
+            .await
+            .map(|blob| blob.data.into())
+            .context(UnableToGetData {
+                location: location.to_owned(),
+            })?;
+
+        // a range past the end of the blob is cut short
+        ensure!(
+            data.len() == range.len(),
+            DataDoesNotMatchLength {
+                expected: range.len(),
+                actual: data.len(),
+            }
+        );
+
+        Ok(data)
+    }
+
     async fn delete(&self, location: &Self::Path) -> Result<()> {
         let location = location.to_raw();
         self.container_client
@@ -6,22 +6,9 @@ use futures::{pin_mut, Stream, StreamExt};
 use std::io::{Cursor, Result, SeekFrom};
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use tokio::fs::File;
 use tokio::io::{copy, AsyncRead, AsyncSeek, AsyncSeekExt, AsyncWrite};
 use tokio_util::io::{ReaderStream, StreamReader};
 
-/// Returns a BufferedStream backed by a temporary file.
-///
-/// The temporary file will be deleted when the result stream
-/// is dropped.
-pub async fn slurp_stream_tempfile<S>(bytes: S) -> Result<BufferedStream<File>>
-where
-    S: Stream<Item = Result<Bytes>> + Send + Sync,
-{
-    let tmp = File::from_std(tempfile::tempfile()?);
-    BufferedStream::new(tmp, bytes).await
-}
-
 /// Returns a BufferedStream backed by a in-memory buffer.
 #[allow(dead_code)]
 pub async fn slurp_stream_memory<S>(bytes: S) -> Result<BufferedStream<Cursor<Vec<u8>>>>
@@ -118,11 +105,6 @@ mod tests {
         check_stream(BufferedStream::new(backing_store, test_data()).await?).await
     }
 
-    #[tokio::test]
-    async fn test_slurp_stream_tempfile() -> Result<()> {
-        check_stream(slurp_stream_tempfile(test_data()).await?).await
-    }
-
     #[tokio::test]
     async fn test_slurp_stream_memory() -> Result<()> {
         check_stream(slurp_stream_memory(test_data()).await?).await
@@ -7,10 +7,16 @@ use futures::{
     stream::{self, BoxStream},
     Stream, StreamExt, TryStreamExt,
 };
-use snafu::{ensure, futures::TryStreamExt as _, OptionExt, ResultExt, Snafu};
-use std::{collections::BTreeSet, convert::TryFrom, io, path::PathBuf};
-use tokio::fs;
-use tokio_util::codec::{BytesCodec, FramedRead};
+use snafu::{futures::TryStreamExt as _, OptionExt, ResultExt, Snafu};
+use std::{collections::BTreeSet, convert::TryFrom, io, ops::Range, path::PathBuf};
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+use tokio_util::{
+    codec::{BytesCodec, FramedRead},
+    io::StreamReader,
+};
 use walkdir::WalkDir;
 
 /// A specialized `Result` for filesystem object store-related errors
@@ -55,9 +61,6 @@ pub enum Error {
 
     #[snafu(display("Unable to read data from file {}: {}", path.display(), source))]
     UnableToReadBytes { source: io::Error, path: PathBuf },
-
-    #[snafu(display("Unable to stream data from the request into memory: {}", source))]
-    UnableToStreamDataIntoMemory { source: std::io::Error },
 }
 
 /// Local filesystem storage suitable for testing or for opting out of using a
@@ -80,22 +83,6 @@ impl ObjectStoreApi for File {
     where
         S: Stream<Item = io::Result<Bytes>> + Send + Sync + 'static,
     {
-        let content = bytes
-            .map_ok(|b| bytes::BytesMut::from(&b[..]))
-            .try_concat()
-            .await
-            .context(UnableToStreamDataIntoMemory)?;
-
-        if let Some(length) = length {
-            ensure!(
-                content.len() == length,
-                DataDoesNotMatchLength {
-                    actual: content.len(),
-                    expected: length,
-                }
-            );
-        }
-
         let path = self.path(location);
 
         let mut file = match fs::File::create(&path).await {
@@ -116,9 +103,25 @@ impl ObjectStoreApi for File {
             Err(err) => return UnableToCreateFile { path, err }.fail(),
         };
 
-        tokio::io::copy(&mut &content[..], &mut file)
-            .await
-            .context(UnableToCopyDataToFile)?;
+        let mut reader = StreamReader::new(Box::pin(bytes));
+        let copied = match tokio::io::copy(&mut reader, &mut file).await {
+            Ok(copied) => copied as usize,
+            Err(source) => {
+                remove_partial_file(file, &path).await;
+                return Err(Error::UnableToCopyDataToFile { source });
+            }
+        };
+
+        if let Some(length) = length {
+            if copied != length {
+                remove_partial_file(file, &path).await;
+                return DataDoesNotMatchLength {
+                    actual: copied,
+                    expected: length,
+                }
+                .fail();
+            }
+        }
 
         Ok(())
     }
@@ -136,6 +139,25 @@ impl ObjectStoreApi for File {
         Ok(s.boxed())
     }
 
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        let path = self.path(location);
+
+        let mut file = fs::File::open(&path)
+            .await
+            .context(UnableToOpenFile { path: &path })?;
+
+        file.seek(io::SeekFrom::Start(range.start as u64))
+            .await
+            .context(UnableToReadBytes { path: &path })?;
+
+        let mut data = vec![0; range.len()];
+        file.read_exact(&mut data)
+            .await
+            .context(UnableToReadBytes { path })?;
+
+        Ok(data.into())
+    }
+
     async fn delete(&self, location: &Self::Path) -> Result<()> {
         let path = self.path(location);
         fs::remove_file(&path)
@@ -259,6 +281,13 @@ impl File {
     }
 }
 
+/// Removes the file of a failed put, so that nothing is stored.
+async fn remove_partial_file(file: fs::File, path: &std::path::Path) {
+    drop(file);
+    // the put has already failed, so a failure to clean up is not reported
+    let _ = fs::remove_file(path).await;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -303,6 +332,31 @@ mod tests {
                 }
             }
         ));
+        assert!(!root.path().join("junk").exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stream_error_stores_nothing() -> Result<()> {
+        let root = TempDir::new()?;
+        let integration = ObjectStore::new_file(File::new(root.path()));
+
+        let bytes = stream::iter(vec![
+            Ok(Bytes::from("hello")),
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "boom")),
+        ]);
+        let mut location = integration.new_path();
+        location.set_file_name("junk");
+        let res = integration.put(&location, bytes, None).await;
+
+        assert!(matches!(
+            res.err().unwrap(),
+            ObjectStoreError::FileObjectStoreError {
+                source: Error::UnableToCopyDataToFile { .. }
+            }
+        ));
+        assert!(!root.path().join("junk").exists());
 
         Ok(())
     }
@@ -6,9 +6,12 @@ use crate::{
 };
 use async_trait::async_trait;
 use bytes::Bytes;
-use futures::{stream::BoxStream, Stream, StreamExt, TryStreamExt};
+use futures::{
+    stream::{self, BoxStream},
+    Stream, StreamExt, TryStreamExt,
+};
 use snafu::{ensure, futures::TryStreamExt as _, ResultExt, Snafu};
-use std::{convert::TryFrom, env, io};
+use std::{convert::TryFrom, env, io, ops::Range};
 
 /// A specialized `Result` for Google Cloud Storage object store-related errors
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -88,30 +91,35 @@ impl ObjectStoreApi for GoogleCloudStorage {
     where
         S: Stream<Item = io::Result<Bytes>> + Send + Sync + 'static,
     {
-        let temporary_non_streaming = bytes
-            .map_ok(|b| bytes::BytesMut::from(&b[..]))
-            .try_concat()
-            .await
-            .expect("Should have been able to collect streaming data")
-            .to_vec();
-
-        if let Some(length) = length {
-            ensure!(
-                temporary_non_streaming.len() == length,
-                DataDoesNotMatchLength {
-                    actual: temporary_non_streaming.len(),
-                    expected: length,
-                }
-            );
-        }
-
         let location = location.to_raw();
         let location_copy = location.clone();
         let bucket_name = self.bucket_name.clone();
 
-        cloud_storage::Object::create(
+        // the stream is sent as the body of the request as it is read. A
+        // stream of the wrong length ends in an error instead, which aborts
+        // the request so that nothing is stored.
+        let checked = stream::unfold(Some((Box::pin(bytes), 0)), move |state| async move {
+            let (mut bytes, actual) = state?;
+            match bytes.next().await {
+                Some(Ok(data)) => {
+                    let actual = actual + data.len();
+                    Some((Ok(data), Some((bytes, actual))))
+                }
+                Some(Err(e)) => Some((Err(e), None)),
+                None => match length {
+                    Some(expected) if expected != actual => {
+                        let e = Error::DataDoesNotMatchLength { expected, actual };
+                        Some((Err(io::Error::new(io::ErrorKind::InvalidData, e)), None))
+                    }
+                    _ => None,
+                },
+            }
+        });
+
+        cloud_storage::Object::create_streamed(
             &bucket_name,
-            temporary_non_streaming,
+            checked,
+            length.map(|length| length as u64),
             &location_copy,
             "application/octet-stream",
         )
@@ -139,6 +147,31 @@ impl ObjectStoreApi for GoogleCloudStorage {
         Ok(futures::stream::once(async move { Ok(bytes.into()) }).boxed())
     }
 
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        let location = location.to_raw();
+        let location_copy = location.clone();
+        let bucket_name = self.bucket_name.clone();
+
+        // the client has no ranged downloads, so the object is downloaded
+        // and the range is cut from it
+        let bytes = cloud_storage::Object::download(&bucket_name, &location_copy)
+            .await
+            .context(UnableToGetData {
+                bucket: &self.bucket_name,
+                location,
+            })?;
+
+        ensure!(
+            range.end <= bytes.len(),
+            DataDoesNotMatchLength {
+                expected: range.end,
+                actual: bytes.len(),
+            }
+        );
+
+        Ok(Bytes::from(bytes).slice(range))
+    }
+
     async fn delete(&self, location: &Self::Path) -> Result<()> {
         let location = location.to_raw();
         let location_copy = location.clone();
@@ -36,7 +36,7 @@ use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use futures::{stream::BoxStream, Stream, StreamExt, TryFutureExt, TryStreamExt};
 use snafu::{ResultExt, Snafu};
-use std::io;
+use std::{io, ops::Range};
 
 /// Universal API to multiple object store services.
 #[async_trait]
@@ -51,6 +51,10 @@ pub trait ObjectStoreApi: Send + Sync + 'static {
     fn new_path(&self) -> Self::Path;
 
     /// Save the provided bytes to the specified location.
+    ///
+    /// The stream is written as it is read, so memory use is bounded however
+    /// long it is. An error in the stream, or a stream that doesn't have
+    /// `length` bytes, fails the put without storing anything.
     async fn put<S>(
         &self,
         location: &Self::Path,
@@ -66,6 +70,14 @@ pub trait ObjectStoreApi: Send + Sync + 'static {
         location: &Self::Path,
     ) -> Result<BoxStream<'static, Result<Bytes, Self::Error>>, Self::Error>;
 
+    /// Return the bytes in `range` of the object at the specified location.
+    /// It is an error if the range extends past the end of the object.
+    async fn get_range(
+        &self,
+        location: &Self::Path,
+        range: Range<usize>,
+    ) -> Result<Bytes, Self::Error>;
+
     /// Delete the object at the specified location.
     async fn delete(&self, location: &Self::Path) -> Result<(), Self::Error>;
 
@@ -185,6 +197,27 @@ impl ObjectStoreApi for ObjectStore {
         })
     }
 
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        use ObjectStoreIntegration::*;
+        Ok(match (&self.0, location) {
+            (AmazonS3(s3), path::Path::AmazonS3(location)) => s3.get_range(location, range).await?,
+            (GoogleCloudStorage(gcs), path::Path::GoogleCloudStorage(location)) => {
+                gcs.get_range(location, range).await?
+            }
+            (InMemory(in_mem), path::Path::InMemory(location)) => {
+                in_mem.get_range(location, range).await?
+            }
+            (File(file), path::Path::File(location)) => file
+                .get_range(location, range)
+                .await
+                .context(FileObjectStoreError)?,
+            (MicrosoftAzure(azure), path::Path::MicrosoftAzure(location)) => {
+                azure.get_range(location, range).await?
+            }
+            _ => unreachable!(),
+        })
+    }
+
     async fn delete(&self, location: &Self::Path) -> Result<()> {
         use ObjectStoreIntegration::*;
         match (&self.0, location) {
@@ -519,6 +552,10 @@ mod tests {
             .await?;
         assert_eq!(&*read_data, data);
 
+        let read_range = storage.get_range(&location, 3..7).await?;
+        assert_eq!(read_range, data.slice(3..7));
+        assert!(storage.get_range(&location, 10..20).await.is_err());
+
         storage.delete(&location).await?;
 
         let content_list = flatten_list_stream(storage, None).await?;
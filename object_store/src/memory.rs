@@ -9,7 +9,7 @@ use chrono::Utc;
 use futures::{stream::BoxStream, Stream, StreamExt, TryStreamExt};
 use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use std::collections::BTreeSet;
-use std::{collections::BTreeMap, io};
+use std::{collections::BTreeMap, io, ops::Range};
 use tokio::sync::RwLock;
 
 /// A specialized `Result` for in-memory object store-related errors
@@ -27,6 +27,18 @@ pub enum Error {
 
     #[snafu(display("No data in memory found. Location: {}", location))]
     NoDataInMemory { location: String },
+
+    #[snafu(display(
+        "Range ending at {} is out of bounds of the {} bytes at {}",
+        end,
+        len,
+        location
+    ))]
+    OutOfRange {
+        location: String,
+        end: usize,
+        len: usize,
+    },
 }
 
 /// In-memory storage suitable for testing or for opting out of using a cloud
@@ -88,6 +100,29 @@ impl ObjectStoreApi for InMemory {
         Ok(futures::stream::once(async move { Ok(data) }).boxed())
     }
 
+    async fn get_range(&self, location: &Self::Path, range: Range<usize>) -> Result<Bytes> {
+        let data = self
+            .storage
+            .read()
+            .await
+            .get(location)
+            .cloned()
+            .context(NoDataInMemory {
+                location: location.display(),
+            })?;
+
+        ensure!(
+            range.end <= data.len(),
+            OutOfRange {
+                location: location.display(),
+                end: range.end,
+                len: data.len(),
+            }
+        );
+
+        Ok(data.slice(range))
+    }
+
     async fn delete(&self, location: &Self::Path) -> Result<()> {
         self.storage.write().await.remove(&location);
         Ok(())
@@ -8,6 +8,8 @@ pub mod fieldlist;
 mod schema_pivot;
 pub mod seriesset;
 pub mod stringset;
+pub mod tagset;
+mod union_batches;
 
 use std::sync::Arc;
 
@@ -19,16 +21,19 @@ use counters::ExecutionCounters;
 
 use context::IOxExecutionContext;
 use schema_pivot::SchemaPivotNode;
+use union_batches::UnionBatchesNode;
 
 use fieldlist::{FieldList, IntoFieldList};
 use seriesset::{Error as SeriesSetError, SeriesSetConverter, SeriesSetItem};
 use stringset::{IntoStringSet, StringSetRef};
+use tagset::{IntoTagSets, SeriesCardinality};
 use tokio::sync::mpsc::{self, error::SendError};
 
 use snafu::{ResultExt, Snafu};
 
 use crate::plan::{
     fieldlist::FieldListPlan,
+    seriescardinality::{SeriesCardinalityPlan, TagSetPlan},
     seriesset::{SeriesSetPlan, SeriesSetPlans},
     stringset::StringSetPlan,
 };
@@ -77,6 +82,9 @@ pub enum Error {
     #[snafu(display("Internal error creating FieldList: {}", source))]
     FieldListConversion { source: fieldlist::Error },
 
+    #[snafu(display("Internal error extracting tag sets: {}", source))]
+    TagSetConversion { source: tagset::Error },
+
     #[snafu(display("Sending series set results during conversion: {:?}", source))]
     SendingDuringConversion {
         source: Box<SendError<Result<SeriesSetItem, SeriesSetError>>>,
@@ -244,6 +252,56 @@ impl Executor {
         results.into_fieldlist().context(FieldListConversion)
     }
 
+    /// Executes `plan` and returns the number of distinct series of
+    /// each table
+    pub async fn to_series_cardinality(
+        &self,
+        plan: SeriesCardinalityPlan,
+    ) -> Result<SeriesCardinality> {
+        let SeriesCardinalityPlan {
+            known: mut tag_sets,
+            plans,
+        } = plan;
+
+        // Run the plans in parallel
+        let handles = plans
+            .into_iter()
+            .map(|plan| {
+                let ctx = self.new_context();
+
+                tokio::task::spawn(async move {
+                    let TagSetPlan {
+                        table_name,
+                        tag_columns,
+                        plan,
+                    } = plan;
+
+                    let physical_plan = ctx
+                        .prepare_plan(&plan)
+                        .await
+                        .context(DataFusionPhysicalPlanning)?;
+
+                    // TODO: avoid this buffering
+                    ctx.collect(physical_plan)
+                        .await
+                        .context(DataFusionExecution)
+                        .map(|batches| (table_name, tag_columns, batches))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // collect them all up and combine them with the known tag sets
+        for join_handle in handles {
+            let (table_name, tag_columns, batches) = join_handle.await.context(JoinError)??;
+
+            batches
+                .into_tag_sets(&tag_columns, tag_sets.entry(table_name).or_default())
+                .context(TagSetConversion)?;
+        }
+
+        Ok(tagset::to_series_cardinality(tag_sets))
+    }
+
     /// Run the plan and return a record batch reader for reading the results
     pub async fn run_logical_plan(&self, plan: LogicalPlan) -> Result<Vec<RecordBatch>> {
         self.run_logical_plans(vec![plan]).await
@@ -305,6 +363,15 @@ pub fn make_schema_pivot(input: LogicalPlan) -> LogicalPlan {
     LogicalPlan::Extension { node }
 }
 
+/// Create a UnionBatches node, which produces the rows of `input`
+/// followed by the rows of `batches`. The batches must have the same
+/// schema as `input`.
+pub fn make_union_batches(input: LogicalPlan, batches: Vec<RecordBatch>) -> LogicalPlan {
+    let node = Arc::new(UnionBatchesNode::new(input, batches));
+
+    LogicalPlan::Extension { node }
+}
+
 #[cfg(test)]
 mod tests {
     use arrow_deps::{
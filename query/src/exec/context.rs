@@ -18,7 +18,10 @@ use arrow_deps::{
     },
 };
 
-use crate::exec::schema_pivot::{SchemaPivotExec, SchemaPivotNode};
+use crate::exec::{
+    schema_pivot::{SchemaPivotExec, SchemaPivotNode},
+    union_batches::{UnionBatchesExec, UnionBatchesNode},
+};
 
 use tracing::debug;
 
@@ -58,17 +61,23 @@ impl ExtensionPlanner for IOxExtensionPlanner {
         inputs: &[Arc<dyn ExecutionPlan>],
         _ctx_state: &ExecutionContextState,
     ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
-        node.as_any()
-            .downcast_ref::<SchemaPivotNode>()
-            .map(|schema_pivot| {
-                assert_eq!(inputs.len(), 1, "Inconsistent number of inputs");
-                let execution_plan = Arc::new(SchemaPivotExec::new(
-                    Arc::clone(&inputs[0]),
-                    schema_pivot.schema().as_ref().clone().into(),
-                ));
-                Ok(execution_plan as _)
-            })
-            .transpose()
+        let any = node.as_any();
+        let plan = if let Some(schema_pivot) = any.downcast_ref::<SchemaPivotNode>() {
+            assert_eq!(inputs.len(), 1, "Inconsistent number of inputs");
+            Some(Arc::new(SchemaPivotExec::new(
+                Arc::clone(&inputs[0]),
+                schema_pivot.schema().as_ref().clone().into(),
+            )) as _)
+        } else if let Some(union_batches) = any.downcast_ref::<UnionBatchesNode>() {
+            assert_eq!(inputs.len(), 1, "Inconsistent number of inputs");
+            Some(Arc::new(UnionBatchesExec::new(
+                Arc::clone(&inputs[0]),
+                union_batches.batches().to_vec(),
+            )) as _)
+        } else {
+            None
+        };
+        Ok(plan)
     }
 }
 
@@ -0,0 +1,168 @@
+//! This module contains the definition of a "TagSet", the names and
+//! values of the tags of a series, and the code to create them from
+//! record batches.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use arrow_deps::arrow::{
+    array::{Array, StringArray},
+    datatypes::SchemaRef,
+    record_batch::RecordBatch,
+};
+use snafu::{OptionExt, Snafu};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Error extracting tag sets from Record Batches: no tag column '{}' in schema: {:?}",
+        column_name,
+        schema
+    ))]
+    InternalTagColumnNotFound {
+        column_name: String,
+        schema: SchemaRef,
+    },
+
+    #[snafu(display(
+        "Internal error, failed to downcast tag column '{}' to Utf8",
+        column_name
+    ))]
+    InternalFailedToDowncast { column_name: String },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The names and values of the non-null tags of a row, ordered by
+/// name. Each distinct tag set of a table identifies a series.
+pub type TagSet = Vec<(String, String)>;
+
+/// A set of distinct `TagSet`s
+pub type TagSets = BTreeSet<TagSet>;
+
+/// The number of distinct series of each table, keyed by table name
+pub type SeriesCardinality = BTreeMap<String, u64>;
+
+/// Trait to convert RecordBatch'y things into `TagSets`. Can return
+/// errors, so don't use `std::convert::From`
+pub trait IntoTagSets {
+    /// Adds the tag set of each row to `dst`, reading the tags from
+    /// the `tag_columns`, which must have Utf8 type. Any other columns
+    /// are ignored.
+    fn into_tag_sets(self, tag_columns: &[String], dst: &mut TagSets) -> Result<()>;
+}
+
+impl IntoTagSets for Vec<RecordBatch> {
+    fn into_tag_sets(self, tag_columns: &[String], dst: &mut TagSets) -> Result<()> {
+        // tag sets are ordered by tag name
+        let mut tag_columns = tag_columns.iter().collect::<Vec<_>>();
+        tag_columns.sort();
+
+        for record_batch in self {
+            let schema = record_batch.schema();
+
+            let arrays =
+                tag_columns
+                    .iter()
+                    .map(|&column_name| {
+                        let index = schema.index_of(column_name).ok().context(
+                            InternalTagColumnNotFound {
+                                column_name,
+                                schema: SchemaRef::clone(&schema),
+                            },
+                        )?;
+
+                        let array = record_batch
+                            .column(index)
+                            .as_any()
+                            .downcast_ref::<StringArray>()
+                            .context(InternalFailedToDowncast { column_name })?;
+
+                        Ok((column_name, array))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+            for row in 0..record_batch.num_rows() {
+                let tag_set = arrays
+                    .iter()
+                    .filter(|(_, array)| !array.is_null(row))
+                    .map(|(column_name, array)| {
+                        (column_name.to_string(), array.value(row).to_string())
+                    })
+                    .collect();
+                dst.insert(tag_set);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Counts the distinct tag sets of each table, leaving out tables
+/// without any
+pub fn to_series_cardinality(tag_sets: BTreeMap<String, TagSets>) -> SeriesCardinality {
+    tag_sets
+        .into_iter()
+        .filter(|(_, tag_sets)| !tag_sets.is_empty())
+        .map(|(table_name, tag_sets)| (table_name, tag_sets.len() as u64))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_deps::arrow::{
+        array::{ArrayRef, Int64Array},
+        datatypes::{DataType, Field, Schema},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_into_tag_sets() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("state", DataType::Utf8, true),
+            Field::new("city", DataType::Utf8, true),
+            Field::new("time", DataType::Int64, false),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec![Some("MA"), Some("MA"), Some("CA")])),
+            Arc::new(StringArray::from(vec![
+                Some("Boston"),
+                Some("Boston"),
+                None,
+            ])),
+            Arc::new(Int64Array::from(vec![100, 200, 300])),
+        ];
+        let batch = RecordBatch::try_new(schema, columns).unwrap();
+
+        let mut tag_sets = TagSets::new();
+        let tag_columns = vec!["state".to_string(), "city".to_string()];
+        vec![batch.clone()]
+            .into_tag_sets(&tag_columns, &mut tag_sets)
+            .unwrap();
+
+        let expected: TagSets = vec![
+            vec![("state".to_string(), "CA".to_string())],
+            vec![
+                ("city".to_string(), "Boston".to_string()),
+                ("state".to_string(), "MA".to_string()),
+            ],
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(tag_sets, expected);
+
+        // a table without tags has a single series
+        let mut tag_sets = TagSets::new();
+        vec![batch.clone()]
+            .into_tag_sets(&[], &mut tag_sets)
+            .unwrap();
+        assert_eq!(tag_sets, vec![vec![]].into_iter().collect::<TagSets>());
+
+        let err = vec![batch]
+            .into_tag_sets(&["time".to_string()], &mut tag_sets)
+            .unwrap_err();
+        assert!(matches!(err, Error::InternalFailedToDowncast { .. }));
+    }
+}
@@ -0,0 +1,241 @@
+//! This module contains code for the "UnionBatches" DataFusion
+//! extension plan node
+//!
+//! A UnionBatches node produces the rows of its input followed by the
+//! rows of a set of record batches that were computed before the plan
+//! runs, which must have the same schema as the input.
+//!
+//! This operation is used to combine partial aggregates that chunks
+//! computed themselves with the rows of the chunks that could not.
+
+use std::{
+    any::Any,
+    fmt::{self, Debug},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+
+use arrow_deps::{
+    arrow::{datatypes::SchemaRef, record_batch::RecordBatch},
+    datafusion::{
+        error::DataFusionError,
+        logical_plan::{self, DFSchemaRef, Expr, LogicalPlan, UserDefinedLogicalNode},
+        physical_plan::{
+            common::SizedRecordBatchStream, Distribution, ExecutionPlan, Partitioning,
+            SendableRecordBatchStream,
+        },
+    },
+};
+
+pub use arrow_deps::datafusion::error::{DataFusionError as Error, Result};
+
+/// Implements the UnionBatches operation described in make_union_batches
+pub struct UnionBatchesNode {
+    input: LogicalPlan,
+    batches: Vec<RecordBatch>,
+    // these expressions represent what columns are "used" by this
+    // node (in this case all of them) -- columns that are not used
+    // are optimzied away by datafusion.
+    exprs: Vec<Expr>,
+}
+
+impl UnionBatchesNode {
+    pub fn new(input: LogicalPlan, batches: Vec<RecordBatch>) -> Self {
+        // Form exprs that refer to all of our input columns (so that
+        // datafusion doesn't opimize them away)
+        let exprs = input
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| logical_plan::col(field.name()))
+            .collect::<Vec<_>>();
+
+        Self {
+            input,
+            batches,
+            exprs,
+        }
+    }
+
+    /// The batches whose rows follow those of the input
+    pub fn batches(&self) -> &[RecordBatch] {
+        &self.batches
+    }
+}
+
+impl Debug for UnionBatchesNode {
+    /// Use explain format for the Debug format.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_for_explain(f)
+    }
+}
+
+impl UserDefinedLogicalNode for UnionBatchesNode {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    /// Schema for UnionBatches is the same as its input
+    fn schema(&self) -> &DFSchemaRef {
+        self.input.schema()
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        self.exprs.clone()
+    }
+
+    /// For example: `UnionBatches: batches=2`
+    fn fmt_for_explain(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "UnionBatches: batches={}", self.batches.len())
+    }
+
+    fn from_template(
+        &self,
+        exprs: &[Expr],
+        inputs: &[LogicalPlan],
+    ) -> Arc<dyn UserDefinedLogicalNode + Send + Sync> {
+        assert_eq!(inputs.len(), 1, "UnionBatches: input sizes inconistent");
+        assert_eq!(
+            exprs.len(),
+            self.exprs.len(),
+            "UnionBatches: expression sizes inconistent"
+        );
+        Arc::new(Self::new(inputs[0].clone(), self.batches.clone()))
+    }
+}
+
+// ------ The implementation of UnionBatches code follows -----
+
+/// Physical operator that implements the UnionBatches operation. Each
+/// partition of the input is one of its partitions, and the batches
+/// are the last one.
+pub struct UnionBatchesExec {
+    input: Arc<dyn ExecutionPlan>,
+    batches: Vec<Arc<RecordBatch>>,
+}
+
+impl UnionBatchesExec {
+    pub fn new(input: Arc<dyn ExecutionPlan>, batches: Vec<RecordBatch>) -> Self {
+        let batches = batches.into_iter().map(Arc::new).collect();
+        Self { input, batches }
+    }
+}
+
+impl Debug for UnionBatchesExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "UnionBatchesExec")
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for UnionBatchesExec {
+    fn as_any(&self) -> &(dyn std::any::Any + 'static) {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        let num_partitions = self.input.output_partitioning().partition_count() + 1;
+        Partitioning::UnknownPartitioning(num_partitions)
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::UnspecifiedDistribution
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(Self {
+                input: Arc::clone(&children[0]),
+                batches: self.batches.clone(),
+            })),
+            _ => Err(DataFusionError::Internal(
+                "UnionBatchesExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    /// Execute one partition and return an iterator over RecordBatch
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        let input_partitions = self.input.output_partitioning().partition_count();
+
+        if partition < input_partitions {
+            self.input.execute(partition).await
+        } else if partition == input_partitions {
+            Ok(Box::pin(SizedRecordBatchStream::new(
+                self.schema(),
+                self.batches.clone(),
+            )))
+        } else {
+            Err(DataFusionError::Internal(format!(
+                "UnionBatchesExec invalid partition {}",
+                partition
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_deps::{
+        arrow::{
+            array::Int64Array,
+            datatypes::{DataType, Field, Schema},
+        },
+        datafusion::physical_plan::{collect, memory::MemoryExec},
+    };
+
+    #[tokio::test]
+    async fn union_batches_exec() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+        let make_batch = |values: &[i64]| {
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![Arc::new(Int64Array::from(values.to_vec()))],
+            )
+            .unwrap()
+        };
+
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![make_batch(&[1, 2])], vec![make_batch(&[3])]],
+            Arc::clone(&schema),
+            None,
+        )?);
+        let exec = UnionBatchesExec::new(input, vec![make_batch(&[4]), make_batch(&[5, 6])]);
+        assert_eq!(exec.output_partitioning().partition_count(), 3);
+
+        // partitions are collected concurrently, so the rows may be in any order
+        let mut values = collect(Arc::new(exec))
+            .await?
+            .iter()
+            .flat_map(|batch| {
+                let array = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap();
+                array.values().to_vec()
+            })
+            .collect::<Vec<_>>();
+        values.sort_unstable();
+        assert_eq!(values, vec![1, 2, 3, 4, 5, 6]);
+
+        Ok(())
+    }
+}
@@ -4,13 +4,20 @@ use std::{
 };
 
 use arrow_deps::{
-    arrow::datatypes::{DataType, Field},
+    arrow::{
+        array::{new_null_array, ArrayRef, UInt64Array},
+        compute::cast,
+        datatypes::{DataType, Field, SchemaRef as ArrowSchemaRef},
+        error::ArrowError,
+        record_batch::RecordBatch,
+    },
     datafusion::{
         error::{DataFusionError, Result as DatafusionResult},
         logical_plan::{
-            Expr, ExpressionVisitor, LogicalPlan, LogicalPlanBuilder, Operator, Recursion,
+            max, min, sum, Expr, ExpressionVisitor, LogicalPlan, LogicalPlanBuilder, Operator,
+            Recursion,
         },
-        prelude::col,
+        prelude::{col, lit},
     },
     util::IntoExpr,
 };
@@ -23,7 +30,10 @@ use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use tracing::debug;
 
 use crate::{
-    exec::{field::FieldColumns, make_schema_pivot, stringset::StringSet},
+    exec::{
+        field::FieldColumns, make_schema_pivot, make_union_batches, stringset::StringSet,
+        tagset::TagSets,
+    },
     func::{
         selectors::{selector_first, selector_last, selector_max, selector_min, SelectorOutput},
         window::make_window_bound_expr,
@@ -31,6 +41,7 @@ use crate::{
     group_by::{Aggregate, WindowDuration},
     plan::{
         fieldlist::FieldListPlan,
+        seriescardinality::{SeriesCardinalityPlan, TagSetPlan},
         seriesset::{SeriesSetPlan, SeriesSetPlans},
         stringset::{Error as StringSetError, StringSetPlan, StringSetPlanBuilder},
     },
@@ -62,6 +73,11 @@ pub enum Error {
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
+    #[snafu(display("gRPC planner got error finding tag sets: {}", source))]
+    FindingTagSets {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
     #[snafu(display(
         "gRPC planner got internal error making table_name with default predicate: {}",
         source
@@ -73,9 +89,6 @@ pub enum Error {
     #[snafu(display("gRPC planner could not get table_names with default predicate, which should always return values"))]
     InternalTableNameCannotGetPlanForDefault {},
 
-    #[snafu(display("Unsupported predicate in gRPC table_names: {:?}", predicate))]
-    UnsupportedPredicateForTableNames { predicate: Predicate },
-
     #[snafu(display(
         "gRPC planner got error checking if chunk {} could pass predicate: {}",
         chunk_id,
@@ -116,6 +129,21 @@ pub enum Error {
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
+    #[snafu(display(
+        "gRPC planner got error reading aggregates of table '{}' in chunk {}: {}",
+        table_name,
+        chunk_id,
+        source
+    ))]
+    ReadingChunkAggregates {
+        table_name: String,
+        chunk_id: u32,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[snafu(display("Internal error converting aggregates read from chunk: {}", source))]
+    InternalConvertingChunkAggregates { source: ArrowError },
+
     #[snafu(display("gRPC planner error: unsupported predicate: {}", source))]
     UnsupportedPredicate { source: DataFusionError },
 
@@ -200,8 +228,15 @@ impl InfluxRPCPlanner {
     where
         D: Database + 'static,
     {
+        debug!(predicate=?predicate, "planning table_names");
+
         let mut builder = StringSetPlanBuilder::new();
 
+        // Key is table name, value is set of chunks which had data
+        // for that table but that we couldn't evaluate the predicate
+        // entirely using the metadata
+        let mut need_full_plans = BTreeMap::new();
+
         for chunk in self.filtered_chunks(database, &predicate).await? {
             let new_table_names = chunk
                 .table_names(&predicate, builder.known_strings())
@@ -209,20 +244,48 @@ impl InfluxRPCPlanner {
                 .map_err(|e| Box::new(e) as _)
                 .context(TableNamePlan)?;
 
-            builder = match new_table_names {
-                Some(new_table_names) => builder.append(new_table_names.into()),
+            match new_table_names {
+                Some(new_table_names) => builder = builder.append(new_table_names.into()),
                 None => {
-                    // TODO: General purpose plans for
-                    // table_names. For now, if we couldn't figure out
-                    // the table names from only metadata, generate an
-                    // error
-                    return UnsupportedPredicateForTableNames { predicate }.fail();
+                    // couldn't find the table names from metadata
+                    // only, so find the tables that could have rows
+                    // that pass, and check them with full plans
+                    let table_names = self.chunk_table_names(chunk.as_ref(), &predicate).await?;
+
+                    debug!(
+                        table_names=?table_names,
+                        chunk_id = chunk.id(),
+                        "table names need full plan"
+                    );
+
+                    for table_name in table_names {
+                        need_full_plans
+                            .entry(table_name)
+                            .or_insert_with(Vec::new)
+                            .push(Arc::clone(&chunk));
+                    }
                 }
             }
         }
 
-        let plan = builder.build().context(CreatingStringSet)?;
-        Ok(plan)
+        // Tables that were already found from metadata don't need to
+        // be checked again
+        let need_full_plans = need_full_plans
+            .into_iter()
+            .filter(|(table_name, _)| !builder.known_strings().contains(table_name))
+            .collect::<Vec<_>>();
+
+        for (table_name, chunks) in need_full_plans {
+            let plan = self
+                .table_name_plan(&table_name, &predicate, chunks)
+                .await?;
+
+            if let Some(plan) = plan {
+                builder = builder.append(plan)
+            }
+        }
+
+        builder.build().context(CreatingStringSet)
     }
 
     /// Returns a set of plans that produces the names of "tag"
@@ -473,6 +536,90 @@ impl InfluxRPCPlanner {
             .context(CreatingStringSet)
     }
 
+    /// Returns a plan that counts the distinct series (combinations
+    /// of tag values) of each table in this database that have at
+    /// least one row which passes the conditions specified by
+    /// `predicate`.
+    pub async fn series_cardinality<D>(
+        &self,
+        database: &D,
+        predicate: Predicate,
+    ) -> Result<SeriesCardinalityPlan>
+    where
+        D: Database + 'static,
+    {
+        debug!(predicate=?predicate, "planning series_cardinality");
+
+        // The basic algorithm is:
+        //
+        // 1. Find all the potential tables in the chunks
+        //
+        // 2. For each table/chunk pair, figure out which have tag
+        // sets that can be found from only metadata and which need
+        // full plans
+
+        // Key is table name, value is set of chunks which had data
+        // for that table but that we couldn't evaluate the predicate
+        // entirely using the metadata
+        let mut need_full_plans = BTreeMap::new();
+
+        // Key is table name, value is the tag sets found from metadata.
+        // A series may be in several chunks, so the tag sets are
+        // merged before they are counted
+        let mut known_tag_sets = BTreeMap::new();
+        for chunk in self.filtered_chunks(database, &predicate).await? {
+            let table_names = self.chunk_table_names(chunk.as_ref(), &predicate).await?;
+
+            for table_name in table_names {
+                debug!(
+                    table_name = table_name.as_str(),
+                    chunk_id = chunk.id(),
+                    "finding tag sets in table"
+                );
+
+                let maybe_tag_sets = chunk
+                    .tag_sets(&table_name, &predicate)
+                    .await
+                    .map_err(|e| Box::new(e) as _)
+                    .context(FindingTagSets)?;
+
+                match maybe_tag_sets {
+                    Some(mut tag_sets) => {
+                        debug!(
+                            num_tag_sets = tag_sets.len(),
+                            chunk_id = chunk.id(),
+                            "tag sets found from metadata"
+                        );
+                        known_tag_sets
+                            .entry(table_name)
+                            .or_insert_with(TagSets::new)
+                            .append(&mut tag_sets);
+                    }
+                    None => {
+                        debug!(
+                            table_name = table_name.as_str(),
+                            chunk_id = chunk.id(),
+                            "need full plan to find tag sets"
+                        );
+                        need_full_plans
+                            .entry(table_name)
+                            .or_insert_with(Vec::new)
+                            .push(Arc::clone(&chunk));
+                    }
+                }
+            }
+        }
+
+        let mut plan = SeriesCardinalityPlan::new(known_tag_sets);
+        for (table_name, chunks) in need_full_plans.into_iter() {
+            if let Some(tag_set_plan) = self.tag_sets_plan(&table_name, &predicate, chunks).await? {
+                plan = plan.append(tag_set_plan);
+            }
+        }
+
+        Ok(plan)
+    }
+
     /// Returns a plan that produces a list of columns and their
     /// datatypes (as defined in the data written via `write_lines`),
     /// and which have more than zero rows which pass the conditions
@@ -602,6 +749,42 @@ impl InfluxRPCPlanner {
         Ok(ss_plans.into())
     }
 
+    /// Creates a GroupedSeriesSet plan that produces only the groups
+    /// of a read_group with `group_columns`, without reading any of
+    /// the points. Each group is a single series without fields.
+    pub async fn read_group_keys<D>(
+        &self,
+        database: &D,
+        predicate: Predicate,
+        group_columns: &[impl AsRef<str>],
+    ) -> Result<SeriesSetPlans>
+    where
+        D: Database + 'static,
+    {
+        debug!(predicate=?predicate, "planning read_group_keys");
+
+        // group tables by chunk, pruning if possible
+        let chunks = self.filtered_chunks(database, &predicate).await?;
+        let table_chunks = self.group_chunks_by_table(&predicate, chunks).await?;
+        let num_prefix_tag_group_columns = group_columns.len();
+
+        // now, build up plans for each table
+        let mut ss_plans = Vec::with_capacity(table_chunks.len());
+        for (table_name, chunks) in table_chunks {
+            let ss_plan = self
+                .read_group_keys_plan(table_name, &predicate, group_columns, chunks)
+                .await?;
+
+            // If we have to do real work, add it to the list of plans
+            if let Some(ss_plan) = ss_plan {
+                let grouped_plan = ss_plan.grouped(num_prefix_tag_group_columns);
+                ss_plans.push(grouped_plan);
+            }
+        }
+
+        Ok(ss_plans.into())
+    }
+
     /// Creates a GroupedSeriesSet plan that produces an output table with rows
     /// that are grouped by window defintions
     pub async fn read_window_aggregate<D>(
@@ -684,20 +867,43 @@ impl InfluxRPCPlanner {
                 table_names
             }
             None => {
-                // couldn't find table names with predicate, get all chunk tables,
-                // fall back to filtering ourself
-                let table_name_predicate = if let Some(table_names) = &predicate.table_names {
-                    PredicateBuilder::new().tables(table_names).build()
-                } else {
-                    Predicate::default()
+                // couldn't find table names with the general
+                // predicates, so try again without them. The tables
+                // can still be pruned by the chunk's statistics
+                // (e.g. of the timestamp range)
+                let no_exprs_predicate = Predicate {
+                    exprs: vec![],
+                    ..predicate.clone()
                 };
-                chunk
-                    .table_names(&table_name_predicate, &no_tables)
+                let table_names = chunk
+                    .table_names(&no_exprs_predicate, &no_tables)
                     .await
                     .map_err(|e| Box::new(e) as _)
-                    .context(InternalTableNamePlanForDefault)?
-                    // unwrap the Option
-                    .context(InternalTableNameCannotGetPlanForDefault)?
+                    .context(TableNamePlan)?;
+
+                match table_names {
+                    Some(table_names) => {
+                        debug!("found table names without general predicates");
+                        table_names
+                    }
+                    None => {
+                        // couldn't find table names with predicate, get all chunk tables,
+                        // fall back to filtering ourself
+                        let table_name_predicate = if let Some(table_names) = &predicate.table_names
+                        {
+                            PredicateBuilder::new().tables(table_names).build()
+                        } else {
+                            Predicate::default()
+                        };
+                        chunk
+                            .table_names(&table_name_predicate, &no_tables)
+                            .await
+                            .map_err(|e| Box::new(e) as _)
+                            .context(InternalTableNamePlanForDefault)?
+                            // unwrap the Option
+                            .context(InternalTableNameCannotGetPlanForDefault)?
+                    }
+                }
             }
         };
         Ok(table_names)
@@ -758,6 +964,135 @@ impl InfluxRPCPlanner {
         Ok(Some(plan.into()))
     }
 
+    /// Creates a DataFusion LogicalPlan that returns the table name
+    /// if the specified table has at least one row that passes the
+    /// predicate, and nothing otherwise.
+    ///
+    /// returns `None` if the table can not contain any rows that
+    /// would pass the predicate.
+    ///
+    /// The created plan looks like:
+    ///
+    /// ```text
+    ///  Projection (select the table name)
+    ///    Limit(1)
+    ///      Filter(predicate) [optional]
+    ///        InMemoryScan
+    /// ```
+    async fn table_name_plan<C>(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        chunks: Vec<Arc<C>>,
+    ) -> Result<Option<StringSetPlan>>
+    where
+        C: PartitionChunk + 'static,
+    {
+        let scan_and_filter = self.scan_and_filter(table_name, predicate, chunks).await?;
+
+        let TableScanAndFilter { plan_builder, .. } = match scan_and_filter {
+            None => return Ok(None),
+            Some(t) => t,
+        };
+
+        // Any single row that passes is enough to know the table name
+        let select_exprs = vec![lit(table_name).alias("table_name")];
+
+        let plan = plan_builder
+            .limit(1)
+            .context(BuildingPlan)?
+            .project(&select_exprs)
+            .context(BuildingPlan)?
+            .build()
+            .context(BuildingPlan)?;
+
+        debug!(table_name=table_name, plan=%plan.display_indent_schema(),
+               "created table_name plan for table");
+
+        Ok(Some(plan.into()))
+    }
+
+    /// Creates a DataFusion LogicalPlan that returns the tag columns
+    /// of the rows of a specified table which pass the predicate, so
+    /// each row is the tag set of a series. The same tag set may be
+    /// returned many times.
+    ///
+    /// returns `None` if the table contains no rows that would pass
+    /// the predicate.
+    ///
+    /// The created plan looks like:
+    ///
+    /// ```text
+    ///  Projection (select the tag columns)
+    ///      Filter(predicate) [optional]
+    ///        InMemoryScan
+    /// ```
+    async fn tag_sets_plan<C>(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        chunks: Vec<Arc<C>>,
+    ) -> Result<Option<TagSetPlan>>
+    where
+        C: PartitionChunk + 'static,
+    {
+        let scan_and_filter = self.scan_and_filter(table_name, predicate, chunks).await?;
+        let TableScanAndFilter {
+            plan_builder,
+            schema,
+        } = match scan_and_filter {
+            None => return Ok(None),
+            Some(t) => t,
+        };
+
+        // With a field restriction, only the rows that have a value for
+        // one of the selected fields belong to a series
+        let plan_builder = if predicate.field_columns.is_some() {
+            let has_field = filtered_fields_iter(&schema, predicate)
+                .map(|field| col(field.name()).is_not_null())
+                .fold(None, |has_field: Option<Expr>, expr| match has_field {
+                    Some(has_field) => Some(has_field.or(expr)),
+                    None => Some(expr),
+                });
+
+            match has_field {
+                Some(has_field) => plan_builder.filter(has_field).context(BuildingPlan)?,
+                // none of the selected fields are in the table
+                None => return Ok(None),
+            }
+        } else {
+            plan_builder
+        };
+
+        let tag_columns: Vec<_> = schema
+            .tags_iter()
+            .map(|field| field.name().to_string())
+            .collect();
+
+        // A table without tags has a single series, if it has any
+        // rows, so select the time column to find whether it does
+        let select_exprs: Vec<_> = if tag_columns.is_empty() {
+            schema.time_iter().map(|field| col(field.name())).collect()
+        } else {
+            tag_columns.iter().map(|name| col(name)).collect()
+        };
+
+        // TODO: optimize this to use "DISTINCT" rather than fetching
+        // the tag values of every row and deduplicating them in the
+        // query Executor
+        let plan = plan_builder
+            .project(&select_exprs)
+            .context(BuildingPlan)?
+            .build()
+            .context(BuildingPlan)?;
+
+        Ok(Some(TagSetPlan {
+            table_name: table_name.to_string(),
+            tag_columns,
+            plan,
+        }))
+    }
+
     /// Creates a DataFusion LogicalPlan that returns the timestamp
     /// and all field columns for a specified table:
     ///
@@ -957,15 +1292,15 @@ impl InfluxRPCPlanner {
         C: PartitionChunk + 'static,
     {
         let table_name = table_name.into();
-        let scan_and_filter = self.scan_and_filter(&table_name, predicate, chunks).await?;
+        let scan_and_filter = self
+            .scan_and_filter(&table_name, predicate, chunks.clone())
+            .await?;
 
-        let TableScanAndFilter {
-            plan_builder,
-            schema,
-        } = match scan_and_filter {
+        let scan = match scan_and_filter {
             None => return Ok(None),
             Some(t) => t,
         };
+        let schema = scan.schema.clone();
 
         // order the tag columns so that the group keys come first (we
         // will group and
@@ -993,9 +1328,20 @@ impl InfluxRPCPlanner {
             .map(|expr| expr.into_sort_expr())
             .collect::<Vec<_>>();
 
-        let plan_builder = plan_builder
-            .aggregate(&group_exprs, &agg_exprs)
-            .context(BuildingPlan)?
+        let chunk_aggregates = self
+            .chunk_aggregates(&table_name, predicate, agg, None, chunks)
+            .await?;
+
+        let plan_builder = self
+            .aggregate_chunks(
+                &table_name,
+                predicate,
+                scan,
+                chunk_aggregates,
+                &group_exprs,
+                &agg_exprs,
+            )
+            .await?
             .sort(&sort_exprs)
             .context(BuildingPlan)?;
 
@@ -1007,6 +1353,79 @@ impl InfluxRPCPlanner {
         Ok(Some(ss_plan))
     }
 
+    /// Creates a plan for the distinct values of `group_columns` in a
+    /// table, returning None if the predicate rules out matching any
+    /// rows in the table
+    ///
+    /// Equivalent to this SQL query
+    ///
+    /// SELECT group_key1, ... group_keyN
+    /// FROM measurement
+    /// GROUP BY group_key1, ... group_keyN
+    /// ORDER BY group_key1, ... group_keyN
+    ///
+    /// The created plan looks like:
+    ///
+    ///  OrderBy(gby cols)
+    ///     GroupBy(gby cols)
+    ///       Filter(predicate)
+    ///          Scan
+    async fn read_group_keys_plan<C>(
+        &self,
+        table_name: impl Into<String>,
+        predicate: &Predicate,
+        group_columns: &[impl AsRef<str>],
+        chunks: Vec<Arc<C>>,
+    ) -> Result<Option<SeriesSetPlan>>
+    where
+        C: PartitionChunk + 'static,
+    {
+        let table_name = table_name.into();
+        let scan_and_filter = self.scan_and_filter(&table_name, predicate, chunks).await?;
+
+        let TableScanAndFilter {
+            plan_builder,
+            schema,
+        } = match scan_and_filter {
+            None => return Ok(None),
+            Some(t) => t,
+        };
+
+        // errors in the same way as read_group if a group column is
+        // not a tag of the table
+        let tag_columns: Vec<_> = schema.tags_iter().map(|f| f.name() as &str).collect();
+        let group_tag_columns: Vec<_> = reorder_prefix(group_columns, tag_columns)?
+            .into_iter()
+            .take(group_columns.len())
+            .map(|name| Arc::new(name.to_string()))
+            .collect();
+
+        let group_exprs = group_tag_columns
+            .iter()
+            .map(|tag_name| tag_name.into_expr())
+            .collect::<Vec<_>>();
+
+        let sort_exprs = group_exprs
+            .iter()
+            .map(|expr| expr.into_sort_expr())
+            .collect::<Vec<_>>();
+
+        let plan = plan_builder
+            .aggregate(&group_exprs, &[])
+            .context(BuildingPlan)?
+            .sort(&sort_exprs)
+            .context(BuildingPlan)?
+            .build()
+            .context(BuildingPlan)?;
+
+        // no fields, so the groups are the only output
+        let field_columns = FieldColumns::DifferentTimestamp(vec![]);
+        let ss_plan =
+            SeriesSetPlan::new(Arc::new(table_name), plan, group_tag_columns, field_columns);
+
+        Ok(Some(ss_plan))
+    }
+
     /// Creates a GroupedSeriesSet plan that produces an output table with rows
     /// that are grouped by window defintions
     ///
@@ -1048,15 +1467,15 @@ impl InfluxRPCPlanner {
         C: PartitionChunk + 'static,
     {
         let table_name = table_name.into();
-        let scan_and_filter = self.scan_and_filter(&table_name, predicate, chunks).await?;
+        let scan_and_filter = self
+            .scan_and_filter(&table_name, predicate, chunks.clone())
+            .await?;
 
-        let TableScanAndFilter {
-            plan_builder,
-            schema,
-        } = match scan_and_filter {
+        let scan = match scan_and_filter {
             None => return Ok(None),
             Some(t) => t,
         };
+        let schema = scan.schema.clone();
 
         // Group by all tag columns and the window bounds
         let window_bound = make_window_bound_expr(TIME_COLUMN_NAME.into_expr(), &every, &offset)
@@ -1079,9 +1498,29 @@ impl InfluxRPCPlanner {
             .map(|expr| expr.into_sort_expr())
             .collect::<Vec<_>>();
 
-        let plan_builder = plan_builder
-            .aggregate(&group_exprs, &agg_exprs)
-            .context(BuildingPlan)?
+        // chunks can only compute windows of a fixed size that are
+        // aligned to the epoch
+        let chunk_aggregates = match (every, offset) {
+            (WindowDuration::Fixed { nanoseconds }, WindowDuration::Fixed { nanoseconds: 0 })
+                if *nanoseconds > 0 =>
+            {
+                let window = Some(*nanoseconds as u64);
+                self.chunk_aggregates(&table_name, predicate, agg, window, chunks)
+                    .await?
+            }
+            _ => ChunkAggregates::none(agg, chunks),
+        };
+
+        let plan_builder = self
+            .aggregate_chunks(
+                &table_name,
+                predicate,
+                scan,
+                chunk_aggregates,
+                &group_exprs,
+                &agg_exprs,
+            )
+            .await?
             .sort(&sort_exprs)
             .context(BuildingPlan)?;
 
@@ -1110,6 +1549,214 @@ impl InfluxRPCPlanner {
         Ok(Some(ss_plan))
     }
 
+    /// Asks each chunk to compute the aggregates of its rows of the
+    /// table itself, grouped by all of the chunk's tags and, if a
+    /// `window` is given, by window of time. Without a window the time
+    /// column is aggregated too, as `read_group_plan` does.
+    ///
+    /// A chunk is only asked if it has all the columns of the predicate
+    /// and `agg` can be computed on all of its fields. Chunks can't
+    /// compute selectors without a window, as the time of each value
+    /// would be lost, nor means, as they can't be combined.
+    async fn chunk_aggregates<C>(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        agg: Aggregate,
+        window: Option<u64>,
+        chunks: Vec<Arc<C>>,
+    ) -> Result<ChunkAggregates<C>>
+    where
+        C: PartitionChunk + 'static,
+    {
+        let can_push_down = match agg {
+            Aggregate::Sum | Aggregate::Count => true,
+            Aggregate::Min | Aggregate::Max => window.is_some(),
+            _ => false,
+        };
+        if !can_push_down {
+            return Ok(ChunkAggregates::none(agg, chunks));
+        }
+
+        let filter_expr = predicate.filter_expr();
+        let mut chunk_aggregates = ChunkAggregates::none(agg, vec![]);
+        for chunk in chunks {
+            let chunk_id = chunk.id();
+
+            let chunk_table_schema = chunk
+                .table_schema(table_name, Selection::All)
+                .await
+                .map_err(|e| Box::new(e) as _)
+                .context(GettingTableSchema {
+                    table_name,
+                    chunk_id,
+                })?;
+
+            let has_filter_columns = filter_expr
+                .as_ref()
+                .map(|expr| schema_has_all_expr_columns(&chunk_table_schema, expr))
+                .unwrap_or(true);
+
+            let time_fields = match window {
+                Some(_) => None,
+                None => Some(chunk_table_schema.time_iter()),
+            };
+            let fields = filtered_fields_iter(&chunk_table_schema, predicate)
+                .chain(time_fields.into_iter().flatten())
+                .collect::<Vec<_>>();
+
+            let is_numeric = |field: &&Field| {
+                matches!(
+                    field.data_type(),
+                    DataType::Int64 | DataType::UInt64 | DataType::Float64
+                )
+            };
+            if !has_filter_columns || !(agg == Aggregate::Count || fields.iter().all(is_numeric)) {
+                chunk_aggregates.chunks.push(chunk);
+                continue;
+            }
+
+            let group_columns = chunk_table_schema
+                .tags_iter()
+                .map(|field| field.name().to_string())
+                .collect::<Vec<_>>();
+            let aggregates = fields
+                .iter()
+                .map(|field| (field.name().to_string(), agg))
+                .collect::<Vec<_>>();
+
+            let batches = chunk
+                .read_aggregate(table_name, predicate, &group_columns, &aggregates, window)
+                .await
+                .map_err(|e| Box::new(e) as _)
+                .context(ReadingChunkAggregates {
+                    table_name,
+                    chunk_id,
+                })?;
+
+            match batches {
+                Some(batches) => {
+                    debug!(table_name, chunk_id, "Aggregated by chunk");
+                    let column_names = group_columns
+                        .into_iter()
+                        .chain(window.map(|_| TIME_COLUMN_NAME.to_string()))
+                        .chain(aggregates.into_iter().map(|(name, _)| name))
+                        .collect::<Vec<_>>();
+                    chunk_aggregates.batches.extend(
+                        batches
+                            .into_iter()
+                            .map(|batch| (batch, column_names.clone())),
+                    );
+                }
+                None => chunk_aggregates.chunks.push(chunk),
+            }
+        }
+
+        Ok(chunk_aggregates)
+    }
+
+    /// Aggregates the rows of `scan`, which reads all chunks of the
+    /// table, with `group_exprs` and `agg_exprs`. The aggregates the
+    /// chunks computed themselves are combined with those of the rows
+    /// of the other chunks, which are the only ones that are read.
+    ///
+    /// The created plan looks like:
+    ///
+    ///  GroupBy(gby: group columns; agg: merge(aggregates))
+    ///    UnionBatches(chunk aggregates)
+    ///      GroupBy(gby: group_exprs; agg: agg_exprs)
+    ///        Filter(predicate)
+    ///          Scan(other chunks)
+    async fn aggregate_chunks<C>(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        scan: TableScanAndFilter,
+        chunk_aggregates: ChunkAggregates<C>,
+        group_exprs: &[Expr],
+        agg_exprs: &[Expr],
+    ) -> Result<LogicalPlanBuilder>
+    where
+        C: PartitionChunk + 'static,
+    {
+        let TableScanAndFilter {
+            plan_builder,
+            schema,
+        } = scan;
+        let plan_builder = plan_builder
+            .aggregate(group_exprs, agg_exprs)
+            .context(BuildingPlan)?;
+
+        let ChunkAggregates {
+            agg,
+            batches,
+            chunks,
+        } = chunk_aggregates;
+        if batches.is_empty() {
+            return Ok(plan_builder);
+        }
+
+        // The aggregates of the chunks are converted to the output of
+        // the aggregate over all chunks
+        let aggregate_plan = plan_builder.build().context(BuildingPlan)?;
+        let aggregate_schema: ArrowSchemaRef =
+            Arc::new(aggregate_plan.schema().as_ref().clone().into());
+        let batches = batches
+            .iter()
+            .map(|(batch, column_names)| {
+                to_aggregate_batch(
+                    batch,
+                    column_names,
+                    &aggregate_schema,
+                    group_exprs.len(),
+                    agg,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let scan = if chunks.is_empty() {
+            None
+        } else {
+            self.scan_and_filter_with_schema(table_name, predicate, chunks, Some(schema))
+                .await?
+        };
+        let input = match scan {
+            Some(TableScanAndFilter { plan_builder, .. }) => {
+                let plan = plan_builder
+                    .aggregate(group_exprs, agg_exprs)
+                    .context(BuildingPlan)?
+                    .build()
+                    .context(BuildingPlan)?;
+                make_union_batches(plan, batches)
+            }
+            None => LogicalPlanBuilder::scan_memory(vec![batches], aggregate_schema, None)
+                .context(BuildingPlan)?
+                .build()
+                .context(BuildingPlan)?,
+        };
+
+        // Sums and counts of the same group add up
+        let merge: fn(Expr) -> Expr = match agg {
+            Aggregate::Min => min,
+            Aggregate::Max => max,
+            _ => sum,
+        };
+
+        let (group_fields, agg_fields) = input.schema().fields().split_at(group_exprs.len());
+        let group_exprs = group_fields
+            .iter()
+            .map(|field| col(field.name()))
+            .collect::<Vec<_>>();
+        let agg_exprs = agg_fields
+            .iter()
+            .map(|field| merge(col(field.name())).alias(field.name()))
+            .collect::<Vec<_>>();
+
+        LogicalPlanBuilder::from(&input)
+            .aggregate(&group_exprs, &agg_exprs)
+            .context(BuildingPlan)
+    }
+
     /// Create a plan that scans the specified table, and applies any
     /// filtering specified on the predicate, if any.
     ///
@@ -1128,6 +1775,23 @@ impl InfluxRPCPlanner {
         predicate: &Predicate,
         chunks: Vec<Arc<C>>,
     ) -> Result<Option<TableScanAndFilter>>
+    where
+        C: PartitionChunk + 'static,
+    {
+        self.scan_and_filter_with_schema(table_name, predicate, chunks, None)
+            .await
+    }
+
+    /// As `scan_and_filter`, but the scanned table also has the columns
+    /// of `table_schema`, if any, which may come from chunks that are
+    /// not scanned.
+    async fn scan_and_filter_with_schema<C>(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        chunks: Vec<Arc<C>>,
+        table_schema: Option<Schema>,
+    ) -> Result<Option<TableScanAndFilter>>
     where
         C: PartitionChunk + 'static,
     {
@@ -1138,6 +1802,11 @@ impl InfluxRPCPlanner {
 
         // Prepare the scan of the table
         let mut builder = ProviderBuilder::new(table_name);
+        if let Some(table_schema) = table_schema {
+            builder = builder
+                .merge_schema(table_schema)
+                .context(CreatingProvider { table_name })?;
+        }
         for chunk in chunks {
             let chunk_id = chunk.id();
 
@@ -1304,6 +1973,64 @@ struct TableScanAndFilter {
     schema: Schema,
 }
 
+/// The aggregates chunks computed themselves, see `chunk_aggregates`
+struct ChunkAggregates<C: PartitionChunk + 'static> {
+    agg: Aggregate,
+    /// The batches read from the chunks, with the names of their columns
+    batches: Vec<(RecordBatch, Vec<String>)>,
+    /// The chunks whose rows must be aggregated by the plan
+    chunks: Vec<Arc<C>>,
+}
+
+impl<C: PartitionChunk + 'static> ChunkAggregates<C> {
+    /// All rows of `chunks` must be aggregated by the plan
+    fn none(agg: Aggregate, chunks: Vec<Arc<C>>) -> Self {
+        Self {
+            agg,
+            batches: vec![],
+            chunks,
+        }
+    }
+}
+
+/// Converts a batch of aggregates read from a chunk, whose columns are
+/// named by `column_names`, to `schema`, the output of the aggregate
+/// over all chunks, whose first `num_group_columns` are the group
+/// columns. Columns the chunk doesn't have are null, except for counts,
+/// which are zero.
+fn to_aggregate_batch(
+    batch: &RecordBatch,
+    column_names: &[String],
+    schema: &ArrowSchemaRef,
+    num_group_columns: usize,
+    agg: Aggregate,
+) -> Result<RecordBatch> {
+    let num_rows = batch.num_rows();
+
+    let columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let column = match column_names.iter().position(|name| name == field.name()) {
+                Some(index) => Arc::clone(batch.column(index)),
+                None if agg == Aggregate::Count && i >= num_group_columns => {
+                    Arc::new(UInt64Array::from(vec![0_u64; num_rows])) as ArrayRef
+                }
+                None => new_null_array(field.data_type(), num_rows),
+            };
+
+            if column.data_type() == field.data_type() {
+                Ok(column)
+            } else {
+                cast(&column, field.data_type()).context(InternalConvertingChunkAggregates)
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    RecordBatch::try_new(Arc::clone(schema), columns).context(InternalConvertingChunkAggregates)
+}
+
 /// Reorders tag_columns so that its prefix matches exactly
 /// prefix_columns. Returns an error if there are duplicates, or other
 /// untoward inputs
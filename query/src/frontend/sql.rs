@@ -133,6 +133,39 @@ impl SQLQueryPlanner {
 
         ctx.prepare_sql(query).await.context(Preparing)
     }
+
+    /// Estimate the number of rows a SQL query against `database` reads,
+    /// without running it. This is the number of rows in all chunks of the
+    /// tables in the query, before any predicates or limits are applied.
+    /// Returns `None` if the row count of any of the chunks isn't known.
+    pub fn estimate_rows<D: Database + 'static>(
+        &self,
+        database: &D,
+        query: &str,
+    ) -> Result<Option<u64>> {
+        let table_names = table_names(query)?;
+
+        let partition_keys = database
+            .partition_keys()
+            .map_err(|e| Box::new(e) as _)
+            .context(GettingDatabasePartition)?;
+
+        let mut rows = 0;
+        for table_name in &table_names {
+            for partition_key in &partition_keys {
+                for chunk in database.chunks(partition_key) {
+                    if chunk.has_table(table_name) {
+                        match chunk.table_rows(table_name) {
+                            Some(chunk_rows) => rows += chunk_rows,
+                            None => return Ok(None),
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Some(rows))
+    }
 }
 
 use sqlparser::{
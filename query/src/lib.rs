@@ -6,12 +6,14 @@
     clippy::clone_on_ref_ptr
 )]
 
-use arrow_deps::datafusion::physical_plan::SendableRecordBatchStream;
+use arrow_deps::{
+    arrow::record_batch::RecordBatch, datafusion::physical_plan::SendableRecordBatchStream,
+};
 use async_trait::async_trait;
 use data_types::{
     data::ReplicatedWrite, partition_metadata::TableSummary, schema::Schema, selection::Selection,
 };
-use exec::{stringset::StringSet, Executor};
+use exec::{stringset::StringSet, tagset::TagSets, Executor};
 
 use std::{fmt::Debug, sync::Arc};
 
@@ -24,7 +26,7 @@ pub mod predicate;
 pub mod provider;
 pub mod util;
 
-use self::predicate::Predicate;
+use self::{group_by::Aggregate, predicate::Predicate};
 
 /// A `Database` is the main trait implemented by the IOx subsystems
 /// that store actual data.
@@ -76,6 +78,13 @@ pub trait PartitionChunk: Debug + Send + Sync {
     /// Returns true if this chunk contains data for the specified table
     fn has_table(&self, table_name: &str) -> bool;
 
+    /// Returns the number of rows of the specified table in this chunk, if it
+    /// is known without reading the data. Used to estimate the size of query
+    /// results before running them.
+    fn table_rows(&self, _table_name: &str) -> Option<u64> {
+        None
+    }
+
     /// Returns all table names from this chunk that have at least one
     /// row that matches the `predicate` and are not already in `known_tables`.
     ///
@@ -115,6 +124,15 @@ pub trait PartitionChunk: Debug + Send + Sync {
         predicate: &Predicate,
     ) -> Result<Option<StringSet>, Self::Error>;
 
+    /// Returns the distinct tag sets of the rows of the specified
+    /// table that match `predicate`, if the predicate can be evaluated
+    /// entirely on the metadata of this Chunk. Returns `None` otherwise
+    async fn tag_sets(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+    ) -> Result<Option<TagSets>, Self::Error>;
+
     /// Returns the Schema for a table in this chunk, with the
     /// specified column selection. An error is returned if the
     /// selection refers to columns that do not exist.
@@ -125,10 +143,11 @@ pub trait PartitionChunk: Debug + Send + Sync {
     ) -> Result<Schema, Self::Error>;
 
     /// Provides access to raw `PartitionChunk` data as an
-    /// asynchronous stream of `RecordBatch`es filtered by a *required*
+    /// asynchronous stream of `RecordBatch`es filtered by a
     /// predicate. Note that not all chunks can evaluate all types of
-    /// predicates and this function will return an error
-    /// if requested to evaluate with a predicate that is not supported
+    /// predicates, so the stream contains *at least* the rows that pass
+    /// the predicate and the caller must still filter the rows. The
+    /// predicate lets chunks skip data that can't match.
     ///
     /// This is the analog of the `TableProvider` in DataFusion
     ///
@@ -143,6 +162,29 @@ pub trait PartitionChunk: Debug + Send + Sync {
         predicate: &Predicate,
         selection: Selection<'_>,
     ) -> Result<SendableRecordBatchStream, Self::Error>;
+
+    /// Returns the aggregates of the rows of the specified table that
+    /// match `predicate`, grouped by the values of `group_columns` and,
+    /// if a `window` (a duration in nanoseconds) is given, by the window
+    /// of time the rows fall in, if the chunk can compute them itself.
+    /// Returns `None` otherwise, in which case the caller aggregates the
+    /// output of `read_filter` instead.
+    ///
+    /// The returned batches have one column for each of the
+    /// `group_columns`, then a `time` column with the (exclusive) stop
+    /// time of the window if `window` is given, then one column for each
+    /// of the `aggregates`, in the requested order. All of the columns
+    /// must exist in the table.
+    async fn read_aggregate(
+        &self,
+        _table_name: &str,
+        _predicate: &Predicate,
+        _group_columns: &[String],
+        _aggregates: &[(String, Aggregate)],
+        _window: Option<u64>,
+    ) -> Result<Option<Vec<RecordBatch>>, Self::Error> {
+        Ok(None)
+    }
 }
 
 #[async_trait]
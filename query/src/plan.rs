@@ -1,3 +1,4 @@
 pub mod fieldlist;
+pub mod seriescardinality;
 pub mod seriesset;
 pub mod stringset;
@@ -0,0 +1,47 @@
+use std::collections::BTreeMap;
+
+use arrow_deps::datafusion::logical_plan::LogicalPlan;
+
+use crate::exec::tagset::TagSets;
+
+/// A plan which counts the distinct series (tag sets) of each table.
+/// Tag sets which are known from metadata only are included directly,
+/// while the others are found by running DataFusion plans.
+#[derive(Debug, Default)]
+pub struct SeriesCardinalityPlan {
+    /// The tag sets of each table that are known without having to
+    /// run a plan, keyed by table name
+    pub known: BTreeMap<String, TagSets>,
+
+    /// Plans whose output rows provide additional tag sets
+    pub plans: Vec<TagSetPlan>,
+}
+
+/// A DataFusion plan whose output rows are the tag sets of a table.
+/// The values produced by the plan may be repeated.
+#[derive(Debug)]
+pub struct TagSetPlan {
+    pub table_name: String,
+
+    /// The names of the tag columns produced by the plan, which must
+    /// have Utf8 type. Other columns produced by the plan are ignored.
+    pub tag_columns: Vec<String>,
+
+    pub plan: LogicalPlan,
+}
+
+impl SeriesCardinalityPlan {
+    /// Create a plan from the tag sets known from metadata
+    pub fn new(known: BTreeMap<String, TagSets>) -> Self {
+        Self {
+            known,
+            plans: vec![],
+        }
+    }
+
+    /// Append a new plan to this list of plans
+    pub fn append(mut self, plan: TagSetPlan) -> Self {
+        self.plans.push(plan);
+        self
+    }
+}
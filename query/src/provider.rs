@@ -11,12 +11,12 @@ use arrow_deps::{
         },
         error::{DataFusionError, Result as DataFusionResult},
         logical_plan::Expr,
-        physical_plan::ExecutionPlan,
+        physical_plan::{memory::MemoryExec, ExecutionPlan},
     },
 };
 use data_types::schema::{builder::SchemaMerger, Schema};
 
-use crate::{predicate::Predicate, util::project_schema, PartitionChunk};
+use crate::{predicate::PredicateBuilder, util::project_schema, PartitionChunk};
 
 use snafu::{ResultExt, Snafu};
 
@@ -122,6 +122,29 @@ impl<C: PartitionChunk> ProviderBuilder<C> {
         })
     }
 
+    /// Adds the columns of `schema` to the table schema without adding a
+    /// chunk, so that the table has the columns of chunks that are read
+    /// some other way. Chunks without a column produce nulls for it.
+    pub fn merge_schema(self, schema: Schema) -> Result<Self> {
+        let Self {
+            table_name,
+            schema_merger,
+            chunk_and_infos,
+        } = self;
+
+        let schema_merger = schema_merger
+            .merge(schema)
+            .context(ChunkSchemaNotCompatible {
+                table_name: table_name.as_ref(),
+            })?;
+
+        Ok(Self {
+            table_name,
+            schema_merger,
+            chunk_and_infos,
+        })
+    }
+
     pub fn build(self) -> Result<ChunkTableProvider<C>> {
         let Self {
             table_name,
@@ -192,24 +215,46 @@ impl<C: PartitionChunk + 'static> TableProvider for ChunkTableProvider<C> {
         &self,
         projection: &Option<Vec<usize>>,
         _batch_size: usize,
-        _filters: &[Expr],
+        filters: &[Expr],
     ) -> std::result::Result<Arc<dyn ExecutionPlan>, DataFusionError> {
-        // TODO Here is where predicate pushdown will happen.  To make
-        // predicate push down happen, the provider need need to
-        // create a Predicate from the Expr .
-        //
-        // Note that `filters` don't actually need to be evaluated in
-        // the scan for the plans to be correct, they are an extra
-        // optimization for providers which can offer them
-        let predicate = Predicate::default();
+        // Push the filters down to the chunks so they can skip data
+        // that can't match. The chunks may still return rows that
+        // don't pass the filters, which DataFusion filters out (see
+        // `supports_filter_pushdown`)
+        let predicate = filters
+            .iter()
+            .fold(
+                PredicateBuilder::default().table(self.table_name.as_ref()),
+                |builder, expr| builder.add_expr(expr.clone()),
+            )
+            .build();
 
         // Figure out the schema of the requested output
         let scan_schema = project_schema(self.arrow_schema(), projection);
 
+        // Don't scan the chunks that can't have any matching rows
+        let mut chunk_and_infos = Vec::with_capacity(self.chunk_and_infos.len());
+        for chunk_info in &self.chunk_and_infos {
+            let could_pass_predicate = chunk_info
+                .chunk
+                .could_pass_predicate(&predicate)
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+            if could_pass_predicate {
+                chunk_and_infos.push(chunk_info.clone());
+            }
+        }
+
+        // A plan needs at least one partition to be executed
+        if chunk_and_infos.is_empty() {
+            let plan = MemoryExec::try_new(&[vec![]], scan_schema, None)?;
+            return Ok(Arc::new(plan));
+        }
+
         let plan = IOxReadFilterNode::new(
             Arc::clone(&self.table_name),
             scan_schema,
-            self.chunk_and_infos.clone(),
+            chunk_and_infos,
             predicate,
         );
 
@@ -221,10 +266,12 @@ impl<C: PartitionChunk + 'static> TableProvider for ChunkTableProvider<C> {
         Statistics::default()
     }
 
+    /// The chunks use the filters to skip data, but don't guarantee
+    /// that all the rows they return pass them
     fn supports_filter_pushdown(
         &self,
         _filter: &Expr,
     ) -> DataFusionResult<TableProviderFilterPushDown> {
-        Ok(TableProviderFilterPushDown::Unsupported)
+        Ok(TableProviderFilterPushDown::Inexact)
     }
 }
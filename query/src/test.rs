@@ -15,6 +15,7 @@ use arrow_deps::{
 use crate::exec::Executor;
 use crate::{
     exec::stringset::{StringSet, StringSetRef},
+    exec::tagset::TagSets,
     Database, DatabaseStore, PartitionChunk, Predicate,
 };
 
@@ -31,7 +32,7 @@ use influxdb_line_protocol::{parse_lines, ParsedLine};
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use data_types::database_rules::Partitioner;
+use data_types::database_rules::{PartitionRow, Partitioner};
 use parking_lot::Mutex;
 use snafu::{OptionExt, ResultExt, Snafu};
 use std::{collections::BTreeMap, sync::Arc};
@@ -160,7 +161,8 @@ impl Database for TestDatabase {
 pub struct TestChunk {
     id: u32,
 
-    /// A copy of the captured predicate passed
+    /// A copy of the captured predicate passed to the metadata
+    /// functions
     predicate: Mutex<Option<Predicate>>,
 
     /// Column names: table_name -> Schema
@@ -352,13 +354,13 @@ impl PartitionChunk for TestChunk {
     async fn read_filter(
         &self,
         table_name: &str,
-        predicate: &Predicate,
+        _predicate: &Predicate,
         _selection: Selection<'_>,
     ) -> Result<SendableRecordBatchStream, Self::Error> {
         self.check_error()?;
 
-        // save the predicate
-        self.predicate.lock().replace(predicate.clone());
+        // The predicate isn't saved, as it only holds the filters
+        // DataFusion chose to push down into the scan
 
         let batches = self.table_data.get(table_name).expect("Table had data");
         let stream = SizedRecordBatchStream::new(batches[0].schema(), batches.clone());
@@ -413,6 +415,15 @@ impl PartitionChunk for TestChunk {
         Ok(None)
     }
 
+    async fn tag_sets(
+        &self,
+        _table_name: &str,
+        _predicate: &Predicate,
+    ) -> Result<Option<TagSets>, Self::Error> {
+        // Model not being able to get tag sets from metadata
+        Ok(None)
+    }
+
     fn has_table(&self, table_name: &str) -> bool {
         self.table_schemas.contains_key(table_name)
     }
@@ -593,7 +604,7 @@ struct TestPartitioner {
 impl Partitioner for TestPartitioner {
     fn partition_key(
         &self,
-        _line: &ParsedLine<'_>,
+        _row: &dyn PartitionRow,
         _default_time: &DateTime<Utc>,
     ) -> data_types::database_rules::Result<String> {
         Ok(self.key.clone())
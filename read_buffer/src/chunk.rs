@@ -8,7 +8,7 @@ use snafu::{ResultExt, Snafu};
 
 use crate::row_group::RowGroup;
 use crate::row_group::{ColumnName, Predicate};
-use crate::schema::AggregateType;
+use crate::schema::{AggregateType, ResultSchema};
 use crate::table;
 use crate::table::Table;
 
@@ -105,6 +105,21 @@ impl Chunk {
         self.chunk_data.read().unwrap().rows
     }
 
+    /// The minimum and maximum timestamps of all tables in this chunk, or
+    /// `None` if it has no rows.
+    pub fn time_range(&self) -> Option<(i64, i64)> {
+        self.chunk_data
+            .read()
+            .unwrap()
+            .data
+            .values()
+            .filter_map(|table| table.time_range())
+            .fold(None, |range, (min, max)| match range {
+                Some((range_min, range_max)) => Some((range_min.min(min), range_max.max(max))),
+                None => Some((min, max)),
+            })
+    }
+
     /// The total number of row groups in all tables in this chunk.
     pub fn row_groups(&self) -> usize {
         self.chunk_data.read().unwrap().row_groups
@@ -124,6 +139,29 @@ impl Chunk {
             .contains_key(table_name)
     }
 
+    /// Determines if the table could have rows that satisfy the predicate,
+    /// using only the meta data of its row groups. Returns `false` if the
+    /// chunk doesn't contain data for this table.
+    pub fn could_pass_predicate(&self, table_name: &str, predicate: &Predicate) -> bool {
+        self.chunk_data
+            .read()
+            .unwrap()
+            .data
+            .get(table_name)
+            .map_or(false, |table| table.could_satisfy_predicate(predicate))
+    }
+
+    /// The total number of rows in all row groups of the table, or `None` if
+    /// the chunk doesn't contain data for this table.
+    pub fn table_rows(&self, table_name: &str) -> Option<u64> {
+        self.chunk_data
+            .read()
+            .unwrap()
+            .data
+            .get(table_name)
+            .map(|table| table.rows())
+    }
+
     /// Returns true if there are no tables under this chunk.
     pub fn is_empty(&self) -> bool {
         self.chunk_data.read().unwrap().data.len() == 0
@@ -191,6 +229,27 @@ impl Chunk {
         Ok(table.read_filter(select_columns, predicate))
     }
 
+    /// Returns the schema of the results of `read_filter` on the provided
+    /// table for the specified column selections, without executing it.
+    /// Returns an error if the specified table does not exist.
+    pub fn read_filter_schema(
+        &self,
+        table_name: &str,
+        select_columns: &Selection<'_>,
+    ) -> Result<ResultSchema, Error> {
+        // read lock on chunk.
+        let chunk_data = self.chunk_data.read().unwrap();
+
+        let table = chunk_data
+            .data
+            .get(table_name)
+            .ok_or(Error::TableNotFound {
+                table_name: table_name.to_owned(),
+            })?;
+
+        Ok(table.read_filter_schema(select_columns))
+    }
+
     /// Returns an iterable collection of data in group columns and aggregate
     /// columns, optionally filtered by the provided predicate. Results are
     /// merged across all row groups within the returned table.
@@ -218,6 +277,27 @@ impl Chunk {
             .map(|table| table.read_aggregate(predicate, group_columns, aggregates))
     }
 
+    /// As `read_aggregate`, but the rows are also grouped by the window of
+    /// time, `window` nanoseconds long, that they fall in.
+    ///
+    /// Returns `None` if the table no longer exists within the chunk.
+    pub fn read_window_aggregate(
+        &self,
+        table_name: &str,
+        predicate: Predicate,
+        group_columns: &Selection<'_>,
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+        window: i64,
+    ) -> Option<table::ReadAggregateResults> {
+        // read lock on chunk.
+        let chunk_data = self.chunk_data.read().unwrap();
+
+        chunk_data
+            .data
+            .get(table_name)
+            .map(|table| table.read_window_aggregate(predicate, group_columns, aggregates, window))
+    }
+
     //
     // ---- Schema API queries
     //
@@ -314,6 +394,22 @@ impl Chunk {
             None => Ok(dst),
         }
     }
+
+    /// Adds the distinct tag sets of the table's rows matching the provided
+    /// predicate to `dst`. A tag set holds the names and values of a row's
+    /// non-null tag columns, ordered by name.
+    pub fn tag_sets(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        dst: &mut BTreeSet<Vec<(String, String)>>,
+    ) {
+        let chunk_data = self.chunk_data.read().unwrap();
+
+        if let Some(table) = chunk_data.data.get(table_name) {
+            table.tag_sets(predicate, dst);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -49,14 +49,17 @@ pub enum Error {
     #[snafu(display("chunk id does not exist: {}", id))]
     ChunkNotFound { id: u32 },
 
+    #[snafu(display("chunk id already exists: {}", id))]
+    ChunkAlreadyExists { id: u32 },
+
     #[snafu(display("table does not exist: {}", table_name))]
     TableNotFound { table_name: String },
 
     #[snafu(display("unsupported operation: {}", msg))]
     UnsupportedOperation { msg: String },
 
-    #[snafu(display("unsupported aggregate: {}", agg))]
-    UnsupportedAggregate { agg: AggregateType },
+    #[snafu(display("invalid window: {}, must be positive", window))]
+    InvalidWindow { window: u64 },
 
     #[snafu(display("error processing chunk: {}", source))]
     ChunkError { source: chunk::Error },
@@ -154,6 +157,67 @@ impl Database {
         })
     }
 
+    /// Replaces the specified chunks of a partition with a new chunk holding
+    /// the provided table data. Each record batch becomes a row group of its
+    /// table. Readers see either all of the old chunks or the new one, never
+    /// both. The partition is created if it doesn't exist and no chunks are
+    /// replaced.
+    pub fn replace_chunks(
+        &self,
+        partition_key: &str,
+        old_chunk_ids: &[u32],
+        new_chunk_id: u32,
+        tables: Vec<(String, RecordBatch)>,
+    ) -> Result<()> {
+        // This is expensive. Complete it before locking.
+        let mut new_chunk: Option<Chunk> = None;
+        for (table_name, table_data) in tables {
+            let row_group = RowGroup::from(table_data);
+            match new_chunk.as_mut() {
+                Some(chunk) => chunk.upsert_table(table_name, row_group),
+                None => {
+                    new_chunk = Some(Chunk::new(new_chunk_id, Table::new(table_name, row_group)))
+                }
+            }
+        }
+
+        let mut guard = self.data.write().unwrap();
+        let partition_data = &mut *guard;
+
+        let partition = match partition_data.partitions.entry(partition_key.to_owned()) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                if let Some(&id) = old_chunk_ids.first() {
+                    return ChunkNotFound { id }.fail();
+                }
+                if let Some(chunk) = new_chunk {
+                    partition_data.rows += chunk.rows();
+                    e.insert(Partition::new(partition_key, chunk));
+                }
+                return Ok(());
+            }
+        };
+
+        // check everything before changing anything
+        let chunk_ids = partition.chunk_ids();
+        for &id in old_chunk_ids {
+            ensure!(chunk_ids.contains(&id), ChunkNotFound { id });
+        }
+        ensure!(
+            !chunk_ids.contains(&new_chunk_id) || old_chunk_ids.contains(&new_chunk_id),
+            ChunkAlreadyExists { id: new_chunk_id }
+        );
+
+        let mut removed_rows = 0;
+        for &id in old_chunk_ids {
+            removed_rows += partition.drop_chunk(id)?.rows();
+        }
+        let added_rows = new_chunk.map_or(0, |chunk| partition.insert_chunk(chunk));
+
+        partition_data.rows = partition_data.rows - removed_rows + added_rows;
+        Ok(())
+    }
+
     /// Clones and returns all partition keys with data for this database.
     pub fn partition_keys(&self) -> Vec<String> {
         self.data
@@ -177,6 +241,18 @@ impl Database {
             .unwrap_or_default()
     }
 
+    /// Returns the estimated size, number of rows and time range of the
+    /// specified chunk.
+    pub fn chunk_stats(&self, partition_key: &str, chunk_id: u32) -> Result<ChunkStats> {
+        let partition_data = self.data.read().unwrap();
+
+        partition_data
+            .partitions
+            .get(partition_key)
+            .context(PartitionNotFound { key: partition_key })?
+            .chunk_stats(chunk_id)
+    }
+
     /// Returns the total estimated size in bytes of the database.
     pub fn size(&self) -> u64 {
         let base_size = std::mem::size_of::<Self>();
@@ -229,6 +305,41 @@ impl Database {
         }
     }
 
+    /// Returns false if none of the specified chunks could have rows of the
+    /// table that satisfy the predicate. This is decided from the meta data
+    /// of the chunks' row groups, so no column data is read and `true` does
+    /// not guarantee there are matching rows.
+    ///
+    /// Every column the predicate refers to must exist in the table, and be
+    /// compared with a literal of the column's type.
+    pub fn could_pass_predicate(
+        &self,
+        partition_key: &str,
+        table_name: &str,
+        chunk_ids: &[u32],
+        predicate: Predicate,
+    ) -> bool {
+        let partition_data = self.data.read().unwrap();
+
+        if let Some(partition) = partition_data.partitions.get(partition_key) {
+            partition.could_pass_predicate(table_name, chunk_ids, &predicate)
+        } else {
+            false
+        }
+    }
+
+    /// Returns the total number of rows of the table in the specified chunks
+    /// of the partition.
+    pub fn table_rows(&self, partition_key: &str, table_name: &str, chunk_ids: &[u32]) -> u64 {
+        let partition_data = self.data.read().unwrap();
+
+        partition_data
+            .partitions
+            .get(partition_key)
+            .map(|partition| partition.table_rows(table_name, chunk_ids))
+            .unwrap_or_default()
+    }
+
     /// Returns rows for the specified columns in the provided table, for the
     /// specified partition key and chunks within that partition.
     ///
@@ -286,6 +397,44 @@ impl Database {
         }
     }
 
+    /// Returns the schema of the record batches `read_filter` emits for the
+    /// specified columns of the table in the specified chunks of the
+    /// partition. Only the meta data of the chunks' tables is read, so this
+    /// is cheap compared to preparing a `read_filter`.
+    pub fn read_filter_schema(
+        &self,
+        partition_key: &str,
+        table_name: &str,
+        chunk_ids: &[u32],
+        select_columns: Selection<'_>,
+    ) -> Result<Schema> {
+        // Get read lock on database's partitions.
+        let partition_data = self.data.read().unwrap();
+        let partition = partition_data
+            .partitions
+            .get(partition_key)
+            .context(PartitionNotFound { key: partition_key })?;
+
+        // Get read lock on partition's chunks.
+        let chunk_data = partition.data.read().unwrap();
+        let mut table_schemas = vec![];
+        for chunk_id in chunk_ids {
+            let chunk = chunk_data
+                .chunks
+                .get(chunk_id)
+                .context(ChunkNotFound { id: *chunk_id })?;
+
+            ensure!(chunk.has_table(table_name), TableNotFound { table_name });
+
+            let table_schema = chunk
+                .read_filter_schema(table_name, &select_columns)
+                .context(ChunkError)?;
+            table_schemas.push(table_schema);
+        }
+
+        merge_result_schemas(table_schemas.iter())
+    }
+
     /// Returns aggregates for each group specified by the values of the
     /// grouping keys, limited to the specified partition key table name and
     /// chunk ids.
@@ -302,7 +451,7 @@ impl Database {
     /// applied to the same column.
     ///
     /// This method might be deprecated in the future, replaced by a call to
-    /// `read_aggregate_window` with a `window` of `0`.
+    /// `read_window_aggregate` without a window.
     pub fn read_aggregate<'input>(
         &self,
         partition_key: &str,
@@ -312,15 +461,74 @@ impl Database {
         group_columns: Selection<'input>,
         aggregates: Vec<(ColumnName<'input>, AggregateType)>,
     ) -> Result<ReadAggregateResults> {
-        for (_, agg) in &aggregates {
-            match agg {
-                AggregateType::First | AggregateType::Last => {
-                    return Err(Error::UnsupportedAggregate { agg: *agg });
-                }
-                _ => {}
-            }
-        }
+        self.aggregate(
+            partition_key,
+            table_name,
+            chunk_ids,
+            predicate,
+            group_columns,
+            aggregates,
+            None,
+        )
+    }
 
+    /// Returns windowed aggregates for each group specified by the values of
+    /// the grouping keys and window, limited to the specified partition key
+    /// table name and chunk ids.
+    ///
+    /// Results may be filtered by conjunctive predicates.
+    /// Whilst the `ReadBuffer` will carry out the most optimal execution
+    /// possible by pruning columns, row groups and tables, it is assumed
+    /// that the caller has already provided an appropriately pruned
+    /// collection of chunks.
+    ///
+    /// Currently, only grouping by string (tag key) columns is supported.
+    /// Required aggregates are specified via a tuple comprising a column name
+    /// and the type of aggregation required. Multiple aggregations can be
+    /// applied to the same column.
+    ///
+    /// `window` should be a positive value indicating a duration in
+    /// nanoseconds. Windows are aligned to the epoch, and each row of the
+    /// results has the (exclusive) stop time of its window in a "time" column
+    /// following the group columns.
+    pub fn read_window_aggregate<'input>(
+        &self,
+        partition_key: &str,
+        table_name: &'input str,
+        chunk_ids: &[u32],
+        predicate: Predicate,
+        group_columns: Selection<'input>,
+        aggregates: Vec<(ColumnName<'input>, AggregateType)>,
+        window: u64,
+    ) -> Result<ReadWindowAggregateResults> {
+        ensure!(
+            window > 0 && window <= i64::MAX as u64,
+            InvalidWindow { window }
+        );
+
+        let results = self.aggregate(
+            partition_key,
+            table_name,
+            chunk_ids,
+            predicate,
+            group_columns,
+            aggregates,
+            Some(window as i64),
+        )?;
+
+        Ok(ReadWindowAggregateResults { results })
+    }
+
+    fn aggregate<'input>(
+        &self,
+        partition_key: &str,
+        table_name: &'input str,
+        chunk_ids: &[u32],
+        predicate: Predicate,
+        group_columns: Selection<'input>,
+        aggregates: Vec<(ColumnName<'input>, AggregateType)>,
+        window: Option<i64>,
+    ) -> Result<ReadAggregateResults> {
         // get read lock on database
         let partition_data = self.data.read().unwrap();
         let mut chunk_table_results = vec![];
@@ -344,9 +552,19 @@ impl Database {
             // Get all relevant row groups for this chunk's table. This
             // is cheap because it doesn't execute the read operation,
             // but just gets references to the needed to data to do so.
-            if let Some(table_results) =
-                chunk.read_aggregate(table_name, predicate.clone(), &group_columns, &aggregates)
-            {
+            let table_results = match window {
+                Some(window) => chunk.read_window_aggregate(
+                    table_name,
+                    predicate.clone(),
+                    &group_columns,
+                    &aggregates,
+                    window,
+                ),
+                None => {
+                    chunk.read_aggregate(table_name, predicate.clone(), &group_columns, &aggregates)
+                }
+            };
+            if let Some(table_results) = table_results {
                 chunk_table_results.push(table_results);
             }
         }
@@ -354,38 +572,6 @@ impl Database {
         Ok(ReadAggregateResults::new(chunk_table_results))
     }
 
-    /// Returns windowed aggregates for each group specified by the values of
-    /// the grouping keys and window, limited to the specified partition key
-    /// table name and chunk ids.
-    ///
-    /// Results may be filtered by conjunctive predicates.
-    /// Whilst the `ReadBuffer` will carry out the most optimal execution
-    /// possible by pruning columns, row groups and tables, it is assumed
-    /// that the caller has already provided an appropriately pruned
-    /// collection of chunks.
-    ///
-    /// Currently, only grouping by string (tag key) columns is supported.
-    /// Required aggregates are specified via a tuple comprising a column name
-    /// and the type of aggregation required. Multiple aggregations can be
-    /// applied to the same column.
-    ///
-    /// `window` should be a positive value indicating a duration in
-    /// nanoseconds.
-    pub fn read_window_aggregate(
-        &self,
-        _partition_key: &str,
-        _table_name: &str,
-        _chunk_ids: &[u32],
-        _predicate: Predicate,
-        _group_columns: Selection<'_>,
-        _aggregates: Vec<(ColumnName<'_>, AggregateType)>,
-        _window: u64,
-    ) -> Result<ReadWindowAggregateResults> {
-        Err(Error::UnsupportedOperation {
-            msg: "`read_aggregate_window` not yet implemented".to_owned(),
-        })
-    }
-
     //
     // ---- Schema API queries
     //
@@ -526,6 +712,39 @@ impl Database {
 
         Ok(values)
     }
+
+    /// Returns the distinct tag sets of the table's rows that satisfy the
+    /// provided predicate. A tag set holds the names and values of the tag
+    /// columns that are non-null in a row, ordered by name, so each tag set
+    /// identifies a series.
+    pub fn tag_sets(
+        &self,
+        partition_key: &str,
+        table_name: &str,
+        chunk_ids: &[u32],
+        predicate: Predicate,
+    ) -> Result<BTreeSet<Vec<(String, String)>>> {
+        let partition_data = self.data.read().unwrap();
+
+        let partition = partition_data
+            .partitions
+            .get(partition_key)
+            .ok_or_else(|| Error::PartitionNotFound {
+                key: partition_key.to_owned(),
+            })?;
+
+        let chunk_data = partition.data.read().unwrap();
+        let mut tag_sets = BTreeSet::new();
+        for id in chunk_ids {
+            chunk_data
+                .chunks
+                .get(id)
+                .ok_or_else(|| Error::ChunkNotFound { id: *id })?
+                .tag_sets(table_name, &predicate, &mut tag_sets);
+        }
+
+        Ok(tag_sets)
+    }
 }
 
 impl fmt::Debug for Database {
@@ -598,6 +817,17 @@ impl Partition {
         };
     }
 
+    // Adds a new chunk to the partition, returning its number of rows.
+    fn insert_chunk(&mut self, chunk: Chunk) -> u64 {
+        let mut chunk_data = self.data.write().unwrap();
+
+        let rows = chunk.rows();
+        chunk_data.rows += rows;
+        chunk_data.row_groups += chunk.row_groups();
+        chunk_data.chunks.insert(chunk.id(), chunk);
+        rows
+    }
+
     // Drops the chunk and all associated data.
     fn drop_chunk(&mut self, chunk_id: u32) -> Result<Chunk> {
         let mut chunk_data = self.data.write().unwrap();
@@ -617,6 +847,20 @@ impl Partition {
         self.data.read().unwrap().chunks.keys().cloned().collect()
     }
 
+    fn chunk_stats(&self, chunk_id: u32) -> Result<ChunkStats> {
+        let chunk_data = self.data.read().unwrap();
+        let chunk = chunk_data
+            .chunks
+            .get(&chunk_id)
+            .context(ChunkNotFound { id: chunk_id })?;
+
+        Ok(ChunkStats {
+            size: chunk.size(),
+            rows: chunk.rows(),
+            time_range: chunk.time_range(),
+        })
+    }
+
     /// Determines the total number of tables under all chunks within the
     /// partition. Useful for tests but not something that is highly performant.
     fn tables(&self) -> usize {
@@ -639,6 +883,33 @@ impl Partition {
             .any(|chunk| chunk.has_table(table_name))
     }
 
+    /// returns false if none of the chunks could have rows of the table that
+    /// satisfy the predicate
+    pub fn could_pass_predicate(
+        &self,
+        table_name: &str,
+        chunk_ids: &[u32],
+        predicate: &Predicate,
+    ) -> bool {
+        let chunk_data = self.data.read().unwrap();
+
+        chunk_ids
+            .iter()
+            .filter_map(|chunk_id| chunk_data.chunks.get(chunk_id))
+            .any(|chunk| chunk.could_pass_predicate(table_name, predicate))
+    }
+
+    /// returns the number of rows of the table in the specified chunks
+    pub fn table_rows(&self, table_name: &str, chunk_ids: &[u32]) -> u64 {
+        let chunk_data = self.data.read().unwrap();
+
+        chunk_ids
+            .iter()
+            .filter_map(|chunk_id| chunk_data.chunks.get(chunk_id))
+            .filter_map(|chunk| chunk.table_rows(table_name))
+            .sum()
+    }
+
     /// Determines the total number of row groups under all tables under all
     /// chunks, within the partition.
     pub fn row_groups(&self) -> usize {
@@ -664,6 +935,18 @@ impl Partition {
     }
 }
 
+/// The estimated size, number of rows and time range of a chunk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkStats {
+    /// The total estimated size in bytes of the chunk and its data
+    pub size: u64,
+    /// The total number of rows in all tables of the chunk
+    pub rows: u64,
+    /// The minimum and maximum timestamps of the chunk's rows, or `None` if
+    /// it has no rows
+    pub time_range: Option<(i64, i64)>,
+}
+
 /// ReadFilterResults implements ...
 pub struct ReadFilterResults {
     // The table results for all chunks being executed against
@@ -694,21 +977,28 @@ impl ReadFilterResults {
     /// Return the union of the schemas that this result will produce,
     /// or an Error if they are not compatible
     pub fn schema(&self) -> Result<Schema> {
-        let builder = self.all_chunks_table_results.iter().try_fold(
-            SchemaMerger::new(),
-            |builder, table_result| {
-                let table_schema = table_result.schema();
+        merge_result_schemas(
+            self.all_chunks_table_results
+                .iter()
+                .map(|table_result| table_result.schema()),
+        )
+    }
+}
 
-                let schema: Schema = table_schema.try_into().context(BuildingSchema)?;
+/// Returns the union of the schemas of the results of several tables, or an
+/// Error if they are not compatible
+fn merge_result_schemas<'a>(
+    table_schemas: impl Iterator<Item = &'a ResultSchema>,
+) -> Result<Schema> {
+    let builder = table_schemas.try_fold(SchemaMerger::new(), |builder, table_schema| {
+        let schema: Schema = table_schema.try_into().context(BuildingSchema)?;
 
-                let builder = builder.merge(schema).context(BuildingSchema)?;
+        let builder = builder.merge(schema).context(BuildingSchema)?;
 
-                Ok(builder)
-            },
-        )?;
+        Ok(builder)
+    })?;
 
-        builder.build().context(BuildingSchema)
-    }
+    builder.build().context(BuildingSchema)
 }
 
 impl Iterator for ReadFilterResults {
@@ -776,15 +1066,17 @@ impl Iterator for ReadAggregateResults {
 
 /// An iterable set of results for calls to `read_window_aggregate`.
 ///
-/// There may be some internal buffering and merging of results before a record
-/// batch is emitted from the iterator.
-pub struct ReadWindowAggregateResults {}
+/// As with `ReadAggregateResults`, the caller can expect at most one record
+/// batch to be yielded for each chunk.
+pub struct ReadWindowAggregateResults {
+    results: ReadAggregateResults,
+}
 
 impl Iterator for ReadWindowAggregateResults {
     type Item = RecordBatch;
 
     fn next(&mut self) -> Option<Self::Item> {
-        None
+        self.results.next()
     }
 }
 
@@ -840,6 +1132,73 @@ mod test {
         RecordBatch::try_new(schema, data).unwrap()
     }
 
+    #[test]
+    fn database_chunk_stats() {
+        let db = Database::new();
+        db.upsert_partition("hour_1", 22, "a_table", gen_recordbatch());
+        db.upsert_partition("hour_1", 22, "b_table", gen_recordbatch());
+
+        let stats = db.chunk_stats("hour_1", 22).unwrap();
+        assert_eq!(stats.rows, 6);
+        assert_eq!(stats.time_range, Some((3333, 11111111)));
+        assert!(stats.size > 0);
+
+        assert!(matches!(
+            db.chunk_stats("hour_1", 23),
+            Err(Error::ChunkNotFound { id: 23 })
+        ));
+        assert!(matches!(
+            db.chunk_stats("hour_2", 22),
+            Err(Error::PartitionNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn database_replace_chunks() {
+        let db = Database::new();
+        db.upsert_partition("hour_1", 22, "a_table", gen_recordbatch());
+        db.upsert_partition("hour_1", 23, "b_table", gen_recordbatch());
+        db.upsert_partition("hour_1", 24, "a_table", gen_recordbatch());
+        assert_eq!(db.rows(), 9);
+
+        db.replace_chunks(
+            "hour_1",
+            &[22, 23],
+            22,
+            vec![
+                ("a_table".to_string(), gen_recordbatch()),
+                ("b_table".to_string(), gen_recordbatch()),
+                ("b_table".to_string(), gen_recordbatch()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(db.chunk_ids("hour_1"), vec![22, 24]);
+        assert_eq!(db.chunk_stats("hour_1", 22).unwrap().rows, 9);
+        assert_eq!(db.rows(), 12);
+
+        // nothing changes if a chunk is missing or the new id is taken
+        assert!(matches!(
+            db.replace_chunks("hour_1", &[22, 25], 22, vec![]),
+            Err(Error::ChunkNotFound { id: 25 })
+        ));
+        assert!(matches!(
+            db.replace_chunks("hour_1", &[22], 24, vec![]),
+            Err(Error::ChunkAlreadyExists { id: 24 })
+        ));
+        assert_eq!(db.chunk_ids("hour_1"), vec![22, 24]);
+
+        // a new partition is created for chunks that replace none
+        db.replace_chunks(
+            "hour_2",
+            &[],
+            1,
+            vec![("a_table".to_string(), gen_recordbatch())],
+        )
+        .unwrap();
+        assert_eq!(db.chunk_ids("hour_2"), vec![1]);
+        assert_eq!(db.rows(), 15);
+    }
+
     #[test]
     fn database_add_drop_row_groups() {
         let mut db = Database::new();
@@ -1078,6 +1437,51 @@ mod test {
         assert_eq!(data, to_set(&["20 Size", "Coolverine"]));
     }
 
+    #[test]
+    fn could_pass_predicate() {
+        let db = Database::new();
+        db.upsert_partition("hour_1", 22, "Coolverine", gen_recordbatch());
+
+        let cases = vec![
+            (Predicate::default(), true),
+            (
+                Predicate::new(vec![BinaryExpr::from(("region", "=", "north"))]),
+                true,
+            ),
+            (
+                Predicate::new(vec![BinaryExpr::from(("region", ">", "west"))]),
+                false,
+            ),
+            (
+                Predicate::new(vec![
+                    BinaryExpr::from(("region", "=", "west")),
+                    BinaryExpr::from(("counter", ">", 40.0)),
+                ]),
+                true,
+            ),
+            (
+                Predicate::new(vec![BinaryExpr::from(("counter", ">", 50.0))]),
+                false,
+            ),
+            (Predicate::with_time_range(&[], 0, 1000), false),
+            (Predicate::with_time_range(&[], 0, 5000), true),
+        ];
+
+        for (predicate, exp) in cases {
+            assert_eq!(
+                db.could_pass_predicate("hour_1", "Coolverine", &[22], predicate.clone()),
+                exp,
+                "{:?}",
+                predicate
+            );
+        }
+
+        // unknown tables, chunks and partitions can't have matching rows
+        assert!(!db.could_pass_predicate("hour_1", "20 Size", &[22], Predicate::default()));
+        assert!(!db.could_pass_predicate("hour_1", "Coolverine", &[2], Predicate::default()));
+        assert!(!db.could_pass_predicate("hour_2", "Coolverine", &[22], Predicate::default()));
+    }
+
     #[test]
     fn column_names() {
         let db = Database::new();
@@ -1360,6 +1764,64 @@ mod test {
         assert!(itr.next().is_none());
     }
 
+    #[test]
+    fn read_filter_schema() {
+        let db = Database::new();
+
+        for &i in &[100, 200] {
+            let schema = SchemaBuilder::new()
+                .non_null_tag("env")
+                .non_null_field("counter", Float64)
+                .timestamp()
+                .build()
+                .unwrap();
+
+            let data: Vec<ArrayRef> = vec![
+                Arc::new(StringArray::from(vec!["us-west", "us-east"])),
+                Arc::new(Float64Array::from(vec![1.2, 300.3])),
+                Arc::new(Int64Array::from(vec![i, 2 * i])),
+            ];
+
+            let rb = RecordBatch::try_new(schema.into(), data).unwrap();
+            db.upsert_partition("hour_1", i as u32, "Coolverine", rb);
+        }
+
+        // the same schema as the results of read_filter
+        for selection in &[Selection::All, Selection::Some(&["time", "env"])] {
+            let expected = db
+                .read_filter(
+                    "hour_1",
+                    "Coolverine",
+                    &[100, 200],
+                    Predicate::default(),
+                    *selection,
+                )
+                .unwrap()
+                .schema()
+                .unwrap();
+
+            let schema = db
+                .read_filter_schema("hour_1", "Coolverine", &[100, 200], *selection)
+                .unwrap();
+            assert_eq!(schema, expected);
+        }
+
+        let schema = db
+            .read_filter_schema("hour_1", "Coolverine", &[100], Selection::Some(&["env"]))
+            .unwrap();
+        assert_eq!(schema.len(), 1);
+        assert_eq!(schema.field(0).1.name(), "env");
+
+        assert!(matches!(
+            db.read_filter_schema("hour_1", "Wolverine", &[100], Selection::All),
+            Err(Error::TableNotFound { .. })
+        ));
+        assert!(matches!(
+            db.read_filter_schema("hour_1", "Coolverine", &[300], Selection::All),
+            Err(Error::ChunkNotFound { id: 300 })
+        ));
+    }
+
     #[test]
     fn read_aggregate() {
         let db = Database::new();
@@ -1486,6 +1948,150 @@ mod test {
         assert_rb_column_equals(&result, "temp_max", &Values::F64(vec![4500.0, 30000.0]));
         assert_rb_column_equals(&result, "counter_sum", &Values::U64(vec![15000, 12000]));
         assert_rb_column_equals(&result, "counter_count", &Values::U64(vec![3, 6]));
+
+        //
+        // Selectors, which pick the value with the earliest or latest
+        // timestamp across all row groups.
+        //
+        //   QUERY:
+        //
+        //   SELECT FIRST("temp"), LAST("temp"), LAST("msg")
+        //   FROM "table_1"
+        //   GROUP BY "region"
+        //
+
+        let itr = db
+            .read_aggregate(
+                "hour_1",
+                "table1",
+                &[1],
+                Predicate::default(),
+                Selection::Some(&["region"]),
+                vec![
+                    ("temp", AggregateType::First),
+                    ("temp", AggregateType::Last),
+                    ("msg", AggregateType::Last),
+                ],
+            )
+            .unwrap();
+        let result = itr.collect::<Vec<RecordBatch>>();
+        assert_eq!(result.len(), 1);
+        let result = &result[0];
+
+        assert_rb_column_equals(
+            &result,
+            "region",
+            &Values::String(vec![Some("east"), Some("west")]),
+        );
+        assert_rb_column_equals(&result, "temp_first", &Values::F64(vec![4500.0, 10.0]));
+        assert_rb_column_equals(&result, "temp_last", &Values::F64(vec![4500.0, 30000.0]));
+        // null values are never selected
+        assert_rb_column_equals(
+            &result,
+            "msg_last",
+            &Values::String(vec![None, Some("msg b")]),
+        );
+    }
+
+    #[test]
+    fn read_window_aggregate() {
+        let db = Database::new();
+
+        for &i in &[100, 200, 300] {
+            let schema = SchemaBuilder::new()
+                .non_null_tag("region")
+                .non_null_field("counter", UInt64)
+                .timestamp()
+                .build()
+                .unwrap();
+
+            let data: Vec<ArrayRef> = vec![
+                Arc::new(StringArray::from(vec!["west", "west", "east"])),
+                Arc::new(UInt64Array::from(vec![1000, 3000, 5000])),
+                Arc::new(Int64Array::from(vec![i, 20 + i, 30 + i])),
+            ];
+
+            let rb = RecordBatch::try_new(schema.into(), data).unwrap();
+            db.upsert_partition("hour_1", 1, "table1", rb);
+        }
+
+        let err = db
+            .read_window_aggregate(
+                "hour_1",
+                "table1",
+                &[1],
+                Predicate::default(),
+                Selection::Some(&["region"]),
+                vec![("counter", AggregateType::Sum)],
+                0,
+            )
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidWindow { window: 0 }));
+
+        //
+        //   QUERY:
+        //
+        //   SELECT SUM("counter"), COUNT("counter"), LAST("counter")
+        //   FROM "table_1"
+        //   WHERE "time" < 300
+        //   GROUP BY "region", time(100)
+        //
+
+        let itr = db
+            .read_window_aggregate(
+                "hour_1",
+                "table1",
+                &[1],
+                Predicate::new(vec![BinaryExpr::from(("time", "<", 300_i64))]),
+                Selection::Some(&["region"]),
+                vec![
+                    ("counter", AggregateType::Sum),
+                    ("counter", AggregateType::Count),
+                    ("counter", AggregateType::Last),
+                ],
+                100,
+            )
+            .unwrap();
+        let result = itr.collect::<Vec<RecordBatch>>();
+        assert_eq!(result.len(), 1);
+        let result = &result[0];
+
+        let column_names = result
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            column_names,
+            vec![
+                "region",
+                "time",
+                "counter_sum",
+                "counter_count",
+                "counter_last"
+            ]
+        );
+
+        assert_rb_column_equals(
+            &result,
+            "region",
+            &Values::String(vec![Some("east"), Some("east"), Some("west"), Some("west")]),
+        );
+        // the (exclusive) stop time of each window
+        assert_rb_column_equals(&result, "time", &Values::I64(vec![200, 300, 200, 300]));
+        assert_rb_column_equals(
+            &result,
+            "counter_sum",
+            &Values::U64(vec![5000, 5000, 4000, 4000]),
+        );
+        assert_rb_column_equals(&result, "counter_count", &Values::U64(vec![1, 1, 2, 2]));
+        assert_rb_column_equals(
+            &result,
+            "counter_last",
+            &Values::U64(vec![5000, 5000, 3000, 3000]),
+        );
     }
 
     fn to_set(v: &[&str]) -> BTreeSet<String> {
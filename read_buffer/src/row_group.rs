@@ -402,9 +402,31 @@ impl RowGroup {
         group_columns: &[ColumnName<'_>],
         aggregates: &[(ColumnName<'_>, AggregateType)],
     ) -> ReadAggregateResult<'_> {
+        self.read_window_aggregate(predicate, group_columns, aggregates, None)
+    }
+
+    /// As `read_aggregate`, but when a `window` (a duration in nanoseconds)
+    /// is provided the rows are also grouped by the window of time they fall
+    /// in. Windows are aligned to the epoch, and each is identified by its
+    /// (exclusive) stop time, which is the last value of the group key.
+    pub fn read_window_aggregate(
+        &self,
+        predicate: &Predicate,
+        group_columns: &[ColumnName<'_>],
+        aggregates: &[(ColumnName<'_>, AggregateType)],
+        window: Option<i64>,
+    ) -> ReadAggregateResult<'_> {
+        let mut group_columns_schema = self.meta.schema_for_column_names(group_columns);
+        if window.is_some() {
+            group_columns_schema.push((
+                schema::ColumnType::Timestamp(TIME_COLUMN_NAME.to_owned()),
+                LogicalDataType::Integer,
+            ));
+        }
+
         let schema = ResultSchema {
             select_columns: vec![],
-            group_columns: self.meta.schema_for_column_names(group_columns),
+            group_columns: group_columns_schema,
             aggregate_columns: self.meta.schema_for_aggregate_column_names(aggregates),
         };
 
@@ -414,22 +436,30 @@ impl RowGroup {
         };
 
         // Pure column aggregates - no grouping.
-        if group_columns.is_empty() {
+        if group_columns.is_empty() && window.is_none() {
             self.aggregate_columns(predicate, &mut result);
             return result;
         }
 
         // All of the below assume grouping by columns.
 
+        // The `First` and `Last` selectors need the timestamp of each row.
+        let has_selectors = aggregates
+            .iter()
+            .any(|(_, agg_type)| matches!(agg_type, AggregateType::First | AggregateType::Last));
+
         // Handle case where there are no predicates and all the columns being
         // grouped support constant-time expression of the row_ids belonging to
         // each grouped value.
-        let all_group_cols_pre_computed = result.schema.group_column_names_iter().all(|name| {
-            self.column_by_name(name)
-                .properties()
-                .has_pre_computed_row_ids
-        });
-        if predicate.is_empty() && all_group_cols_pre_computed {
+        if predicate.is_empty()
+            && window.is_none()
+            && !has_selectors
+            && group_columns.iter().all(|&name| {
+                self.column_by_name(name)
+                    .properties()
+                    .has_pre_computed_row_ids
+            })
+        {
             self.read_group_all_rows_all_rle(&mut result);
             return result;
         }
@@ -450,10 +480,9 @@ impl RowGroup {
         // materialise all *encoded* values for each column we are grouping on.
         // These will not be the logical (typically string) values, but will be
         // vectors of integers representing the physical values.
-        let groupby_encoded_ids: Vec<_> = result
-            .schema
-            .group_column_names_iter()
-            .map(|name| {
+        let mut groupby_encoded_ids: Vec<_> = group_columns
+            .iter()
+            .map(|&name| {
                 let col = self.column_by_name(name);
                 let mut encoded_values_buf =
                     EncodedValues::with_capacity_u32(col.num_rows() as usize);
@@ -474,6 +503,33 @@ impl RowGroup {
             })
             .collect();
 
+        // Materialise the timestamps if they are needed to window the rows or
+        // to update selectors.
+        let timestamps = if window.is_some() || has_selectors {
+            Some(self.timestamps(filter_row_ids.as_deref()))
+        } else {
+            None
+        };
+
+        // The window of each row is encoded as an id into the stop times of
+        // the windows seen, so that windows are grouped like the values of
+        // any other group column.
+        let mut window_stops = vec![];
+        if let (Some(window), Some(timestamps)) = (window, &timestamps) {
+            let mut window_ids: HashMap<i64, u32> = HashMap::default();
+            let encoded_windows = timestamps
+                .iter()
+                .map(|&ts| {
+                    let stop = ts - ts.rem_euclid(window) + window;
+                    *window_ids.entry(stop).or_insert_with(|| {
+                        window_stops.push(stop);
+                        (window_stops.len() - 1) as u32
+                    })
+                })
+                .collect();
+            groupby_encoded_ids.push(encoded_windows);
+        }
+
         // Materialise values in aggregate columns.
         let mut aggregate_columns_data = Vec::with_capacity(agg_cols_num);
         for (col_type, _, _) in &result.schema.aggregate_columns {
@@ -498,20 +554,52 @@ impl RowGroup {
 
         // If there is a single group column then we can use an optimised
         // approach for building group keys
-        if group_columns.len() == 1 {
+        if groupby_encoded_ids.len() == 1 && window.is_none() {
             self.read_group_single_group_column(
                 &mut result,
                 &groupby_encoded_ids[0],
                 aggregate_columns_data,
+                timestamps.as_deref(),
             );
             return result;
         }
 
         // Perform the group by using a hashmap
-        self.read_group_with_hashing(&mut result, &groupby_encoded_ids, aggregate_columns_data);
+        self.read_group_with_hashing(
+            &mut result,
+            &groupby_encoded_ids,
+            aggregate_columns_data,
+            timestamps.as_deref(),
+            &window_stops,
+        );
         result
     }
 
+    // Materialises the timestamps of the rows with the provided ids, or of
+    // all rows if `row_ids` is `None`.
+    fn timestamps(&self, row_ids: Option<&[u32]>) -> Vec<i64> {
+        let values = match row_ids {
+            Some(row_ids) => self.time_column().values(row_ids),
+            None => self.time_column().all_values(),
+        };
+
+        match values {
+            Values::I64(timestamps) => timestamps,
+            _ => unreachable!("timestamps must be non-null i64 values"),
+        }
+    }
+
+    // Returns the columns for the group key values of the row group, which
+    // exclude the window of windowed aggregates.
+    fn group_key_columns(&self, schema: &ResultSchema) -> Vec<&Column> {
+        schema
+            .group_columns
+            .iter()
+            .filter(|(col_type, _)| !matches!(col_type, schema::ColumnType::Timestamp(_)))
+            .map(|(col_type, _)| self.column_by_name(col_type.as_str()))
+            .collect()
+    }
+
     // read_group_hash executes a read-group-aggregate operation on the
     // `RowGroup` using a hashmap to build up a collection of group keys and
     // aggregates.
@@ -522,16 +610,30 @@ impl RowGroup {
         dst: &mut ReadAggregateResult<'a>,
         groupby_encoded_ids: &[Vec<u32>],
         aggregate_columns_data: Vec<Values<'a>>,
+        timestamps: Option<&[i64]>,
+        window_stops: &[i64],
     ) {
         // An optimised approach to building the hashmap of group keys using a
         // single 128-bit integer as the group key. If grouping is on more than
         // four columns then a fallback to using an vector as a key will happen.
         if dst.schema.group_columns.len() <= 4 {
-            self.read_group_hash_with_u128_key(dst, &groupby_encoded_ids, &aggregate_columns_data);
+            self.read_group_hash_with_u128_key(
+                dst,
+                &groupby_encoded_ids,
+                &aggregate_columns_data,
+                timestamps,
+                window_stops,
+            );
             return;
         }
 
-        self.read_group_hash_with_vec_key(dst, &groupby_encoded_ids, &aggregate_columns_data);
+        self.read_group_hash_with_vec_key(
+            dst,
+            &groupby_encoded_ids,
+            &aggregate_columns_data,
+            timestamps,
+            window_stops,
+        );
     }
 
     // This function is used with `read_group_hash` when the number of columns
@@ -542,6 +644,8 @@ impl RowGroup {
         dst: &mut ReadAggregateResult<'a>,
         groupby_encoded_ids: &[Vec<u32>],
         aggregate_columns_data: &[Values<'a>],
+        timestamps: Option<&[i64]>,
+        window_stops: &[i64],
     ) {
         // Now begin building the group keys.
         let mut groups: HashMap<Vec<u32>, Vec<AggregateResult<'_>>> = HashMap::default();
@@ -562,9 +666,7 @@ impl RowGroup {
                 // aggregates for this group key are already present. Update
                 // them
                 hash_map::RawEntryMut::Occupied(mut entry) => {
-                    for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        entry.get_mut()[i].update(values.value(row));
-                    }
+                    update_aggregates(entry.get_mut(), aggregate_columns_data, timestamps, row);
                 }
                 // group key does not exist, so create it.
                 hash_map::RawEntryMut::Vacant(entry) => {
@@ -573,9 +675,7 @@ impl RowGroup {
                         group_key_aggs.push(AggregateResult::from(agg_type));
                     }
 
-                    for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        group_key_aggs[i].update(values.value(row));
-                    }
+                    update_aggregates(&mut group_key_aggs, aggregate_columns_data, timestamps, row);
 
                     entry.insert(key_buf.clone(), group_key_aggs);
                 }
@@ -584,11 +684,7 @@ impl RowGroup {
 
         // Finally, build results set. Each encoded group key needs to be
         // materialised into a logical group key
-        let columns = dst
-            .schema
-            .group_column_names_iter()
-            .map(|name| self.column_by_name(name))
-            .collect::<Vec<_>>();
+        let columns = self.group_key_columns(&dst.schema);
         let mut group_key_vec: Vec<GroupKey<'_>> = Vec::with_capacity(groups.len());
         let mut aggregate_vec: Vec<AggregateResults<'_>> = Vec::with_capacity(groups.len());
 
@@ -596,7 +692,12 @@ impl RowGroup {
             let mut logical_key = Vec::with_capacity(group_key.len());
             for (col_idx, &encoded_id) in group_key.iter().enumerate() {
                 // TODO(edd): address the cast to u32
-                logical_key.push(columns[col_idx].decode_id(encoded_id as u32));
+                logical_key.push(decode_group_key_id(
+                    &columns,
+                    window_stops,
+                    col_idx,
+                    encoded_id as u32,
+                ));
             }
 
             group_key_vec.push(GroupKey(logical_key));
@@ -620,6 +721,8 @@ impl RowGroup {
         dst: &mut ReadAggregateResult<'a>,
         groupby_encoded_ids: &[Vec<u32>],
         aggregate_columns_data: &[Values<'a>],
+        timestamps: Option<&[i64]>,
+        window_stops: &[i64],
     ) {
         let total_rows = groupby_encoded_ids[0].len();
         assert!(groupby_encoded_ids.iter().all(|x| x.len() == total_rows));
@@ -640,9 +743,7 @@ impl RowGroup {
                 // aggregates for this group key are already present. Update
                 // them
                 hash_map::RawEntryMut::Occupied(mut entry) => {
-                    for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        entry.get_mut()[i].update(values.value(row));
-                    }
+                    update_aggregates(entry.get_mut(), aggregate_columns_data, timestamps, row);
                 }
                 // group key does not exist, so create it.
                 hash_map::RawEntryMut::Vacant(entry) => {
@@ -651,9 +752,7 @@ impl RowGroup {
                         group_key_aggs.push(AggregateResult::from(agg_type));
                     }
 
-                    for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        group_key_aggs[i].update(values.value(row));
-                    }
+                    update_aggregates(&mut group_key_aggs, aggregate_columns_data, timestamps, row);
 
                     entry.insert(group_key_packed, group_key_aggs);
                 }
@@ -662,23 +761,24 @@ impl RowGroup {
 
         // Finally, build results set. Each encoded group key needs to be
         // materialised into a logical group key
-        let columns = dst
-            .schema
-            .group_column_names_iter()
-            .map(|name| self.column_by_name(name))
-            .collect::<Vec<_>>();
+        let columns = self.group_key_columns(&dst.schema);
         let mut group_key_vec: Vec<GroupKey<'_>> = Vec::with_capacity(groups.len());
         let mut aggregate_vec: Vec<AggregateResults<'_>> = Vec::with_capacity(groups.len());
 
         for (group_key_packed, aggs) in groups.into_iter() {
-            let mut logical_key = Vec::with_capacity(columns.len());
+            let mut logical_key = Vec::with_capacity(groupby_encoded_ids.len());
 
             // Unpack the appropriate encoded id for each column from the packed
             // group key, then materialise the logical value for that id and add
             // it to the materialised group key (`logical_key`).
-            for (col_idx, column) in columns.iter().enumerate() {
+            for col_idx in 0..groupby_encoded_ids.len() {
                 let encoded_id = (group_key_packed >> (col_idx * 32)) as u32;
-                logical_key.push(column.decode_id(encoded_id));
+                logical_key.push(decode_group_key_id(
+                    &columns,
+                    window_stops,
+                    col_idx,
+                    encoded_id,
+                ));
             }
 
             group_key_vec.push(GroupKey(logical_key));
@@ -788,8 +888,9 @@ impl RowGroup {
                     AggregateType::Count => {
                         AggregateResult::Count(agg_col.count(&aggregate_row_ids.to_vec()) as u64)
                     }
-                    AggregateType::First => todo!(),
-                    AggregateType::Last => todo!(),
+                    AggregateType::First | AggregateType::Last => {
+                        unreachable!("selectors are aggregated with the rows' timestamps")
+                    }
                     AggregateType::Min => {
                         AggregateResult::Min(agg_col.min(&aggregate_row_ids.to_vec()))
                     }
@@ -815,6 +916,7 @@ impl RowGroup {
         dst: &mut ReadAggregateResult<'a>,
         groupby_encoded_ids: &[u32],
         aggregate_columns_data: Vec<Values<'a>>,
+        timestamps: Option<&[i64]>,
     ) {
         assert_eq!(dst.schema().group_columns.len(), 1);
         let column = self.column_by_name(dst.schema.group_column_names_iter().next().unwrap());
@@ -831,9 +933,7 @@ impl RowGroup {
             match &mut groups[idx] {
                 Some(group_key_aggs) => {
                     // Update all aggregates for the group key
-                    for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        group_key_aggs[i].update(values.value(row));
-                    }
+                    update_aggregates(group_key_aggs, &aggregate_columns_data, timestamps, row);
                 }
                 None => {
                     let mut group_key_aggs = dst
@@ -843,9 +943,12 @@ impl RowGroup {
                         .map(|(_, agg_type, _)| AggregateResult::from(agg_type))
                         .collect::<Vec<_>>();
 
-                    for (i, values) in aggregate_columns_data.iter().enumerate() {
-                        group_key_aggs[i].update(values.value(row));
-                    }
+                    update_aggregates(
+                        &mut group_key_aggs,
+                        &aggregate_columns_data,
+                        timestamps,
+                        row,
+                    );
 
                     groups[idx] = Some(group_key_aggs);
                 }
@@ -926,7 +1029,15 @@ impl RowGroup {
                 AggregateType::Max => {
                     aggregate_row.push(AggregateResult::Max(col.max(&row_ids)));
                 }
-                _ => unimplemented!("Other aggregates are not yet supported"),
+                AggregateType::First | AggregateType::Last => {
+                    let mut aggregate = AggregateResult::from(&agg_type);
+                    let values = col.values(&row_ids);
+                    let timestamps = self.timestamps(Some(row_ids.as_slice()));
+                    for (i, &timestamp) in timestamps.iter().enumerate() {
+                        aggregate.update_with_timestamp(values.value(i), timestamp);
+                    }
+                    aggregate_row.push(aggregate);
+                }
             }
         }
         dst.aggregates.push(AggregateResults(aggregate_row)); // write the row
@@ -1048,6 +1159,67 @@ impl RowGroup {
 
         dst
     }
+
+    /// Determines the distinct tag sets of the rows satisfying the predicate,
+    /// which are added to `dst`. A tag set holds the names and values of the
+    /// tag columns that are non-null in a row, ordered by name.
+    ///
+    /// Tag sets are deduplicated on the encoded ids of the tag columns, so
+    /// only the logical values of distinct tag sets are materialised.
+    ///
+    /// If you are familiar with InfluxDB, this is essentially the set of
+    /// series keys in the row group.
+    pub fn tag_sets(&self, predicate: &Predicate, dst: &mut BTreeSet<Vec<(String, String)>>) {
+        let row_ids = match self.row_ids_from_predicate(predicate) {
+            RowIDsOption::None(_) => return, // nothing matches predicate
+            RowIDsOption::Some(row_ids) => Some(row_ids.to_vec()),
+            RowIDsOption::All(_) => None,
+        };
+        let rows = match &row_ids {
+            Some(row_ids) => row_ids.len(),
+            None => self.rows() as usize,
+        };
+
+        // the meta data columns are ordered by name.
+        let tag_columns = self
+            .meta
+            .columns
+            .iter()
+            .filter(|(_, meta)| matches!(meta.typ, schema::ColumnType::Tag(_)))
+            .map(|(name, _)| self.column_name_and_column(name))
+            .collect::<Vec<_>>();
+
+        // materialise the *encoded* values of each tag column for the rows.
+        let encoded_ids = tag_columns
+            .iter()
+            .map(|(_, column)| {
+                let mut encoded_values_buf = EncodedValues::with_capacity_u32(rows);
+                encoded_values_buf = match &row_ids {
+                    Some(row_ids) => column.encoded_values(row_ids, encoded_values_buf),
+                    None => column.all_encoded_values(encoded_values_buf),
+                };
+                encoded_values_buf.take_u32()
+            })
+            .collect::<Vec<_>>();
+
+        let encoded_tag_sets = (0..rows)
+            .map(|row| encoded_ids.iter().map(|ids| ids[row]).collect::<Vec<_>>())
+            .collect::<BTreeSet<_>>();
+
+        for encoded_tag_set in encoded_tag_sets {
+            let tag_set = tag_columns
+                .iter()
+                .zip(encoded_tag_set)
+                .filter_map(
+                    |((name, column), encoded_id)| match column.decode_id(encoded_id) {
+                        Value::String(value) => Some((name.to_string(), value.to_owned())),
+                        _ => None, // NULL values are not part of the tag set
+                    },
+                )
+                .collect();
+            dst.insert(tag_set);
+        }
+    }
 }
 
 /// Initialise a `RowGroup` from an Arrow RecordBatch.
@@ -1119,6 +1291,36 @@ impl From<RecordBatch> for RowGroup {
     }
 }
 
+// Updates the aggregates of a group with the values of a row. Timestamps are
+// provided when the aggregates include the `First` or `Last` selectors.
+fn update_aggregates<'a>(
+    aggregates: &mut [AggregateResult<'a>],
+    aggregate_columns_data: &[Values<'a>],
+    timestamps: Option<&[i64]>,
+    row: usize,
+) {
+    for (aggregate, values) in aggregates.iter_mut().zip(aggregate_columns_data) {
+        match timestamps {
+            Some(timestamps) => aggregate.update_with_timestamp(values.value(row), timestamps[row]),
+            None => aggregate.update(values.value(row)),
+        }
+    }
+}
+
+// Materialises the logical value of an encoded group key value. Ids past the
+// group columns are windows, encoded as indexes into their stop times.
+fn decode_group_key_id<'a>(
+    columns: &[&'a Column],
+    window_stops: &[i64],
+    col_idx: usize,
+    encoded_id: u32,
+) -> Value<'a> {
+    match columns.get(col_idx) {
+        Some(&column) => column.decode_id(encoded_id),
+        None => Value::Scalar(Scalar::I64(window_stops[encoded_id as usize])),
+    }
+}
+
 // Packs an encoded values into a `u128` at `pos`, which must be `[0,4)`.
 #[inline(always)]
 fn pack_u32_in_u128(packed_value: u128, encoded_id: u32, pos: usize) -> u128 {
@@ -1389,8 +1591,8 @@ impl Ord for GroupKey<'_> {
         let cols = self.0.len();
         for i in 0..cols {
             match self.0[i].partial_cmp(&other.0[i]) {
+                Some(std::cmp::Ordering::Equal) | None => continue,
                 Some(ord) => return ord,
-                None => continue,
             }
         }
 
@@ -1801,10 +2003,10 @@ impl<'row_group> ReadAggregateResult<'row_group> {
                 // drained other, add the rest of self
                 result
                     .group_keys
-                    .extend(self.group_keys.iter().skip(j).cloned());
+                    .extend(self.group_keys.iter().skip(i).cloned());
                 result
                     .aggregates
-                    .extend(self.aggregates.iter().skip(j).cloned());
+                    .extend(self.aggregates.iter().skip(i).cloned());
                 return result;
             }
 
@@ -1876,30 +2078,38 @@ impl TryFrom<ReadAggregateResult<'_>> for RecordBatch {
         let arrow_schema: arrow_deps::arrow::datatypes::SchemaRef = schema.into();
 
         // Build the columns for the group keys. This involves pivoting the
-        // row-wise group keys into column-wise data.
-        let mut group_column_builders = (0..result.schema.group_columns.len())
-            .map(|_| {
-                arrow::array::StringBuilder::with_capacity(
-                    result.cardinality(),
-                    result.cardinality() * 8, // arbitrarily picked for now
-                )
-            })
-            .collect::<Vec<_>>();
-
-        // build each column for a group key value row by row.
-        for gk in result.group_keys.iter() {
-            for (i, v) in gk.0.iter().enumerate() {
-                group_column_builders[i]
-                    .append_value(v.string())
-                    .map_err(|source| Error::ArrowError { source })?;
-            }
-        }
-
-        // Add the group columns to the set of column data for the record batch.
+        // row-wise group keys into column-wise data. Group keys hold tag
+        // values, and the stop time of the window for windowed aggregates.
         let mut columns: Vec<Arc<dyn arrow::array::Array>> =
             Vec::with_capacity(result.schema.len());
-        for col in group_column_builders.iter_mut() {
-            columns.push(Arc::new(col.finish()));
+        for (i, (_, data_type)) in result.schema.group_columns.iter().enumerate() {
+            match data_type {
+                LogicalDataType::Integer => {
+                    let mut builder = array::Int64Builder::new(result.cardinality());
+                    for gk in result.group_keys.iter() {
+                        match gk.0[i] {
+                            Value::Scalar(Scalar::I64(v)) => {
+                                builder.append_value(v).context(ArrowError)?
+                            }
+                            _ => builder.append_null().context(ArrowError)?,
+                        }
+                    }
+                    columns.push(Arc::new(builder.finish()));
+                }
+                _ => {
+                    let mut builder = array::StringBuilder::with_capacity(
+                        result.cardinality(),
+                        result.cardinality() * 8, // arbitrarily picked for now
+                    );
+                    for gk in result.group_keys.iter() {
+                        match gk.0[i] {
+                            Value::Null => builder.append_null().context(ArrowError)?,
+                            v => builder.append_value(v.string()).context(ArrowError)?,
+                        }
+                    }
+                    columns.push(Arc::new(builder.finish()));
+                }
+            }
         }
 
         // For the aggregate columns, build one column at a time, repeatedly
@@ -3077,4 +3287,58 @@ west,host-d,11,9
         );
         assert_eq!(result, to_map(vec![]));
     }
+
+    #[test]
+    fn tag_sets() {
+        // Build a row group.
+        let mut columns = BTreeMap::new();
+        let tc = ColumnType::Time(Column::from(&[1_i64, 2, 3, 4][..]));
+        columns.insert("time".to_string(), tc);
+
+        let rc = ColumnType::Tag(Column::from(
+            &[Some("west"), Some("west"), Some("east"), Some("west")][..],
+        ));
+        columns.insert("region".to_string(), rc);
+
+        let hc = ColumnType::Tag(Column::from(&[Some("a"), Some("a"), None, None][..]));
+        columns.insert("host".to_string(), hc);
+
+        let fc = ColumnType::Field(Column::from(&[100_u64, 101, 200, 203][..]));
+        columns.insert("count".to_string(), fc);
+
+        let rg = RowGroup::new(4, columns);
+
+        let tag_set = |tags: &[(&str, &str)]| {
+            tags.iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        let mut dst = BTreeSet::new();
+        rg.tag_sets(&Predicate::default(), &mut dst);
+        let expected = vec![
+            tag_set(&[("host", "a"), ("region", "west")]),
+            tag_set(&[("region", "east")]),
+            tag_set(&[("region", "west")]),
+        ];
+        assert_eq!(dst, expected.into_iter().collect());
+
+        let mut dst = BTreeSet::new();
+        rg.tag_sets(
+            &Predicate::new(vec![BinaryExpr::from(("time", ">", 2_i64))]),
+            &mut dst,
+        );
+        let expected = vec![
+            tag_set(&[("region", "east")]),
+            tag_set(&[("region", "west")]),
+        ];
+        assert_eq!(dst, expected.into_iter().collect());
+
+        let mut dst = BTreeSet::new();
+        rg.tag_sets(
+            &Predicate::new(vec![BinaryExpr::from(("time", ">", 4_i64))]),
+            &mut dst,
+        );
+        assert!(dst.is_empty());
+    }
 }
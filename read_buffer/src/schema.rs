@@ -130,7 +130,7 @@ impl TryFrom<&ResultSchema> for data_types::schema::Schema {
                 ColumnType::Field(_) => {
                     builder = builder.influx_field(col_name.as_str(), data_type.into())
                 }
-                ColumnType::Other(_) => {
+                ColumnType::Timestamp(_) | ColumnType::Other(_) => {
                     builder = builder.field(col_name.as_str(), data_type.into())
                 }
                 ct => unreachable!("not possible to aggregate {:?} columns", ct),
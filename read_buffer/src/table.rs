@@ -10,7 +10,7 @@ use arrow_deps::arrow::record_batch::RecordBatch;
 use data_types::selection::Selection;
 use snafu::{ensure, Snafu};
 
-use crate::row_group::{self, ColumnName, GroupKey, Predicate, RowGroup};
+use crate::row_group::{self, ColumnName, Predicate, RowGroup};
 use crate::schema::{AggregateType, ColumnType, LogicalDataType, ResultSchema};
 use crate::value::{AggregateResult, Scalar, Value};
 #[derive(Debug, Snafu)]
@@ -197,10 +197,7 @@ impl Table {
         let (meta, row_groups) = self.filter_row_groups(predicate);
 
         let schema = ResultSchema {
-            select_columns: match columns {
-                Selection::All => meta.schema_for_all_columns(),
-                Selection::Some(column_names) => meta.schema_for_column_names(column_names),
-            },
+            select_columns: meta.schema_for_selection(columns),
             ..ResultSchema::default()
         };
 
@@ -212,6 +209,17 @@ impl Table {
         }
     }
 
+    /// Returns the schema of the results `read_filter` returns for the
+    /// specified column selections. Only the table's meta data is read.
+    pub fn read_filter_schema(&self, columns: &Selection<'_>) -> ResultSchema {
+        let table_data = self.table_data.read().unwrap();
+
+        ResultSchema {
+            select_columns: table_data.meta.schema_for_selection(columns),
+            ..ResultSchema::default()
+        }
+    }
+
     /// Returns an iterable collection of data in group columns and aggregate
     /// columns, optionally filtered by the provided predicate. Results are
     /// merged across all row groups within the table.
@@ -226,15 +234,51 @@ impl Table {
         predicate: Predicate,
         group_columns: &'input Selection<'_>,
         aggregates: &'input [(ColumnName<'input>, AggregateType)],
+    ) -> ReadAggregateResults {
+        self.aggregate(predicate, group_columns, aggregates, None)
+    }
+
+    /// Returns aggregates segmented by grouping keys and windowed by time.
+    ///
+    /// As `read_aggregate`, but rows are also grouped by the window of time
+    /// they fall in, according to the `window` parameter, which represents an
+    /// interval in nanoseconds. For example, to window results by one minute,
+    /// window should be set to 60_000_000_000. Windows are aligned to the
+    /// epoch, and the stop (exclusive) time of each row's window is returned
+    /// in a "time" column following the group columns.
+    pub fn read_window_aggregate<'input>(
+        &self,
+        predicate: Predicate,
+        group_columns: &'input Selection<'_>,
+        aggregates: &'input [(ColumnName<'input>, AggregateType)],
+        window: i64,
+    ) -> ReadAggregateResults {
+        self.aggregate(predicate, group_columns, aggregates, Some(window))
+    }
+
+    fn aggregate<'input>(
+        &self,
+        predicate: Predicate,
+        group_columns: &'input Selection<'_>,
+        aggregates: &'input [(ColumnName<'input>, AggregateType)],
+        window: Option<i64>,
     ) -> ReadAggregateResults {
         let (meta, row_groups) = self.filter_row_groups(&predicate);
 
         // Filter out any column names that we do not have data for.
+        let mut group_columns = match group_columns {
+            Selection::All => meta.schema_for_all_columns(),
+            Selection::Some(column_names) => meta.schema_for_column_names(column_names),
+        };
+        if window.is_some() {
+            group_columns.push((
+                ColumnType::Timestamp(row_group::TIME_COLUMN_NAME.to_owned()),
+                LogicalDataType::Integer,
+            ));
+        }
+
         let schema = ResultSchema {
-            group_columns: match group_columns {
-                Selection::All => meta.schema_for_all_columns(),
-                Selection::Some(column_names) => meta.schema_for_column_names(column_names),
-            },
+            group_columns,
             aggregate_columns: meta.schema_for_aggregate_column_names(aggregates),
             ..ResultSchema::default()
         };
@@ -244,42 +288,11 @@ impl Table {
             schema,
             predicate,
             row_groups,
+            window,
             ..Default::default()
         }
     }
 
-    /// Returns aggregates segmented by grouping keys and windowed by time.
-    ///
-    /// The set of data to be aggregated may be filtered by (currently only)
-    /// equality predicates, but can be ranged by time, which should be
-    /// represented as nanoseconds since the epoch. Results are included if they
-    /// satisfy the predicate and fall with the [min, max) time range domain.
-    ///
-    /// Group keys are determined according to the provided group column names
-    /// (`group_columns`). Currently only grouping by string (tag key) columns
-    /// is supported.
-    ///
-    /// Required aggregates are specified via a tuple comprising a column name
-    /// and the type of aggregation required. Multiple aggregations can be
-    /// applied to the same column.
-    ///
-    /// Results are grouped and windowed according to the `window` parameter,
-    /// which represents an interval in nanoseconds. For example, to window
-    /// results by one minute, window should be set to 600_000_000_000.
-    pub fn aggregate_window<'a>(
-        &self,
-        _time_range: (i64, i64),
-        _predicates: &[(&str, &str)],
-        _group_columns: Vec<ColumnName<'a>>,
-        _aggregates: Vec<(ColumnName<'a>, AggregateType)>,
-        _window: i64,
-    ) -> BTreeMap<GroupKey<'_>, Vec<(ColumnName<'a>, AggregateResult<'_>)>> {
-        // identify segments where time range and predicates match could match
-        // using segment meta data, and then execute against those segments and
-        // merge results.
-        todo!()
-    }
-
     // Perform aggregates without any grouping. Filtering on optional predicates
     // and time range is still supported.
     fn read_aggregate_no_group<'a>(
@@ -500,12 +513,23 @@ impl Table {
         Ok(dst)
     }
 
+    /// Adds the distinct tag sets of the rows matching the provided predicate
+    /// to `dst`. A tag set holds the names and values of a row's non-null tag
+    /// columns, ordered by name. All tag sets are deduplicated across row
+    /// groups in the table.
+    pub fn tag_sets(&self, predicate: &Predicate, dst: &mut BTreeSet<Vec<(String, String)>>) {
+        let (_, row_groups) = self.filter_row_groups(predicate);
+        for row_group in row_groups {
+            row_group.tag_sets(predicate, dst);
+        }
+    }
+
     /// Determines if this table could satisfy the provided predicate.
     ///
     /// `false` is proof that no row within this table would match the
     /// predicate, whilst `true` indicates one or more rows *might* match the
     /// predicate.
-    fn could_satisfy_predicate(&self, predicate: &Predicate) -> bool {
+    pub fn could_satisfy_predicate(&self, predicate: &Predicate) -> bool {
         // Get a snapshot of the table data under a read lock.
         let (meta, row_groups) = {
             let table_data = self.table_data.read().unwrap();
@@ -677,6 +701,15 @@ impl MetaData {
             .collect::<Vec<_>>()
     }
 
+    // As `schema_for_column_names` but for either all columns or only the
+    // selected columns.
+    fn schema_for_selection(&self, columns: &Selection<'_>) -> Vec<(ColumnType, LogicalDataType)> {
+        match columns {
+            Selection::All => self.schema_for_all_columns(),
+            Selection::Some(column_names) => self.schema_for_column_names(column_names),
+        }
+    }
+
     // As `schema_for_column_names` but also embeds the provided aggregate type.
     fn schema_for_aggregate_column_names(
         &self,
@@ -828,6 +861,9 @@ pub struct ReadAggregateResults {
     // aggregates to produce are determined by the `schema`.
     row_groups: Vec<Arc<RowGroup>>,
 
+    // the duration of the windows the rows are grouped by, if any.
+    window: Option<i64>,
+
     drained: bool, // currently this iterator only yields once.
 }
 
@@ -846,38 +882,38 @@ impl ReadAggregateResults {
             return None;
         }
 
-        let mut merged_results = self.row_groups.get(0).unwrap().read_aggregate(
+        // The window is not a column of the row groups, they add it to the
+        // group columns themselves.
+        let group_columns = self
+            .schema
+            .group_columns
+            .iter()
+            .filter(|(col_type, _)| !matches!(col_type, ColumnType::Timestamp(_)))
+            .map(|(col_type, _)| col_type.as_str())
+            .collect::<Vec<_>>();
+        let aggregates = self
+            .schema
+            .aggregate_columns
+            .iter()
+            .map(|(col_type, agg_type, _)| (col_type.as_str(), *agg_type))
+            .collect::<Vec<_>>();
+
+        let mut merged_results = self.row_groups.get(0).unwrap().read_window_aggregate(
             &self.predicate,
-            &self
-                .schema
-                .group_column_names_iter()
-                .map(|s| s.as_str())
-                .collect::<Vec<_>>(),
-            &self
-                .schema
-                .aggregate_columns
-                .iter()
-                .map(|(name, agg_type, _)| (name.as_str(), *agg_type))
-                .collect::<Vec<_>>(),
+            &group_columns,
+            &aggregates,
+            self.window,
         );
         assert_eq!(merged_results.schema(), self.schema()); // validate schema
 
         // Execute against remaining row groups, merging each into the merged
         // set.
         for row_group in self.row_groups.iter().skip(1) {
-            let result = row_group.read_aggregate(
+            let result = row_group.read_window_aggregate(
                 &self.predicate,
-                &self
-                    .schema
-                    .group_column_names_iter()
-                    .map(|s| s.as_str())
-                    .collect::<Vec<_>>(),
-                &self
-                    .schema
-                    .aggregate_columns
-                    .iter()
-                    .map(|(name, agg_type, _)| (name.as_str(), *agg_type))
-                    .collect::<Vec<_>>(),
+                &group_columns,
+                &aggregates,
+                self.window,
             );
 
             if result.is_empty() {
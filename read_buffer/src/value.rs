@@ -123,7 +123,31 @@ impl<'a> AggregateResult<'a> {
                 (_, Value::Scalar(b)) => *v += b,
                 (_, _) => unreachable!("not a possible variant combination"),
             },
-            _ => unimplemented!("First and Last aggregates not implemented yet"),
+            Self::First(_) | Self::Last(_) => {
+                panic!("{:?} must be updated with the value's timestamp", self)
+            }
+        }
+    }
+
+    /// Updates the aggregate with a value and the timestamp of the row it
+    /// belongs to. `First` keeps the value with the earliest timestamp and
+    /// `Last` the one with the latest; other aggregates ignore the timestamp.
+    pub fn update_with_timestamp(&mut self, other: Value<'a>, timestamp: i64) {
+        if other.is_null() {
+            // a NULL value has no effect on aggregates
+            return;
+        }
+
+        match self {
+            Self::First(v) => match *v {
+                Some((ts, _)) if ts <= timestamp => {}
+                _ => *v = Some((timestamp, other)),
+            },
+            Self::Last(v) => match *v {
+                Some((ts, _)) if ts >= timestamp => {}
+                _ => *v = Some((timestamp, other)),
+            },
+            _ => self.update(other),
         }
     }
 
@@ -142,6 +166,16 @@ impl<'a> AggregateResult<'a> {
                     *this = *that;
                 }
             }
+            (AggregateResult::First(this), AggregateResult::First(that)) => match (*this, that) {
+                (_, None) => {}
+                (Some((this_ts, _)), Some((that_ts, _))) if this_ts <= *that_ts => {}
+                _ => *this = *that,
+            },
+            (AggregateResult::Last(this), AggregateResult::Last(that)) => match (*this, that) {
+                (_, None) => {}
+                (Some((this_ts, _)), Some((that_ts, _))) if this_ts >= *that_ts => {}
+                _ => *this = *that,
+            },
             (a, b) => unimplemented!("merging {:?} into {:?} not yet implemented", b, a),
         }
     }
@@ -158,8 +192,11 @@ impl<'a> AggregateResult<'a> {
                 Value::String(s) => Some(s),
                 v => panic!("cannot convert {:?} to &str", v),
             },
-            AggregateResult::First(_) => panic!("cannot convert first tuple to &str"),
-            AggregateResult::Last(_) => panic!("cannot convert last tuple to &str"),
+            AggregateResult::First(v) | AggregateResult::Last(v) => match v {
+                None | Some((_, Value::Null)) => None,
+                Some((_, Value::String(s))) => Some(s),
+                Some((_, v)) => panic!("cannot convert {:?} to &str", v),
+            },
             AggregateResult::Sum(v) => panic!("cannot convert {:?} to &str", v),
             AggregateResult::Count(_) => panic!("cannot convert count to &str"),
         }
@@ -177,8 +214,11 @@ impl<'a> AggregateResult<'a> {
                 Value::ByteArray(s) => Some(s),
                 v => panic!("cannot convert {:?} to &[u8]", v),
             },
-            AggregateResult::First(_) => panic!("cannot convert first tuple to &[u8]"),
-            AggregateResult::Last(_) => panic!("cannot convert last tuple to &[u8]"),
+            AggregateResult::First(v) | AggregateResult::Last(v) => match v {
+                None | Some((_, Value::Null)) => None,
+                Some((_, Value::ByteArray(s))) => Some(s),
+                Some((_, v)) => panic!("cannot convert {:?} to &[u8]", v),
+            },
             AggregateResult::Sum(v) => panic!("cannot convert {:?} to &[u8]", v),
             AggregateResult::Count(_) => panic!("cannot convert count to &[u8]"),
         }
@@ -196,8 +236,11 @@ impl<'a> AggregateResult<'a> {
                 Value::Boolean(s) => Some(*s),
                 v => panic!("cannot convert {:?} to bool", v),
             },
-            AggregateResult::First(_) => panic!("cannot convert first tuple to bool"),
-            AggregateResult::Last(_) => panic!("cannot convert last tuple to bool"),
+            AggregateResult::First(v) | AggregateResult::Last(v) => match v {
+                None | Some((_, Value::Null)) => None,
+                Some((_, Value::Boolean(s))) => Some(*s),
+                Some((_, v)) => panic!("cannot convert {:?} to bool", v),
+            },
             AggregateResult::Sum(v) => panic!("cannot convert {:?} to bool", v),
             AggregateResult::Count(_) => panic!("cannot convert count to bool"),
         }
@@ -228,8 +271,11 @@ impl<'a> AggregateResult<'a> {
                 },
                 v => panic!("cannot convert {:?} to i64", v),
             },
-            AggregateResult::First(_) => panic!("cannot convert first tuple to scalar"),
-            AggregateResult::Last(_) => panic!("cannot convert last tuple to scalar"),
+            AggregateResult::First(v) | AggregateResult::Last(v) => match v {
+                None | Some((_, Value::Null)) | Some((_, Value::Scalar(Scalar::Null))) => None,
+                Some((_, Value::Scalar(Scalar::I64(v)))) => Some(*v),
+                Some((_, v)) => panic!("cannot convert {:?} to i64", v),
+            },
             AggregateResult::Count(_) => panic!("cannot represent count as i64"),
         }
     }
@@ -260,8 +306,11 @@ impl<'a> AggregateResult<'a> {
                 },
                 v => panic!("cannot convert {:?} to u64", v),
             },
-            AggregateResult::First(_) => panic!("cannot convert first tuple to scalar"),
-            AggregateResult::Last(_) => panic!("cannot convert last tuple to scalar"),
+            AggregateResult::First(v) | AggregateResult::Last(v) => match v {
+                None | Some((_, Value::Null)) | Some((_, Value::Scalar(Scalar::Null))) => None,
+                Some((_, Value::Scalar(Scalar::U64(v)))) => Some(*v),
+                Some((_, v)) => panic!("cannot convert {:?} to u64", v),
+            },
         }
     }
 
@@ -290,8 +339,11 @@ impl<'a> AggregateResult<'a> {
                 },
                 v => panic!("cannot convert {:?} to f64", v),
             },
-            AggregateResult::First(_) => panic!("cannot convert first tuple to scalar"),
-            AggregateResult::Last(_) => panic!("cannot convert last tuple to scalar"),
+            AggregateResult::First(v) | AggregateResult::Last(v) => match v {
+                None | Some((_, Value::Null)) | Some((_, Value::Scalar(Scalar::Null))) => None,
+                Some((_, Value::Scalar(Scalar::F64(v)))) => Some(*v),
+                Some((_, v)) => panic!("cannot convert {:?} to f64", v),
+            },
             AggregateResult::Count(_) => panic!("cannot represent count as f64"),
         }
     }
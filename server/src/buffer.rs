@@ -3,6 +3,7 @@
 use data_types::{
     data::ReplicatedWrite,
     database_rules::{WalBufferRollover, WriterId},
+    job::Job,
     DatabaseName,
 };
 use generated_types::wal;
@@ -13,9 +14,13 @@ use std::{
     convert::{TryFrom, TryInto},
     mem,
     sync::Arc,
+    time::Duration,
 };
 
-use crate::tracker::{TrackedFutureExt, TrackerRegistry};
+use crate::{
+    jobs::{JobRegistry, TrackedJob},
+    tracker::TrackedFutureExt,
+};
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use crc32fast::Hasher;
@@ -23,6 +28,7 @@ use data_types::database_rules::WalBufferConfig;
 use data_types::wal::{SegmentPersistence, SegmentSummary, WriterSummary};
 use parking_lot::Mutex;
 use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use tokio::sync::broadcast;
 use tracing::{error, info, warn};
 
 #[derive(Debug, Snafu)]
@@ -71,16 +77,19 @@ pub enum Error {
 
     #[snafu(display("the flatbuffers Segment is invalid"))]
     InvalidFlatbuffersSegment,
-}
 
-#[derive(Debug, Clone)]
-pub struct SegmentPersistenceTask {
-    writer_id: u32,
-    location: object_store::path::Path,
+    #[snafu(display("invalid flatbuffers in segment: {}", source))]
+    InvalidFlatbuffer {
+        source: flatbuffers::InvalidFlatbuffer,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// How many appended writes a subscriber can fall behind before it has to
+/// catch up from the segments
+const SUBSCRIBER_CHANNEL_CAPACITY: usize = 1024;
+
 /// An in-memory buffer of a write ahead log. It is split up into segments,
 /// which can be persisted to object storage.
 #[derive(Debug)]
@@ -92,6 +101,13 @@ pub struct Buffer {
     open_segment: Segment,
     closed_segments: Vec<Arc<Segment>>,
     rollover_behavior: WalBufferRollover,
+    appended_writes: broadcast::Sender<Arc<ReplicatedWrite>>,
+    /// The sequence number of the last write appended for each writer,
+    /// including writes in segments that have since been dropped
+    writer_sequences: BTreeMap<WriterId, u64>,
+    /// If set, the open segment is closed once it is older than this, even
+    /// if it hasn't reached the segment size
+    close_segment_after: Option<Duration>,
 }
 
 impl Buffer {
@@ -101,6 +117,8 @@ impl Buffer {
         rollover_behavior: WalBufferRollover,
         persist: bool,
     ) -> Self {
+        let (appended_writes, _) = broadcast::channel(SUBSCRIBER_CHANNEL_CAPACITY);
+
         Self {
             max_size,
             segment_size,
@@ -109,15 +127,28 @@ impl Buffer {
             open_segment: Segment::new(1),
             current_size: 0,
             closed_segments: vec![],
+            appended_writes,
+            writer_sequences: BTreeMap::new(),
+            close_segment_after: None,
         }
     }
 
+    /// Applies a changed config to the buffer. Smaller buffer and segment
+    /// sizes take effect as the next writes are appended.
+    pub fn update_config(&mut self, config: &WalBufferConfig) {
+        self.max_size = config.buffer_size;
+        self.segment_size = config.segment_size;
+        self.rollover_behavior = config.buffer_rollover;
+        self.persist = config.store_segments;
+        self.close_segment_after = config.close_segment_after;
+    }
+
     /// Appends a replicated write onto the buffer, returning the segment if it
     /// has been closed out. If the max size of the buffer would be exceeded
     /// by accepting the write, the oldest (first) of the closed segments
     /// will be dropped, if it is persisted. Otherwise, an error is returned.
     pub fn append(&mut self, write: Arc<ReplicatedWrite>) -> Result<Option<Arc<Segment>>> {
-        let write_size = u64::try_from(write.data.len())
+        let write_size = u64::try_from(write.bytes().len())
             .expect("appended data must be less than a u64 in length");
 
         while self.current_size + write_size > self.max_size {
@@ -159,24 +190,74 @@ impl Buffer {
         let mut closed_segment = None;
 
         self.current_size += write_size;
-        self.open_segment.append(write)?;
-        if self.open_segment.size > self.segment_size {
-            let next_id = self.open_segment.id + 1;
-            let segment = mem::replace(&mut self.open_segment, Segment::new(next_id));
-            let segment = Arc::new(segment);
+        self.open_segment.append(Arc::clone(&write))?;
 
-            self.closed_segments.push(Arc::clone(&segment));
-            closed_segment = Some(segment);
+        let (writer, sequence) = write.writer_and_sequence();
+        self.writer_sequences.insert(writer, sequence);
+
+        // there may not be any subscribers, in which case the write is dropped
+        let _ = self.appended_writes.send(write);
+
+        if self.open_segment.size > self.segment_size {
+            closed_segment = Some(self.close_open_segment());
         }
 
         Ok(closed_segment)
     }
 
+    /// Closes the open segment if it has writes and was created longer than
+    /// `close_segment_after` before `now`, returning the closed segment so it
+    /// can be persisted. This bounds how long a write can wait in a segment
+    /// that isn't receiving enough writes to be closed on size.
+    pub fn close_segment_if_expired(&mut self, now: DateTime<Utc>) -> Option<Arc<Segment>> {
+        let close_after = chrono::Duration::from_std(self.close_segment_after?).ok()?;
+
+        if self.open_segment.writes.is_empty() || now - self.open_segment.created_at < close_after {
+            return None;
+        }
+
+        Some(self.close_open_segment())
+    }
+
+    fn close_open_segment(&mut self) -> Arc<Segment> {
+        let next_id = self.open_segment.id + 1;
+        let segment = mem::replace(&mut self.open_segment, Segment::new(next_id));
+        let segment = Arc::new(segment);
+
+        self.closed_segments.push(Arc::clone(&segment));
+        segment
+    }
+
     /// Returns the current size of the buffer.
     pub fn size(&self) -> u64 {
         self.current_size
     }
 
+    /// Returns the sequence number of the last write appended for each
+    /// writer
+    pub fn writer_sequences(&self) -> BTreeMap<WriterId, u64> {
+        self.writer_sequences.clone()
+    }
+
+    /// Restores the state of a buffer whose segments were persisted before
+    /// the server restarted: new segments continue after the last persisted
+    /// segment id, so they don't overwrite it in object storage, and the
+    /// writers' positions include the persisted writes.
+    pub(crate) fn restore(
+        &mut self,
+        last_segment_id: u64,
+        writer_sequences: &BTreeMap<WriterId, u64>,
+    ) {
+        if self.open_segment.writes.is_empty() && self.open_segment.id <= last_segment_id {
+            self.open_segment = Segment::new(last_segment_id + 1);
+        }
+
+        for (&writer, &sequence) in writer_sequences {
+            let position = self.writer_sequences.entry(writer).or_insert(sequence);
+            *position = (*position).max(sequence);
+        }
+    }
+
     /// Returns any replicated writes from the given writer ID and sequence
     /// number onward. This will include writes from other writers. The
     /// given writer ID and sequence are to identify from what point to
@@ -246,6 +327,44 @@ impl Buffer {
         writes
     }
 
+    /// Returns replicated writes from every writer that come after the
+    /// writer's position in `since`. If a writer isn't in `since`, or its
+    /// position doesn't match any write in the buffer, all of the writer's
+    /// replicated writes within the buffer are returned. Writes are returned
+    /// in the order they were appended.
+    pub fn writes_since_each(&self, since: &[WriterSequence]) -> Vec<Arc<ReplicatedWrite>> {
+        let mut positions: BTreeMap<WriterId, (u64, bool)> = since
+            .iter()
+            .map(|since| (since.id, (since.sequence, false)))
+            .collect();
+        let mut writes = Vec::new();
+
+        // start with the newest writes and go back, skipping a writer's writes
+        // once its position has been found
+        let all_writes = std::iter::once(&self.open_segment)
+            .chain(self.closed_segments.iter().rev().map(|s| s.as_ref()))
+            .flat_map(|s| s.writes.iter().rev());
+
+        for w in all_writes {
+            let (writer, sequence) = w.writer_and_sequence();
+            match positions.get_mut(&writer) {
+                Some((_, true)) => continue,
+                Some((since_sequence, found)) if *since_sequence == sequence => *found = true,
+                _ => writes.push(Arc::clone(w)),
+            }
+        }
+
+        writes.reverse();
+        writes
+    }
+
+    /// Returns a receiver of every write appended to the buffer from now on.
+    /// Calling this and `writes_since_each` while holding the buffer's lock
+    /// gives a subscriber every write exactly once.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<ReplicatedWrite>> {
+        self.appended_writes.subscribe()
+    }
+
     /// Returns a list of segment summaries for stored segments
     pub fn segments(&self, offset: Option<usize>) -> impl Iterator<Item = SegmentSummary> + '_ {
         std::iter::once(&self.open_segment)
@@ -264,12 +383,14 @@ impl Buffer {
 
 impl From<&WalBufferConfig> for Buffer {
     fn from(config: &WalBufferConfig) -> Self {
-        Self::new(
+        let mut buffer = Self::new(
             config.buffer_size,
             config.segment_size,
             config.buffer_rollover,
             config.store_segments,
-        )
+        );
+        buffer.close_segment_after = config.close_segment_after;
+        buffer
     }
 }
 
@@ -316,7 +437,7 @@ impl Segment {
         let (writer_id, sequence_number) = write.writer_and_sequence();
         self.validate_and_update_sequence_summary(writer_id, sequence_number)?;
 
-        let size = write.data.len();
+        let size = write.bytes().len();
         let size = u64::try_from(size).expect("appended data must be less than a u64 in length");
         self.size += size;
 
@@ -373,10 +494,10 @@ impl Segment {
     }
 
     /// Spawns a tokio task that will continuously try to persist the bytes to
-    /// the given object store location.
+    /// the given object store location. The task is registered as a job.
     pub fn persist_bytes_in_background(
         &self,
-        reg: &TrackerRegistry<SegmentPersistenceTask>,
+        jobs: &JobRegistry,
         writer_id: u32,
         db_name: &DatabaseName<'_>,
         store: Arc<ObjectStore>,
@@ -385,10 +506,10 @@ impl Segment {
         let location = database_object_store_path(writer_id, db_name, &store);
         let location = object_store_path_for_segment(&location, self.id)?;
 
-        let task_meta = SegmentPersistenceTask {
-            writer_id,
-            location: location.clone(),
-        };
+        let job = TrackedJob::new(Job::PersistSegment {
+            db_name: db_name.to_string(),
+            segment_id: self.id,
+        });
 
         let len = data.len();
         let mut stream_data = std::io::Result::Ok(data.clone());
@@ -414,7 +535,7 @@ impl Segment {
                 // TODO: Mark segment as persisted
                 info!("persisted data to {}", location.display());
             }
-            .track(reg, task_meta),
+            .track(jobs, job),
         );
 
         Ok(())
@@ -512,15 +633,13 @@ impl Segment {
             .decompress_vec(data)
             .context(UnableToDecompressData)?;
 
-        let fb_segment = flatbuffers::get_root::<wal::Segment<'_>>(&data);
+        let fb_segment = flatbuffers::root::<wal::Segment<'_>>(&data).context(InvalidFlatbuffer)?;
 
         let writes = fb_segment.writes().context(InvalidFlatbuffersSegment)?;
         let mut segment = Self::new_with_capacity(fb_segment.id(), writes.len());
         for w in writes {
             let data = w.payload().context(InvalidFlatbuffersSegment)?;
-            let rw = ReplicatedWrite {
-                data: data.to_vec(),
-            };
+            let rw = ReplicatedWrite::try_from(data).context(InvalidFlatbuffer)?;
             segment.append(Arc::new(rw))?;
         }
 
@@ -540,7 +659,10 @@ const SEGMENT_FILE_EXTENSION: &str = ".segment";
 
 /// Builds the path for a given segment id, given the root object store path.
 /// The path should be where the root of the database is (e.g. 1/my_db/).
-fn object_store_path_for_segment<P: ObjectStorePath>(root_path: &P, segment_id: u64) -> Result<P> {
+pub(crate) fn object_store_path_for_segment<P: ObjectStorePath>(
+    root_path: &P,
+    segment_id: u64,
+) -> Result<P> {
     ensure!(
         segment_id < MAX_SEGMENT_ID && segment_id > 0,
         SegmentIdOutOfBounds
@@ -563,8 +685,16 @@ fn object_store_path_for_segment<P: ObjectStorePath>(root_path: &P, segment_id:
     Ok(path)
 }
 
+/// Returns the directory below the database's root path that its segments
+/// are persisted to
+pub(crate) fn object_store_path_for_segments<P: ObjectStorePath>(root_path: &P) -> P {
+    let mut path = root_path.clone();
+    path.push_dir(WAL_DIR);
+    path
+}
+
 // base location in object store for a given database name
-fn database_object_store_path(
+pub(crate) fn database_object_store_path(
     writer_id: u32,
     database_name: &DatabaseName<'_>,
     store: &ObjectStore,
@@ -589,7 +719,7 @@ mod tests {
         let mut buf = Buffer::new(max, segment, WalBufferRollover::ReturnError, false);
         let write = lp_to_replicated_write(1, 1, "cpu val=1 10");
 
-        let size = write.data.len() as u64;
+        let size = write.bytes().len() as u64;
         assert_eq!(0, buf.size());
         let segment = buf.append(write).unwrap();
         assert_eq!(size, buf.size());
@@ -619,6 +749,45 @@ mod tests {
         assert_eq!(segment.id, 2);
     }
 
+    #[test]
+    fn closes_segment_after_duration() {
+        let config = WalBufferConfig {
+            buffer_size: 1 << 16,
+            segment_size: 1 << 16,
+            buffer_rollover: WalBufferRollover::ReturnError,
+            store_segments: false,
+            close_segment_after: Some(Duration::from_secs(60)),
+        };
+        let mut buf = Buffer::from(&config);
+        let created_at = buf.open_segment.created_at;
+        let expired = created_at + chrono::Duration::seconds(61);
+
+        // an empty segment is left open
+        assert!(buf.close_segment_if_expired(expired).is_none());
+
+        buf.append(lp_to_replicated_write(1, 1, "cpu val=1 10"))
+            .unwrap();
+        assert!(buf
+            .close_segment_if_expired(created_at + chrono::Duration::seconds(30))
+            .is_none());
+
+        let segment = buf.close_segment_if_expired(expired).unwrap();
+        assert_eq!(segment.id, 1);
+        assert_eq!(segment.writes.len(), 1);
+        assert_eq!(buf.open_segment.id, 2);
+        assert!(buf.open_segment.writes.is_empty());
+    }
+
+    #[test]
+    fn never_closes_segment_without_duration() {
+        let mut buf = Buffer::new(1 << 16, 1 << 16, WalBufferRollover::ReturnError, false);
+        buf.append(lp_to_replicated_write(1, 1, "cpu val=1 10"))
+            .unwrap();
+
+        let later = Utc::now() + chrono::Duration::days(1);
+        assert!(buf.close_segment_if_expired(later).is_none());
+    }
+
     #[test]
     fn drops_persisted_segment_when_over_size() {
         let max = 600;
@@ -742,7 +911,7 @@ mod tests {
     fn all_writes_since() {
         let max = 1 << 63;
         let write = lp_to_replicated_write(1, 1, "cpu val=1 10");
-        let segment = (write.data.len() + 1) as u64;
+        let segment = (write.bytes().len() + 1) as u64;
         let mut buf = Buffer::new(max, segment, WalBufferRollover::ReturnError, false);
 
         let segment = buf.append(write).unwrap();
@@ -799,7 +968,7 @@ mod tests {
     fn writes_since() {
         let max = 1 << 63;
         let write = lp_to_replicated_write(1, 1, "cpu val=1 10");
-        let segment = (write.data.len() + 1) as u64;
+        let segment = (write.bytes().len() + 1) as u64;
         let mut buf = Buffer::new(max, segment, WalBufferRollover::ReturnError, false);
 
         let segment = buf.append(write).unwrap();
@@ -846,7 +1015,7 @@ mod tests {
     fn returns_error_if_sequence_decreases() {
         let max = 1 << 63;
         let write = lp_to_replicated_write(1, 3, "cpu val=1 10");
-        let segment = (write.data.len() + 1) as u64;
+        let segment = (write.bytes().len() + 1) as u64;
         let mut buf = Buffer::new(max, segment, WalBufferRollover::ReturnError, false);
 
         let segment = buf.append(write).unwrap();
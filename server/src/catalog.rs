@@ -0,0 +1,514 @@
+//! This module contains the catalog of the chunks a database has persisted to
+//! Parquet files in object storage. Each snapshot adds its chunk to the
+//! catalog, each drop of a persisted chunk removes it and each compaction of
+//! persisted chunks replaces them, by writing a new version of the catalog to
+//! `<db>/catalog/`.
+//! Versions are never overwritten, so the catalog is updated atomically: a
+//! reader uses the newest version it can read, and a version that was only
+//! partly written is never read.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use bytes::Bytes;
+use data_types::{database_rules::WriterId, partition_metadata::TableSummary};
+use futures::TryStreamExt;
+use object_store::{path::ObjectStorePath, ObjectStore, ObjectStoreApi};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use tokio::sync::Mutex;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{recovery::read_bytes, snapshot::snapshot_data_path};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error listing catalog versions in {}: {}", path, source))]
+    ListingVersions {
+        path: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display("Error serializing catalog version {}: {}", version, source))]
+    SerializingCatalog {
+        version: u64,
+        source: serde_json::Error,
+    },
+
+    #[snafu(display("Error writing catalog version to {}: {}", path, source))]
+    WritingCatalog {
+        path: String,
+        source: object_store::Error,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A version of the catalog, as written to object storage
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CatalogState {
+    /// Incremented by every update, starting at 1 for the first one
+    pub version: u64,
+    pub chunks: Vec<CatalogChunk>,
+    /// The WAL positions of the chunks that were dropped from the catalog,
+    /// by partition key. The dropped chunks' writes must not be replayed
+    /// either.
+    #[serde(default)]
+    pub dropped_wal_positions: BTreeMap<String, BTreeMap<WriterId, u64>>,
+}
+
+impl CatalogState {
+    /// Returns the WAL positions covered by the persisted chunks of each
+    /// partition, which is the latest position of each writer over all of
+    /// the partition's chunks
+    pub fn wal_positions(&self) -> BTreeMap<String, BTreeMap<WriterId, u64>> {
+        let mut positions = self.dropped_wal_positions.clone();
+        for chunk in &self.chunks {
+            chunk.merge_wal_positions(&mut positions);
+        }
+        positions
+    }
+}
+
+/// A chunk whose tables were written to Parquet files by a snapshot
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CatalogChunk {
+    pub partition_key: String,
+    pub chunk_id: u32,
+    /// The id of the snapshot that wrote the files, which names their
+    /// directory
+    pub snapshot_id: Uuid,
+    /// For each writer, the sequence number of its last write in the
+    /// database's WAL buffer before the chunk was rolled over. The
+    /// partition's writes up to these positions are in the chunk or in one
+    /// persisted before it. Empty if other chunks of the partition were not
+    /// persisted yet when the chunk was snapshotted.
+    pub wal_positions: BTreeMap<WriterId, u64>,
+    pub files: Vec<CatalogFile>,
+}
+
+impl CatalogChunk {
+    /// Returns the directory the chunk's files are in, given the database's
+    /// root path in object storage
+    pub fn data_path(&self, db_path: &object_store::path::Path) -> object_store::path::Path {
+        snapshot_data_path(db_path, &self.partition_key, self.snapshot_id)
+    }
+
+    /// Returns the locations of the chunk's files, given the database's root
+    /// path in object storage
+    pub fn file_paths(&self, db_path: &object_store::path::Path) -> Vec<object_store::path::Path> {
+        let data_path = self.data_path(db_path);
+        self.files
+            .iter()
+            .map(|file| {
+                let mut path = data_path.clone();
+                path.set_file_name(&file.file_name);
+                path
+            })
+            .collect()
+    }
+
+    /// Returns the summaries of the tables in the chunk's files
+    pub fn table_summaries(&self) -> Vec<TableSummary> {
+        self.files.iter().map(|file| file.table.clone()).collect()
+    }
+
+    /// Raises the WAL positions of the chunk's partition to those the chunk
+    /// covers
+    pub fn merge_wal_positions(&self, positions: &mut BTreeMap<String, BTreeMap<WriterId, u64>>) {
+        let partition_positions = positions
+            .entry(self.partition_key.clone())
+            .or_insert_with(BTreeMap::new);
+
+        for (&writer, &sequence) in &self.wal_positions {
+            let position = partition_positions.entry(writer).or_insert(sequence);
+            *position = (*position).max(sequence);
+        }
+    }
+}
+
+/// A Parquet file holding one of a chunk's tables
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CatalogFile {
+    /// The name of the file in the chunk's directory
+    pub file_name: String,
+    /// The statistics of the table's columns
+    pub table: TableSummary,
+    /// The minimum and maximum timestamps of the table's rows, or `None` if
+    /// it has none
+    pub time_range: Option<(i64, i64)>,
+    /// The size of the file in bytes
+    pub size: u64,
+}
+
+/// The catalog of a database, which holds the latest version that was read
+/// from or written to object storage. Updates are serialized so that every
+/// one of them is based on the one before.
+#[derive(Debug, Default)]
+pub struct Catalog {
+    state: Mutex<CatalogState>,
+}
+
+impl Catalog {
+    /// Returns the latest version of the catalog
+    pub async fn state(&self) -> CatalogState {
+        self.state.lock().await.clone()
+    }
+
+    /// Replaces the catalog's state with one loaded from object storage
+    pub(crate) async fn restore(&self, state: CatalogState) {
+        *self.state.lock().await = state;
+    }
+
+    /// Adds the chunk to the catalog, replacing an entry written by the same
+    /// snapshot, and writes the new version of the catalog below the
+    /// database's root path `db_path`. The catalog is only changed once the
+    /// new version has been written. Returns the new version.
+    pub async fn add_chunk(
+        &self,
+        store: &ObjectStore,
+        db_path: &object_store::path::Path,
+        chunk: CatalogChunk,
+    ) -> Result<u64> {
+        self.update(store, db_path, |state| {
+            // chunk ids are only unique within a partition's lifetime in the
+            // mutable buffer, so an entry is only replaced by its own snapshot
+            state.chunks.retain(|c| c.snapshot_id != chunk.snapshot_id);
+            state.chunks.push(chunk);
+        })
+        .await
+    }
+
+    /// Removes the entries of the partition's chunk from the catalog and
+    /// writes the new version of the catalog, like `add_chunk`. The removed
+    /// entries' WAL positions are kept, so that recovery doesn't replay the
+    /// dropped writes. Returns the new version.
+    pub async fn drop_chunk(
+        &self,
+        store: &ObjectStore,
+        db_path: &object_store::path::Path,
+        partition_key: &str,
+        chunk_id: u32,
+    ) -> Result<u64> {
+        self.update(store, db_path, |state| {
+            let (dropped, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut state.chunks)
+                .into_iter()
+                .partition(|c| c.partition_key == partition_key && c.chunk_id == chunk_id);
+            state.chunks = kept;
+
+            for chunk in dropped {
+                chunk.merge_wal_positions(&mut state.dropped_wal_positions);
+            }
+        })
+        .await
+    }
+
+    /// Replaces the entries of the partition's chunks with the chunk they
+    /// were compacted into, in a single new version of the catalog that is
+    /// written like `add_chunk`. The new entry covers the WAL positions of
+    /// the entries it replaces. Returns the new version.
+    pub async fn replace_chunks(
+        &self,
+        store: &ObjectStore,
+        db_path: &object_store::path::Path,
+        chunk_ids: &[u32],
+        mut chunk: CatalogChunk,
+    ) -> Result<u64> {
+        self.update(store, db_path, |state| {
+            let (replaced, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut state.chunks)
+                .into_iter()
+                .partition(|c| {
+                    c.partition_key == chunk.partition_key && chunk_ids.contains(&c.chunk_id)
+                });
+            state.chunks = kept;
+
+            let mut positions = BTreeMap::new();
+            for replaced in &replaced {
+                replaced.merge_wal_positions(&mut positions);
+            }
+            if let Some(positions) = positions.remove(&chunk.partition_key) {
+                chunk.wal_positions = positions;
+            }
+            state.chunks.push(chunk);
+        })
+        .await
+    }
+
+    /// Applies the update to a copy of the latest version and writes it as
+    /// the next version, which only becomes the catalog's state once it
+    /// has been written
+    async fn update(
+        &self,
+        store: &ObjectStore,
+        db_path: &object_store::path::Path,
+        update: impl FnOnce(&mut CatalogState),
+    ) -> Result<u64> {
+        let mut state = self.state.lock().await;
+
+        let mut new_state = state.clone();
+        new_state.version += 1;
+        update(&mut new_state);
+
+        let path = catalog_version_path(db_path, new_state.version);
+        let data = serde_json::to_vec(&new_state).context(SerializingCatalog {
+            version: new_state.version,
+        })?;
+        let data = Bytes::from(data);
+        let len = data.len();
+        store
+            .put(
+                &path,
+                futures::stream::once(async move { Ok(data) }),
+                Some(len),
+            )
+            .await
+            .context(WritingCatalog {
+                path: path.display(),
+            })?;
+
+        *state = new_state;
+        Ok(state.version)
+    }
+}
+
+/// Returns the directory the versions of a database's catalog are written
+/// to, given the database's root path in object storage
+pub(crate) fn catalog_path(db_path: &object_store::path::Path) -> object_store::path::Path {
+    let mut path = db_path.clone();
+    path.push_dir("catalog");
+    path
+}
+
+/// Returns the location of a version of the catalog. The version is zero
+/// padded so that versions sort in order.
+pub(crate) fn catalog_version_path(
+    db_path: &object_store::path::Path,
+    version: u64,
+) -> object_store::path::Path {
+    let mut path = catalog_path(db_path);
+    path.set_file_name(format!("{:020}.json", version));
+    path
+}
+
+/// Reads the newest version of the catalog below the database's root path
+/// `db_path`, or an empty catalog if none was written. Versions that can't
+/// be read are logged and skipped in favour of the one before, and their
+/// number is returned with the catalog.
+pub(crate) async fn load_catalog(
+    store: &Arc<ObjectStore>,
+    db_path: &object_store::path::Path,
+) -> Result<(CatalogState, usize)> {
+    let path = catalog_path(db_path);
+    let mut locations: Vec<_> = store
+        .list(Some(&path))
+        .await
+        .context(ListingVersions {
+            path: path.display(),
+        })?
+        .try_concat()
+        .await
+        .context(ListingVersions {
+            path: path.display(),
+        })?;
+
+    // newest first
+    locations.sort_by_key(|location| std::cmp::Reverse(location.display()));
+
+    let mut skipped = 0;
+    for location in locations {
+        let state = read_bytes(store, &location).await.and_then(|data| {
+            serde_json::from_slice::<CatalogState>(&data).map_err(|e| e.to_string())
+        });
+
+        match state {
+            Ok(state) => return Ok((state, skipped)),
+            Err(e) => {
+                warn!(
+                    "skipping catalog version {} that could not be read: {}",
+                    location.display(),
+                    e
+                );
+                skipped += 1;
+            }
+        }
+    }
+
+    Ok((CatalogState::default(), skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    fn chunk(partition_key: &str, chunk_id: u32, sequence: u64) -> CatalogChunk {
+        CatalogChunk {
+            partition_key: partition_key.to_string(),
+            chunk_id,
+            snapshot_id: Uuid::new_v4(),
+            wal_positions: vec![(1, sequence)].into_iter().collect(),
+            files: vec![CatalogFile {
+                file_name: "cpu.parquet".to_string(),
+                table: TableSummary::new("cpu"),
+                time_range: None,
+                size: 10,
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn catalog_versions() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let db_path = store.new_path();
+
+        let (state, skipped) = load_catalog(&store, &db_path).await.unwrap();
+        assert_eq!(state, CatalogState::default());
+        assert_eq!(skipped, 0);
+
+        let catalog = Catalog::default();
+        let first = chunk("p1", 0, 5);
+        let second = chunk("p1", 1, 8);
+        let third = chunk("p2", 0, 3);
+        assert_eq!(
+            catalog
+                .add_chunk(&store, &db_path, first.clone())
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            catalog
+                .add_chunk(&store, &db_path, second.clone())
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            catalog
+                .add_chunk(&store, &db_path, third.clone())
+                .await
+                .unwrap(),
+            3
+        );
+
+        // a chunk that reuses the id of a persisted one is added next to it
+        let reused = chunk("p1", 0, 6);
+        catalog
+            .add_chunk(&store, &db_path, reused.clone())
+            .await
+            .unwrap();
+
+        // while the same snapshot replaces its entry
+        let mut rewritten = second.clone();
+        rewritten.files[0].size = 20;
+        catalog
+            .add_chunk(&store, &db_path, rewritten.clone())
+            .await
+            .unwrap();
+
+        let state = catalog.state().await;
+        assert_eq!(state.version, 5);
+        assert_eq!(state.chunks, vec![first, third, reused, rewritten]);
+
+        let (loaded, skipped) = load_catalog(&store, &db_path).await.unwrap();
+        assert_eq!(loaded, state);
+        assert_eq!(skipped, 0);
+
+        let positions = state.wal_positions();
+        assert_eq!(positions["p1"], vec![(1, 8)].into_iter().collect());
+        assert_eq!(positions["p2"], vec![(1, 3)].into_iter().collect());
+    }
+
+    #[tokio::test]
+    async fn drop_chunk_keeps_wal_positions() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let db_path = store.new_path();
+
+        let catalog = Catalog::default();
+        let first = chunk("p1", 0, 5);
+        let second = chunk("p1", 1, 8);
+        catalog
+            .add_chunk(&store, &db_path, first.clone())
+            .await
+            .unwrap();
+        catalog
+            .add_chunk(&store, &db_path, second.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            catalog.drop_chunk(&store, &db_path, "p1", 1).await.unwrap(),
+            3
+        );
+
+        let state = catalog.state().await;
+        assert_eq!(state.chunks, vec![first]);
+        assert_eq!(
+            state.wal_positions()["p1"],
+            vec![(1, 8)].into_iter().collect()
+        );
+
+        let (loaded, _) = load_catalog(&store, &db_path).await.unwrap();
+        assert_eq!(loaded, state);
+    }
+
+    #[tokio::test]
+    async fn replace_chunks_keeps_wal_positions() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let db_path = store.new_path();
+
+        let catalog = Catalog::default();
+        let first = chunk("p1", 0, 5);
+        let second = chunk("p1", 1, 8);
+        let other = chunk("p2", 1, 3);
+        for chunk in vec![first, second, other.clone()] {
+            catalog.add_chunk(&store, &db_path, chunk).await.unwrap();
+        }
+
+        let mut compacted = chunk("p1", 0, 0);
+        compacted.wal_positions = Default::default();
+        assert_eq!(
+            catalog
+                .replace_chunks(&store, &db_path, &[0, 1], compacted.clone())
+                .await
+                .unwrap(),
+            4
+        );
+
+        compacted.wal_positions = vec![(1, 8)].into_iter().collect();
+        let state = catalog.state().await;
+        assert_eq!(state.chunks, vec![other, compacted]);
+        assert!(state.dropped_wal_positions.is_empty());
+
+        let (loaded, _) = load_catalog(&store, &db_path).await.unwrap();
+        assert_eq!(loaded, state);
+    }
+
+    #[tokio::test]
+    async fn skips_unreadable_versions() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let db_path = store.new_path();
+
+        let catalog = Catalog::default();
+        catalog
+            .add_chunk(&store, &db_path, chunk("p1", 0, 5))
+            .await
+            .unwrap();
+        let state = catalog.state().await;
+
+        let data = Bytes::from("not json");
+        let len = data.len();
+        store
+            .put(
+                &catalog_version_path(&db_path, 2),
+                futures::stream::once(async move { Ok(data) }),
+                Some(len),
+            )
+            .await
+            .unwrap();
+
+        let (loaded, skipped) = load_catalog(&store, &db_path).await.unwrap();
+        assert_eq!(loaded, state);
+        assert_eq!(skipped, 1);
+    }
+}
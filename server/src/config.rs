@@ -1,5 +1,5 @@
 /// This module contains code for managing the configuration of the server.
-use crate::{db::Db, Error, Result};
+use crate::{db::Db, hash_ring::HashRing, Error, Result};
 use data_types::{
     database_rules::{DatabaseRules, HostGroup, HostGroupId},
     DatabaseName,
@@ -60,14 +60,23 @@ impl Config {
         state.databases.get(name).cloned()
     }
 
+    /// Removes the database from the config, returning it if it existed
+    pub(crate) fn remove_db(&self, name: &DatabaseName<'_>) -> Option<Arc<Db>> {
+        // the map's keys are 'static, so a key with the same name is needed
+        let name = DatabaseName::new(name.to_string()).expect("name was already validated");
+        let mut state = self.state.write().expect("mutex poisoned");
+        state.databases.remove(&name)
+    }
+
     pub(crate) fn create_host_group(&self, host_group: HostGroup) {
+        let ring = Arc::new(HashRing::new(&host_group));
         let mut state = self.state.write().expect("mutex poisoned");
-        state
-            .host_groups
-            .insert(host_group.id.clone(), Arc::new(host_group));
+        state.host_groups.insert(host_group.id, ring);
     }
 
-    pub(crate) fn host_group(&self, host_group_id: &str) -> Option<Arc<HostGroup>> {
+    /// Returns the hash ring over the hosts of the group, which is used to
+    /// pick the host that receives each partition's writes.
+    pub(crate) fn host_group(&self, host_group_id: &str) -> Option<Arc<HashRing>> {
         let state = self.state.read().expect("mutex poisoned");
         state.host_groups.get(host_group_id).cloned()
     }
@@ -106,7 +115,7 @@ pub fn object_store_path_for_database_config<P: ObjectStorePath>(
 struct ConfigState {
     reservations: BTreeSet<DatabaseName<'static>>,
     databases: BTreeMap<DatabaseName<'static>, Arc<Db>>,
-    host_groups: BTreeMap<HostGroupId, Arc<HostGroup>>,
+    host_groups: BTreeMap<HostGroupId, Arc<HashRing>>,
 }
 
 /// CreateDatabaseHandle is retunred when a call is made to `create_db` on
@@ -159,7 +168,18 @@ mod test {
         db_reservation.commit();
         assert!(config.db(&name).is_some());
 
-        assert_eq!(config.db_names_sorted(), vec![name]);
+        assert_eq!(config.db_names_sorted(), vec![name.clone()]);
+
+        assert!(config.remove_db(&name).is_some());
+        assert!(config.db(&name).is_none());
+        assert!(config.remove_db(&name).is_none());
+
+        // the name can be used again
+        config
+            .create_db(name.clone(), DatabaseRules::new())
+            .unwrap()
+            .commit();
+        assert!(config.db(&name).is_some());
     }
 
     #[test]
@@ -2,28 +2,47 @@
 //! instances of the mutable buffer, read buffer, and object store
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     sync::{
         atomic::{AtomicU64, Ordering},
         Arc,
     },
 };
 
+use arrow_deps::arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
-use data_types::{data::ReplicatedWrite, database_rules::DatabaseRules, selection::Selection};
+use data_types::{
+    chunk::{ChunkStorage, ChunkSummary},
+    data::ReplicatedWrite,
+    database_rules::{DatabaseRules, HostGroupId, WriterId},
+    partition_metadata::TableSummary,
+    selection::Selection,
+};
 use mutable_buffer::MutableBufferDb;
-use parking_lot::Mutex;
-use query::{Database, PartitionChunk};
+use object_store::ObjectStore;
+use parking_lot::{Mutex, RwLock};
+use query::{exec::Executor, Database, PartitionChunk};
 use read_buffer::Database as ReadBufferDb;
-use serde::{Deserialize, Serialize};
-use snafu::{OptionExt, ResultExt, Snafu};
-
-use crate::buffer::Buffer;
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use tokio::sync::OwnedMutexGuard;
+use uuid::Uuid;
+
+use crate::{
+    buffer::Buffer,
+    catalog::{Catalog, CatalogChunk},
+    jobs::JobProgress,
+    matcher::CompiledMatcher,
+    replication::ReplicationQueue,
+    snapshot::{snapshot_data_path, write_table_file},
+};
 
-use tracing::info;
+use tracing::{info, warn};
 
 mod chunk;
 pub(crate) use chunk::DBChunk;
+mod compact;
+use parquet_file::ParquetChunk;
+pub mod parquet_file;
 pub mod pred;
 mod streams;
 
@@ -65,36 +84,134 @@ pub enum Error {
 
     #[snafu(display("Error dropping data from read buffer: {}", source))]
     ReadBufferDrop { source: read_buffer::Error },
+
+    #[snafu(display(
+        "Rejecting write: the mutable buffer is {} bytes, over its limit of {} bytes, \
+         and holds no persisted partitions that can be dropped",
+        size,
+        limit
+    ))]
+    MutableBufferFull { size: usize, limit: usize },
+
+    #[snafu(display(
+        "Cannot add or remove the mutable buffer of a loaded database, \
+         it must be deleted and created again"
+    ))]
+    MutableBufferChange {},
+
+    #[snafu(display(
+        "Cannot add or remove the WAL buffer of a loaded database, \
+         it must be deleted and created again"
+    ))]
+    WalBufferChange {},
+
+    #[snafu(display(
+        "Unknown persisted chunk {} in partition '{}'",
+        chunk_id,
+        partition_key
+    ))]
+    UnknownPersistedChunk {
+        partition_key: String,
+        chunk_id: u32,
+    },
+
+    #[snafu(display("Error updating the catalog: {}", source))]
+    CatalogUpdate { source: crate::catalog::Error },
+
+    #[snafu(display("At least two chunks are needed for a compaction, got {}", count))]
+    CompactTooFewChunks { count: usize },
+
+    #[snafu(display(
+        "Chunk {} of partition '{}' is not in the read buffer or object storage",
+        chunk_id,
+        partition_key
+    ))]
+    UnknownCompactChunk {
+        partition_key: String,
+        chunk_id: u32,
+    },
+
+    #[snafu(display(
+        "Chunks {:?} of partition '{}' are persisted to object storage while the \
+         others are not, either all of the compacted chunks or none of them must be \
+         persisted",
+        persisted,
+        partition_key
+    ))]
+    CompactMixedChunks {
+        partition_key: String,
+        persisted: Vec<u32>,
+    },
+
+    #[snafu(display(
+        "Chunk {} is still in the mutable buffer, it must be dropped from there \
+         before it is compacted",
+        chunk_id
+    ))]
+    CompactMutableBufferChunk { chunk_id: u32 },
+
+    #[snafu(display("Error compacting chunks: {}", source))]
+    Compacting { source: compact::Error },
+
+    #[snafu(display("Error replacing chunks in read buffer: {}", source))]
+    ReadBufferReplace { source: read_buffer::Error },
+
+    #[snafu(display(
+        "Error persisting table '{}' of the compacted chunk: {}",
+        table_name,
+        source
+    ))]
+    PersistingCompactedTable {
+        table_name: String,
+        source: crate::snapshot::Error,
+    },
 }
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 const STARTING_SEQUENCE: u64 = 1;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug)]
 /// This is the main IOx Database object. It is the root object of any
 /// specific InfluxDB IOx instance
 pub struct Db {
-    #[serde(flatten)]
-    pub rules: DatabaseRules,
+    /// The database's rules, which can be replaced with `update_rules`
+    /// while the database is loaded
+    pub rules: RwLock<DatabaseRules>,
 
-    #[serde(skip)]
     /// The (optional) mutable buffer stores incoming writes. If a
     /// database does not have a mutable buffer it can not accept
     /// writes (it is a read replica)
     pub mutable_buffer: Option<MutableBufferDb>,
 
-    #[serde(skip)]
     /// The read buffer holds chunk data in an in-memory optimized
     /// format.
     pub read_buffer: Arc<ReadBufferDb>,
 
-    #[serde(skip)]
     /// The wal buffer holds replicated writes in an append in-memory
     /// buffer. This buffer is used for sending data to subscribers
     /// and to persist segments in object storage for recovery.
     pub wal_buffer: Option<Mutex<Buffer>>,
 
-    #[serde(skip)]
+    /// The chunks that were persisted to Parquet files in object storage,
+    /// by partition key and chunk id
+    persisted_chunks: RwLock<BTreeMap<String, BTreeMap<u32, Arc<DBChunk>>>>,
+
+    /// The catalog of the chunk files persisted to object storage, which
+    /// snapshots add their chunks to
+    pub catalog: Arc<Catalog>,
+
+    /// Writes that still need to be sent to some of the database's
+    /// replication host groups, which are retried in the background.
+    pub(crate) replication_queue: Mutex<ReplicationQueue>,
+
+    /// The host group of each subscription and the compiled matcher that
+    /// selects the part of each write to send to it.
+    pub(crate) subscriptions: RwLock<Arc<Vec<(HostGroupId, CompiledMatcher)>>>,
+
+    /// Serializes the snapshots, compactions and drops of persisted chunks
+    /// of each partition, by partition key
+    partition_locks: Mutex<BTreeMap<String, Arc<tokio::sync::Mutex<()>>>>,
+
     sequence: AtomicU64,
 }
 impl Db {
@@ -106,15 +223,62 @@ impl Db {
     ) -> Self {
         let wal_buffer = wal_buffer.map(Mutex::new);
         let read_buffer = Arc::new(read_buffer);
+        let replication_queue = Mutex::new(ReplicationQueue::new(
+            rules.replication_queue_max_size,
+            rules.replication_queue_overflow,
+        ));
+        let subscriptions = RwLock::new(Arc::new(compile_subscriptions(&rules)));
         Self {
-            rules,
+            rules: RwLock::new(rules),
             mutable_buffer,
             read_buffer,
             wal_buffer,
+            persisted_chunks: Default::default(),
+            catalog: Default::default(),
+            replication_queue,
+            subscriptions,
+            partition_locks: Default::default(),
             sequence: AtomicU64::new(STARTING_SEQUENCE),
         }
     }
 
+    /// Checks that the database can switch to the new rules while it is
+    /// loaded. Whether it has a mutable buffer or a WAL buffer can't change.
+    pub fn check_rules_update(&self, rules: &DatabaseRules) -> Result<()> {
+        ensure!(
+            rules.mutable_buffer_config.is_some() == self.mutable_buffer.is_some(),
+            MutableBufferChange
+        );
+        ensure!(
+            rules.wal_buffer_config.is_some() == self.wal_buffer.is_some(),
+            WalBufferChange
+        );
+        Ok(())
+    }
+
+    /// Replaces the database's rules. The new partition template,
+    /// replication and subscriptions apply to the following writes, the
+    /// lifecycle manager picks up the new mutable buffer config, and the
+    /// WAL buffer and replication queue are resized.
+    pub fn update_rules(&self, rules: DatabaseRules) -> Result<()> {
+        // held until the new rules are in place, so concurrent updates are
+        // applied one after the other
+        let mut current = self.rules.write();
+        self.check_rules_update(&rules)?;
+
+        if let (Some(wal_buffer), Some(config)) = (&self.wal_buffer, &rules.wal_buffer_config) {
+            wal_buffer.lock().update_config(config);
+        }
+        self.replication_queue.lock().configure(
+            rules.replication_queue_max_size,
+            rules.replication_queue_overflow,
+        );
+        *self.subscriptions.write() = Arc::new(compile_subscriptions(&rules));
+        *current = rules;
+
+        Ok(())
+    }
+
     /// Rolls over the active chunk in the database's specified partition
     pub async fn rollover_partition(&self, partition_key: &str) -> Result<Arc<DBChunk>> {
         if let Some(local_store) = self.mutable_buffer.as_ref() {
@@ -151,6 +315,97 @@ impl Db {
             .collect()
     }
 
+    /// Waits until no other snapshot, compaction or drop of a persisted chunk
+    /// of the partition runs, and keeps them waiting until the returned guard
+    /// is dropped. Whether a chunk of the partition is persisted only stays
+    /// true or false while the guard is held.
+    pub(crate) async fn lock_partition(&self, partition_key: &str) -> OwnedMutexGuard<()> {
+        let lock = Arc::clone(
+            self.partition_locks
+                .lock()
+                .entry(partition_key.to_string())
+                .or_default(),
+        );
+        lock.lock_owned().await
+    }
+
+    /// List chunks that were persisted to object storage
+    pub fn persisted_chunks(&self, partition_key: &str) -> Vec<Arc<DBChunk>> {
+        self.persisted_chunks
+            .read()
+            .get(partition_key)
+            .map(|chunks| chunks.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Makes a chunk persisted to object storage queryable, replacing any
+    /// persisted chunk with the same id in its partition
+    pub fn add_persisted_chunk(&self, chunk: ParquetChunk) -> Arc<DBChunk> {
+        let partition_key = chunk.partition_key().to_string();
+        let chunk_id = chunk.id();
+        let chunk = DBChunk::new_parquet(Arc::new(chunk));
+
+        self.persisted_chunks
+            .write()
+            .entry(partition_key)
+            .or_default()
+            .insert(chunk_id, Arc::clone(&chunk));
+
+        chunk
+    }
+
+    /// Stops querying the specified persisted chunk and removes it from the
+    /// catalog below the database's root path `db_path`, so that it isn't
+    /// loaded again when the server restarts. Returns the dropped chunk. Its
+    /// files are left to the garbage collection.
+    pub async fn drop_persisted_chunk(
+        &self,
+        store: &ObjectStore,
+        db_path: &object_store::path::Path,
+        partition_key: &str,
+        chunk_id: u32,
+    ) -> Result<Arc<DBChunk>> {
+        let _partition_lock = self.lock_partition(partition_key).await;
+
+        let is_persisted = self
+            .persisted_chunks
+            .read()
+            .get(partition_key)
+            .map_or(false, |chunks| chunks.contains_key(&chunk_id));
+        ensure!(
+            is_persisted,
+            UnknownPersistedChunk {
+                partition_key,
+                chunk_id,
+            }
+        );
+
+        // the chunk is only dropped from memory once the drop is recorded
+        self.catalog
+            .drop_chunk(store, db_path, partition_key, chunk_id)
+            .await
+            .context(CatalogUpdate)?;
+
+        let mut persisted_chunks = self.persisted_chunks.write();
+        let chunks = persisted_chunks.get_mut(partition_key);
+        let chunk =
+            chunks
+                .and_then(|chunks| chunks.remove(&chunk_id))
+                .context(UnknownPersistedChunk {
+                    partition_key,
+                    chunk_id,
+                })?;
+
+        if persisted_chunks
+            .get(partition_key)
+            .map_or(false, BTreeMap::is_empty)
+        {
+            persisted_chunks.remove(partition_key);
+        }
+
+        Ok(chunk)
+    }
+
     /// Drops the specified chunk from the mutable buffer, returning
     /// the dropped chunk.
     pub async fn drop_mutable_buffer_chunk(
@@ -230,20 +485,387 @@ impl Db {
         ))
     }
 
+    /// Merges chunks of a partition into a single read buffer chunk, which
+    /// takes the lowest id of the compacted chunks. The schemas of each
+    /// table are merged and its rows are sorted by tag columns and time. The
+    /// compacted chunk replaces the chunks in the read buffer and object
+    /// storage in one step.
+    ///
+    /// The compacted chunks can be in the read buffer or persisted to object
+    /// storage, but not in the mutable buffer, as queries would return the
+    /// rows of a mutable buffer chunk as well as the compacted chunk. If they
+    /// are persisted, the compacted chunk is written to Parquet files below
+    /// the database's root path `db_path` and replaces them in the catalog,
+    /// so that it is loaded instead of them after a restart. Either all of
+    /// them or none of them must be persisted: the writes of chunks that
+    /// aren't would be replayed after a restart as well.
+    ///
+    /// Snapshots, other compactions and drops of persisted chunks of the
+    /// partition wait until the compaction has finished.
+    pub async fn compact_chunks(
+        &self,
+        executor: &Executor,
+        store: &ObjectStore,
+        db_path: &object_store::path::Path,
+        partition_key: &str,
+        chunk_ids: &[u32],
+        progress: &JobProgress,
+    ) -> Result<Arc<DBChunk>> {
+        let chunk_ids: BTreeSet<_> = chunk_ids.iter().cloned().collect();
+        ensure!(
+            chunk_ids.len() >= 2,
+            CompactTooFewChunks {
+                count: chunk_ids.len()
+            }
+        );
+
+        let _partition_lock = self.lock_partition(partition_key).await;
+
+        if let Some(mutable_buffer) = self.mutable_buffer.as_ref() {
+            // listing the chunks of a partition the mutable buffer doesn't
+            // have would create it
+            let has_partition = mutable_buffer
+                .partition_keys()
+                .context(MutableBufferRead)?
+                .iter()
+                .any(|key| key == partition_key);
+
+            if has_partition {
+                for chunk in mutable_buffer.chunks(partition_key) {
+                    ensure!(
+                        !chunk_ids.contains(&chunk.id()),
+                        CompactMutableBufferChunk {
+                            chunk_id: chunk.id()
+                        }
+                    );
+                }
+            }
+        }
+
+        // persisted chunks are in the read buffer until the server restarts,
+        // and only loaded from object storage after that
+        let read_buffer_ids = self.read_buffer.chunk_ids(partition_key);
+        let parquet_chunks = self
+            .persisted_chunks
+            .read()
+            .get(partition_key)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut chunks = vec![];
+        let mut read_buffer_replaced = vec![];
+        for &chunk_id in &chunk_ids {
+            if read_buffer_ids.contains(&chunk_id) {
+                read_buffer_replaced.push(chunk_id);
+                chunks.push(DBChunk::new_rb(
+                    Arc::clone(&self.read_buffer),
+                    partition_key,
+                    chunk_id,
+                ));
+            } else {
+                let chunk = parquet_chunks.get(&chunk_id).context(UnknownCompactChunk {
+                    partition_key,
+                    chunk_id,
+                })?;
+                chunks.push(Arc::clone(chunk));
+            }
+        }
+
+        let persisted: Vec<_> = self
+            .catalog
+            .state()
+            .await
+            .chunks
+            .into_iter()
+            .filter(|chunk| {
+                chunk.partition_key == partition_key && chunk_ids.contains(&chunk.chunk_id)
+            })
+            .collect();
+        let persisted_ids: BTreeSet<_> = persisted.iter().map(|chunk| chunk.chunk_id).collect();
+        ensure!(
+            persisted_ids.is_empty() || persisted_ids == chunk_ids,
+            CompactMixedChunks {
+                partition_key,
+                persisted: persisted_ids.into_iter().collect::<Vec<_>>(),
+            }
+        );
+
+        let table_names = compact::table_names(&chunks).context(Compacting)?;
+        progress.add_tasks(table_names.len());
+
+        let mut tables = vec![];
+        for table_name in table_names {
+            let batches = compact::compact_table(executor, &table_name, &chunks)
+                .await
+                .context(Compacting)?;
+
+            tables.extend(
+                batches
+                    .into_iter()
+                    .filter(|batch| batch.num_rows() > 0)
+                    .map(|batch| (table_name.clone(), batch)),
+            );
+            progress.complete_task();
+        }
+
+        let new_chunk_id = *chunk_ids.iter().next().expect("at least two chunks");
+        if !persisted.is_empty() {
+            let chunk = persist_compacted_chunk(
+                store,
+                db_path,
+                partition_key,
+                new_chunk_id,
+                &persisted,
+                &tables,
+            )
+            .await?;
+
+            // the compacted chunk is only used once the catalog refers to it
+            let replaced_ids: Vec<_> = chunk_ids.iter().cloned().collect();
+            self.catalog
+                .replace_chunks(store, db_path, &replaced_ids, chunk)
+                .await
+                .context(CatalogUpdate)?;
+        }
+
+        // queries wait for the persisted chunks until the compacted chunk is
+        // in the read buffer
+        let mut persisted_chunks = self.persisted_chunks.write();
+        self.read_buffer
+            .replace_chunks(partition_key, &read_buffer_replaced, new_chunk_id, tables)
+            .context(ReadBufferReplace)?;
+
+        if let Some(partition_chunks) = persisted_chunks.get_mut(partition_key) {
+            partition_chunks.retain(|chunk_id, _| !chunk_ids.contains(chunk_id));
+            if partition_chunks.is_empty() {
+                persisted_chunks.remove(partition_key);
+            }
+        }
+        drop(persisted_chunks);
+
+        info!(
+            partition_key,
+            ?chunk_ids,
+            new_chunk_id,
+            "compacted chunks into read buffer"
+        );
+
+        Ok(DBChunk::new_rb(
+            Arc::clone(&self.read_buffer),
+            partition_key,
+            new_chunk_id,
+        ))
+    }
+
+    /// Returns the keys of all partitions with chunks in the mutable buffer,
+    /// the read buffer or object storage
+    pub fn all_partition_keys(&self) -> Result<Vec<String>> {
+        let mut keys = match self.mutable_buffer.as_ref() {
+            Some(mutable_buffer) => mutable_buffer.partition_keys().context(MutableBufferRead)?,
+            None => vec![],
+        };
+
+        keys.extend(self.read_buffer.partition_keys());
+        keys.extend(self.persisted_chunks.read().keys().cloned());
+        keys.sort();
+        keys.dedup();
+        Ok(keys)
+    }
+
+    /// Returns a summary of the chunks of every partition, ordered by
+    /// partition key and chunk id
+    pub fn chunk_summaries(&self) -> Result<Vec<ChunkSummary>> {
+        let mut summaries = vec![];
+        for partition_key in self.all_partition_keys()? {
+            summaries.extend(self.partition_chunk_summaries(&partition_key)?);
+        }
+        Ok(summaries)
+    }
+
+    /// Returns a summary of the chunks of the specified partition in the
+    /// mutable buffer, the read buffer and object storage, ordered by chunk id
+    pub fn partition_chunk_summaries(&self, partition_key: &str) -> Result<Vec<ChunkSummary>> {
+        let mut summaries = vec![];
+
+        if let Some(mutable_buffer) = self.mutable_buffer.as_ref() {
+            // listing the chunks of a partition the mutable buffer doesn't
+            // have would create it
+            let has_partition = mutable_buffer
+                .partition_keys()
+                .context(MutableBufferRead)?
+                .iter()
+                .any(|key| key == partition_key);
+
+            if has_partition {
+                for chunk in mutable_buffer.chunks(partition_key) {
+                    let storage = if chunk.time_closed.is_some() {
+                        ChunkStorage::ClosedMutableBuffer
+                    } else {
+                        ChunkStorage::OpenMutableBuffer
+                    };
+                    let tables = chunk.table_stats().context(MutableBufferChunk)?;
+
+                    summaries.push(ChunkSummary::from_table_summaries(
+                        partition_key,
+                        chunk.id(),
+                        storage,
+                        chunk.size(),
+                        &tables,
+                    ));
+                }
+            }
+        }
+
+        for chunk_id in self.read_buffer.chunk_ids(partition_key) {
+            // skip chunks dropped since they were listed
+            if let Ok(stats) = self.read_buffer.chunk_stats(partition_key, chunk_id) {
+                summaries.push(ChunkSummary {
+                    partition_key: partition_key.to_string(),
+                    id: chunk_id,
+                    storage: ChunkStorage::ReadBuffer,
+                    estimated_bytes: stats.size as usize,
+                    row_count: stats.rows as usize,
+                    time_range: stats.time_range,
+                });
+            }
+        }
+
+        for chunk in self.persisted_chunks(partition_key) {
+            if let DBChunk::ParquetFile { chunk } = chunk.as_ref() {
+                summaries.push(ChunkSummary::from_table_summaries(
+                    partition_key,
+                    chunk.id(),
+                    ChunkStorage::ObjectStore,
+                    0,
+                    &chunk.table_stats(),
+                ));
+            }
+        }
+
+        summaries.sort();
+        Ok(summaries)
+    }
+
+    /// Returns the summary of the specified chunk if the partition has it in
+    /// the given storage
+    pub fn chunk_summary(
+        &self,
+        partition_key: &str,
+        chunk_id: u32,
+        storage: ChunkStorage,
+    ) -> Result<Option<ChunkSummary>> {
+        Ok(self
+            .partition_chunk_summaries(partition_key)?
+            .into_iter()
+            .find(|summary| summary.id == chunk_id && summary.storage == storage))
+    }
+
     /// Returns the next write sequence number
     pub fn next_sequence(&self) -> u64 {
         self.sequence.fetch_add(1, Ordering::SeqCst)
     }
 
-    /// Drops partitions from the mutable buffer if it is over size
+    /// Returns the sequence number of each writer's last write in the WAL
+    /// buffer. A snapshot of chunks rolled over after this is called includes
+    /// these writes, so they don't need to be replayed when the server
+    /// restarts.
+    pub(crate) fn wal_positions(&self) -> BTreeMap<WriterId, u64> {
+        self.wal_buffer
+            .as_ref()
+            .map(|buffer| buffer.lock().writer_sequences())
+            .unwrap_or_default()
+    }
+
+    /// Returns the WAL positions the snapshot of the partition's chunk can
+    /// record, given the positions captured before the chunk was rolled over.
+    /// They mark all of the partition's writes up to them as persisted, so
+    /// they are only recorded once every other closed chunk of the partition
+    /// is in the catalog. Until then the snapshot records none.
+    pub(crate) async fn snapshot_wal_positions(
+        &self,
+        partition_key: &str,
+        chunk_id: u32,
+        wal_positions: BTreeMap<WriterId, u64>,
+    ) -> BTreeMap<WriterId, u64> {
+        let persisted_ids: BTreeSet<_> = self
+            .catalog
+            .state()
+            .await
+            .chunks
+            .into_iter()
+            .filter(|chunk| chunk.partition_key == partition_key)
+            .map(|chunk| chunk.chunk_id)
+            .collect();
+
+        let summaries = match self.partition_chunk_summaries(partition_key) {
+            Ok(summaries) => summaries,
+            Err(e) => {
+                warn!(partition_key, %e, "error listing chunks, not recording wal positions");
+                return BTreeMap::new();
+            }
+        };
+
+        let others_persisted = summaries
+            .iter()
+            .filter(|summary| summary.id != chunk_id)
+            .filter(|summary| {
+                matches!(
+                    summary.storage,
+                    ChunkStorage::ClosedMutableBuffer | ChunkStorage::ReadBuffer
+                )
+            })
+            .all(|summary| persisted_ids.contains(&summary.id));
+
+        if others_persisted {
+            wal_positions
+        } else {
+            BTreeMap::new()
+        }
+    }
+
+    /// Ensures the sequence numbers of new writes come after `sequence`, the
+    /// last sequence number this server used before it was restarted
+    pub(crate) fn restore_sequence(&self, sequence: u64) {
+        self.sequence.fetch_max(sequence + 1, Ordering::SeqCst);
+    }
+
+    /// Returns an error if writes are rejected because the mutable buffer is
+    /// over size and its unpersisted partitions can't be dropped
+    pub(crate) fn check_mutable_buffer_capacity(&self) -> Result<()> {
+        // the lifecycle manager drops what it can in the background, so if
+        // the buffer is still over size nothing more can be dropped
+        let config = self.rules.read().mutable_buffer_config.clone();
+        if let (Some(mutable_buffer), Some(config)) = (&self.mutable_buffer, config) {
+            if config.reject_if_not_persisted {
+                let size = mutable_buffer.size();
+                ensure!(
+                    size <= config.buffer_size,
+                    MutableBufferFull {
+                        size,
+                        limit: config.buffer_size
+                    }
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Drops partitions from the mutable buffer if it is over size. If the
+    /// config has `reject_if_not_persisted` set, only partitions without
+    /// data are dropped: chunks are dropped from the mutable buffer once they
+    /// have been persisted, so any data left in a partition has not been.
     pub fn check_size_and_drop_partitions(&self) -> Result<()> {
-        if let (Some(db), Some(config)) = (&self.mutable_buffer, &self.rules.mutable_buffer_config)
-        {
+        let config = self.rules.read().mutable_buffer_config.clone();
+        if let (Some(db), Some(config)) = (&self.mutable_buffer, config) {
             let mut size = db.size();
             if size > config.buffer_size {
                 let mut partitions = db.partitions_sorted_by(&config.partition_drop_order);
                 while let Some(p) = partitions.pop() {
                     let p = p.read().expect("mutex poisoned");
+                    if config.reject_if_not_persisted && !p.is_empty() {
+                        continue;
+                    }
                     let partition_size = p.size();
                     size -= partition_size;
                     let key = p.key();
@@ -265,11 +887,89 @@ impl Db {
 
 impl PartialEq for Db {
     fn eq(&self, other: &Self) -> bool {
-        self.rules == other.rules
+        *self.rules.read() == *other.rules.read()
     }
 }
 impl Eq for Db {}
 
+// Matchers are validated when the server creates or updates the rules, so a
+// subscription only fails to compile if its stored config was edited by hand.
+// It is skipped rather than preventing the database from loading.
+fn compile_subscriptions(rules: &DatabaseRules) -> Vec<(HostGroupId, CompiledMatcher)> {
+    rules
+        .subscriptions
+        .iter()
+        .filter_map(
+            |subscription| match CompiledMatcher::new(&subscription.matcher) {
+                Ok(matcher) => Some((subscription.host_group_id.clone(), matcher)),
+                Err(e) => {
+                    warn!(
+                        subscription = %subscription.name,
+                        %e,
+                        "invalid subscription matcher, skipping"
+                    );
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// Writes the tables of a chunk compacted from persisted chunks to Parquet
+/// files, and returns the catalog's entry for it. The statistics of each
+/// table are merged from those of the persisted chunks.
+async fn persist_compacted_chunk(
+    store: &ObjectStore,
+    db_path: &object_store::path::Path,
+    partition_key: &str,
+    chunk_id: u32,
+    persisted: &[CatalogChunk],
+    tables: &[(String, RecordBatch)],
+) -> Result<CatalogChunk> {
+    let snapshot_id = Uuid::new_v4();
+    let data_path = snapshot_data_path(db_path, partition_key, snapshot_id);
+
+    let mut summaries: BTreeMap<&str, TableSummary> = BTreeMap::new();
+    for file in persisted.iter().flat_map(|chunk| &chunk.files) {
+        summaries
+            .entry(file.table.name.as_str())
+            .or_insert_with(|| TableSummary::new(&file.table.name))
+            .update_from(&file.table);
+    }
+
+    let mut table_batches: BTreeMap<&str, Vec<RecordBatch>> = BTreeMap::new();
+    for (table_name, batch) in tables {
+        table_batches
+            .entry(table_name.as_str())
+            .or_default()
+            .push(batch.clone());
+    }
+
+    let mut files = vec![];
+    for (table_name, batches) in table_batches {
+        let schema = batches[0].schema();
+        let summary = summaries
+            .remove(table_name)
+            .unwrap_or_else(|| TableSummary::new(table_name));
+        let stream = futures::stream::iter(batches.into_iter().map(Ok));
+
+        files.push(
+            write_table_file(store, &data_path, summary, schema, stream)
+                .await
+                .context(PersistingCompactedTable { table_name })?,
+        );
+    }
+
+    Ok(CatalogChunk {
+        partition_key: partition_key.to_string(),
+        chunk_id,
+        snapshot_id,
+        // the catalog takes them from the replaced chunks
+        wal_positions: Default::default(),
+        files,
+    })
+}
+
 #[async_trait]
 impl Database for Db {
     type Error = Error;
@@ -290,28 +990,48 @@ impl Database for Db {
             .map(|chunk| (chunk.id(), chunk))
             .collect();
 
-        // inserting into the map will have removed any dupes
-        chunks.into_iter().map(|(_id, chunk)| chunk).collect()
+        // inserting into the map will have removed any dupes. Persisted
+        // chunks are listed separately as their ids may have been reused by
+        // the mutable buffer after a restart
+        chunks
+            .into_iter()
+            .map(|(_id, chunk)| chunk)
+            .chain(self.persisted_chunks(partition_key))
+            .collect()
     }
 
     // Note that most of the functions below will eventually be removed from
     // this trait. For now, pass them directly on to the local store
 
     async fn store_replicated_write(&self, write: &ReplicatedWrite) -> Result<(), Self::Error> {
-        self.mutable_buffer
+        let mutable_buffer = self
+            .mutable_buffer
             .as_ref()
-            .context(DatatbaseNotWriteable)?
+            .context(DatatbaseNotWriteable)?;
+
+        self.check_mutable_buffer_capacity()?;
+
+        mutable_buffer
             .store_replicated_write(write)
             .await
             .context(MutableBufferWrite)
     }
 
     fn partition_keys(&self) -> Result<Vec<String>, Self::Error> {
-        self.mutable_buffer
-            .as_ref()
-            .context(DatabaseNotReadable)?
-            .partition_keys()
-            .context(MutableBufferRead)
+        // compacted chunks may be in the read buffer only
+        let mut other_keys: Vec<_> = self.persisted_chunks.read().keys().cloned().collect();
+        other_keys.extend(self.read_buffer.partition_keys());
+
+        let mut keys = match self.mutable_buffer.as_ref() {
+            Some(mutable_buffer) => mutable_buffer.partition_keys().context(MutableBufferRead)?,
+            None if other_keys.is_empty() => return DatabaseNotReadable.fail(),
+            None => vec![],
+        };
+
+        keys.extend(other_keys);
+        keys.sort();
+        keys.dedup();
+        Ok(keys)
     }
 }
 
@@ -324,14 +1044,24 @@ mod tests {
     use arrow_deps::{
         arrow::record_batch::RecordBatch, assert_table_eq, datafusion::physical_plan::collect,
     };
-    use data_types::database_rules::{
-        MutableBufferConfig, Order, PartitionSort, PartitionSortRules,
+    use data_types::{
+        database_rules::{
+            Matcher, MutableBufferConfig, Order, PartitionSort, PartitionSortRules, Subscription,
+            WalBufferConfig, WalBufferRollover,
+        },
+        job::Job,
     };
+    use object_store::{memory::InMemory, ObjectStoreApi};
     use query::{
         exec::Executor, frontend::sql::SQLQueryPlanner, test::TestLPWriter, PartitionChunk,
     };
     use test_helpers::assert_contains;
 
+    use crate::{
+        jobs::{JobRegistry, TrackedJob},
+        recovery::rebuild_database,
+    };
+
     #[tokio::test]
     async fn write_no_mutable_buffer() {
         // Validate that writes are rejected if there is no mutable buffer
@@ -471,6 +1201,79 @@ mod tests {
         // cpu").await; assert_table_eq!(expected, &batches);
     }
 
+    #[tokio::test]
+    async fn estimate_query_rows() {
+        let db = make_db();
+        let mut writer = TestLPWriter::default();
+        writer
+            .write_lp_string(&db, "cpu bar=1 10\ncpu bar=2 20\nmem foo=1 10")
+            .await
+            .unwrap();
+
+        let planner = SQLQueryPlanner::default();
+        let estimate = |query| planner.estimate_rows(&db, query).unwrap();
+        assert_eq!(estimate("select * from cpu"), Some(2));
+
+        // rows are counted in the read buffer as well
+        let partition_key = "1970-01-01T00";
+        let mb_chunk = db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        db.drop_mutable_buffer_chunk(partition_key, mb_chunk.id())
+            .await
+            .unwrap();
+        writer.write_lp_string(&db, "cpu bar=3 30").await.unwrap();
+
+        assert_eq!(estimate("select * from cpu"), Some(3));
+        assert_eq!(estimate("select * from mem"), Some(1));
+        assert_eq!(estimate("select * from disk"), Some(0));
+    }
+
+    #[tokio::test]
+    async fn chunk_summaries() {
+        let db = make_db();
+        let mut writer = TestLPWriter::default();
+        writer.write_lp_string(&db, "cpu bar=1 1").await.unwrap();
+        let partition_key = "1970-01-01T00";
+        db.rollover_partition(partition_key).await.unwrap();
+        db.load_chunk_to_read_buffer(partition_key, 0)
+            .await
+            .unwrap();
+
+        writer.write_lp_string(&db, "cpu bar=2 2").await.unwrap();
+
+        let summaries = db.chunk_summaries().unwrap();
+        let storage: Vec<_> = summaries.iter().map(|s| (s.id, s.storage)).collect();
+        assert_eq!(
+            storage,
+            vec![
+                (0, ChunkStorage::ClosedMutableBuffer),
+                (0, ChunkStorage::ReadBuffer),
+                (1, ChunkStorage::OpenMutableBuffer),
+            ]
+        );
+
+        for summary in &summaries {
+            assert_eq!(summary.partition_key, partition_key);
+            assert!(summary.estimated_bytes > 0);
+        }
+        assert_eq!(summaries[0].row_count, 1);
+        assert_eq!(summaries[0].time_range, Some((1, 1)));
+        assert_eq!(summaries[1].row_count, 1);
+        assert_eq!(summaries[1].time_range, Some((1, 1)));
+        assert_eq!(summaries[2].time_range, Some((2, 2)));
+
+        assert_eq!(db.all_partition_keys().unwrap(), vec![partition_key]);
+        assert_eq!(
+            db.partition_chunk_summaries(partition_key).unwrap(),
+            summaries
+        );
+        assert!(db.partition_chunk_summaries("unknown").unwrap().is_empty());
+        // listing an unknown partition doesn't create it
+        assert_eq!(db.all_partition_keys().unwrap(), vec![partition_key]);
+    }
+
     #[tokio::test]
     async fn chunk_id_listing() {
         // Test that chunk id listing is hooked up
@@ -504,6 +1307,327 @@ mod tests {
         assert_eq!(read_buffer_chunk_ids(&db, partition_key), vec![1]);
     }
 
+    #[tokio::test]
+    async fn compact_chunks() {
+        let db = make_db();
+        let executor = Executor::new();
+        let progress = JobProgress::default();
+        let store = ObjectStore::new_in_memory(InMemory::new());
+        let db_path = store.new_path();
+        let mut writer = TestLPWriter::default();
+        let partition_key = "1970-01-01T00";
+
+        let lines = [
+            "cpu,region=west bar=1 10\nmem foo=1 10",
+            "cpu,region=east bar=2 20",
+            "cpu,host=a bar=3,baz=1 30",
+        ];
+        for lp in &lines {
+            writer.write_lp_string(&db, lp).await.unwrap();
+            let chunk = db.rollover_partition(partition_key).await.unwrap();
+            db.load_chunk_to_read_buffer(partition_key, chunk.id())
+                .await
+                .unwrap();
+        }
+
+        let err = db
+            .compact_chunks(
+                &executor,
+                &store,
+                &db_path,
+                partition_key,
+                &[0, 1],
+                &progress,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::CompactMutableBufferChunk { chunk_id: 0 }
+        ));
+
+        for chunk_id in 0..3 {
+            db.drop_mutable_buffer_chunk(partition_key, chunk_id)
+                .await
+                .unwrap();
+        }
+
+        let err = db
+            .compact_chunks(
+                &executor,
+                &store,
+                &db_path,
+                partition_key,
+                &[1, 1],
+                &progress,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::CompactTooFewChunks { count: 1 }));
+
+        let err = db
+            .compact_chunks(
+                &executor,
+                &store,
+                &db_path,
+                partition_key,
+                &[1, 7],
+                &progress,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::UnknownCompactChunk { chunk_id: 7, .. }));
+
+        let chunk = db
+            .compact_chunks(
+                &executor,
+                &store,
+                &db_path,
+                partition_key,
+                &[2, 0, 1],
+                &progress,
+            )
+            .await
+            .unwrap();
+        assert_eq!(chunk.id(), 0);
+        assert_eq!(read_buffer_chunk_ids(&db, partition_key), vec![0]);
+        assert_eq!(
+            db.read_buffer.chunk_stats(partition_key, 0).unwrap().rows,
+            4
+        );
+        assert_eq!(progress.tasks(), (2, 2));
+
+        let expected = vec![
+            "+-----+-----+------+--------+------+",
+            "| bar | baz | host | region | time |",
+            "+-----+-----+------+--------+------+",
+            "| 1   |     |      | west   | 10   |",
+            "| 2   |     |      | east   | 20   |",
+            "| 3   | 1   | a    |        | 30   |",
+            "+-----+-----+------+--------+------+",
+        ];
+        let batches = run_query(&db, "select * from cpu order by time").await;
+        assert_table_eq!(&expected, &batches);
+
+        let expected = vec![
+            "+-----+------+",
+            "| foo | time |",
+            "+-----+------+",
+            "| 1   | 10   |",
+            "+-----+------+",
+        ];
+        let batches = run_query(&db, "select * from mem").await;
+        assert_table_eq!(&expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn compact_chunks_rejects_mixed_chunks() {
+        let db = make_db();
+        let executor = Executor::new();
+        let progress = JobProgress::default();
+        let mut writer = TestLPWriter::default();
+        let partition_key = "1970-01-01T00";
+
+        for lp in &["cpu bar=1 10", "cpu bar=2 20"] {
+            writer.write_lp_string(&db, lp).await.unwrap();
+            let chunk = db.rollover_partition(partition_key).await.unwrap();
+            db.load_chunk_to_read_buffer(partition_key, chunk.id())
+                .await
+                .unwrap();
+            db.drop_mutable_buffer_chunk(partition_key, chunk.id())
+                .await
+                .unwrap();
+        }
+
+        // chunk 1 was snapshotted while it was in the read buffer
+        let store = ObjectStore::new_in_memory(InMemory::new());
+        let db_path = store.new_path();
+        let persisted = crate::catalog::CatalogChunk {
+            partition_key: partition_key.to_string(),
+            chunk_id: 1,
+            snapshot_id: uuid::Uuid::new_v4(),
+            wal_positions: Default::default(),
+            files: vec![],
+        };
+        db.catalog
+            .add_chunk(&store, &db_path, persisted)
+            .await
+            .unwrap();
+
+        let err = db
+            .compact_chunks(
+                &executor,
+                &store,
+                &db_path,
+                partition_key,
+                &[0, 1],
+                &progress,
+            )
+            .await
+            .unwrap_err();
+        match err {
+            Error::CompactMixedChunks { persisted, .. } => assert_eq!(persisted, vec![1]),
+            err => panic!("unexpected error: {}", err),
+        }
+        assert_eq!(read_buffer_chunk_ids(&db, partition_key), vec![0, 1]);
+        assert_eq!(db.catalog.state().await.chunks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn compact_persisted_chunks() {
+        let db = make_db();
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let db_path = store.new_path();
+        let jobs = JobRegistry::new();
+        let mut writer = TestLPWriter::default();
+        let partition_key = "1970-01-01T00";
+
+        for (lp, sequence) in &[
+            ("cpu,region=west bar=1 10", 3),
+            ("cpu,region=east bar=2 20", 5),
+        ] {
+            writer.write_lp_string(&db, lp).await.unwrap();
+            let chunk = db.rollover_partition(partition_key).await.unwrap();
+            let chunk_id = chunk.id();
+
+            let registration = jobs.register(TrackedJob::new(Job::SnapshotChunk {
+                db_name: "test_db".to_string(),
+                partition_key: partition_key.to_string(),
+                chunk_id,
+            }));
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            crate::snapshot::snapshot_chunk(
+                &db_path,
+                Arc::clone(&store),
+                Arc::clone(&db.catalog),
+                partition_key,
+                chunk,
+                vec![(1, *sequence)].into_iter().collect(),
+                Some(tx),
+                registration,
+            )
+            .unwrap();
+            rx.await.unwrap();
+        }
+
+        // after a restart the persisted chunks are only in object storage
+        let restored = make_db();
+        rebuild_database(&restored, 1, &db_path, Arc::clone(&store), None)
+            .await
+            .unwrap();
+        assert_eq!(restored.persisted_chunks(partition_key).len(), 2);
+
+        let executor = Executor::new();
+        let progress = JobProgress::default();
+        let chunk = restored
+            .compact_chunks(
+                &executor,
+                &store,
+                &db_path,
+                partition_key,
+                &[1, 0],
+                &progress,
+            )
+            .await
+            .unwrap();
+        assert_eq!(chunk.id(), 0);
+        assert_eq!(read_buffer_chunk_ids(&restored, partition_key), vec![0]);
+        assert!(restored.persisted_chunks(partition_key).is_empty());
+
+        // the compacted chunk replaced both in the catalog
+        let state = restored.catalog.state().await;
+        assert_eq!(state.chunks.len(), 1);
+        let compacted = &state.chunks[0];
+        assert_eq!(compacted.chunk_id, 0);
+        assert_eq!(compacted.wal_positions, vec![(1, 5)].into_iter().collect());
+        assert_eq!(compacted.files.len(), 1);
+        assert_eq!(compacted.files[0].table.row_count(), 2);
+        assert_eq!(compacted.files[0].time_range, Some((10, 20)));
+
+        let expected = vec![
+            "+-----+--------+------+",
+            "| bar | region | time |",
+            "+-----+--------+------+",
+            "| 1   | west   | 10   |",
+            "| 2   | east   | 20   |",
+            "+-----+--------+------+",
+        ];
+        let batches = run_query(&restored, "select * from cpu order by time").await;
+        assert_table_eq!(&expected, &batches);
+
+        // and is loaded instead of them after the next restart
+        let restarted = make_db();
+        let report = rebuild_database(&restarted, 1, &db_path, Arc::clone(&store), None)
+            .await
+            .unwrap();
+        assert_eq!(report.chunks_loaded, 1);
+        let batches = run_query(&restarted, "select * from cpu order by time").await;
+        assert_table_eq!(&expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn compaction_waits_for_snapshots_of_the_partition() {
+        let db = Arc::new(make_db());
+        let executor = Arc::new(Executor::new());
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let db_path = store.new_path();
+        let mut writer = TestLPWriter::default();
+        let partition_key = "1970-01-01T00";
+
+        for lp in &["cpu bar=1 10", "cpu bar=2 20"] {
+            writer.write_lp_string(db.as_ref(), lp).await.unwrap();
+            let chunk = db.rollover_partition(partition_key).await.unwrap();
+            db.load_chunk_to_read_buffer(partition_key, chunk.id())
+                .await
+                .unwrap();
+            db.drop_mutable_buffer_chunk(partition_key, chunk.id())
+                .await
+                .unwrap();
+        }
+
+        // a snapshot of chunk 1 is running
+        let snapshot_lock = db.lock_partition(partition_key).await;
+
+        let compaction = tokio::spawn({
+            let db = Arc::clone(&db);
+            let store = Arc::clone(&store);
+            let db_path = db_path.clone();
+            async move {
+                let progress = JobProgress::default();
+                db.compact_chunks(
+                    &executor,
+                    &store,
+                    &db_path,
+                    partition_key,
+                    &[0, 1],
+                    &progress,
+                )
+                .await
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert_eq!(read_buffer_chunk_ids(&db, partition_key), vec![0, 1]);
+
+        // and adds it to the catalog before it finishes
+        let persisted = crate::catalog::CatalogChunk {
+            partition_key: partition_key.to_string(),
+            chunk_id: 1,
+            snapshot_id: uuid::Uuid::new_v4(),
+            wal_positions: Default::default(),
+            files: vec![],
+        };
+        db.catalog
+            .add_chunk(&store, &db_path, persisted)
+            .await
+            .unwrap();
+        drop(snapshot_lock);
+
+        // which the compaction sees once it can go ahead
+        let err = compaction.await.unwrap().unwrap_err();
+        assert!(matches!(err, Error::CompactMixedChunks { .. }));
+        assert_eq!(read_buffer_chunk_ids(&db, partition_key), vec![0, 1]);
+    }
+
     #[tokio::test]
     async fn check_size_and_drop_partitions() {
         let mut mbconf = MutableBufferConfig {
@@ -515,7 +1639,7 @@ mod tests {
             ..Default::default()
         };
 
-        let mut db = Db::new(
+        let db = Db::new(
             rules,
             Some(MutableBufferDb::new("foo")),
             read_buffer::Database::new(),
@@ -556,7 +1680,51 @@ mod tests {
             order: Order::Desc,
             sort: PartitionSort::LastWriteTime,
         };
-        db.rules.mutable_buffer_config = Some(mbconf);
+        db.rules.write().mutable_buffer_config = Some(mbconf);
+    }
+
+    #[tokio::test]
+    async fn update_rules() {
+        let db = make_db();
+
+        let rules = DatabaseRules {
+            replication_queue_max_size: 3,
+            subscriptions: vec![Subscription {
+                name: "all".to_string(),
+                host_group_id: "group".to_string(),
+                matcher: Matcher::default(),
+            }],
+            ..db.rules.read().clone()
+        };
+        db.update_rules(rules.clone()).unwrap();
+
+        assert_eq!(*db.rules.read(), rules);
+        assert_eq!(db.subscriptions.read().len(), 1);
+        assert_eq!(db.replication_queue.lock().status().max_size, 3);
+
+        // buffers can't be added or removed while the database is loaded
+        let err = db
+            .update_rules(DatabaseRules {
+                mutable_buffer_config: None,
+                ..rules.clone()
+            })
+            .unwrap_err();
+        assert!(matches!(err, Error::MutableBufferChange { .. }));
+
+        let err = db
+            .update_rules(DatabaseRules {
+                wal_buffer_config: Some(WalBufferConfig {
+                    buffer_size: 1000,
+                    segment_size: 100,
+                    buffer_rollover: WalBufferRollover::DropOldSegment,
+                    store_segments: false,
+                    close_segment_after: None,
+                }),
+                ..rules.clone()
+            })
+            .unwrap_err();
+        assert!(matches!(err, Error::WalBufferChange { .. }));
+        assert_eq!(*db.rules.read(), rules);
     }
 
     // run a sql query against the database, returning the results as record batches
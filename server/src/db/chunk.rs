@@ -1,15 +1,26 @@
-use arrow_deps::datafusion::physical_plan::SendableRecordBatchStream;
+use arrow_deps::{
+    arrow::record_batch::RecordBatch,
+    datafusion::physical_plan::{common::SizedRecordBatchStream, SendableRecordBatchStream},
+};
 use data_types::{schema::Schema, selection::Selection};
 use mutable_buffer::chunk::Chunk as MBChunk;
-use query::{exec::stringset::StringSet, predicate::Predicate, PartitionChunk};
-use read_buffer::Database as ReadBufferDb;
+use query::{
+    exec::{stringset::StringSet, tagset::TagSets},
+    group_by::Aggregate,
+    predicate::Predicate,
+    PartitionChunk,
+};
+use read_buffer::{AggregateType, Database as ReadBufferDb};
 use snafu::{ResultExt, Snafu};
 use tracing::debug;
 
 use std::sync::Arc;
 
 use super::{
-    pred::{to_mutable_buffer_predicate, to_read_buffer_predicate},
+    parquet_file::ParquetChunk,
+    pred::{
+        to_mutable_buffer_predicate, to_partial_read_buffer_predicate, to_read_buffer_predicate,
+    },
     streams::{MutableBufferChunkStream, ReadFilterResultsStream},
 };
 
@@ -28,18 +39,15 @@ pub enum Error {
         chunk_id: u32,
     },
 
+    #[snafu(display("Parquet File Error in chunk {}: {}", chunk_id, source))]
+    ParquetFileChunk {
+        source: super::parquet_file::Error,
+        chunk_id: u32,
+    },
+
     #[snafu(display("Internal error restricting schema: {}", source))]
     InternalSelectingSchema { source: data_types::schema::Error },
 
-    #[snafu(display("Predicate conversion error: {}", source))]
-    PredicateConversion { source: super::pred::Error },
-
-    #[snafu(display(
-        "Internal error: mutable buffer does not support predicate pushdown, but got: {:?}",
-        predicate
-    ))]
-    InternalPredicateNotSupported { predicate: Predicate },
-
     #[snafu(display("internal error creating plan: {}", source))]
     InternalPlanCreation {
         source: arrow_deps::datafusion::error::DataFusionError,
@@ -64,7 +72,9 @@ pub enum DBChunk {
         partition_key: String,
         chunk_id: u32,
     },
-    ParquetFile, // TODO add appropriate type here
+    ParquetFile {
+        chunk: Arc<ParquetChunk>,
+    },
 }
 
 impl DBChunk {
@@ -86,6 +96,11 @@ impl DBChunk {
             partition_key,
         })
     }
+
+    /// create a new chunk from Parquet files in object storage
+    pub fn new_parquet(chunk: Arc<ParquetChunk>) -> Arc<Self> {
+        Arc::new(Self::ParquetFile { chunk })
+    }
 }
 
 #[async_trait]
@@ -96,7 +111,7 @@ impl PartitionChunk for DBChunk {
         match self {
             Self::MutableBuffer { chunk } => chunk.id(),
             Self::ReadBuffer { chunk_id, .. } => *chunk_id,
-            Self::ParquetFile => unimplemented!("parquet file not implemented"),
+            Self::ParquetFile { chunk } => chunk.id(),
         }
     }
 
@@ -106,7 +121,7 @@ impl PartitionChunk for DBChunk {
         match self {
             Self::MutableBuffer { chunk } => chunk.table_stats().context(MutableBufferChunk),
             Self::ReadBuffer { .. } => unimplemented!("read buffer not implemented"),
-            Self::ParquetFile => unimplemented!("parquet file not implemented"),
+            Self::ParquetFile { chunk } => Ok(chunk.table_stats()),
         }
     }
 
@@ -166,9 +181,7 @@ impl PartitionChunk for DBChunk {
 
                 Some(names)
             }
-            Self::ParquetFile => {
-                unimplemented!("parquet file not implemented")
-            }
+            Self::ParquetFile { chunk } => chunk.table_names(predicate),
         };
 
         // Prune out tables that should not be
@@ -199,36 +212,13 @@ impl PartitionChunk for DBChunk {
                 db,
                 partition_key,
                 chunk_id,
-            } => {
-                let chunk_id = *chunk_id;
-
-                // TODO: Andrew -- I think technically this reordering
-                // should be happening inside the read buffer, but
-                // we'll see when we get to read_filter as the same
-                // issue will appear when actually reading columns
-                // back
-                let needs_sort = matches!(selection, Selection::All);
-
-                // For now, since read_filter is evaluated lazily,
-                // "run" a query with no predicates simply to get back the
-                // schema
-                let predicate = read_buffer::Predicate::default();
-                let mut schema = db
-                    .read_filter(partition_key, table_name, &[chunk_id], predicate, selection)
-                    .context(ReadBufferChunk { chunk_id })?
-                    .schema()
-                    .context(ReadBufferChunk { chunk_id })?;
-
-                // Ensure the order of the output columns is as
-                // specified
-                if needs_sort {
-                    schema = schema.sort_fields_by_name()
-                }
-
-                Ok(schema)
-            }
-            DBChunk::ParquetFile => {
-                unimplemented!("parquet file not implemented for table schema")
+            } => read_buffer_table_schema(db, partition_key, *chunk_id, table_name, selection),
+            DBChunk::ParquetFile { chunk } => {
+                chunk
+                    .table_schema(table_name, selection)
+                    .context(ParquetFileChunk {
+                        chunk_id: chunk.id(),
+                    })
             }
         }
     }
@@ -244,9 +234,22 @@ impl PartitionChunk for DBChunk {
                 let chunk_id = *chunk_id;
                 db.has_table(partition_key, table_name, &[chunk_id])
             }
-            Self::ParquetFile => {
-                unimplemented!("parquet file not implemented for has_table")
+            Self::ParquetFile { chunk } => chunk.has_table(table_name),
+        }
+    }
+
+    fn table_rows(&self, table_name: &str) -> Option<u64> {
+        match self {
+            Self::MutableBuffer { chunk } => chunk.table_rows(table_name).map(|rows| rows as u64),
+            Self::ReadBuffer {
+                db,
+                partition_key,
+                chunk_id,
+            } => {
+                let chunk_id = *chunk_id;
+                Some(db.table_rows(partition_key, table_name, &[chunk_id]))
             }
+            Self::ParquetFile { chunk } => chunk.table_rows(table_name),
         }
     }
 
@@ -260,13 +263,8 @@ impl PartitionChunk for DBChunk {
             Self::MutableBuffer { chunk } => {
                 // Note MutableBuffer doesn't support predicate
                 // pushdown (other than pruning out the entire chunk
-                // via `might_pass_predicate)
-                if !predicate.is_empty() {
-                    return InternalPredicateNotSupported {
-                        predicate: predicate.clone(),
-                    }
-                    .fail();
-                }
+                // via `could_pass_predicate`), so all of the rows are
+                // returned and the predicate is applied by the caller
                 let schema: Schema = self.table_schema(table_name, selection).await?;
 
                 Ok(Box::pin(MutableBufferChunkStream::new(
@@ -281,9 +279,18 @@ impl PartitionChunk for DBChunk {
                 chunk_id,
             } => {
                 let chunk_id = *chunk_id;
-                // Error converting to a rb_predicate needs to fail
-                let rb_predicate =
-                    to_read_buffer_predicate(&predicate).context(PredicateConversion)?;
+
+                // Only the parts of the predicate the read buffer can
+                // evaluate on this table are pushed down. The rest is
+                // applied by the caller.
+                let table_schema = read_buffer_table_schema(
+                    db,
+                    partition_key,
+                    chunk_id,
+                    table_name,
+                    Selection::All,
+                )?;
+                let rb_predicate = to_partial_read_buffer_predicate(predicate, &table_schema);
 
                 let chunk_ids = &[chunk_id];
 
@@ -304,13 +311,96 @@ impl PartitionChunk for DBChunk {
 
                 Ok(Box::pin(ReadFilterResultsStream::new(read_results, schema)))
             }
-            Self::ParquetFile => {
-                unimplemented!("parquet file not implemented for scan_data")
+            Self::ParquetFile { chunk } => {
+                let (schema, batches) = chunk
+                    .read_filter(table_name, predicate, selection)
+                    .await
+                    .context(ParquetFileChunk {
+                    chunk_id: chunk.id(),
+                })?;
+
+                let batches = batches.into_iter().map(Arc::new).collect();
+                Ok(Box::pin(SizedRecordBatchStream::new(schema, batches)))
+            }
+        }
+    }
+
+    async fn read_aggregate(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        group_columns: &[String],
+        aggregates: &[(String, Aggregate)],
+        window: Option<u64>,
+    ) -> Result<Option<Vec<RecordBatch>>, Self::Error> {
+        match self {
+            // Only the read buffer can compute aggregates itself
+            Self::MutableBuffer { .. } | Self::ParquetFile { .. } => Ok(None),
+            Self::ReadBuffer {
+                db,
+                partition_key,
+                chunk_id,
+            } => {
+                let chunk_id = *chunk_id;
+
+                let rb_predicate = match to_read_buffer_predicate(&predicate) {
+                    Ok(rb_predicate) => rb_predicate,
+                    Err(e) => {
+                        debug!(?predicate, %e, "read buffer predicate not supported for read_aggregate, falling back");
+                        return Ok(None);
+                    }
+                };
+
+                let aggregates = aggregates
+                    .iter()
+                    .map(|(column_name, agg)| {
+                        to_read_buffer_aggregate(*agg).map(|agg| (column_name.as_str(), agg))
+                    })
+                    .collect::<Option<Vec<_>>>();
+                let aggregates = match aggregates {
+                    Some(aggregates) => aggregates,
+                    None => return Ok(None),
+                };
+
+                let group_columns = group_columns
+                    .iter()
+                    .map(|column_name| column_name.as_str())
+                    .collect::<Vec<_>>();
+                let group_columns = Selection::Some(&group_columns);
+                let chunk_ids = &[chunk_id];
+
+                let batches = match window {
+                    Some(window) => db
+                        .read_window_aggregate(
+                            partition_key,
+                            table_name,
+                            chunk_ids,
+                            rb_predicate,
+                            group_columns,
+                            aggregates,
+                            window,
+                        )
+                        .context(ReadBufferChunk { chunk_id })?
+                        .collect(),
+                    None => db
+                        .read_aggregate(
+                            partition_key,
+                            table_name,
+                            chunk_ids,
+                            rb_predicate,
+                            group_columns,
+                            aggregates,
+                        )
+                        .context(ReadBufferChunk { chunk_id })?
+                        .collect(),
+                };
+
+                Ok(Some(batches))
             }
         }
     }
 
-    fn could_pass_predicate(&self, _predicate: &Predicate) -> Result<bool> {
+    fn could_pass_predicate(&self, predicate: &Predicate) -> Result<bool> {
         match self {
             Self::MutableBuffer { .. } => {
                 // For now, we might get an error if we try and
@@ -323,14 +413,46 @@ impl PartitionChunk for DBChunk {
                 // distinguish the two cases.
                 Ok(true)
             }
-            Self::ReadBuffer { .. } => {
-                // TODO: ask Edd how he wants this wired up in the read buffer
-                Ok(true)
-            }
-            Self::ParquetFile => {
-                // TODO proper filtering for parquet files
-                Ok(true)
+            Self::ReadBuffer {
+                db,
+                partition_key,
+                chunk_id,
+            } => {
+                let chunk_id = *chunk_id;
+                let table_names = db
+                    .table_names(
+                        partition_key,
+                        &[chunk_id],
+                        read_buffer::Predicate::default(),
+                    )
+                    .context(ReadBufferChunk { chunk_id })?;
+
+                // The chunk can be skipped if none of its tables could
+                // have rows that pass the parts of the predicate the
+                // read buffer can evaluate, judging by the row group
+                // meta data
+                for table_name in table_names
+                    .iter()
+                    .filter(|table_name| predicate.should_include_table(table_name))
+                {
+                    let table_schema = read_buffer_table_schema(
+                        db,
+                        partition_key,
+                        chunk_id,
+                        table_name,
+                        Selection::All,
+                    )?;
+                    let rb_predicate = to_partial_read_buffer_predicate(predicate, &table_schema);
+
+                    if db.could_pass_predicate(partition_key, table_name, &[chunk_id], rb_predicate)
+                    {
+                        return Ok(true);
+                    }
+                }
+
+                Ok(false)
             }
+            Self::ParquetFile { chunk } => Ok(chunk.could_pass_predicate(predicate)),
         }
     }
 
@@ -376,9 +498,7 @@ impl PartitionChunk for DBChunk {
 
                 Ok(names)
             }
-            Self::ParquetFile => {
-                unimplemented!("parquet file not implemented for column_names")
-            }
+            Self::ParquetFile { chunk } => Ok(chunk.column_names(table_name, predicate, columns)),
         }
     }
 
@@ -416,9 +536,106 @@ impl PartitionChunk for DBChunk {
                 // https://github.com/influxdata/influxdb_iox/issues/857
                 Ok(None)
             }
-            Self::ParquetFile => {
-                unimplemented!("parquet file not implemented for column_values")
+            Self::ParquetFile { chunk } => chunk
+                .column_values(table_name, column_name, predicate)
+                .await
+                .context(ParquetFileChunk {
+                    chunk_id: chunk.id(),
+                }),
+        }
+    }
+
+    async fn tag_sets(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+    ) -> Result<Option<TagSets>, Self::Error> {
+        match self {
+            Self::MutableBuffer { chunk } => {
+                let chunk_predicate = match to_mutable_buffer_predicate(chunk, predicate) {
+                    Ok(chunk_predicate) => chunk_predicate,
+                    Err(e) => {
+                        debug!(?predicate, %e, "mutable buffer predicate not supported for tag_sets, falling back");
+                        return Ok(None);
+                    }
+                };
+
+                chunk
+                    .tag_sets(table_name, &chunk_predicate)
+                    .context(MutableBufferChunk)
+            }
+            Self::ReadBuffer {
+                db,
+                partition_key,
+                chunk_id,
+            } => {
+                // The read buffer's tag sets don't take the field
+                // restriction into account
+                if predicate.field_columns.is_some() {
+                    return Ok(None);
+                }
+
+                let chunk_id = *chunk_id;
+                let rb_predicate = match to_read_buffer_predicate(&predicate) {
+                    Ok(rb_predicate) => rb_predicate,
+                    Err(e) => {
+                        debug!(?predicate, %e, "read buffer predicate not supported for tag_sets, falling back");
+                        return Ok(None);
+                    }
+                };
+
+                let tag_sets = db
+                    .tag_sets(partition_key, table_name, &[chunk_id], rb_predicate)
+                    .context(ReadBufferChunk { chunk_id })?;
+
+                Ok(Some(tag_sets))
+            }
+            Self::ParquetFile { .. } => {
+                // The Parquet files are only read by a full plan
+                Ok(None)
             }
         }
     }
 }
+
+/// Returns the schema of the selected columns of a table in a read buffer
+/// chunk, from the meta data of the table
+fn read_buffer_table_schema(
+    db: &ReadBufferDb,
+    partition_key: &str,
+    chunk_id: u32,
+    table_name: &str,
+    selection: Selection<'_>,
+) -> Result<Schema> {
+    // TODO: Andrew -- I think technically this reordering
+    // should be happening inside the read buffer, but
+    // we'll see when we get to read_filter as the same
+    // issue will appear when actually reading columns
+    // back
+    let needs_sort = matches!(selection, Selection::All);
+
+    let mut schema = db
+        .read_filter_schema(partition_key, table_name, &[chunk_id], selection)
+        .context(ReadBufferChunk { chunk_id })?;
+
+    // Ensure the order of the output columns is as
+    // specified
+    if needs_sort {
+        schema = schema.sort_fields_by_name()
+    }
+
+    Ok(schema)
+}
+
+/// Returns the read buffer aggregate that computes `agg`, if there is one
+fn to_read_buffer_aggregate(agg: Aggregate) -> Option<AggregateType> {
+    match agg {
+        Aggregate::Sum => Some(AggregateType::Sum),
+        Aggregate::Count => Some(AggregateType::Count),
+        Aggregate::Min => Some(AggregateType::Min),
+        Aggregate::Max => Some(AggregateType::Max),
+        Aggregate::First => Some(AggregateType::First),
+        Aggregate::Last => Some(AggregateType::Last),
+        Aggregate::Mean | Aggregate::None => None,
+    }
+}
@@ -0,0 +1,140 @@
+//! This module merges chunks of a partition into a single chunk. The rows of
+//! each table are sorted by its tag columns and then time, so that the read
+//! buffer can encode the compacted chunk with long runs of repeated values.
+
+use std::{collections::BTreeSet, sync::Arc};
+
+use arrow_deps::{
+    arrow::record_batch::RecordBatch,
+    datafusion::{error::DataFusionError, logical_plan::LogicalPlanBuilder},
+    util::IntoExpr,
+};
+use data_types::selection::Selection;
+use query::{exec::Executor, provider::ProviderBuilder, PartitionChunk};
+use snafu::{ResultExt, Snafu};
+
+use super::DBChunk;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error listing the tables of chunk {}: {}", chunk_id, source))]
+    ListingTables {
+        chunk_id: u32,
+        source: read_buffer::Error,
+    },
+
+    #[snafu(display(
+        "Error reading the schema of table '{}' in chunk {}: {}",
+        table_name,
+        chunk_id,
+        source
+    ))]
+    ReadingSchema {
+        table_name: String,
+        chunk_id: u32,
+        source: super::chunk::Error,
+    },
+
+    #[snafu(display("Error merging the schemas of table '{}': {}", table_name, source))]
+    MergingSchemas {
+        table_name: String,
+        source: query::provider::Error,
+    },
+
+    #[snafu(display("Error planning the compaction of table '{}': {}", table_name, source))]
+    Planning {
+        table_name: String,
+        source: DataFusionError,
+    },
+
+    #[snafu(display("Error compacting table '{}': {}", table_name, source))]
+    Executing {
+        table_name: String,
+        source: query::exec::Error,
+    },
+
+    #[snafu(display("Error restoring the schema of table '{}': {}", table_name, source))]
+    RestoringSchema {
+        table_name: String,
+        source: arrow_deps::arrow::error::ArrowError,
+    },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Returns the names of the tables in any of the chunks, which must be read
+/// buffer or Parquet file chunks
+pub(super) fn table_names(chunks: &[Arc<DBChunk>]) -> Result<BTreeSet<String>> {
+    let mut names = BTreeSet::new();
+    for chunk in chunks {
+        match chunk.as_ref() {
+            DBChunk::ReadBuffer {
+                db,
+                partition_key,
+                chunk_id,
+            } => {
+                let chunk_id = *chunk_id;
+                names.extend(
+                    db.table_names(partition_key, &[chunk_id], Default::default())
+                        .context(ListingTables { chunk_id })?,
+                );
+            }
+            DBChunk::ParquetFile { chunk } => {
+                names.extend(chunk.table_stats().into_iter().map(|table| table.name));
+            }
+            DBChunk::MutableBuffer { .. } => {
+                unreachable!("chunk {} is in the mutable buffer", chunk.id())
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Reads all rows of the table from the chunks that have it, with the
+/// merged schema of the chunks, ordered by the table's tag columns and time
+pub(super) async fn compact_table(
+    executor: &Executor,
+    table_name: &str,
+    chunks: &[Arc<DBChunk>],
+) -> Result<Vec<RecordBatch>> {
+    let mut builder = ProviderBuilder::new(table_name);
+    for chunk in chunks.iter().filter(|chunk| chunk.has_table(table_name)) {
+        let chunk_table_schema = chunk
+            .table_schema(table_name, Selection::All)
+            .await
+            .context(ReadingSchema {
+                table_name,
+                chunk_id: chunk.id(),
+            })?;
+
+        builder = builder
+            .add_chunk(Arc::clone(chunk), chunk_table_schema)
+            .context(MergingSchemas { table_name })?;
+    }
+
+    let provider = builder.build().context(MergingSchemas { table_name })?;
+    let schema = provider.iox_schema();
+
+    let sort_exprs: Vec<_> = schema
+        .tags_iter()
+        .chain(schema.time_iter())
+        .map(|field| field.name().into_sort_expr())
+        .collect();
+
+    let plan = LogicalPlanBuilder::scan(table_name, Arc::new(provider), None)
+        .and_then(|builder| builder.sort(&sort_exprs))
+        .and_then(|builder| builder.build())
+        .context(Planning { table_name })?;
+
+    let batches = executor
+        .run_logical_plan(plan)
+        .await
+        .context(Executing { table_name })?;
+
+    // the read buffer needs the IOx metadata of the columns to tell tags,
+    // fields and time apart
+    batches
+        .into_iter()
+        .map(|batch| RecordBatch::try_new(schema.as_arrow(), batch.columns().to_vec()))
+        .collect::<Result<_, _>>()
+        .context(RestoringSchema { table_name })
+}
@@ -0,0 +1,1061 @@
+//! This module contains the chunks of a partition that have been persisted
+//! to Parquet files in object storage by a snapshot, one file per table.
+//!
+//! Only the footer of each file is read when a chunk is loaded, and its
+//! metadata is kept in memory. The data stays in object storage until a query
+//! needs it: the row group statistics rule out the row groups that can't
+//! contain matching rows, and only the column chunks of the other row groups
+//! that the query reads are downloaded. Downloaded column chunks are kept so
+//! that later queries don't download them again.
+
+use std::{
+    collections::BTreeMap,
+    convert::{TryFrom, TryInto},
+    io::Cursor,
+    ops::Range,
+    sync::Arc,
+};
+
+use arrow_deps::{
+    arrow::{
+        array::{Array, BooleanArray, Int64Array, StringArray},
+        compute::kernels::filter::filter_record_batch,
+        datatypes::{DataType, Schema as ArrowSchema, SchemaRef as ArrowSchemaRef},
+        error::ArrowError,
+        record_batch::RecordBatch,
+    },
+    parquet::{
+        self,
+        arrow::{parquet_to_arrow_schema, ArrowReader, ParquetFileArrowReader},
+        errors::ParquetError,
+        file::{
+            metadata::{FileMetaData, ParquetMetaData, RowGroupMetaData},
+            reader::{ChunkReader, FileReader, Length, RowGroupReader, SerializedFileReader},
+            statistics::Statistics,
+        },
+        record::reader::RowIter,
+        schema::types::Type as SchemaType,
+    },
+};
+use bytes::Bytes;
+use data_types::{
+    partition_metadata::TableSummary,
+    schema::{InfluxColumnType, Schema},
+    selection::Selection,
+    timestamp::TimestampRange,
+    TIME_COLUMN_NAME,
+};
+use object_store::{path::ObjectStorePath, ObjectStore, ObjectStoreApi};
+use parking_lot::Mutex;
+use query::{exec::stringset::StringSet, predicate::Predicate};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+
+use crate::catalog::CatalogFile;
+
+/// The number of rows in each record batch read from a file
+const BATCH_SIZE: usize = 8 * 1024;
+
+/// The length of the metadata and the magic bytes at the end of a file
+const FOOTER_SIZE: usize = 8;
+
+/// How much of the end of a file is read to find its metadata, which holds
+/// the whole metadata of most files in a single request
+const FOOTER_READ_SIZE: usize = 64 * 1024;
+
+const PARQUET_MAGIC: &[u8] = b"PAR1";
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error reading '{}' from object store: {}", path, source))]
+    ReadingObjectStore {
+        path: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display("Error reading Parquet file '{}': {}", path, source))]
+    ReadingParquet { path: String, source: ParquetError },
+
+    #[snafu(display("'{}' of {} bytes does not end with a Parquet footer", path, size))]
+    InvalidFooter { path: String, size: u64 },
+
+    #[snafu(display("Error reading record batch from '{}': {}", path, source))]
+    ReadingBatch { path: String, source: ArrowError },
+
+    #[snafu(display("Invalid schema in Parquet file '{}': {}", path, source))]
+    InvalidSchema {
+        path: String,
+        source: data_types::schema::Error,
+    },
+
+    #[snafu(display(
+        "Column '{}' of '{}' is not a timestamp column",
+        TIME_COLUMN_NAME,
+        path
+    ))]
+    InvalidTimeColumn { path: String },
+
+    #[snafu(display("Error filtering record batch from '{}': {}", path, source))]
+    FilteringBatch { path: String, source: ArrowError },
+
+    #[snafu(display("Unknown table '{}' in chunk {}", table_name, chunk_id))]
+    UnknownTable { table_name: String, chunk_id: u32 },
+
+    #[snafu(display("Unknown column '{}' in table '{}'", column_name, table_name))]
+    UnknownColumn {
+        table_name: String,
+        column_name: String,
+    },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A chunk whose tables were written to Parquet files in object storage
+#[derive(Debug)]
+pub struct ParquetChunk {
+    partition_key: String,
+    id: u32,
+    store: Arc<ObjectStore>,
+    tables: BTreeMap<String, ParquetTable>,
+    table_summaries: Vec<TableSummary>,
+}
+
+impl ParquetChunk {
+    /// Loads the metadata of the files the snapshot of a chunk wrote to
+    /// `data_path`, reading only the footer of each file
+    pub async fn load(
+        partition_key: impl Into<String>,
+        id: u32,
+        store: Arc<ObjectStore>,
+        data_path: &object_store::path::Path,
+        files: &[CatalogFile],
+    ) -> Result<Self> {
+        let mut tables = BTreeMap::new();
+        let mut table_summaries = Vec::with_capacity(files.len());
+
+        for file in files {
+            let mut path = data_path.clone();
+            path.set_file_name(&file.file_name);
+
+            let footer = read_footer(&store, &path, file.size).await?;
+            tables.insert(file.table.name.clone(), ParquetTable::new(path, footer)?);
+            table_summaries.push(file.table.clone());
+        }
+
+        Ok(Self {
+            partition_key: partition_key.into(),
+            id,
+            store,
+            tables,
+            table_summaries,
+        })
+    }
+
+    pub fn partition_key(&self) -> &str {
+        &self.partition_key
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the summaries of the tables recorded by the snapshot
+    pub fn table_stats(&self) -> Vec<TableSummary> {
+        self.table_summaries.clone()
+    }
+
+    pub fn has_table(&self, table_name: &str) -> bool {
+        self.tables.contains_key(table_name)
+    }
+
+    pub fn table_rows(&self, table_name: &str) -> Option<u64> {
+        self.tables
+            .get(table_name)
+            .map(|table| table.row_groups.iter().map(|rg| rg.rows).sum())
+    }
+
+    /// Returns false if the row group statistics show that no table in the
+    /// chunk has rows that pass the predicate
+    pub fn could_pass_predicate(&self, predicate: &Predicate) -> bool {
+        self.tables.iter().any(|(table_name, table)| {
+            table.included_by(table_name, predicate)
+                && table
+                    .row_groups
+                    .iter()
+                    .any(|rg| rg.overlap(predicate.range) != Overlap::Disjoint)
+        })
+    }
+
+    /// Returns the names of the tables with rows that pass the predicate, or
+    /// `None` if that can't be decided from the row group statistics
+    pub fn table_names(&self, predicate: &Predicate) -> Option<StringSet> {
+        if predicate.has_exprs() {
+            return None;
+        }
+
+        let mut names = StringSet::new();
+        for (table_name, table) in &self.tables {
+            if !table.included_by(table_name, predicate) {
+                continue;
+            }
+
+            let overlaps: Vec<_> = table
+                .row_groups
+                .iter()
+                .map(|rg| rg.overlap(predicate.range))
+                .collect();
+
+            if overlaps.contains(&Overlap::Full) {
+                names.insert(table_name.clone());
+            } else if overlaps.contains(&Overlap::Partial) {
+                return None;
+            }
+        }
+
+        Some(names)
+    }
+
+    /// Returns the names of the selected columns with values in rows that
+    /// pass the predicate, or `None` if that can't be decided from the row
+    /// group statistics
+    pub fn column_names(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        selection: Selection<'_>,
+    ) -> Option<StringSet> {
+        if predicate.has_exprs() {
+            return None;
+        }
+
+        let table = match self.tables.get(table_name) {
+            Some(table) if table.included_by(table_name, predicate) => table,
+            _ => return Some(StringSet::new()),
+        };
+
+        let mut names = StringSet::new();
+        for rg in &table.row_groups {
+            match rg.overlap(predicate.range) {
+                Overlap::Disjoint => continue,
+                Overlap::Partial => return None,
+                Overlap::Full => {}
+            }
+
+            for (_, field) in table.schema.iter() {
+                let selected = match selection {
+                    Selection::All => true,
+                    Selection::Some(columns) => columns.contains(&field.name().as_str()),
+                };
+
+                if selected && rg.has_values(field.name()) {
+                    names.insert(field.name().clone());
+                }
+            }
+        }
+
+        Some(names)
+    }
+
+    /// Returns the distinct values of the string column in rows that pass
+    /// the predicate, or `None` if the column doesn't hold strings or the
+    /// predicate can't be evaluated on the chunk
+    pub async fn column_values(
+        &self,
+        table_name: &str,
+        column_name: &str,
+        predicate: &Predicate,
+    ) -> Result<Option<StringSet>> {
+        if predicate.has_exprs() {
+            return Ok(None);
+        }
+
+        let table = match self.tables.get(table_name) {
+            Some(table) if table.included_by(table_name, predicate) => table,
+            _ => return Ok(Some(StringSet::new())),
+        };
+
+        match table.schema.find_index_of(column_name) {
+            Some(idx) if table.schema.field(idx).1.data_type() == &DataType::Utf8 => {}
+            Some(_) => return Ok(None),
+            None => return Ok(Some(StringSet::new())),
+        }
+
+        let schema = table.select(table_name, Selection::Some(&[column_name]))?;
+        let batches = self
+            .read_table(table, predicate.range, schema.as_arrow())
+            .await?;
+
+        let mut values = StringSet::new();
+        for batch in batches {
+            let column = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .expect("column was checked to be a string column");
+
+            for i in 0..column.len() {
+                if column.is_valid(i) {
+                    values.insert(column.value(i).to_string());
+                }
+            }
+        }
+
+        Ok(Some(values))
+    }
+
+    /// Returns the schema of the selected columns of the table
+    pub fn table_schema(&self, table_name: &str, selection: Selection<'_>) -> Result<Schema> {
+        self.table(table_name)?.select(table_name, selection)
+    }
+
+    /// Reads the selected columns of the table's rows that pass the
+    /// predicate's table and time range restrictions, returning the schema
+    /// of the batches and the batches. The predicate's expressions are not
+    /// evaluated, so they must be applied to the batches by the caller.
+    pub async fn read_filter(
+        &self,
+        table_name: &str,
+        predicate: &Predicate,
+        selection: Selection<'_>,
+    ) -> Result<(ArrowSchemaRef, Vec<RecordBatch>)> {
+        let table = self.table(table_name)?;
+        let schema = table.select(table_name, selection)?.as_arrow();
+
+        let batches = if predicate.should_include_table(table_name) {
+            self.read_table(table, predicate.range, Arc::clone(&schema))
+                .await?
+        } else {
+            vec![]
+        };
+
+        Ok((schema, batches))
+    }
+
+    fn table(&self, table_name: &str) -> Result<&ParquetTable> {
+        self.tables.get(table_name).context(UnknownTable {
+            table_name,
+            chunk_id: self.id,
+        })
+    }
+
+    /// Reads the rows of the table in the time range, projected to the
+    /// columns of `schema`. Only the row groups whose statistics overlap the
+    /// range are read, and rows are only filtered if a row group is
+    /// partially inside it.
+    async fn read_table(
+        &self,
+        table: &ParquetTable,
+        range: Option<TimestampRange>,
+        schema: ArrowSchemaRef,
+    ) -> Result<Vec<RecordBatch>> {
+        let overlaps: Vec<_> = table
+            .row_groups
+            .iter()
+            .map(|rg| rg.overlap(range))
+            .collect();
+        let row_groups: Vec<_> = overlaps
+            .iter()
+            .enumerate()
+            .filter(|(_, overlap)| **overlap != Overlap::Disjoint)
+            .map(|(idx, _)| idx)
+            .collect();
+        if row_groups.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // Only rows in the range are returned, so a table without a time
+        // column has none
+        let time_filter = match range {
+            Some(range) if overlaps.contains(&Overlap::Partial) => {
+                match table.schema.find_index_of(TIME_COLUMN_NAME) {
+                    Some(idx) => Some((idx, range)),
+                    None => return Ok(vec![]),
+                }
+            }
+            _ => None,
+        };
+
+        // the selected columns, and the time column to filter by
+        let mut columns: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                table
+                    .schema
+                    .find_index_of(field.name())
+                    .expect("schema was selected from the table")
+            })
+            .chain(time_filter.map(|(idx, _)| idx))
+            .collect();
+        columns.sort_unstable();
+        columns.dedup();
+
+        let path = table.path.display();
+        let reader = table
+            .read_row_groups(&self.store, row_groups, &columns)
+            .await?;
+        let mut reader = ParquetFileArrowReader::new(Arc::new(reader));
+
+        let mut batches = vec![];
+        for batch in reader
+            .get_record_reader_by_columns(columns, BATCH_SIZE)
+            .context(ReadingParquet { path: &path })?
+        {
+            let mut batch = batch.context(ReadingBatch { path: &path })?;
+
+            if let Some((_, range)) = time_filter {
+                let idx = batch
+                    .schema()
+                    .index_of(TIME_COLUMN_NAME)
+                    .context(ReadingBatch { path: &path })?;
+                batch = filter_time_range(&batch, idx, range, &path)?;
+            }
+
+            if batch.num_rows() == 0 {
+                continue;
+            }
+
+            let columns = schema
+                .fields()
+                .iter()
+                .map(|field| {
+                    let idx = batch
+                        .schema()
+                        .index_of(field.name())
+                        .context(ReadingBatch { path: &path })?;
+                    Ok(Arc::clone(batch.column(idx)))
+                })
+                .collect::<Result<_>>()?;
+            let batch = RecordBatch::try_new(Arc::clone(&schema), columns)
+                .context(ReadingBatch { path: &path })?;
+
+            batches.push(batch);
+        }
+
+        Ok(batches)
+    }
+}
+
+/// The metadata of the Parquet file holding one of the chunk's tables
+#[derive(Debug)]
+struct ParquetTable {
+    path: object_store::path::Path,
+    schema: Schema,
+    metadata: ParquetMetaData,
+    row_groups: Vec<RowGroupStats>,
+    /// The footer and the column chunks of the file downloaded so far
+    downloaded: Mutex<FileRanges>,
+}
+
+impl ParquetTable {
+    fn new(path: object_store::path::Path, footer: FileRanges) -> Result<Self> {
+        let metadata = parquet::file::footer::parse_metadata(&footer).context(ReadingParquet {
+            path: path.display(),
+        })?;
+
+        let row_groups = metadata
+            .row_groups()
+            .iter()
+            .map(RowGroupStats::new)
+            .collect();
+
+        let file_metadata = metadata.file_metadata();
+        let schema = parquet_to_arrow_schema(
+            file_metadata.schema_descr(),
+            file_metadata.key_value_metadata(),
+        )
+        .context(ReadingParquet {
+            path: path.display(),
+        })?;
+        let schema = Schema::try_from(Arc::new(schema)).context(InvalidSchema {
+            path: path.display(),
+        })?;
+
+        Ok(Self {
+            path,
+            schema,
+            metadata,
+            row_groups,
+            downloaded: Mutex::new(footer),
+        })
+    }
+
+    /// Returns a reader of the row groups with the given indexes, after
+    /// downloading the chunks of `columns` in them that weren't downloaded
+    /// before. Only these columns can be read from the returned reader.
+    async fn read_row_groups(
+        &self,
+        store: &ObjectStore,
+        row_groups: Vec<usize>,
+        columns: &[usize],
+    ) -> Result<RowGroupsReader> {
+        let missing: Vec<_> = {
+            let downloaded = self.downloaded.lock();
+            row_groups
+                .iter()
+                .flat_map(|&rg| {
+                    let rg = self.metadata.row_group(rg);
+                    columns.iter().map(move |&column| {
+                        let (start, length) = rg.column(column).byte_range();
+                        start as usize..(start + length) as usize
+                    })
+                })
+                .filter(|range| !downloaded.contains(range))
+                .collect()
+        };
+
+        for range in missing {
+            let data =
+                store
+                    .get_range(&self.path, range.clone())
+                    .await
+                    .context(ReadingObjectStore {
+                        path: self.path.display(),
+                    })?;
+            self.downloaded.lock().insert(range.start, data);
+        }
+
+        let file = self.downloaded.lock().clone();
+        RowGroupsReader::new(file, &self.metadata, row_groups).context(ReadingParquet {
+            path: self.path.display(),
+        })
+    }
+
+    /// Returns true if the predicate's table and field restrictions allow
+    /// this table
+    fn included_by(&self, table_name: &str, predicate: &Predicate) -> bool {
+        let has_field = |fields: &std::collections::BTreeSet<String>| {
+            self.schema.iter().any(|(column_type, field)| {
+                matches!(column_type, Some(InfluxColumnType::Field(_)))
+                    && fields.contains(field.name())
+            })
+        };
+
+        predicate.should_include_table(table_name)
+            && predicate.field_columns.as_ref().map_or(true, has_field)
+    }
+
+    /// Returns the schema of the selected columns, in the order selected or
+    /// sorted by name if all columns are selected
+    fn select(&self, table_name: &str, selection: Selection<'_>) -> Result<Schema> {
+        let columns = match selection {
+            Selection::All => return Ok(self.schema.clone().sort_fields_by_name()),
+            Selection::Some(columns) => columns,
+        };
+
+        let arrow_schema = self.schema.as_arrow();
+        let fields = columns
+            .iter()
+            .map(|&column_name| {
+                self.schema
+                    .find_index_of(column_name)
+                    .map(|idx| arrow_schema.field(idx).clone())
+                    .context(UnknownColumn {
+                        table_name,
+                        column_name,
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let schema = ArrowSchema::new_with_metadata(fields, arrow_schema.metadata().clone());
+        Schema::try_from(Arc::new(schema)).context(InvalidSchema {
+            path: self.path.display(),
+        })
+    }
+}
+
+/// The statistics of a row group used to decide whether it can contain rows
+/// that pass a predicate
+#[derive(Debug)]
+struct RowGroupStats {
+    rows: u64,
+    /// The minimum and maximum timestamps, if the file recorded them
+    time_range: Option<(i64, i64)>,
+    /// The number of nulls in each column that the file recorded it for
+    null_counts: BTreeMap<String, u64>,
+}
+
+impl RowGroupStats {
+    fn new(metadata: &RowGroupMetaData) -> Self {
+        let mut time_range = None;
+        let mut null_counts = BTreeMap::new();
+
+        for column in metadata.columns() {
+            let column_name = column.column_path().string();
+
+            if let Some(statistics) = column.statistics() {
+                if let Statistics::Int64(values) = statistics {
+                    if column_name == TIME_COLUMN_NAME && values.has_min_max_set() {
+                        time_range = Some((*values.min(), *values.max()));
+                    }
+                }
+
+                null_counts.insert(column_name, statistics.null_count());
+            }
+        }
+
+        Self {
+            rows: metadata.num_rows() as u64,
+            time_range,
+            null_counts,
+        }
+    }
+
+    fn overlap(&self, range: Option<TimestampRange>) -> Overlap {
+        if self.rows == 0 {
+            return Overlap::Disjoint;
+        }
+
+        let range = match range {
+            Some(range) => range,
+            None => return Overlap::Full,
+        };
+
+        match self.time_range {
+            Some((min, max)) if max < range.start || min >= range.end => Overlap::Disjoint,
+            Some((min, max)) if range.contains(min) && range.contains(max) => Overlap::Full,
+            _ => Overlap::Partial,
+        }
+    }
+
+    /// Returns true if the column might have a non null value
+    fn has_values(&self, column_name: &str) -> bool {
+        self.null_counts
+            .get(column_name)
+            .map_or(true, |&nulls| nulls < self.rows)
+    }
+}
+
+/// How many of a row group's rows are inside a time range
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Overlap {
+    Disjoint,
+    Partial,
+    Full,
+}
+
+/// Parts of a Parquet file of `len` bytes, keyed by their offset in the file
+#[derive(Debug, Clone)]
+struct FileRanges {
+    len: u64,
+    ranges: BTreeMap<usize, Bytes>,
+}
+
+impl FileRanges {
+    fn new(len: u64) -> Self {
+        Self {
+            len,
+            ranges: BTreeMap::new(),
+        }
+    }
+
+    fn insert(&mut self, start: usize, data: Bytes) {
+        self.ranges.insert(start, data);
+    }
+
+    /// Returns the bytes in `range` if a single part holds all of them
+    fn get(&self, range: &Range<usize>) -> Option<Bytes> {
+        let (&start, data) = self.ranges.range(..=range.start).next_back()?;
+        if range.end <= start + data.len() {
+            Some(data.slice(range.start - start..range.end - start))
+        } else {
+            None
+        }
+    }
+
+    fn contains(&self, range: &Range<usize>) -> bool {
+        self.get(range).is_some()
+    }
+}
+
+impl Length for FileRanges {
+    fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+impl ChunkReader for FileRanges {
+    type T = Cursor<Bytes>;
+
+    fn get_read(&self, start: u64, length: usize) -> parquet::errors::Result<Self::T> {
+        let range = start as usize..start as usize + length;
+        self.get(&range).map(Cursor::new).ok_or_else(|| {
+            ParquetError::General(format!("bytes {:?} of the file were not downloaded", range))
+        })
+    }
+}
+
+/// Downloads the end of the Parquet file at `path`, which holds its metadata
+async fn read_footer(
+    store: &ObjectStore,
+    path: &object_store::path::Path,
+    size: u64,
+) -> Result<FileRanges> {
+    let len = size as usize;
+    ensure!(
+        len >= FOOTER_SIZE,
+        InvalidFooter {
+            path: path.display(),
+            size
+        }
+    );
+
+    let get_range = |range: Range<usize>| async move {
+        store
+            .get_range(path, range)
+            .await
+            .context(ReadingObjectStore {
+                path: path.display(),
+            })
+    };
+
+    let mut start = len - FOOTER_READ_SIZE.min(len);
+    let mut data = get_range(start..len).await?;
+
+    let footer = &data[data.len() - FOOTER_SIZE..];
+    ensure!(
+        &footer[4..] == PARQUET_MAGIC,
+        InvalidFooter {
+            path: path.display(),
+            size
+        }
+    );
+    let metadata_len = u32::from_le_bytes(footer[..4].try_into().expect("4 bytes")) as usize;
+    ensure!(
+        metadata_len + FOOTER_SIZE <= len,
+        InvalidFooter {
+            path: path.display(),
+            size
+        }
+    );
+
+    // the metadata didn't fit in what was read
+    if metadata_len + FOOTER_SIZE > data.len() {
+        start = len - metadata_len - FOOTER_SIZE;
+        data = get_range(start..len).await?;
+    }
+
+    let mut footer = FileRanges::new(size);
+    footer.insert(start, data);
+    Ok(footer)
+}
+
+/// A reader of only some of the row groups of a Parquet file, whose column
+/// chunks are read from the parts of the file that were downloaded
+struct RowGroupsReader {
+    file: SerializedFileReader<FileRanges>,
+    /// The metadata of the file with only the row groups that are read
+    metadata: ParquetMetaData,
+    row_groups: Vec<usize>,
+}
+
+impl RowGroupsReader {
+    fn new(
+        file: FileRanges,
+        metadata: &ParquetMetaData,
+        row_groups: Vec<usize>,
+    ) -> parquet::errors::Result<Self> {
+        // the footer was downloaded, so this doesn't read the object store
+        let file = SerializedFileReader::new(file)?;
+
+        let selected: Vec<_> = row_groups
+            .iter()
+            .map(|&rg| metadata.row_group(rg).clone())
+            .collect();
+        let file_metadata = metadata.file_metadata();
+        let file_metadata = FileMetaData::new(
+            file_metadata.version(),
+            selected.iter().map(|rg| rg.num_rows()).sum(),
+            file_metadata.created_by().clone(),
+            file_metadata.key_value_metadata().clone(),
+            file_metadata.schema_descr_ptr(),
+            file_metadata.column_orders().cloned(),
+        );
+
+        Ok(Self {
+            file,
+            metadata: ParquetMetaData::new(file_metadata, selected),
+            row_groups,
+        })
+    }
+}
+
+impl FileReader for RowGroupsReader {
+    fn metadata(&self) -> &ParquetMetaData {
+        &self.metadata
+    }
+
+    fn num_row_groups(&self) -> usize {
+        self.row_groups.len()
+    }
+
+    fn get_row_group(&self, i: usize) -> parquet::errors::Result<Box<dyn RowGroupReader + '_>> {
+        self.file.get_row_group(self.row_groups[i])
+    }
+
+    fn get_row_iter(&self, projection: Option<SchemaType>) -> parquet::errors::Result<RowIter<'_>> {
+        RowIter::from_file(projection, self)
+    }
+}
+
+fn filter_time_range(
+    batch: &RecordBatch,
+    time_idx: usize,
+    range: TimestampRange,
+    path: &str,
+) -> Result<RecordBatch> {
+    let times = batch
+        .column(time_idx)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .context(InvalidTimeColumn { path })?;
+
+    let mask: Vec<_> = (0..times.len())
+        .map(|i| times.is_valid(i) && range.contains(times.value(i)))
+        .collect();
+
+    filter_record_batch(batch, &BooleanArray::from(mask)).context(FilteringBatch { path })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{DBChunk, Db},
+        jobs::{JobRegistry, TrackedJob},
+        query_tests::utils::make_db,
+        snapshot::snapshot_chunk,
+    };
+    use arrow_deps::{assert_table_eq, datafusion::physical_plan::common::collect};
+    use data_types::job::Job;
+    use futures::TryStreamExt;
+    use object_store::memory::InMemory;
+    use query::{predicate::PredicateBuilder, test::TestLPWriter, Database, PartitionChunk};
+
+    const PARTITION_KEY: &str = "1970-01-01T00";
+
+    /// Snapshots the chunk of the data to an in memory object store and
+    /// loads it back as a Parquet file chunk
+    async fn parquet_chunk(lp: &str) -> Arc<DBChunk> {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        parquet_chunk_in(store, lp).await
+    }
+
+    async fn parquet_chunk_in(store: Arc<ObjectStore>, lp: &str) -> Arc<DBChunk> {
+        let db: Db = make_db();
+        let mut writer = TestLPWriter::default();
+        writer.write_lp_string(&db, lp).await.unwrap();
+
+        let registration = JobRegistry::new().register(TrackedJob::new(Job::SnapshotChunk {
+            db_name: "test_db".to_string(),
+            partition_key: PARTITION_KEY.to_string(),
+            chunk_id: 0,
+        }));
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let snapshot = snapshot_chunk(
+            &store.new_path(),
+            Arc::clone(&store),
+            Arc::clone(&db.catalog),
+            PARTITION_KEY,
+            Arc::clone(&db.chunks(PARTITION_KEY)[0]),
+            Default::default(),
+            Some(tx),
+            registration,
+        )
+        .unwrap();
+        rx.await.unwrap();
+
+        let catalog = db.catalog.state().await;
+        let chunk = ParquetChunk::load(
+            PARTITION_KEY,
+            0,
+            store,
+            &snapshot.data_path,
+            &catalog.chunks[0].files,
+        )
+        .await
+        .unwrap();
+
+        DBChunk::new_parquet(Arc::new(chunk))
+    }
+
+    async fn read(chunk: &DBChunk, table_name: &str, predicate: &Predicate) -> Vec<RecordBatch> {
+        let columns = ["host", "time", "user"];
+        let stream = chunk
+            .read_filter(table_name, predicate, Selection::Some(&columns))
+            .await
+            .unwrap();
+        collect(stream).await.unwrap()
+    }
+
+    const LP: &str = r#"
+cpu,host=A,region=west user=23.2 10
+cpu,host=B,region=east user=10.0 20
+mem,host=C used=45i 30
+"#;
+
+    #[tokio::test]
+    async fn parquet_chunk_metadata() {
+        let chunk = parquet_chunk(LP).await;
+
+        assert_eq!(chunk.id(), 0);
+        assert!(chunk.has_table("cpu"));
+        assert!(!chunk.has_table("disk"));
+        assert_eq!(chunk.table_rows("cpu"), Some(2));
+
+        let names: StringSet = chunk
+            .table_stats()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, to_set(&["cpu", "mem"]));
+
+        let schema = chunk
+            .table_schema("cpu", Selection::Some(&["user", "host"]))
+            .await
+            .unwrap();
+        let (column_type, field) = schema.field(1);
+        assert_eq!(column_type, Some(InfluxColumnType::Tag));
+        assert_eq!(field.name(), "host");
+
+        let err = chunk
+            .table_schema("cpu", Selection::Some(&["used"]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Unknown column 'used'"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn parquet_chunk_read_filter() {
+        let chunk = parquet_chunk(LP).await;
+
+        let batches = read(&chunk, "cpu", &Predicate::default()).await;
+        let expected = vec![
+            "+------+------+------+",
+            "| host | time | user |",
+            "+------+------+------+",
+            "| A    | 10   | 23.2 |",
+            "| B    | 20   | 10   |",
+            "+------+------+------+",
+        ];
+        assert_table_eq!(expected, &batches);
+
+        // only the rows in the time range are returned
+        let predicate = PredicateBuilder::default().timestamp_range(15, 25).build();
+        let batches = read(&chunk, "cpu", &predicate).await;
+        let expected = vec![
+            "+------+------+------+",
+            "| host | time | user |",
+            "+------+------+------+",
+            "| B    | 20   | 10   |",
+            "+------+------+------+",
+        ];
+        assert_table_eq!(expected, &batches);
+
+        let predicate = PredicateBuilder::default()
+            .timestamp_range(100, 200)
+            .build();
+        assert!(read(&chunk, "cpu", &predicate).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn parquet_chunk_keeps_downloaded_columns() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let chunk = parquet_chunk_in(Arc::clone(&store), LP).await;
+        let expected = vec![
+            "+------+------+------+",
+            "| host | time | user |",
+            "+------+------+------+",
+            "| A    | 10   | 23.2 |",
+            "| B    | 20   | 10   |",
+            "+------+------+------+",
+        ];
+        assert_table_eq!(expected, &read(&chunk, "cpu", &Predicate::default()).await);
+
+        let paths: Vec<_> = store.list(None).await.unwrap().try_concat().await.unwrap();
+        for path in paths {
+            store.delete(&path).await.unwrap();
+        }
+
+        // the columns that were read are not downloaded again, and the others
+        // are only downloaded when they are read
+        assert_table_eq!(expected, &read(&chunk, "cpu", &Predicate::default()).await);
+        let err = chunk
+            .read_filter("cpu", &Predicate::default(), Selection::Some(&["region"]))
+            .await;
+        assert!(err.is_err());
+    }
+
+    #[tokio::test]
+    async fn parquet_chunk_pruning() {
+        let chunk = parquet_chunk(LP).await;
+
+        let predicate = PredicateBuilder::default().timestamp_range(0, 100).build();
+        assert!(chunk.could_pass_predicate(&predicate).unwrap());
+        let predicate = PredicateBuilder::default()
+            .timestamp_range(100, 200)
+            .build();
+        assert!(!chunk.could_pass_predicate(&predicate).unwrap());
+        let predicate = PredicateBuilder::default().table("disk").build();
+        assert!(!chunk.could_pass_predicate(&predicate).unwrap());
+
+        // the time range covers all of mem's rows but only part of cpu's
+        let predicate = PredicateBuilder::default().timestamp_range(15, 35).build();
+        assert_eq!(table_names(&chunk, &predicate).await, None);
+        let predicate = PredicateBuilder::default()
+            .timestamp_range(15, 35)
+            .table("mem")
+            .build();
+        assert_eq!(
+            table_names(&chunk, &predicate).await,
+            Some(to_set(&["mem"]))
+        );
+        let predicate = PredicateBuilder::default().timestamp_range(0, 35).build();
+        assert_eq!(
+            table_names(&chunk, &predicate).await,
+            Some(to_set(&["cpu", "mem"]))
+        );
+    }
+
+    #[tokio::test]
+    async fn parquet_chunk_columns() {
+        let chunk = parquet_chunk(LP).await;
+
+        let names = chunk
+            .column_names("cpu", &Predicate::default(), Selection::All)
+            .await
+            .unwrap();
+        assert_eq!(names, Some(to_set(&["host", "region", "time", "user"])));
+
+        let values = chunk
+            .column_values("cpu", "host", &Predicate::default())
+            .await
+            .unwrap();
+        assert_eq!(values, Some(to_set(&["A", "B"])));
+
+        let predicate = PredicateBuilder::default().timestamp_range(15, 25).build();
+        let values = chunk
+            .column_values("cpu", "region", &predicate)
+            .await
+            .unwrap();
+        assert_eq!(values, Some(to_set(&["east"])));
+
+        // values of non string columns can't be listed
+        let values = chunk
+            .column_values("cpu", "user", &Predicate::default())
+            .await
+            .unwrap();
+        assert_eq!(values, None);
+    }
+
+    async fn table_names(chunk: &DBChunk, predicate: &Predicate) -> Option<StringSet> {
+        chunk
+            .table_names(predicate, &StringSet::new())
+            .await
+            .unwrap()
+    }
+
+    fn to_set(v: &[&str]) -> StringSet {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+}
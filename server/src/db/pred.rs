@@ -3,6 +3,14 @@
 
 use std::convert::TryFrom;
 
+use arrow_deps::{
+    arrow::datatypes::DataType,
+    datafusion::{
+        logical_plan::{Expr, Operator},
+        scalar::ScalarValue,
+    },
+};
+use data_types::schema::Schema;
 use mutable_buffer::{chunk::Chunk, pred::ChunkPredicate};
 use query::predicate::Predicate;
 use snafu::Snafu;
@@ -32,6 +40,7 @@ pub fn to_read_buffer_predicate(predicate: &Predicate) -> Result<read_buffer::Pr
     match predicate
         .exprs
         .iter()
+        .flat_map(split_conjunction)
         .map(read_buffer::BinaryExpr::try_from)
         .collect::<Result<Vec<_>, _>>()
     {
@@ -52,6 +61,99 @@ pub fn to_read_buffer_predicate(predicate: &Predicate) -> Result<read_buffer::Pr
     }
 }
 
+/// Converts the parts of a [`query::Predicate`] that the ReadBuffer can
+/// evaluate on a table with `schema` into a [`read_buffer::Predicate`].
+///
+/// Unlike [`to_read_buffer_predicate`] this never fails: expressions
+/// that aren't a comparison between a column of `schema` and a literal
+/// of the column's type are left out, as are the table and field
+/// restrictions. The result may therefore select more rows than
+/// `predicate`, and the caller must still apply `predicate` to them.
+pub fn to_partial_read_buffer_predicate(
+    predicate: &Predicate,
+    schema: &Schema,
+) -> read_buffer::Predicate {
+    let exprs = predicate
+        .exprs
+        .iter()
+        .flat_map(split_conjunction)
+        .filter_map(|expr| to_read_buffer_expr(expr, schema))
+        .collect::<Vec<_>>();
+
+    match predicate.range {
+        Some(range) => read_buffer::Predicate::with_time_range(&exprs, range.start, range.end),
+        None => read_buffer::Predicate::new(exprs),
+    }
+}
+
+/// Splits `expr` into the expressions that are AND'ed together in it
+fn split_conjunction(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        } => {
+            let mut exprs = split_conjunction(left);
+            exprs.extend(split_conjunction(right));
+            exprs
+        }
+        expr => vec![expr],
+    }
+}
+
+/// Converts `expr` into a ReadBuffer expression if it compares a column
+/// of `schema` with a literal of the same type. Evaluating a literal of
+/// another type against the column's encoding is not supported by the
+/// ReadBuffer.
+fn to_read_buffer_expr(expr: &Expr, schema: &Schema) -> Option<read_buffer::BinaryExpr> {
+    let (column_name, op, value) = match expr {
+        Expr::BinaryExpr { left, op, right } => match (left.as_ref(), right.as_ref()) {
+            (Expr::Column(column_name), Expr::Literal(value)) => (column_name, *op, value),
+            // the ReadBuffer expects the column on the left
+            (Expr::Literal(value), Expr::Column(column_name)) => {
+                (column_name, swap_operands(op)?, value)
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let (_, field) = schema.field(schema.find_index_of(column_name)?);
+    let is_same_type = matches!(
+        (value, field.data_type()),
+        (ScalarValue::Utf8(_), DataType::Utf8)
+            | (ScalarValue::Int64(_), DataType::Int64)
+            | (ScalarValue::UInt64(_), DataType::UInt64)
+            | (ScalarValue::Float64(_), DataType::Float64)
+            | (ScalarValue::Boolean(_), DataType::Boolean)
+    );
+    if !is_same_type {
+        return None;
+    }
+
+    let expr = Expr::BinaryExpr {
+        left: Box::new(Expr::Column(column_name.clone())),
+        op,
+        right: Box::new(Expr::Literal(value.clone())),
+    };
+    read_buffer::BinaryExpr::try_from(&expr).ok()
+}
+
+/// Returns the operator `op2` such that `a op b` is `b op2 a`, if there
+/// is one
+fn swap_operands(op: &Operator) -> Option<Operator> {
+    match op {
+        Operator::Eq => Some(Operator::Eq),
+        Operator::NotEq => Some(Operator::NotEq),
+        Operator::Lt => Some(Operator::Gt),
+        Operator::LtEq => Some(Operator::GtEq),
+        Operator::Gt => Some(Operator::Lt),
+        Operator::GtEq => Some(Operator::LtEq),
+        _ => None,
+    }
+}
+
 /// Converts a [`query::Predicate`] into [`ChunkPredicate`],
 /// suitable for evaluating on the MutableBuffer.
 pub fn to_mutable_buffer_predicate(
@@ -74,8 +176,8 @@ pub fn to_mutable_buffer_predicate(
 #[cfg(test)]
 pub mod test {
     use super::*;
-    use arrow_deps::datafusion::logical_plan::Expr;
-    use arrow_deps::datafusion::scalar::ScalarValue;
+    use arrow_deps::datafusion::logical_plan::{col, lit};
+    use data_types::schema::builder::SchemaBuilder;
 
     use query::predicate::PredicateBuilder;
     use read_buffer::BinaryExpr as RBBinaryExpr;
@@ -118,6 +220,25 @@ pub mod test {
                     RBBinaryExpr::from(("counter", ">", 2992_i64)),
                 ]),
             ),
+            // conjunctions of expressions
+            (
+                PredicateBuilder::default()
+                    .add_expr(
+                        Expr::Column("track".to_owned())
+                            .eq(Expr::Literal(ScalarValue::Utf8(Some(
+                                "Star Roving".to_owned(),
+                            ))))
+                            .and(
+                                Expr::Column("counter".to_owned())
+                                    .gt(Expr::Literal(ScalarValue::Int64(Some(2992)))),
+                            ),
+                    )
+                    .build(),
+                RBPredicate::new(vec![
+                    RBBinaryExpr::from(("track", "=", "Star Roving")),
+                    RBBinaryExpr::from(("counter", ">", 2992_i64)),
+                ]),
+            ),
             // a bit of everything
             (
                 PredicateBuilder::default()
@@ -200,4 +321,49 @@ pub mod test {
             }
         }
     }
+
+    #[test]
+    fn into_partial_read_buffer_predicate() {
+        let schema = SchemaBuilder::new()
+            .tag("track")
+            .field("counter", DataType::Int64)
+            .field("temp", DataType::Float64)
+            .timestamp()
+            .build()
+            .unwrap();
+
+        let predicate = PredicateBuilder::default()
+            .timestamp_range(100, 2000)
+            .add_expr(
+                col("track")
+                    .eq(lit("Star Roving"))
+                    .and(lit(2992_i64).lt(col("counter"))),
+            )
+            // literal of a different type than the column
+            .add_expr(col("temp").gt(lit(10_i64)))
+            // column not in the schema
+            .add_expr(col("album").eq(lit("Sonic Youth")))
+            // not a comparison of a column and a literal
+            .add_expr(col("track").like(lit("Star%")))
+            .add_expr(col("track").eq(Expr::Literal(ScalarValue::Utf8(None))))
+            .add_expr(col("counter").gt(lit(100_i64)).or(col("temp").lt(lit(1.5))))
+            .build();
+
+        assert_eq!(
+            to_partial_read_buffer_predicate(&predicate, &schema),
+            RBPredicate::with_time_range(
+                &[
+                    RBBinaryExpr::from(("track", "=", "Star Roving")),
+                    RBBinaryExpr::from(("counter", ">", 2992_i64)),
+                ],
+                100,
+                2000,
+            )
+        );
+
+        assert_eq!(
+            to_partial_read_buffer_predicate(&PredicateBuilder::default().build(), &schema),
+            RBPredicate::default()
+        );
+    }
 }
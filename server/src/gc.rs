@@ -0,0 +1,447 @@
+//! This module garbage collects the files in a database's object storage
+//! prefix that nothing refers to anymore:
+//!
+//! * Parquet files that aren't in the newest version of the catalog, such as
+//!   those of failed or stopped snapshots, or of an earlier snapshot of a chunk
+//!   that was snapshotted again
+//! * catalog versions older than the newest one
+//! * WAL segments whose writes are all covered by persisted chunks, except for
+//!   the newest segment, which the ids of new segments continue from
+//!
+//! A file is only deleted once it is older than the grace period, so that the
+//! files of a snapshot that hasn't been added to the catalog yet are kept.
+//! Persisted chunks that were dropped are removed from the catalog, so their
+//! files are collected too.
+
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use object_store::{path::ObjectStorePath, ObjectMeta, ObjectStore, ObjectStoreApi};
+use snafu::{ensure, ResultExt, Snafu};
+use tracing::info;
+
+use crate::{
+    buffer::{object_store_path_for_segments, Segment},
+    catalog::{self, catalog_path, catalog_version_path, load_catalog, CatalogState},
+    recovery::{read_bytes, unpersisted_parts, Watermarks},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error listing object store path {}: {}", path, source))]
+    ListingObjectStore {
+        path: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display("Error loading catalog: {}", source))]
+    LoadingCatalog { source: catalog::Error },
+
+    #[snafu(display(
+        "Not collecting garbage: the {} newest catalog versions could not be read",
+        count
+    ))]
+    UnreadableCatalog { count: usize },
+
+    #[snafu(display("Error deleting {}: {}", path, source))]
+    DeletingFile {
+        path: String,
+        source: object_store::Error,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// How a server garbage collects the object storage files of its databases
+#[derive(Debug, Clone, PartialEq)]
+pub struct GcConfig {
+    /// How often the files of each database are collected
+    pub interval: Duration,
+    /// How old an unreferenced file must be before it is deleted
+    pub grace_period: Duration,
+    /// If set, the files that would be deleted are only reported
+    pub dry_run: bool,
+}
+
+/// What was found by `collect_garbage`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GcReport {
+    /// The unreferenced files that were deleted, or would have been in a dry
+    /// run
+    pub deleted: Vec<String>,
+    /// The total size of the deleted files in bytes
+    pub deleted_bytes: u64,
+    /// The number of unreferenced files that were kept because they are
+    /// younger than the grace period
+    pub in_grace_period: usize,
+}
+
+/// Deletes the files below the database's root path `db_path` that aren't
+/// referenced by its catalog or needed for recovery and were last modified
+/// more than `grace_period` before `now`. In a dry run the files are only
+/// reported.
+pub(crate) async fn collect_garbage(
+    store: &Arc<ObjectStore>,
+    db_path: &object_store::path::Path,
+    now: DateTime<Utc>,
+    grace_period: Duration,
+    dry_run: bool,
+) -> Result<GcReport> {
+    let (state, skipped) = load_catalog(store, db_path).await.context(LoadingCatalog)?;
+    // the files of the unreadable versions can't be told apart from garbage
+    ensure!(skipped == 0, UnreadableCatalog { count: skipped });
+
+    let objects = list_recursive(store, db_path).await?;
+    let unreferenced = unreferenced_files(store, db_path, &state, objects).await;
+
+    let grace_period =
+        chrono::Duration::from_std(grace_period).unwrap_or_else(|_| chrono::Duration::max_value());
+    let mut report = GcReport::default();
+    for object in unreferenced {
+        let path = object.location.display();
+        if now.signed_duration_since(object.last_modified) < grace_period {
+            report.in_grace_period += 1;
+            continue;
+        }
+
+        if dry_run {
+            info!(path = path.as_str(), "garbage collection would delete file");
+        } else {
+            store
+                .delete(&object.location)
+                .await
+                .context(DeletingFile { path: &path })?;
+            info!(path = path.as_str(), "garbage collection deleted file");
+        }
+
+        report.deleted_bytes += object.size as u64;
+        report.deleted.push(path);
+    }
+
+    Ok(report)
+}
+
+/// Lists all objects below the prefix
+async fn list_recursive(
+    store: &ObjectStore,
+    prefix: &object_store::path::Path,
+) -> Result<Vec<ObjectMeta<object_store::path::Path>>> {
+    let mut objects = vec![];
+    let mut prefixes = vec![prefix.clone()];
+
+    while let Some(prefix) = prefixes.pop() {
+        let list_result = store
+            .list_with_delimiter(&prefix)
+            .await
+            .context(ListingObjectStore {
+                path: prefix.display(),
+            })?;
+
+        objects.extend(list_result.objects);
+        prefixes.extend(list_result.common_prefixes);
+    }
+
+    Ok(objects)
+}
+
+/// Returns the objects that nothing refers to. Objects outside of the data,
+/// catalog and WAL directories, such as the database's rules, are always
+/// referenced.
+async fn unreferenced_files(
+    store: &ObjectStore,
+    db_path: &object_store::path::Path,
+    state: &CatalogState,
+    objects: Vec<ObjectMeta<object_store::path::Path>>,
+) -> Vec<ObjectMeta<object_store::path::Path>> {
+    let data_dir = {
+        let mut path = db_path.clone();
+        path.push_dir("data");
+        path.display()
+    };
+    let catalog_dir = catalog_path(db_path).display();
+    let segments_dir = object_store_path_for_segments(db_path).display();
+
+    let chunk_files: BTreeSet<_> = state
+        .chunks
+        .iter()
+        .flat_map(|chunk| chunk.file_paths(db_path))
+        .map(|path| path.display())
+        .collect();
+    let catalog_version = catalog_version_path(db_path, state.version).display();
+    // segment paths are zero padded, so the newest one sorts last
+    let newest_segment = objects
+        .iter()
+        .map(|object| object.location.display())
+        .filter(|path| path.starts_with(&segments_dir))
+        .max();
+    let watermarks = state.wal_positions();
+
+    let mut unreferenced = vec![];
+    for object in objects {
+        let path = object.location.display();
+
+        let is_garbage = if path.starts_with(&data_dir) {
+            !chunk_files.contains(&path)
+        } else if path.starts_with(&catalog_dir) {
+            // newer versions than the one that was loaded are being written
+            path < catalog_version
+        } else if path.starts_with(&segments_dir) {
+            Some(&path) != newest_segment.as_ref()
+                && is_persisted_segment(store, &object.location, &watermarks).await
+        } else {
+            false
+        };
+
+        if is_garbage {
+            unreferenced.push(object);
+        }
+    }
+
+    unreferenced
+}
+
+/// Returns true if all of the segment's writes are covered by persisted
+/// chunks. A segment that can't be read is kept.
+async fn is_persisted_segment(
+    store: &ObjectStore,
+    location: &object_store::path::Path,
+    watermarks: &Watermarks,
+) -> bool {
+    match read_bytes(store, location)
+        .await
+        .and_then(|data| Segment::from_file_bytes(&data).map_err(|e| e.to_string()))
+    {
+        Ok(segment) => segment
+            .writes
+            .iter()
+            .all(|write| unpersisted_parts(write, watermarks).is_none()),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        buffer::{object_store_path_for_segment, Buffer},
+        catalog::{Catalog, CatalogChunk, CatalogFile},
+    };
+    use bytes::Bytes;
+    use data_types::{
+        data::{lines_to_replicated_write, ReplicatedWrite},
+        database_rules::{DatabaseRules, PartitionTemplate, TemplatePart, WalBufferRollover},
+        partition_metadata::TableSummary,
+    };
+    use influxdb_line_protocol::parse_lines;
+    use object_store::memory::InMemory;
+    use uuid::Uuid;
+
+    fn db_path(store: &ObjectStore) -> object_store::path::Path {
+        let mut path = store.new_path();
+        path.push_all_dirs(&["1", "my_db"]);
+        path
+    }
+
+    async fn put(store: &ObjectStore, location: &object_store::path::Path, data: &'static str) {
+        let data = Bytes::from(data);
+        let len = data.len();
+        let stream = futures::stream::once(async move { Ok(data) });
+        store.put(location, stream, Some(len)).await.unwrap();
+    }
+
+    fn write(sequence: u64, lp: &str) -> Arc<ReplicatedWrite> {
+        let rules = DatabaseRules {
+            partition_template: PartitionTemplate {
+                parts: vec![TemplatePart::Table],
+            },
+            ..Default::default()
+        };
+        let lines: Vec<_> = parse_lines(lp).map(|l| l.unwrap()).collect();
+        Arc::new(lines_to_replicated_write(1, sequence, &lines, &rules))
+    }
+
+    /// Persists each write in its own segment
+    async fn persist_segments(store: &ObjectStore, writes: &[Arc<ReplicatedWrite>]) {
+        let mut buffer = Buffer::new(1 << 20, 10, WalBufferRollover::ReturnError, false);
+        for write in writes {
+            let segment = buffer.append(Arc::clone(write)).unwrap().unwrap();
+            let location = object_store_path_for_segment(&db_path(store), segment.id).unwrap();
+            let data = segment.to_file_bytes(1).unwrap();
+            let len = data.len();
+            let stream = futures::stream::once(async move { Ok(data) });
+            store.put(&location, stream, Some(len)).await.unwrap();
+        }
+    }
+
+    fn chunk(partition_key: &str, chunk_id: u32, sequence: u64) -> CatalogChunk {
+        CatalogChunk {
+            partition_key: partition_key.to_string(),
+            chunk_id,
+            snapshot_id: Uuid::new_v4(),
+            wal_positions: vec![(1, sequence)].into_iter().collect(),
+            files: vec![CatalogFile {
+                file_name: "cpu.parquet".to_string(),
+                table: TableSummary::new("cpu"),
+                time_range: None,
+                size: 4,
+            }],
+        }
+    }
+
+    /// Sets up a database whose cpu partition was snapshotted twice, once
+    /// by a snapshot that failed, and whose mem partition wasn't
+    async fn setup(store: &Arc<ObjectStore>) -> (CatalogChunk, Vec<String>) {
+        let db_path = db_path(store);
+
+        let mut rules = db_path.clone();
+        rules.set_file_name("rules.json");
+        put(store, &rules, "{}").await;
+
+        persist_segments(
+            store,
+            &[
+                write(1, "cpu bar=1 10"),
+                write(2, "mem foo=1 10"),
+                write(3, "cpu bar=2 20"),
+                write(4, "cpu bar=3 30"),
+            ],
+        )
+        .await;
+
+        let catalog = Catalog::default();
+        let first = chunk("cpu", 0, 3);
+        let superseded = chunk("cpu", 0, 3);
+        let failed = chunk("cpu", 1, 4);
+        for chunk in &[&superseded, &first, &failed] {
+            put(store, &chunk.file_paths(&db_path)[0], "data").await;
+        }
+        catalog
+            .add_chunk(store, &db_path, superseded.clone())
+            .await
+            .unwrap();
+        catalog
+            .add_chunk(store, &db_path, first.clone())
+            .await
+            .unwrap();
+
+        let mut expected = vec![
+            superseded.file_paths(&db_path)[0].display(),
+            failed.file_paths(&db_path)[0].display(),
+            object_store_path_for_segment(&db_path, 1)
+                .unwrap()
+                .display(),
+            object_store_path_for_segment(&db_path, 3)
+                .unwrap()
+                .display(),
+        ];
+        let old_catalog = catalog_version_path(&db_path, 1);
+        expected.push(old_catalog.display());
+        expected.sort();
+
+        (first, expected)
+    }
+
+    #[tokio::test]
+    async fn deletes_unreferenced_files() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let db_path = db_path(&store);
+        let (first, expected) = setup(&store).await;
+        let sizes: std::collections::BTreeMap<_, _> = list_recursive(&store, &db_path)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|object| (object.location.display(), object.size as u64))
+            .collect();
+
+        let later = Utc::now() + chrono::Duration::hours(2);
+        let grace_period = Duration::from_secs(3600);
+        let mut report = collect_garbage(&store, &db_path, later, grace_period, false)
+            .await
+            .unwrap();
+        report.deleted.sort();
+
+        assert_eq!(report.deleted, expected);
+        assert_eq!(
+            report.deleted_bytes,
+            expected.iter().map(|path| sizes[path]).sum::<u64>()
+        );
+        assert_eq!(report.in_grace_period, 0);
+
+        // the chunk in the catalog, the mem and newest segments and the
+        // rules are kept
+        let remaining = list_recursive(&store, &db_path).await.unwrap();
+        let mut remaining: Vec<_> = remaining
+            .into_iter()
+            .map(|o| o.location.display())
+            .collect();
+        remaining.sort();
+        let catalog_version = catalog_version_path(&db_path, 2);
+        let mut rules = db_path.clone();
+        rules.set_file_name("rules.json");
+        let mut kept = vec![
+            first.file_paths(&db_path)[0].display(),
+            catalog_version.display(),
+            rules.display(),
+            object_store_path_for_segment(&db_path, 2)
+                .unwrap()
+                .display(),
+            object_store_path_for_segment(&db_path, 4)
+                .unwrap()
+                .display(),
+        ];
+        kept.sort();
+        assert_eq!(remaining, kept);
+
+        // there's nothing left to collect
+        let report = collect_garbage(&store, &db_path, later, grace_period, false)
+            .await
+            .unwrap();
+        assert_eq!(report, GcReport::default());
+    }
+
+    #[tokio::test]
+    async fn dry_run_and_grace_period() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let db_path = db_path(&store);
+        let (_, expected) = setup(&store).await;
+        let count = list_recursive(&store, &db_path).await.unwrap().len();
+
+        // everything was just written
+        let report = collect_garbage(
+            &store,
+            &db_path,
+            Utc::now(),
+            Duration::from_secs(3600),
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(report.deleted.is_empty());
+        assert_eq!(report.in_grace_period, expected.len());
+
+        // a dry run reports the files without deleting them
+        let later = Utc::now() + chrono::Duration::hours(2);
+        let mut report = collect_garbage(&store, &db_path, later, Duration::from_secs(3600), true)
+            .await
+            .unwrap();
+        report.deleted.sort();
+        assert_eq!(report.deleted, expected);
+        assert_eq!(list_recursive(&store, &db_path).await.unwrap().len(), count);
+    }
+
+    #[tokio::test]
+    async fn stops_on_unreadable_catalog() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let db_path = db_path(&store);
+        setup(&store).await;
+
+        let bad_catalog = catalog_version_path(&db_path, 3);
+        put(&store, &bad_catalog, "not json").await;
+
+        let later = Utc::now() + chrono::Duration::hours(2);
+        let err = collect_garbage(&store, &db_path, later, Duration::from_secs(0), false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::UnreadableCatalog { count: 1 }));
+    }
+}
@@ -0,0 +1,161 @@
+//! This module contains a consistent hash ring that is used to pick which
+//! host in a `HostGroup` receives the writes for a given partition key.
+
+use data_types::database_rules::HostGroup;
+
+/// The number of points each host is placed at on the ring. Using several
+/// points per host spreads the keys more evenly when there are only a few
+/// hosts in a group.
+const VIRTUAL_NODES_PER_HOST: usize = 64;
+
+/// A consistent hash ring over the hosts of a `HostGroup`. Adding or removing
+/// a host only moves the keys that hash next to that host's points, rather
+/// than reshuffling every partition key in the group.
+#[derive(Debug)]
+pub(crate) struct HashRing {
+    hosts: Vec<String>,
+    /// Points on the ring as (hash, index into `hosts`), sorted by hash.
+    nodes: Vec<(u32, usize)>,
+}
+
+impl HashRing {
+    pub(crate) fn new(group: &HostGroup) -> Self {
+        let hosts = group.hosts.clone();
+
+        let mut nodes: Vec<_> = hosts
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, host)| {
+                (0..VIRTUAL_NODES_PER_HOST)
+                    .map(move |vnode| (hash(format!("{}-{}", host, vnode).as_bytes()), idx))
+            })
+            .collect();
+        nodes.sort_unstable();
+
+        Self { hosts, nodes }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.hosts.is_empty()
+    }
+
+    /// Returns the host that owns the key.
+    pub(crate) fn host_for_key(&self, key: &str) -> Option<&str> {
+        self.hosts_for_key(key).next()
+    }
+
+    /// Returns every host in the group in the order they should be tried for
+    /// the key. The first host owns the key and the rest follow it around the
+    /// ring, so that if a host is down its keys fall to the same fallback
+    /// host every time.
+    pub(crate) fn hosts_for_key(&self, key: &str) -> impl Iterator<Item = &str> + '_ {
+        let hash = hash(key.as_bytes());
+        let start = match self.nodes.binary_search_by(|(h, _)| h.cmp(&hash)) {
+            Ok(pos) | Err(pos) => pos,
+        };
+
+        let mut seen = vec![false; self.hosts.len()];
+        let hosts = &self.hosts;
+
+        self.nodes[start..]
+            .iter()
+            .chain(self.nodes[..start].iter())
+            .filter_map(move |(_, idx)| {
+                if seen[*idx] {
+                    None
+                } else {
+                    seen[*idx] = true;
+                    Some(hosts[*idx].as_str())
+                }
+            })
+    }
+}
+
+/// Hashes the data onto the ring. The crc32 is stable across processes and
+/// versions, which matters because every router must pick the same host for a
+/// key, but on its own it clusters similar keys (such as consecutive dates),
+/// so its output is mixed with the murmur3 finalizer.
+fn hash(data: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(data);
+    let mut h = hasher.finalize();
+
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn ring(hosts: &[&str]) -> HashRing {
+        HashRing::new(&HostGroup {
+            id: "group".to_string(),
+            hosts: hosts.iter().map(|h| h.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn empty_ring() {
+        let ring = ring(&[]);
+        assert!(ring.is_empty());
+        assert_eq!(ring.host_for_key("foo"), None);
+    }
+
+    #[test]
+    fn keys_are_spread_over_hosts() {
+        let ring = ring(&["serverA", "serverB", "serverC"]);
+
+        let mut counts = BTreeMap::new();
+        for i in 0..300 {
+            let host = ring.host_for_key(&format!("2021-01-{}", i)).unwrap();
+            *counts.entry(host).or_insert(0) += 1;
+        }
+
+        assert_eq!(counts.len(), 3);
+        for count in counts.values() {
+            assert!(*count > 30, "uneven distribution: {:?}", counts);
+        }
+    }
+
+    #[test]
+    fn same_key_same_host() {
+        let ring = ring(&["serverA", "serverB", "serverC"]);
+        let other = ring(&["serverA", "serverB", "serverC"]);
+
+        for i in 0..50 {
+            let key = format!("key{}", i);
+            assert_eq!(ring.host_for_key(&key), other.host_for_key(&key));
+        }
+    }
+
+    #[test]
+    fn hosts_for_key_visits_each_host_once() {
+        let ring = ring(&["serverA", "serverB", "serverC"]);
+
+        let mut hosts: Vec<_> = ring.hosts_for_key("foo").collect();
+        assert_eq!(hosts[0], ring.host_for_key("foo").unwrap());
+
+        hosts.sort_unstable();
+        assert_eq!(hosts, vec!["serverA", "serverB", "serverC"]);
+    }
+
+    #[test]
+    fn removing_a_host_only_moves_its_keys() {
+        let before = ring(&["serverA", "serverB", "serverC"]);
+        let after = ring(&["serverA", "serverB"]);
+
+        for i in 0..100 {
+            let key = format!("key{}", i);
+            let old_host = before.host_for_key(&key).unwrap();
+            if old_host != "serverC" {
+                assert_eq!(Some(old_host), after.host_for_key(&key));
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! This module contains the metadata of the long-running jobs a server runs,
+//! such as snapshots and persistence. Each job is registered with the
+//! server's `JobRegistry` for as long as it runs, so that it can be listed
+//! and cancelled through the operations API.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use chrono::{DateTime, Utc};
+use data_types::job::Job;
+
+use crate::tracker::TrackerRegistry;
+
+/// The registry of a server's running jobs
+pub type JobRegistry = TrackerRegistry<TrackedJob>;
+
+/// A running job, with when it started and how far it has progressed
+#[derive(Debug, Clone)]
+pub struct TrackedJob {
+    pub job: Job,
+    pub start_time: DateTime<Utc>,
+    progress: Arc<JobProgress>,
+}
+
+impl TrackedJob {
+    /// Creates the metadata of a job that is starting now
+    pub fn new(job: Job) -> Self {
+        Self {
+            job,
+            start_time: Utc::now(),
+            progress: Default::default(),
+        }
+    }
+
+    /// The job's progress, which the job updates as it runs
+    pub fn progress(&self) -> &Arc<JobProgress> {
+        &self.progress
+    }
+}
+
+/// The number of tasks a job consists of, such as the tables of a snapshot,
+/// and how many of them have completed
+#[derive(Debug, Default)]
+pub struct JobProgress {
+    total: AtomicUsize,
+    completed: AtomicUsize,
+}
+
+impl JobProgress {
+    /// Adds tasks to the job
+    pub fn add_tasks(&self, count: usize) {
+        self.total.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Records that one of the job's tasks has completed
+    pub fn complete_task(&self) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the total and the completed number of tasks
+    pub fn tasks(&self) -> (usize, usize) {
+        (
+            self.total.load(Ordering::Relaxed),
+            self.completed.load(Ordering::Relaxed),
+        )
+    }
+}
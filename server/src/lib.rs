@@ -68,39 +68,68 @@
 )]
 
 pub mod buffer;
+pub mod catalog;
 mod config;
 pub mod db;
+pub mod gc;
+mod hash_ring;
+pub mod jobs;
+mod lifecycle;
+mod local_wal;
+pub mod matcher;
+mod recovery;
+pub mod replication;
 pub mod snapshot;
-mod tracker;
+mod subscription;
+pub mod tracker;
 
 #[cfg(test)]
 mod query_tests;
 
-use std::sync::{
-    atomic::{AtomicU32, Ordering},
-    Arc,
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
 };
 
 use crate::{
-    buffer::SegmentPersistenceTask,
+    buffer::{Segment, WriterSequence},
     config::{object_store_path_for_database_config, Config, DB_RULES_FILE_NAME},
-    db::Db,
-    tracker::TrackerRegistry,
+    db::{DBChunk, Db},
+    gc::{GcConfig, GcReport},
+    hash_ring::HashRing,
+    jobs::{JobProgress, JobRegistry, TrackedJob},
+    lifecycle::{LifecycleHandle, LifecycleManager},
+    local_wal::{LocalWal, LocalWals},
+    matcher::CompiledMatcher,
+    replication::ReplicationQueueStatus,
+    snapshot::Snapshot,
 };
+use arrow_deps::arrow::record_batch::RecordBatch;
 use data_types::{
-    data::{lines_to_replicated_write, ReplicatedWrite},
-    database_rules::{DatabaseRules, HostGroup, HostGroupId, MatchTables},
+    data::{
+        lines_to_replicated_write, record_batch_to_replicated_write, replicated_write_from_entries,
+        ReplicatedWrite,
+    },
+    database_rules::{DatabaseRules, HostGroup, HostGroupId},
+    job::Job,
+    schema::InfluxColumnType,
     {DatabaseName, DatabaseNameError},
 };
 use influxdb_line_protocol::ParsedLine;
 use object_store::{path::ObjectStorePath, ObjectStore, ObjectStoreApi};
-use query::{exec::Executor, DatabaseStore};
+use query::{exec::Executor, DatabaseStore, PartitionChunk};
 
 use async_trait::async_trait;
 use bytes::Bytes;
-use futures::stream::TryStreamExt;
-use snafu::{OptionExt, ResultExt, Snafu};
-use tracing::error;
+use chrono::Utc;
+use futures::stream::{Stream, TryStreamExt};
+use parking_lot::{Mutex, RwLock};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use tokio::sync::oneshot;
+use tracing::{error, info, warn};
 
 type DatabaseError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
@@ -130,6 +159,12 @@ pub enum Error {
     },
     #[snafu(display("error replicating to remote: {}", source))]
     ErrorReplicating { source: DatabaseError },
+    #[snafu(display(
+        "replication queue of database {} is full with {} writes",
+        db_name,
+        max_size
+    ))]
+    ReplicationQueueFull { db_name: String, max_size: usize },
     #[snafu(display("unable to use server until id is set"))]
     IdNotSet,
     #[snafu(display("error serializing configuration {}", source))]
@@ -142,12 +177,34 @@ pub enum Error {
     DatabaseAlreadyExists { db_name: String },
     #[snafu(display("error appending to wal buffer: {}", source))]
     WalError { source: buffer::Error },
+    #[snafu(display("no wal buffer for database: {}", db_name))]
+    NoWalBuffer { db_name: String },
+    #[snafu(display("error starting snapshot: {}", source))]
+    SnapshotError { source: snapshot::Error },
+    #[snafu(display("error writing to local wal: {}", source))]
+    LocalWalError { source: local_wal::Error },
+    #[snafu(display("cannot update rules of database {}: {}", db_name, source))]
+    InvalidRulesUpdate { db_name: String, source: db::Error },
+    #[snafu(display("invalid matcher of subscription {}: {}", index, source))]
+    InvalidSubscription {
+        index: usize,
+        source: matcher::Error,
+    },
+    #[snafu(display("error dropping chunk of database {}: {}", db_name, source))]
+    DroppingChunk { db_name: String, source: db::Error },
+    #[snafu(display("error compacting chunks of database {}: {}", db_name, source))]
+    CompactingChunks { db_name: String, source: db::Error },
+    #[snafu(display("error collecting garbage of database {}: {}", db_name, source))]
+    GarbageCollectionError { db_name: String, source: gc::Error },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 const STORE_ERROR_PAUSE_SECONDS: u64 = 100;
 
+/// How often the background worker runs its periodic tasks
+const BACKGROUND_WORKER_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
 /// `Server` is the container struct for how servers store data internally, as
 /// well as how they communicate with other servers. Each server will have one
 /// of these structs, which keeps track of all replication and query rules.
@@ -158,7 +215,15 @@ pub struct Server<M: ConnectionManager> {
     connection_manager: Arc<M>,
     pub store: Arc<ObjectStore>,
     executor: Arc<Executor>,
-    segment_persistence_registry: TrackerRegistry<SegmentPersistenceTask>,
+    jobs: JobRegistry,
+    local_wals: Option<Arc<LocalWals>>,
+    gc_config: Option<GcConfig>,
+    /// The running lifecycle manager of each database
+    lifecycle_managers: Mutex<BTreeMap<DatabaseName<'static>, LifecycleHandle>>,
+    /// Held while the rules of a database are updated or a database is
+    /// deleted, so that these changes don't interleave with each other or
+    /// with the starting of lifecycle managers
+    database_changes: tokio::sync::Mutex<()>,
 }
 
 impl<M: ConnectionManager> Server<M> {
@@ -169,10 +234,38 @@ impl<M: ConnectionManager> Server<M> {
             store,
             connection_manager: Arc::new(connection_manager),
             executor: Arc::new(Executor::new()),
-            segment_persistence_registry: TrackerRegistry::new(),
+            jobs: JobRegistry::new(),
+            local_wals: None,
+            gc_config: None,
+            lifecycle_managers: Default::default(),
+            database_changes: Default::default(),
         }
     }
 
+    /// Makes the server append every write to a local-disk WAL of its
+    /// database below `root` before acknowledging it. The WALs are replayed
+    /// by `load_database_configs`.
+    pub fn with_local_wal(mut self, root: impl Into<std::path::PathBuf>) -> Self {
+        self.local_wals = Some(Arc::new(LocalWals::new(root)));
+        self
+    }
+
+    /// Makes the background worker garbage collect the object storage files
+    /// of every database once per `config.interval`
+    pub fn with_garbage_collection(mut self, config: GcConfig) -> Self {
+        self.gc_config = Some(config);
+        self
+    }
+
+    /// Returns the database's local WAL if the server has them
+    fn local_wal(&self, db_name: &str) -> Result<Option<Arc<LocalWal>>> {
+        self.local_wals
+            .as_ref()
+            .map(|local_wals| local_wals.open(db_name))
+            .transpose()
+            .context(LocalWalError)
+    }
+
     /// sets the id of the server, which is used for replication and the base
     /// path in object storage.
     ///
@@ -202,14 +295,145 @@ impl<M: ConnectionManager> Server<M> {
         let name = db_name.into();
         let db_name = DatabaseName::new(name.clone()).context(InvalidDatabaseName)?;
         rules.name = name;
+        validate_subscriptions(&rules)?;
 
         let db_reservation = self.config.create_db(db_name, rules)?;
 
-        let data =
-            Bytes::from(serde_json::to_vec(&db_reservation.db.rules).context(ErrorSerializing)?);
+        let rules = db_reservation.db.rules.read().clone();
+        self.persist_database_rules(&db_reservation.name, &rules)
+            .await?;
+
+        db_reservation.commit();
+
+        Ok(())
+    }
+
+    /// Replaces the rules of a database, persisting them to object storage
+    /// and applying them to the loaded database. Adding or removing the
+    /// mutable buffer or WAL buffer requires recreating the database.
+    pub async fn update_database(
+        &self,
+        db_name: &DatabaseName<'_>,
+        mut rules: DatabaseRules,
+    ) -> Result<()> {
+        self.require_id()?;
+
+        // the rules checked are the ones that are replaced, and the rules
+        // persisted last are the ones applied last
+        let _changes = self.database_changes.lock().await;
+
+        let db = self.config.db(db_name).context(DatabaseNotFound {
+            db_name: db_name.as_str(),
+        })?;
+        rules.name = db_name.to_string();
+        validate_subscriptions(&rules)?;
+
+        db.check_rules_update(&rules).context(InvalidRulesUpdate {
+            db_name: db_name.as_str(),
+        })?;
+        self.persist_database_rules(db_name, &rules).await?;
+        db.update_rules(rules).context(InvalidRulesUpdate {
+            db_name: db_name.as_str(),
+        })?;
+
+        info!(db_name = db_name.as_str(), "updated database rules");
+        Ok(())
+    }
+
+    /// Removes the database from the server and its rules from object
+    /// storage, so it isn't loaded again when the server restarts. Its
+    /// lifecycle manager is stopped and its running jobs are cancelled
+    /// first. If `delete_data` is set, the database's snapshots and WAL
+    /// segments in object storage and its local WAL are deleted as well;
+    /// otherwise they are recovered if a database with the same name is
+    /// created and the server restarts.
+    pub async fn delete_database(
+        &self,
+        db_name: &DatabaseName<'_>,
+        delete_data: bool,
+    ) -> Result<()> {
+        let root_path = self.root_path()?;
+        let _changes = self.database_changes.lock().await;
+
+        ensure!(
+            self.config.db(db_name).is_some(),
+            DatabaseNotFound {
+                db_name: db_name.as_str(),
+            }
+        );
+
+        // nothing may write to the database's files once they are deleted
+        let manager = {
+            let name = DatabaseName::new(db_name.to_string()).expect("name was already validated");
+            self.lifecycle_managers.lock().remove(&name)
+        };
+        if let Some(manager) = manager {
+            manager.stop().await;
+        }
+        self.cancel_jobs(db_name).await;
+
+        let location = object_store_path_for_database_config(&root_path, db_name);
+        self.store.delete(&location).await.context(StoreError)?;
+
+        self.config.remove_db(db_name);
+        if let Some(local_wals) = &self.local_wals {
+            local_wals
+                .remove(db_name.as_str(), delete_data)
+                .context(LocalWalError)?;
+        }
+
+        if delete_data {
+            let mut db_path = root_path;
+            db_path.push_dir(db_name.to_string());
+
+            let paths: Vec<_> = self
+                .store
+                .list(Some(&db_path))
+                .await
+                .context(StoreError)?
+                .try_concat()
+                .await
+                .context(StoreError)?;
+            for path in paths {
+                self.store.delete(&path).await.context(StoreError)?;
+            }
+        }
+
+        info!(db_name = db_name.as_str(), delete_data, "deleted database");
+        Ok(())
+    }
+
+    /// Cancels the running jobs of the database and waits for them to stop
+    async fn cancel_jobs(&self, db_name: &DatabaseName<'_>) {
+        loop {
+            let jobs: Vec<_> = self
+                .jobs
+                .tracked()
+                .into_iter()
+                .filter(|(_, tracked)| tracked.job.db_name() == db_name.as_str())
+                .map(|(id, _)| id)
+                .collect();
+            if jobs.is_empty() {
+                return;
+            }
+
+            // a job stops the next time it yields
+            for id in jobs {
+                self.jobs.terminate(id);
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Writes the database's rules to its rules file in object storage
+    async fn persist_database_rules(
+        &self,
+        db_name: &DatabaseName<'_>,
+        rules: &DatabaseRules,
+    ) -> Result<()> {
+        let data = Bytes::from(serde_json::to_vec(rules).context(ErrorSerializing)?);
         let len = data.len();
-        let location =
-            object_store_path_for_database_config(&self.root_path()?, &db_reservation.name);
+        let location = object_store_path_for_database_config(&self.root_path()?, db_name);
 
         let stream_data = std::io::Result::Ok(data);
         self.store
@@ -219,11 +443,7 @@ impl<M: ConnectionManager> Server<M> {
                 Some(len),
             )
             .await
-            .context(StoreError)?;
-
-        db_reservation.commit();
-
-        Ok(())
+            .context(StoreError)
     }
 
     // base location in object store for this writer
@@ -236,9 +456,12 @@ impl<M: ConnectionManager> Server<M> {
     }
 
     /// Loads the database configurations based on the databases in the
-    /// object store. Any databases in the config already won't be
-    /// replaced.
+    /// object store, and rebuilds each database from the snapshots and WAL
+    /// segments persisted for it and from its local WAL. Any databases in the
+    /// config already won't be replaced.
     pub async fn load_database_configs(&self) -> Result<()> {
+        let writer_id = self.require_id()?;
+
         // get the database names from the object store prefixes
         // TODO: update object store to pull back all common prefixes by
         //       following the next tokens.
@@ -251,10 +474,12 @@ impl<M: ConnectionManager> Server<M> {
         let handles: Vec<_> = list_result
             .common_prefixes
             .into_iter()
-            .map(|mut path| {
+            .map(|db_path| {
                 let store = Arc::clone(&self.store);
                 let config = Arc::clone(&self.config);
+                let local_wals = self.local_wals.clone();
 
+                let mut path = db_path.clone();
                 path.set_file_name(DB_RULES_FILE_NAME);
 
                 tokio::task::spawn(async move {
@@ -281,7 +506,34 @@ impl<M: ConnectionManager> Server<M> {
                             Err(e) => error!("error parsing name {} from rules: {}", rules.name, e),
                             Ok(name) => match config.create_db(name, rules) {
                                 Err(e) => error!("error adding database to config: {}", e),
-                                Ok(handle) => handle.commit(),
+                                Ok(handle) => {
+                                    let local_wal = match local_wals
+                                        .map(|local_wals| local_wals.open(&handle.name))
+                                        .transpose()
+                                    {
+                                        Ok(local_wal) => local_wal,
+                                        Err(e) => {
+                                            error!("error opening local wal: {}", e);
+                                            None
+                                        }
+                                    };
+
+                                    if let Err(e) = recovery::rebuild_database(
+                                        &handle.db,
+                                        writer_id,
+                                        &db_path,
+                                        Arc::clone(&store),
+                                        local_wal.as_deref(),
+                                    )
+                                    .await
+                                    {
+                                        error!(
+                                            "error rebuilding database {} from object store: {}",
+                                            handle.name, e
+                                        );
+                                    }
+                                    handle.commit()
+                                }
                             },
                         },
                     }
@@ -297,7 +549,7 @@ impl<M: ConnectionManager> Server<M> {
     /// Creates a host group with a set of connection strings to hosts. These
     /// host connection strings should be something that the connection
     /// manager can use to return a remote server to work with.
-    pub async fn create_host_group(&mut self, id: HostGroupId, hosts: Vec<String>) -> Result<()> {
+    pub async fn create_host_group(&self, id: HostGroupId, hosts: Vec<String>) -> Result<()> {
         // Return an error if this server hasn't yet been setup with an id
         self.require_id()?;
 
@@ -320,24 +572,106 @@ impl<M: ConnectionManager> Server<M> {
             .context(DatabaseNotFound { db_name: &*db_name })?;
 
         let sequence = db.next_sequence();
-        let write = lines_to_replicated_write(id, sequence, lines, &db.rules);
+        let write = lines_to_replicated_write(id, sequence, lines, &db.rules.read());
+
+        self.handle_replicated_write(&db_name, &db, write).await?;
+
+        Ok(())
+    }
+
+    /// `write_record_batch` converts the rows of a record batch of a table
+    /// into a `ReplicatedWrite` and handles it like `write_lines` does.
+    /// `columns` holds the name and IOx column type of each column of the
+    /// batch, whose arrays must have the arrow data types of these column
+    /// types.
+    pub async fn write_record_batch(
+        &self,
+        db_name: &str,
+        table_name: &str,
+        columns: &[(String, InfluxColumnType)],
+        batch: &RecordBatch,
+    ) -> Result<()> {
+        let id = self.require_id()?;
+
+        let db_name = DatabaseName::new(db_name).context(InvalidDatabaseName)?;
+        let db = self
+            .config
+            .db(&db_name)
+            .context(DatabaseNotFound { db_name: &*db_name })?;
+
+        let sequence = db.next_sequence();
+        let write = record_batch_to_replicated_write(
+            id,
+            sequence,
+            table_name,
+            columns,
+            batch,
+            &db.rules.read(),
+        );
 
         self.handle_replicated_write(&db_name, &db, write).await?;
 
         Ok(())
     }
 
+    /// Stores a write this server accepted from a client, then replicates it
+    /// to the database's host groups and pushes it to its subscriptions
     pub async fn handle_replicated_write(
         &self,
         db_name: &DatabaseName<'_>,
         db: &Db,
         write: ReplicatedWrite,
     ) -> Result<()> {
+        self.check_replication_queue(db_name, db)?;
+
+        let write = self.store_replicated_write(db_name, db, write).await?;
+
+        self.replicate_to_host_groups(db_name, db, &write).await?;
+
+        let subscriptions = Arc::clone(&*db.subscriptions.read());
+        for (host_group_id, matcher) in subscriptions.iter() {
+            if let Some(write) = matcher.filter(&write) {
+                self.replicate_to_host_group(host_group_id, db_name, &write)
+                    .await?
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stores a write in the database's local WAL, mutable buffer and WAL
+    /// buffer without replicating it. Writes another server replicated to
+    /// this one take this path: the server that accepted the write already
+    /// sent it to every host group and subscription, and sending it on again
+    /// would make servers that replicate to each other do so forever.
+    /// Subscribers of this server still receive it through `subscribe`.
+    pub async fn store_replicated_write(
+        &self,
+        db_name: &DatabaseName<'_>,
+        db: &Db,
+        write: ReplicatedWrite,
+    ) -> Result<Arc<ReplicatedWrite>> {
         if let Some(buf) = &db.mutable_buffer {
+            // a rejected write mustn't be in the local wal, where it would be
+            // replayed after a restart
+            db.check_mutable_buffer_capacity()
+                .map_err(|e| Box::new(e) as DatabaseError)
+                .context(UnknownDatabaseError {})?;
+
+            // the write is durable in the local wal before it is stored, and
+            // the guard keeps the wal position from being read in between
+            let local_wal = self.local_wal(db_name)?;
+            let appended = match &local_wal {
+                Some(local_wal) => Some(local_wal.append(&write).await.context(LocalWalError)?),
+                None => None,
+            };
+
             buf.store_replicated_write(&write)
                 .await
                 .map_err(|e| Box::new(e) as DatabaseError)
                 .context(UnknownDatabaseError {})?;
+
+            drop(appended);
         }
 
         let write = Arc::new(write);
@@ -359,60 +693,519 @@ impl<M: ConnectionManager> Server<M> {
 
             if let Some(segment) = segment {
                 if persist {
-                    let writer_id = self.require_id()?;
-                    let store = Arc::clone(&self.store);
-                    segment
-                        .persist_bytes_in_background(
-                            &self.segment_persistence_registry,
-                            writer_id,
-                            db_name,
-                            store,
-                        )
-                        .context(WalError)?;
+                    self.persist_segment(db_name, &segment)?;
                 }
             }
         }
 
-        for host_group_id in &db.rules.replication {
-            self.replicate_to_host_group(host_group_id, db_name, &write)
-                .await?;
+        Ok(write)
+    }
+
+    // rejects a write before it is stored or sent anywhere if the replication
+    // queue rejects writes and might not have room for it: every host group
+    // beyond the `replication_count` that acknowledge it may have to be queued
+    fn check_replication_queue(&self, db_name: &DatabaseName<'_>, db: &Db) -> Result<()> {
+        let queued = {
+            let rules = db.rules.read();
+            let groups = rules.replication.len();
+            groups - usize::from(rules.replication_count).min(groups)
+        };
+
+        let queue = db.replication_queue.lock();
+        ensure!(
+            queue.has_room_for(queued),
+            ReplicationQueueFull {
+                db_name: db_name.as_str(),
+                max_size: queue.max_size(),
+            }
+        );
+
+        Ok(())
+    }
+
+    // replicates the write to the database's host groups. Success is returned
+    // once `replication_count` groups have acknowledged the write. The write is
+    // sent to the remaining groups, and to any group that couldn't be reached,
+    // in the background from the database's replication queue. If the queue is
+    // disabled every group is tried synchronously. The write has already been
+    // stored, so if it doesn't fit into the queue after all, for example because
+    // of concurrent writes, it is dropped for the missed groups and logged.
+    async fn replicate_to_host_groups(
+        &self,
+        db_name: &DatabaseName<'_>,
+        db: &Db,
+        write: &Arc<ReplicatedWrite>,
+    ) -> Result<()> {
+        let (groups, replication_count) = {
+            let rules = db.rules.read();
+            (rules.replication.clone(), rules.replication_count)
+        };
+        let required = usize::from(replication_count).min(groups.len());
+        let queue_enabled = db.replication_queue.lock().is_enabled();
+
+        let mut acknowledged = 0;
+        let mut last_error = None;
+        let mut missed = vec![];
+
+        for host_group_id in &groups {
+            if acknowledged >= required && queue_enabled {
+                missed.push(host_group_id.clone());
+                continue;
+            }
+
+            match self
+                .replicate_to_host_group(host_group_id, db_name, write)
+                .await
+            {
+                Ok(()) => acknowledged += 1,
+                Err(e) => {
+                    warn!(%host_group_id, %e, "error replicating write to host group");
+                    missed.push(host_group_id.clone());
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if acknowledged < required {
+            return Err(last_error.expect("a host group failed"));
+        }
+
+        db.replication_queue.lock().push_all(missed, write);
+
+        Ok(())
+    }
+
+    /// Retries the writes in every database's replication queue, removing the
+    /// ones that are sent successfully. Writes for a host group that fails are
+    /// left in the queue, in order, to be retried on the next call.
+    pub async fn retry_replication(&self) {
+        for db_name in self.config.db_names_sorted() {
+            let db = match self.config.db(&db_name) {
+                Some(db) => db,
+                None => continue,
+            };
+
+            let pending = db.replication_queue.lock().pending();
+            let mut failed_groups = BTreeSet::new();
+
+            for queued in pending {
+                if failed_groups.contains(&queued.host_group_id) {
+                    continue;
+                }
+
+                match self
+                    .replicate_to_host_group(&queued.host_group_id, &db_name, &queued.write)
+                    .await
+                {
+                    Ok(()) => db.replication_queue.lock().remove(queued.id),
+                    Err(e) => {
+                        warn!(host_group_id = %queued.host_group_id, %e, "error retrying replication");
+                        failed_groups.insert(queued.host_group_id);
+                    }
+                }
+            }
+
+            let status = db.replication_queue.lock().status();
+            if status.queued_writes > 0 {
+                info!(
+                    db_name = %db_name,
+                    queued_writes = status.queued_writes,
+                    dropped_writes = status.dropped_writes,
+                    lag_ms = status.lag.as_millis() as u64,
+                    "replication queue"
+                );
+            }
+        }
+    }
+
+    /// Returns a stream of the writes in the database's WAL buffer that come
+    /// after the subscriber's last seen write from each writer, which then
+    /// continues with new writes as they arrive. If a matcher is passed only
+    /// the matching tables and rows of each write are returned.
+    pub fn subscribe(
+        &self,
+        db_name: &DatabaseName<'_>,
+        since: Vec<WriterSequence>,
+        matcher: Option<CompiledMatcher>,
+    ) -> Result<impl Stream<Item = Arc<ReplicatedWrite>> + Send + 'static> {
+        let db = self.config.db(db_name).context(DatabaseNotFound {
+            db_name: db_name.as_str(),
+        })?;
+
+        subscription::subscribe(db, since, matcher).context(NoWalBuffer {
+            db_name: db_name.as_str(),
+        })
+    }
+
+    /// Returns the depth and lag of the database's replication queue
+    pub fn replication_status(&self, name: &DatabaseName<'_>) -> Option<ReplicationQueueStatus> {
+        self.config
+            .db(name)
+            .map(|db| db.replication_queue.lock().status())
+    }
+
+    /// Returns the registry of the server's running jobs
+    pub fn jobs(&self) -> &JobRegistry {
+        &self.jobs
+    }
+
+    /// Drops the persisted chunk from the database and its catalog, so that
+    /// it isn't loaded again when the server restarts
+    pub async fn drop_persisted_chunk(
+        &self,
+        db_name: &DatabaseName<'_>,
+        partition_key: &str,
+        chunk_id: u32,
+    ) -> Result<()> {
+        let db = self.config.db(db_name).context(DatabaseNotFound {
+            db_name: db_name.as_str(),
+        })?;
+
+        let mut db_path = self.root_path()?;
+        db_path.push_dir(db_name.to_string());
+
+        db.drop_persisted_chunk(&self.store, &db_path, partition_key, chunk_id)
+            .await
+            .context(DroppingChunk {
+                db_name: db_name.as_str(),
+            })?;
+
+        Ok(())
+    }
+
+    /// Compacts the chunks of the database's partition into a single chunk.
+    /// If the chunks are persisted, the compacted chunk replaces them in the
+    /// database's catalog as well. Each compacted table completes one of the
+    /// tasks of `progress`.
+    pub async fn compact_chunks(
+        &self,
+        db_name: &DatabaseName<'_>,
+        partition_key: &str,
+        chunk_ids: &[u32],
+        progress: &JobProgress,
+    ) -> Result<Arc<DBChunk>> {
+        let db = self.config.db(db_name).context(DatabaseNotFound {
+            db_name: db_name.as_str(),
+        })?;
+
+        let mut db_path = self.root_path()?;
+        db_path.push_dir(db_name.to_string());
+
+        db.compact_chunks(
+            &self.executor,
+            &self.store,
+            &db_path,
+            partition_key,
+            chunk_ids,
+            progress,
+        )
+        .await
+        .context(CompactingChunks {
+            db_name: db_name.as_str(),
+        })
+    }
+
+    /// Rolls over the open chunk of the database's partition and writes the
+    /// closed chunk to Parquet files in object storage. The snapshot runs in
+    /// the background as a registered job; the returned `Snapshot` tracks its
+    /// progress.
+    pub async fn snapshot_partition(
+        &self,
+        db_name: &DatabaseName<'_>,
+        partition_key: &str,
+    ) -> Result<Arc<Snapshot<DBChunk>>> {
+        let db = self.config.db(db_name).context(DatabaseNotFound {
+            db_name: db_name.as_str(),
+        })?;
+
+        let mut db_path = self.root_path()?;
+        db_path.push_dir(db_name.to_string());
+
+        // the lock is held until the chunk is in the catalog, so that
+        // compactions of the partition see that it is persisted
+        let partition_lock = db.lock_partition(partition_key).await;
+
+        let wal_positions = db.wal_positions();
+
+        let chunk = db
+            .rollover_partition(partition_key)
+            .await
+            .map_err(|e| Box::new(e) as DatabaseError)
+            .context(UnknownDatabaseError)?;
+        let wal_positions = db
+            .snapshot_wal_positions(partition_key, chunk.id(), wal_positions)
+            .await;
+
+        let registration = self.jobs.register(TrackedJob::new(Job::SnapshotChunk {
+            db_name: db_name.to_string(),
+            partition_key: partition_key.to_string(),
+            chunk_id: chunk.id(),
+        }));
+
+        let (tx, rx) = oneshot::channel();
+        let snapshot = snapshot::snapshot_chunk(
+            &db_path,
+            Arc::clone(&self.store),
+            Arc::clone(&db.catalog),
+            partition_key,
+            chunk,
+            wal_positions,
+            Some(tx),
+            registration,
+        )
+        .context(SnapshotError)?;
+
+        // a failed snapshot drops the sender, which releases the lock as well
+        tokio::spawn(async move {
+            let _partition_lock = partition_lock;
+            let _ = rx.await;
+        });
+
+        Ok(snapshot)
+    }
+
+    /// Runs the server's periodic background tasks, such as retrying queued
+    /// replication, starting a lifecycle manager for each database and
+    /// garbage collection, until the returned future is dropped.
+    pub async fn background_worker(&self) {
+        let mut interval = tokio::time::interval(BACKGROUND_WORKER_INTERVAL);
+        let mut last_gc = std::time::Instant::now();
+
+        loop {
+            interval.tick().await;
+
+            // the writer id is needed for replication and persistence, so
+            // wait until it has been set
+            if self.require_id().is_err() {
+                continue;
+            }
+
+            self.retry_replication().await;
+            self.close_expired_segments();
+
+            // a database that is being deleted must not get a new manager,
+            // so the managers are started on the next tick instead
+            if let Ok(_changes) = self.database_changes.try_lock() {
+                if let Err(e) = self.start_lifecycle_managers() {
+                    error!("error starting database lifecycle managers: {}", e);
+                }
+            }
+
+            if let Some(gc_config) = &self.gc_config {
+                if last_gc.elapsed() >= gc_config.interval {
+                    self.collect_all_garbage(gc_config).await;
+                    last_gc = std::time::Instant::now();
+                }
+            }
+        }
+    }
+
+    /// Deletes the object storage files of the database that nothing refers
+    /// to anymore and that were last modified more than `grace_period` ago.
+    /// In a dry run the files that would be deleted are only reported.
+    pub async fn collect_garbage(
+        &self,
+        db_name: &DatabaseName<'_>,
+        grace_period: std::time::Duration,
+        dry_run: bool,
+    ) -> Result<GcReport> {
+        self.config.db(db_name).context(DatabaseNotFound {
+            db_name: db_name.as_str(),
+        })?;
+
+        let mut db_path = self.root_path()?;
+        db_path.push_dir(db_name.to_string());
+
+        gc::collect_garbage(&self.store, &db_path, Utc::now(), grace_period, dry_run)
+            .await
+            .context(GarbageCollectionError {
+                db_name: db_name.as_str(),
+            })
+    }
+
+    /// Garbage collects the files of every database, logging what was found
+    async fn collect_all_garbage(&self, gc_config: &GcConfig) {
+        for name in self.config.db_names_sorted() {
+            match self
+                .collect_garbage(&name, gc_config.grace_period, gc_config.dry_run)
+                .await
+            {
+                Ok(report) => info!(
+                    db_name = name.as_str(),
+                    deleted = report.deleted.len(),
+                    deleted_bytes = report.deleted_bytes,
+                    in_grace_period = report.in_grace_period,
+                    dry_run = gc_config.dry_run,
+                    "collected garbage"
+                ),
+                Err(e) => error!(db_name = name.as_str(), "error collecting garbage: {}", e),
+            }
         }
+    }
+
+    fn persist_segment(&self, db_name: &DatabaseName<'_>, segment: &Segment) -> Result<()> {
+        let writer_id = self.require_id()?;
+        let store = Arc::clone(&self.store);
+        segment
+            .persist_bytes_in_background(&self.jobs, writer_id, db_name, store)
+            .context(WalError)
+    }
 
-        for subscription in &db.rules.subscriptions {
-            match subscription.matcher.tables {
-                MatchTables::All => {
-                    self.replicate_to_host_group(&subscription.host_group_id, db_name, &write)
-                        .await?
+    /// Closes the open WAL segment of each database whose segment is older
+    /// than its `close_segment_after`, persisting the closed segments if the
+    /// database stores its segments
+    fn close_expired_segments(&self) {
+        let now = Utc::now();
+
+        for name in self.config.db_names_sorted() {
+            let wal_buffer = self.config.db(&name).and_then(|db| {
+                let mut wal_buffer = db.wal_buffer.as_ref()?.lock();
+                let segment = wal_buffer.close_segment_if_expired(now)?;
+                Some((segment, wal_buffer.persist))
+            });
+
+            if let Some((segment, true)) = wal_buffer {
+                if let Err(e) = self.persist_segment(&name, &segment) {
+                    error!(
+                        "error persisting expired WAL segment of database {}: {}",
+                        name, e
+                    );
                 }
-                MatchTables::Table(_) => unimplemented!(),
-                MatchTables::Regex(_) => unimplemented!(),
             }
         }
+    }
+
+    /// Spawns a lifecycle manager for each database that doesn't have one
+    /// yet. A manager stops by itself once its database is dropped, and
+    /// once its handle is replaced by that of a new database with the same
+    /// name.
+    fn start_lifecycle_managers(&self) -> Result<()> {
+        let root_path = self.root_path()?;
+        let mut managers = self.lifecycle_managers.lock();
+
+        for name in self.config.db_names_sorted() {
+            let db = match self.config.db(&name) {
+                Some(db) => db,
+                None => continue,
+            };
+
+            let is_managed = managers
+                .get(&name)
+                .and_then(LifecycleHandle::db)
+                .map_or(false, |managed| Arc::ptr_eq(&managed, &db));
+            if is_managed {
+                continue;
+            }
+
+            let local_wal = match self.local_wal(&name) {
+                Ok(local_wal) => local_wal,
+                Err(e) => {
+                    error!(db_name = name.as_str(), "error opening local wal: {}", e);
+                    continue;
+                }
+            };
+
+            let mut db_path = root_path.clone();
+            db_path.push_dir(name.to_string());
+            let manager = LifecycleManager::new(
+                &db,
+                Arc::clone(&self.store),
+                db_path,
+                local_wal,
+                self.jobs.clone(),
+            );
+            let handle = manager.spawn();
+
+            info!(db_name = name.as_str(), "started lifecycle manager");
+            managers.insert(name, handle);
+        }
 
         Ok(())
     }
 
-    // replicates to a single host in the group based on hashing rules. If that host
-    // is unavailable an error will be returned. The request may still succeed
-    // if enough of the other host groups have returned a success.
+    // replicates to the hosts in the group based on hashing rules. Each
+    // partition key in the write is consistently hashed to a host in the group,
+    // and the write is split up so that each host only receives the entries
+    // for the partitions it owns. If a host is unavailable the next host on the
+    // ring is tried. If no host in the group can be reached an error will be
+    // returned. The request may still succeed if enough of the other host
+    // groups have returned a success.
     async fn replicate_to_host_group(
         &self,
         host_group_id: &str,
         db_name: &DatabaseName<'_>,
         write: &ReplicatedWrite,
     ) -> Result<()> {
-        let group = self
+        let ring = self
             .config
             .host_group(host_group_id)
             .context(HostGroupNotFound { id: host_group_id })?;
 
-        // TODO: handle hashing rules to determine which host in the group should get
-        // the write.       for now, just write to the first one.
-        let host = group
-            .hosts
-            .get(0)
-            .context(NoHostInGroup { id: host_group_id })?;
+        ensure!(!ring.is_empty(), NoHostInGroup { id: host_group_id });
+
+        // group the entries by the host that owns their partition key, keeping
+        // the first key of each group to find the fallback hosts
+        let mut entries_by_host = BTreeMap::new();
+        if let Some(entries) = write.write_buffer_batch().and_then(|b| b.entries()) {
+            for entry in entries {
+                let key = entry.partition_key().unwrap_or("");
+                let host = ring.host_for_key(key).expect("ring is not empty");
+                entries_by_host
+                    .entry(host)
+                    .or_insert_with(|| (key, vec![]))
+                    .1
+                    .push(entry);
+            }
+        }
+
+        if entries_by_host.len() <= 1 {
+            // the whole write goes to one host, no need to split it up
+            let key = entries_by_host.values().next().map_or("", |(key, _)| *key);
+            return self.replicate_to_ring(&ring, key, db_name, write).await;
+        }
+
+        // the parts keep the writer and sequence of the write, so a host that
+        // receives several of them can only tell them apart by their partitions
+        let (writer, sequence) = write.writer_and_sequence();
+        for (_, (key, entries)) in entries_by_host {
+            let host_write = replicated_write_from_entries(writer, sequence, entries);
+            self.replicate_to_ring(&ring, key, db_name, &host_write)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    // sends the write to the host that owns the key on the ring, falling back
+    // to the following hosts on the ring if it can't be reached.
+    async fn replicate_to_ring(
+        &self,
+        ring: &HashRing,
+        key: &str,
+        db_name: &DatabaseName<'_>,
+        write: &ReplicatedWrite,
+    ) -> Result<()> {
+        let mut last_error = None;
+
+        for host in ring.hosts_for_key(key) {
+            match self.replicate_to_host(host, db_name, write).await {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    warn!(host, %e, "error replicating to host, trying next host in group");
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.expect("ring is not empty"))
+    }
 
+    async fn replicate_to_host(
+        &self,
+        host: &str,
+        db_name: &DatabaseName<'_>,
+        write: &ReplicatedWrite,
+    ) -> Result<()> {
         let connection = self
             .connection_manager
             .remote_server(host)
@@ -434,7 +1227,7 @@ impl<M: ConnectionManager> Server<M> {
     }
 
     pub async fn db_rules(&self, name: &DatabaseName<'_>) -> Option<DatabaseRules> {
-        self.config.db(name).map(|d| d.rules.clone())
+        self.config.db(name).map(|d| d.rules.read().clone())
     }
 }
 
@@ -510,25 +1303,54 @@ pub trait RemoteServer {
     ) -> Result<(), Self::Error>;
 }
 
-/// The connection manager maps a host identifier to a remote server.
-#[derive(Debug)]
-pub struct ConnectionManagerImpl {}
+/// The connection manager maps a host identifier to a remote server. One
+/// connection is kept per connect string and shared by all callers.
+#[derive(Debug, Default)]
+pub struct ConnectionManagerImpl {
+    remotes: RwLock<BTreeMap<String, Arc<RemoteServerImpl>>>,
+}
+
+impl ConnectionManagerImpl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
 
 #[async_trait]
 impl ConnectionManager for ConnectionManagerImpl {
     type Error = Error;
     type RemoteServer = RemoteServerImpl;
 
-    async fn remote_server(&self, _connect: &str) -> Result<Arc<Self::RemoteServer>, Self::Error> {
-        unimplemented!()
+    async fn remote_server(&self, connect: &str) -> Result<Arc<Self::RemoteServer>, Self::Error> {
+        let cached = self.remotes.read().get(connect).cloned();
+        if let Some(remote) = cached {
+            return Ok(remote);
+        }
+
+        let connection = influxdb_iox_client::connection::Builder::default()
+            .build(connect)
+            .await
+            .map_err(|e| Box::new(e) as DatabaseError)
+            .context(UnableToGetConnection { server: connect })?;
+
+        let remote = Arc::new(RemoteServerImpl {
+            write_client: influxdb_iox_client::write::Client::new(connection),
+        });
+
+        // Another task may have connected to the same server while this one
+        // was waiting, in which case the connection created first is kept.
+        let mut remotes = self.remotes.write();
+        let remote = remotes.entry(connect.to_string()).or_insert(remote);
+        Ok(Arc::clone(remote))
     }
 }
 
-/// An implementation for communicating with other IOx servers. This should
-/// be moved into and implemented in an influxdb_iox_client create at a later
-/// date.
+/// An implementation for communicating with other IOx servers using the
+/// gRPC write service through `influxdb_iox_client`.
 #[derive(Debug)]
-pub struct RemoteServerImpl {}
+pub struct RemoteServerImpl {
+    write_client: influxdb_iox_client::write::Client,
+}
 
 #[async_trait]
 impl RemoteServer for RemoteServerImpl {
@@ -536,15 +1358,34 @@ impl RemoteServer for RemoteServerImpl {
 
     async fn replicate(
         &self,
-        _db: &str,
-        _replicated_write: &ReplicatedWrite,
+        db: &str,
+        replicated_write: &ReplicatedWrite,
     ) -> Result<(), Self::Error> {
-        unimplemented!()
+        // The generated client requires `&mut self`, but clones share the
+        // same underlying channel, so this is cheap.
+        let mut client = self.write_client.clone();
+
+        client
+            .write_replicated(db, replicated_write.bytes().clone())
+            .await
+            .map_err(|e| Box::new(e) as DatabaseError)
+            .context(ErrorReplicating {})?;
+
+        Ok(())
     }
 }
 
-// get bytes from the location in object store
-async fn get_store_bytes(
+// rules loaded from object storage skip subscriptions that don't compile,
+// so rules with an invalid matcher are rejected before they are stored
+fn validate_subscriptions(rules: &DatabaseRules) -> Result<()> {
+    for (index, subscription) in rules.subscriptions.iter().enumerate() {
+        CompiledMatcher::new(&subscription.matcher).context(InvalidSubscription { index })?;
+    }
+    Ok(())
+}
+
+// get bytes from the location in object store
+async fn get_store_bytes(
     location: &object_store::path::Path,
     store: &ObjectStore,
 ) -> Result<bytes::BytesMut> {
@@ -563,20 +1404,33 @@ async fn get_store_bytes(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::buffer::Segment;
-    use arrow_deps::{assert_table_eq, datafusion::physical_plan::collect};
+    use arrow_deps::{
+        arrow::{
+            array::{Float64Array, Int64Array, StringArray},
+            datatypes::DataType,
+        },
+        assert_table_eq,
+        datafusion::physical_plan::collect,
+    };
     use async_trait::async_trait;
-    use data_types::database_rules::{
-        MatchTables, Matcher, PartitionTemplate, Subscription, TemplatePart, WalBufferConfig,
-        WalBufferRollover,
+    use data_types::{
+        chunk::ChunkStorage,
+        database_rules::{
+            MatchTables, Matcher, MutableBufferConfig, PartitionTemplate, ReplicationQueueOverflow,
+            Subscription, TemplatePart, WalBufferConfig, WalBufferRollover,
+        },
+        schema::builder::SchemaBuilder,
     };
-    use futures::TryStreamExt;
+    use futures::{StreamExt, TryStreamExt};
     use influxdb_line_protocol::parse_lines;
     use object_store::{memory::InMemory, path::ObjectStorePath};
     use parking_lot::Mutex;
-    use query::frontend::sql::SQLQueryPlanner;
+    use query::{frontend::sql::SQLQueryPlanner, Database};
     use snafu::Snafu;
-    use std::collections::BTreeMap;
+    use std::{
+        convert::TryFrom,
+        sync::{atomic::AtomicBool, Weak},
+    };
 
     type TestError = Box<dyn std::error::Error + Send + Sync + 'static>;
     type Result<T = (), E = TestError> = std::result::Result<T, E>;
@@ -585,7 +1439,7 @@ mod tests {
     async fn server_api_calls_return_error_with_no_id_set() -> Result {
         let manager = TestConnectionManager::new();
         let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
-        let mut server = Server::new(manager, store);
+        let server = Server::new(manager, store);
 
         let rules = DatabaseRules::new();
         let resp = server.create_database("foo", rules).await.unwrap_err();
@@ -693,6 +1547,170 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn update_database_rules() -> Result {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(TestConnectionManager::new(), Arc::clone(&store));
+        server.set_id(1);
+
+        let name = DatabaseName::new("bananas")?;
+        let mut rules = DatabaseRules {
+            partition_template: PartitionTemplate {
+                parts: vec![TemplatePart::TimeFormat("%Y-%m".to_string())],
+            },
+            mutable_buffer_config: Some(Default::default()),
+            ..Default::default()
+        };
+        server.create_database(name.as_str(), rules.clone()).await?;
+        server
+            .write_lines(&name, &parsed_lines("cpu bar=1 10"))
+            .await?;
+
+        // the new partition template applies to the following writes
+        rules.partition_template = PartitionTemplate {
+            parts: vec![TemplatePart::Table],
+        };
+        rules.replication_queue_max_size = 7;
+        server.update_database(&name, rules.clone()).await?;
+        server
+            .write_lines(&name, &parsed_lines("cpu bar=2 20"))
+            .await?;
+
+        let db = server.db(&name).await.unwrap();
+        assert_eq!(db.partition_keys()?, vec!["1970-01", "cpu"]);
+        assert_eq!(db.replication_queue.lock().status().max_size, 7);
+
+        // the mutable buffer can't be removed from a loaded database
+        let err = server
+            .update_database(
+                &name,
+                DatabaseRules {
+                    mutable_buffer_config: None,
+                    ..rules.clone()
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidRulesUpdate {
+                source: db::Error::MutableBufferChange {},
+                ..
+            }
+        ));
+
+        let err = server
+            .update_database(&DatabaseName::new("unknown")?, rules.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::DatabaseNotFound { .. }));
+
+        // the updated rules are loaded after a restart
+        let restarted = Server::new(TestConnectionManager::new(), store);
+        restarted.set_id(1);
+        restarted.load_database_configs().await?;
+        rules.name = name.to_string();
+        assert_eq!(restarted.db_rules(&name).await, Some(rules));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_database() -> Result {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(TestConnectionManager::new(), Arc::clone(&store));
+        server.set_id(1);
+
+        let name = DatabaseName::new("bananas")?;
+        server
+            .create_database(name.as_str(), DatabaseRules::new())
+            .await?;
+        server
+            .create_database("bananas2", DatabaseRules::new())
+            .await?;
+
+        // stands in for the database's snapshots and WAL segments
+        let mut data_path = store.new_path();
+        data_path.push_all_dirs(&["1", "bananas", "wal"]);
+        data_path.set_file_name("segment");
+        let data = Bytes::from("data");
+        let len = data.len();
+        store
+            .put(
+                &data_path,
+                futures::stream::once(async move { Ok(data) }),
+                Some(len),
+            )
+            .await?;
+
+        let list = |store: Arc<ObjectStore>| async move {
+            let paths: Vec<_> = store.list(None).await?.try_concat().await?;
+            let mut paths: Vec<_> = paths.iter().map(|p| p.display()).collect();
+            paths.sort();
+            Ok::<_, TestError>(paths)
+        };
+
+        server.delete_database(&name, false).await?;
+        assert!(server.db(&name).await.is_none());
+        assert_eq!(
+            list(Arc::clone(&store)).await?,
+            vec!["1/bananas/wal/segment", "1/bananas2/rules.json"]
+        );
+
+        let err = server.delete_database(&name, false).await.unwrap_err();
+        assert!(matches!(err, Error::DatabaseNotFound { .. }));
+
+        // a restarted server doesn't load the deleted database
+        let restarted = Server::new(TestConnectionManager::new(), Arc::clone(&store));
+        restarted.set_id(1);
+        restarted.load_database_configs().await?;
+        assert_eq!(restarted.db_names_sorted().await, vec!["bananas2"]);
+
+        server
+            .create_database(name.as_str(), DatabaseRules::new())
+            .await?;
+        server.delete_database(&name, true).await?;
+        assert_eq!(
+            list(Arc::clone(&store)).await?,
+            vec!["1/bananas2/rules.json"]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_database_ends_subscriptions() -> Result {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(TestConnectionManager::new(), Arc::clone(&store));
+        server.set_id(1);
+
+        let name = DatabaseName::new("bananas")?;
+        let rules = DatabaseRules {
+            wal_buffer_config: Some(WalBufferConfig {
+                buffer_size: 500,
+                segment_size: 100,
+                buffer_rollover: WalBufferRollover::ReturnError,
+                store_segments: false,
+                close_segment_after: None,
+            }),
+            ..Default::default()
+        };
+        server.create_database(name.as_str(), rules).await?;
+        server
+            .write_lines(&name, &parsed_lines("cpu bar=1 10"))
+            .await?;
+
+        let writes = server.subscribe(&name, vec![], None)?;
+        futures::pin_mut!(writes);
+        assert!(writes.next().await.is_some());
+
+        server.delete_database(&name, false).await?;
+        let end = tokio::time::timeout(std::time::Duration::from_secs(5), writes.next()).await;
+        assert!(matches!(end, Ok(None)));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn db_names_sorted() -> Result {
         let manager = TestConnectionManager::new();
@@ -776,6 +1794,66 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn writes_record_batch_local() -> Result {
+        let manager = TestConnectionManager::new();
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(manager, store);
+        server.set_id(1);
+        server.create_database("foo", DatabaseRules::new()).await?;
+
+        let schema = SchemaBuilder::new()
+            .tag("region")
+            .field("bar", DataType::Float64)
+            .timestamp()
+            .build()
+            .unwrap();
+        let columns: Vec<_> = schema
+            .iter()
+            .map(|(column_type, field)| (field.name().clone(), column_type.unwrap()))
+            .collect();
+        let batch = RecordBatch::try_new(
+            schema.into(),
+            vec![
+                Arc::new(StringArray::from(vec![Some("west"), None])),
+                Arc::new(Float64Array::from(vec![1.0, 2.0])),
+                Arc::new(Int64Array::from(vec![10, 20])),
+            ],
+        )
+        .unwrap();
+        server
+            .write_record_batch("foo", "cpu", &columns, &batch)
+            .await
+            .unwrap();
+
+        let db_name = DatabaseName::new("foo").unwrap();
+        let db = server.db(&db_name).await.unwrap();
+
+        let planner = SQLQueryPlanner::default();
+        let executor = server.executor();
+        let physical_plan = planner
+            .query(
+                db.as_ref(),
+                "select * from cpu order by time",
+                executor.as_ref(),
+            )
+            .await
+            .unwrap();
+
+        let batches = collect(physical_plan).await.unwrap();
+        let expected = vec![
+            "+-----+--------+------+",
+            "| bar | region | time |",
+            "+-----+--------+------+",
+            "| 1   | west   | 10   |",
+            "| 2   |        | 20   |",
+            "+-----+--------+------+",
+        ];
+        assert_table_eq!(expected, &batches);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn replicate_to_single_group() -> Result {
         let mut manager = TestConnectionManager::new();
@@ -787,7 +1865,7 @@ mod tests {
 
         let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
 
-        let mut server = Server::new(manager, store);
+        let server = Server::new(manager, store);
         server.set_id(1);
         let host_group_id = "az1".to_string();
         let rules = DatabaseRules {
@@ -846,7 +1924,7 @@ partition_key:
 
         let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
 
-        let mut server = Server::new(manager, store);
+        let server = Server::new(manager, store);
         server.set_id(1);
         let host_group_id = "az1".to_string();
         let rules = DatabaseRules {
@@ -901,107 +1979,1154 @@ partition_key:
     }
 
     #[tokio::test]
-    async fn segment_persisted_on_rollover() {
-        let manager = TestConnectionManager::new();
+    async fn sends_matching_rows_to_subscriber() -> Result {
+        let mut manager = TestConnectionManager::new();
+        let remote = Arc::new(TestRemoteServer::default());
+        let remote_id = "serverA";
+        manager
+            .remotes
+            .insert(remote_id.to_string(), Arc::clone(&remote));
+
         let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
 
-        let server = Server::new(manager, Arc::clone(&store));
+        let server = Server::new(manager, store);
         server.set_id(1);
-        let db_name = "my_db";
+        let host_group_id = "az1".to_string();
         let rules = DatabaseRules {
-            wal_buffer_config: Some(WalBufferConfig {
-                buffer_size: 500,
-                segment_size: 10,
-                buffer_rollover: WalBufferRollover::ReturnError,
-                store_segments: true,
-                close_segment_after: None,
-            }),
+            subscriptions: vec![Subscription {
+                name: "query_server_1".to_string(),
+                host_group_id: host_group_id.clone(),
+                matcher: Matcher {
+                    tables: MatchTables::Regex("^(cpu|disk)$".to_string()),
+                    predicate: Some("region = 'west' AND (user > 22 OR user < 0)".to_string()),
+                },
+            }],
             ..Default::default()
         };
+        server
+            .create_host_group(host_group_id.clone(), vec![remote_id.to_string()])
+            .await
+            .unwrap();
+        let db_name = "foo";
         server.create_database(db_name, rules).await.unwrap();
 
-        let lines = parsed_lines("disk,host=a used=10.1 12");
-        server.write_lines(db_name, &lines).await.unwrap();
+        let lines = parsed_lines(
+            "cpu,region=west user=23.2 10\n\
+             cpu,region=east user=21.0 10\n\
+             mem,region=west free=100i 10",
+        );
+        server.write_lines("foo", &lines).await.unwrap();
 
-        // write lines should have caused a segment rollover and persist, wait
-        tokio::task::yield_now().await;
+        // nothing matches, so nothing is sent
+        let lines = parsed_lines("mem,region=west free=99i 20");
+        server.write_lines("foo", &lines).await.unwrap();
 
-        let mut path = store.new_path();
-        path.push_all_dirs(&["1", "my_db", "wal", "000", "000"]);
-        path.set_file_name("001.segment");
+        let writes = remote.writes.lock().get(db_name).unwrap().clone();
+        assert_eq!(1, writes.len());
+
+        // the subscriber gets a new write with the original writer and sequence
+        assert_eq!((1, 1), writes[0].writer_and_sequence());
+        let write_text = writes[0].to_string();
+        let rows: Vec<_> = write_text.lines().skip(2).collect();
+        assert_eq!(
+            rows,
+            vec![
+                "partition_key:",
+                "  table:cpu",
+                "    region:west user:23.2 time:10"
+            ]
+        );
 
-        let data = store
-            .get(&path)
-            .await
-            .unwrap()
-            .map_ok(|b| bytes::BytesMut::from(&b[..]))
-            .try_concat()
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_subscription_matcher() -> Result {
+        let manager = TestConnectionManager::new();
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(manager, store);
+        server.set_id(1);
+
+        let subscription = |predicate: &str| Subscription {
+            name: "query_server_1".to_string(),
+            host_group_id: "az1".to_string(),
+            matcher: Matcher {
+                tables: MatchTables::All,
+                predicate: Some(predicate.to_string()),
+            },
+        };
+
+        let rules = DatabaseRules {
+            subscriptions: vec![subscription("region = 'west'"), subscription("region =")],
+            ..Default::default()
+        };
+        let err = server.create_database("foo", rules).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidSubscription { index: 1, .. }));
+        assert!(server
+            .db_rules(&DatabaseName::new("foo").unwrap())
             .await
-            .unwrap();
+            .is_none());
 
-        let segment = Segment::from_file_bytes(&data).unwrap();
-        assert_eq!(segment.writes.len(), 1);
-        let write = r#"
-writer:1, sequence:1, checksum:2741956553
-partition_key:
-  table:disk
-    host:a used:10.1 time:12
-"#;
-        assert_eq!(segment.writes[0].to_string(), write);
-    }
+        server.create_database("foo", DatabaseRules::new()).await?;
+        let rules = DatabaseRules {
+            subscriptions: vec![subscription("region LIKE 'w%'")],
+            ..Default::default()
+        };
+        let err = server
+            .update_database(&DatabaseName::new("foo").unwrap(), rules)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidSubscription { index: 0, .. }));
 
-    #[derive(Snafu, Debug, Clone)]
-    enum TestClusterError {
-        #[snafu(display("Test cluster error:  {}", message))]
-        General { message: String },
+        Ok(())
     }
 
-    #[derive(Debug)]
-    struct TestConnectionManager {
-        remotes: BTreeMap<String, Arc<TestRemoteServer>>,
-    }
+    #[tokio::test]
+    async fn replicate_splits_partitions_across_host_group() -> Result {
+        let hosts = vec!["serverA", "serverB", "serverC"];
 
-    impl TestConnectionManager {
-        fn new() -> Self {
-            Self {
-                remotes: BTreeMap::new(),
-            }
+        let mut manager = TestConnectionManager::new();
+        for host in &hosts {
+            manager
+                .remotes
+                .insert(host.to_string(), Arc::new(TestRemoteServer::default()));
         }
-    }
+        let remotes = manager.remotes.clone();
 
-    #[async_trait]
-    impl ConnectionManager for TestConnectionManager {
-        type Error = TestClusterError;
-        type RemoteServer = TestRemoteServer;
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(manager, store);
+        server.set_id(1);
 
-        async fn remote_server(&self, id: &str) -> Result<Arc<TestRemoteServer>, Self::Error> {
-            Ok(Arc::clone(&self.remotes.get(id).unwrap()))
+        let host_group_id = "az1".to_string();
+        let hosts: Vec<_> = hosts.into_iter().map(ToString::to_string).collect();
+        server
+            .create_host_group(host_group_id.clone(), hosts.clone())
+            .await
+            .unwrap();
+
+        let rules = DatabaseRules {
+            partition_template: PartitionTemplate {
+                parts: vec![TemplatePart::Table],
+            },
+            replication: vec![host_group_id.clone()],
+            replication_count: 1,
+            ..Default::default()
+        };
+        let db_name = "foo";
+        server.create_database(db_name, rules).await.unwrap();
+
+        let lines = parsed_lines("cpu bar=1 10\nmem bar=2 10\ndisk bar=3 10");
+        server.write_lines(db_name, &lines).await.unwrap();
+
+        // work out where each partition should have gone
+        let ring = HashRing::new(&HostGroup {
+            id: host_group_id,
+            hosts,
+        });
+        let mut expected: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for key in &["cpu", "disk", "mem"] {
+            let host = ring.host_for_key(key).unwrap();
+            expected.entry(host).or_default().push(key.to_string());
         }
-    }
 
-    #[derive(Debug, Default)]
-    struct TestRemoteServer {
-        writes: Mutex<BTreeMap<String, Vec<ReplicatedWrite>>>,
+        for (host, remote) in &remotes {
+            let writes = remote
+                .writes
+                .lock()
+                .get(db_name)
+                .cloned()
+                .unwrap_or_default();
+
+            match expected.get(host.as_str()) {
+                Some(keys) => {
+                    assert_eq!(writes.len(), 1, "host {}", host);
+                    assert_eq!(writes[0].writer_and_sequence(), (1, 1));
+                    assert_eq!(&partition_keys(&writes[0]), keys);
+                }
+                None => assert!(writes.is_empty(), "host {}", host),
+            }
+        }
+
+        Ok(())
     }
 
-    #[async_trait]
-    impl RemoteServer for TestRemoteServer {
-        type Error = TestClusterError;
+    #[tokio::test]
+    async fn servers_replicating_to_each_other_store_writes_once() -> Result {
+        let manager = LocalConnectionManager::default();
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
 
-        async fn replicate(
-            &self,
-            db: &str,
-            replicated_write: &ReplicatedWrite,
-        ) -> Result<(), Self::Error> {
-            let mut writes = self.writes.lock();
-            let entries = writes.entry(db.to_string()).or_insert_with(Vec::new);
-            entries.push(replicated_write.clone());
+        let server_a = Arc::new(Server::new(manager.clone(), Arc::clone(&store)));
+        server_a.set_id(1);
+        manager.add("serverA", &server_a);
+        let server_b = Arc::new(Server::new(manager.clone(), Arc::clone(&store)));
+        server_b.set_id(2);
+        manager.add("serverB", &server_b);
 
-            Ok(())
+        for (server, peer) in &[(&server_a, "serverB"), (&server_b, "serverA")] {
+            server
+                .create_host_group("peer".to_string(), vec![peer.to_string()])
+                .await?;
+            let rules = DatabaseRules {
+                replication: vec!["peer".to_string()],
+                replication_count: 1,
+                ..Default::default()
+            };
+            server.create_database("foo", rules).await?;
         }
-    }
 
-    fn parsed_lines(lp: &str) -> Vec<ParsedLine<'_>> {
+        server_a
+            .write_lines("foo", &parsed_lines("cpu bar=1 10"))
+            .await?;
+        server_b
+            .write_lines("foo", &parsed_lines("cpu bar=2 20"))
+            .await?;
+
+        // the replicated writes weren't sent back to the server they came from
+        let db_name = DatabaseName::new("foo").unwrap();
+        for server in &[&server_a, &server_b] {
+            let db = server.db(&db_name).await.unwrap();
+            let planner = SQLQueryPlanner::default();
+            let executor = server.executor();
+            let physical_plan = planner
+                .query(
+                    db.as_ref(),
+                    "select * from cpu order by time",
+                    executor.as_ref(),
+                )
+                .await?;
+
+            let batches = collect(physical_plan).await?;
+            let expected = vec![
+                "+-----+------+",
+                "| bar | time |",
+                "+-----+------+",
+                "| 1   | 10   |",
+                "| 2   | 20   |",
+                "+-----+------+",
+            ];
+            assert_table_eq!(expected, &batches);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replicate_falls_back_to_next_host() -> Result {
+        let hosts = vec!["serverA".to_string(), "serverB".to_string()];
+        let host_group_id = "az1".to_string();
+
+        // only register the host that does *not* own the partition, so the
+        // connection to the owner fails
+        let ring = HashRing::new(&HostGroup {
+            id: host_group_id.clone(),
+            hosts: hosts.clone(),
+        });
+        let owner = ring.host_for_key("cpu").unwrap();
+        let fallback = hosts.iter().find(|h| *h != owner).unwrap().clone();
+
+        let mut manager = TestConnectionManager::new();
+        let remote = Arc::new(TestRemoteServer::default());
+        manager.remotes.insert(fallback, Arc::clone(&remote));
+
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(manager, store);
+        server.set_id(1);
+        server
+            .create_host_group(host_group_id.clone(), hosts)
+            .await
+            .unwrap();
+
+        let rules = DatabaseRules {
+            partition_template: PartitionTemplate {
+                parts: vec![TemplatePart::Table],
+            },
+            replication: vec![host_group_id],
+            replication_count: 1,
+            ..Default::default()
+        };
+        let db_name = "foo";
+        server.create_database(db_name, rules).await.unwrap();
+
+        let lines = parsed_lines("cpu bar=1 10");
+        server.write_lines(db_name, &lines).await.unwrap();
+
+        let writes = remote.writes.lock().get(db_name).unwrap().clone();
+        assert_eq!(writes.len(), 1);
+        assert_eq!(partition_keys(&writes[0]), vec!["cpu"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replicate_errors_when_no_host_available() -> Result {
+        let manager = TestConnectionManager::new();
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(manager, store);
+        server.set_id(1);
+
+        let host_group_id = "az1".to_string();
+        server
+            .create_host_group(host_group_id.clone(), vec!["serverA".to_string()])
+            .await
+            .unwrap();
+
+        let rules = DatabaseRules {
+            replication: vec![host_group_id],
+            replication_count: 1,
+            ..Default::default()
+        };
+        server.create_database("foo", rules).await.unwrap();
+
+        let lines = parsed_lines("cpu bar=1 10");
+        let err = server.write_lines("foo", &lines).await.unwrap_err();
+        assert!(matches!(err, Error::UnableToGetConnection { .. }));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replication_count_met_queues_remaining_groups() -> Result {
+        let mut manager = TestConnectionManager::new();
+        let remote_a = Arc::new(TestRemoteServer::default());
+        let remote_b = Arc::new(TestRemoteServer::default());
+        manager
+            .remotes
+            .insert("serverA".to_string(), Arc::clone(&remote_a));
+        manager
+            .remotes
+            .insert("serverB".to_string(), Arc::clone(&remote_b));
+
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(manager, store);
+        server.set_id(1);
+        server
+            .create_host_group("az1".to_string(), vec!["serverA".to_string()])
+            .await?;
+        server
+            .create_host_group("az2".to_string(), vec!["serverB".to_string()])
+            .await?;
+
+        let rules = DatabaseRules {
+            replication: vec!["az1".to_string(), "az2".to_string()],
+            replication_count: 1,
+            replication_queue_max_size: 10,
+            ..Default::default()
+        };
+        let db_name = DatabaseName::new("foo").unwrap();
+        server.create_database("foo", rules).await?;
+
+        // the write returns once az1 has it, az2 gets it from the queue
+        let lines = parsed_lines("cpu bar=1 10");
+        server.write_lines("foo", &lines).await?;
+        assert_eq!(remote_a.write_count("foo"), 1);
+        assert_eq!(remote_b.write_count("foo"), 0);
+
+        let status = server.replication_status(&db_name).unwrap();
+        assert_eq!(status.queued_writes, 1);
+        assert_eq!(status.host_groups["az2"].queued_writes, 1);
+
+        server.retry_replication().await;
+        assert_eq!(remote_b.write_count("foo"), 1);
+        assert_eq!(
+            server.replication_status(&db_name).unwrap().queued_writes,
+            0
+        );
+
+        // writes stay queued while the host group is down
+        remote_b.set_unavailable(true);
+        server.write_lines("foo", &lines).await?;
+        server.retry_replication().await;
+        assert_eq!(remote_b.write_count("foo"), 1);
+        assert_eq!(
+            server.replication_status(&db_name).unwrap().queued_writes,
+            1
+        );
+
+        remote_b.set_unavailable(false);
+        server.retry_replication().await;
+        assert_eq!(remote_b.write_count("foo"), 2);
+        assert_eq!(
+            server.replication_status(&db_name).unwrap().queued_writes,
+            0
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replication_count_not_met_returns_error() -> Result {
+        let mut manager = TestConnectionManager::new();
+        let remote_a = Arc::new(TestRemoteServer::default());
+        let remote_b = Arc::new(TestRemoteServer::default());
+        remote_b.set_unavailable(true);
+        manager
+            .remotes
+            .insert("serverA".to_string(), Arc::clone(&remote_a));
+        manager
+            .remotes
+            .insert("serverB".to_string(), Arc::clone(&remote_b));
+
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(manager, store);
+        server.set_id(1);
+        server
+            .create_host_group("az1".to_string(), vec!["serverA".to_string()])
+            .await?;
+        server
+            .create_host_group("az2".to_string(), vec!["serverB".to_string()])
+            .await?;
+
+        let rules = DatabaseRules {
+            replication: vec!["az1".to_string(), "az2".to_string()],
+            replication_count: 2,
+            replication_queue_max_size: 10,
+            ..Default::default()
+        };
+        server.create_database("foo", rules).await?;
+
+        let lines = parsed_lines("cpu bar=1 10");
+        let err = server.write_lines("foo", &lines).await.unwrap_err();
+        assert!(matches!(err, Error::ErrorReplicating { .. }));
+
+        // a failed write isn't queued, the client is expected to retry it
+        let db_name = DatabaseName::new("foo").unwrap();
+        assert_eq!(
+            server.replication_status(&db_name).unwrap().queued_writes,
+            0
+        );
+
+        Ok(())
+    }
+
+    /// Returns a server that replicates the database "foo" to az1, which is
+    /// up, and az2, which is down, with a replication queue of one write
+    async fn server_with_replication_queue(
+        overflow: ReplicationQueueOverflow,
+    ) -> Result<(
+        Server<TestConnectionManager>,
+        Arc<TestRemoteServer>,
+        Arc<TestRemoteServer>,
+    )> {
+        let mut manager = TestConnectionManager::new();
+        let remote_a = Arc::new(TestRemoteServer::default());
+        let remote_b = Arc::new(TestRemoteServer::default());
+        remote_b.set_unavailable(true);
+        manager
+            .remotes
+            .insert("serverA".to_string(), Arc::clone(&remote_a));
+        manager
+            .remotes
+            .insert("serverB".to_string(), Arc::clone(&remote_b));
+
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(manager, store);
+        server.set_id(1);
+        server
+            .create_host_group("az1".to_string(), vec!["serverA".to_string()])
+            .await?;
+        server
+            .create_host_group("az2".to_string(), vec!["serverB".to_string()])
+            .await?;
+
+        let rules = DatabaseRules {
+            replication: vec!["az1".to_string(), "az2".to_string()],
+            replication_count: 1,
+            replication_queue_max_size: 1,
+            replication_queue_overflow: overflow,
+            ..Default::default()
+        };
+        server.create_database("foo", rules).await?;
+
+        Ok((server, remote_a, remote_b))
+    }
+
+    #[tokio::test]
+    async fn full_replication_queue_rejects_writes() -> Result {
+        let (server, remote_a, remote_b) =
+            server_with_replication_queue(ReplicationQueueOverflow::ReturnError).await?;
+        let db_name = DatabaseName::new("foo").unwrap();
+
+        server
+            .write_lines("foo", &parsed_lines("cpu bar=1 10"))
+            .await?;
+        let db = server.db(&db_name).await.unwrap();
+        let stored_size = db.mutable_buffer.as_ref().unwrap().size();
+
+        let err = server
+            .write_lines("foo", &parsed_lines("cpu bar=2 20"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::ReplicationQueueFull { max_size: 1, .. }
+        ));
+
+        let status = server.replication_status(&db_name).unwrap();
+        assert_eq!(status.queued_writes, 1);
+        assert_eq!(status.dropped_writes, 0);
+
+        // the rejected write was neither stored nor sent, so the client's
+        // retry doesn't duplicate it
+        assert_eq!(remote_a.write_count("foo"), 1);
+        assert_eq!(db.mutable_buffer.as_ref().unwrap().size(), stored_size);
+
+        // the queue takes writes again once it has drained
+        remote_b.set_unavailable(false);
+        server.retry_replication().await;
+        assert_eq!(remote_b.write_count("foo"), 1);
+        server
+            .write_lines("foo", &parsed_lines("cpu bar=2 20"))
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn full_replication_queue_drops_oldest_writes() -> Result {
+        let (server, _, remote_b) =
+            server_with_replication_queue(ReplicationQueueOverflow::DropOldest).await?;
+        let db_name = DatabaseName::new("foo").unwrap();
+
+        server
+            .write_lines("foo", &parsed_lines("cpu bar=1 10"))
+            .await?;
+        server
+            .write_lines("foo", &parsed_lines("cpu bar=2 20"))
+            .await?;
+
+        let status = server.replication_status(&db_name).unwrap();
+        assert_eq!(status.queued_writes, 1);
+        assert_eq!(status.dropped_writes, 1);
+
+        // only the newer write is left to send
+        remote_b.set_unavailable(false);
+        server.retry_replication().await;
+        assert_eq!(remote_b.write_count("foo"), 1);
+        assert_eq!(
+            server.replication_status(&db_name).unwrap().queued_writes,
+            0
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn segment_persisted_on_rollover() {
+        let manager = TestConnectionManager::new();
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+
+        let server = Server::new(manager, Arc::clone(&store));
+        server.set_id(1);
+        let db_name = "my_db";
+        let rules = DatabaseRules {
+            wal_buffer_config: Some(WalBufferConfig {
+                buffer_size: 500,
+                segment_size: 10,
+                buffer_rollover: WalBufferRollover::ReturnError,
+                store_segments: true,
+                close_segment_after: None,
+            }),
+            ..Default::default()
+        };
+        server.create_database(db_name, rules).await.unwrap();
+
+        let lines = parsed_lines("disk,host=a used=10.1 12");
+        server.write_lines(db_name, &lines).await.unwrap();
+
+        // write lines should have caused a segment rollover and persist, wait
+        tokio::task::yield_now().await;
+
+        let mut path = store.new_path();
+        path.push_all_dirs(&["1", "my_db", "wal", "000", "000"]);
+        path.set_file_name("001.segment");
+
+        let data = store
+            .get(&path)
+            .await
+            .unwrap()
+            .map_ok(|b| bytes::BytesMut::from(&b[..]))
+            .try_concat()
+            .await
+            .unwrap();
+
+        let segment = Segment::from_file_bytes(&data).unwrap();
+        assert_eq!(segment.writes.len(), 1);
+        let write = r#"
+writer:1, sequence:1, checksum:2741956553
+partition_key:
+  table:disk
+    host:a used:10.1 time:12
+"#;
+        assert_eq!(segment.writes[0].to_string(), write);
+    }
+
+    #[tokio::test]
+    async fn expired_segment_closed_and_persisted() -> Result {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(TestConnectionManager::new(), Arc::clone(&store));
+        server.set_id(1);
+        let rules = DatabaseRules {
+            wal_buffer_config: Some(WalBufferConfig {
+                buffer_size: 1 << 20,
+                segment_size: 1 << 20,
+                buffer_rollover: WalBufferRollover::ReturnError,
+                store_segments: true,
+                close_segment_after: Some(std::time::Duration::from_secs(0)),
+            }),
+            ..Default::default()
+        };
+        server.create_database("my_db", rules).await?;
+
+        // the segment is too small to be closed on size
+        server
+            .write_lines("my_db", &parsed_lines("disk,host=a used=10.1 12"))
+            .await?;
+        let mut wal_path = store.new_path();
+        wal_path.push_all_dirs(&["1", "my_db", "wal"]);
+        tokio::task::yield_now().await;
+        let segments: Vec<_> = store.list(Some(&wal_path)).await?.try_concat().await?;
+        assert!(segments.is_empty());
+
+        server.close_expired_segments();
+        tokio::task::yield_now().await;
+
+        let segments: Vec<_> = store.list(Some(&wal_path)).await?.try_concat().await?;
+        assert_eq!(segments.len(), 1);
+        let data = get_store_bytes(&segments[0], &store).await?;
+        let segment = Segment::from_file_bytes(&data)?;
+        assert_eq!(segment.id, 1);
+        assert_eq!(segment.writes.len(), 1);
+
+        // the new open segment has no writes, so it isn't closed
+        server.close_expired_segments();
+        tokio::task::yield_now().await;
+        let segments: Vec<_> = store.list(Some(&wal_path)).await?.try_concat().await?;
+        assert_eq!(segments.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rebuilds_database_on_restart() -> Result {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(TestConnectionManager::new(), Arc::clone(&store));
+        server.set_id(1);
+        let rules = DatabaseRules {
+            wal_buffer_config: Some(WalBufferConfig {
+                buffer_size: 500,
+                segment_size: 10,
+                buffer_rollover: WalBufferRollover::ReturnError,
+                store_segments: true,
+                close_segment_after: None,
+            }),
+            ..Default::default()
+        };
+        server.create_database("my_db", rules).await?;
+        let db_name = DatabaseName::new("my_db").unwrap();
+        let db = server.db(&db_name).await.unwrap();
+
+        server
+            .write_lines("my_db", &parsed_lines("cpu bar=1 10"))
+            .await?;
+        let partition_key = db.partition_keys()?.remove(0);
+        let snapshot = server.snapshot_partition(&db_name, &partition_key).await?;
+
+        // this write is only in the WAL segments
+        server
+            .write_lines("my_db", &parsed_lines("cpu bar=2 20"))
+            .await?;
+
+        let mut wal_path = store.new_path();
+        wal_path.push_all_dirs(&["1", "my_db", "wal"]);
+        for _ in 0..100 {
+            let segments: Vec<_> = store.list(Some(&wal_path)).await?.try_concat().await?;
+            if snapshot.finished() && segments.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+        assert!(snapshot.finished());
+
+        let restarted = Server::new(TestConnectionManager::new(), Arc::clone(&store));
+        restarted.set_id(1);
+        restarted.load_database_configs().await?;
+        let db = restarted.db(&db_name).await.unwrap();
+
+        let storage: Vec<_> = db
+            .chunk_summaries()?
+            .into_iter()
+            .map(|summary| summary.storage)
+            .collect();
+        assert_eq!(
+            storage,
+            vec![ChunkStorage::ObjectStore, ChunkStorage::OpenMutableBuffer]
+        );
+
+        let planner = SQLQueryPlanner::default();
+        let executor = restarted.executor();
+        let physical_plan = planner
+            .query(
+                db.as_ref(),
+                "select * from cpu order by time",
+                executor.as_ref(),
+            )
+            .await?;
+
+        let batches = collect(physical_plan).await?;
+        let expected = vec![
+            "+-----+------+",
+            "| bar | time |",
+            "+-----+------+",
+            "| 1   | 10   |",
+            "| 2   | 20   |",
+            "+-----+------+",
+        ];
+        assert_table_eq!(expected, &batches);
+
+        // the restarted server's writes continue after the persisted ones
+        restarted
+            .write_lines("my_db", &parsed_lines("cpu bar=3 30"))
+            .await?;
+        let buffer = db.wal_buffer.as_ref().unwrap().lock();
+        assert_eq!(buffer.writer_sequences().get(&1), Some(&3));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn dropped_persisted_chunk_stays_dropped_on_restart() -> Result {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(TestConnectionManager::new(), Arc::clone(&store));
+        server.set_id(1);
+        let rules = DatabaseRules {
+            wal_buffer_config: Some(WalBufferConfig {
+                buffer_size: 500,
+                segment_size: 10,
+                buffer_rollover: WalBufferRollover::ReturnError,
+                store_segments: true,
+                close_segment_after: None,
+            }),
+            ..Default::default()
+        };
+        server.create_database("my_db", rules).await?;
+        let db_name = DatabaseName::new("my_db").unwrap();
+        let db = server.db(&db_name).await.unwrap();
+
+        server
+            .write_lines("my_db", &parsed_lines("cpu bar=1 10"))
+            .await?;
+        let partition_key = db.partition_keys()?.remove(0);
+        let snapshot = server.snapshot_partition(&db_name, &partition_key).await?;
+
+        let mut wal_path = store.new_path();
+        wal_path.push_all_dirs(&["1", "my_db", "wal"]);
+        for _ in 0..100 {
+            let segments: Vec<_> = store.list(Some(&wal_path)).await?.try_concat().await?;
+            if snapshot.finished() && segments.len() == 1 {
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+        assert!(snapshot.finished());
+
+        let restarted = Server::new(TestConnectionManager::new(), Arc::clone(&store));
+        restarted.set_id(1);
+        restarted.load_database_configs().await?;
+        let db = restarted.db(&db_name).await.unwrap();
+        assert_eq!(db.persisted_chunks(&partition_key).len(), 1);
+
+        restarted
+            .drop_persisted_chunk(&db_name, &partition_key, snapshot.chunk_id())
+            .await?;
+        assert!(db.persisted_chunks(&partition_key).is_empty());
+
+        // neither the chunk nor its writes in the WAL segment come back
+        let restarted = Server::new(TestConnectionManager::new(), Arc::clone(&store));
+        restarted.set_id(1);
+        restarted.load_database_configs().await?;
+        let db = restarted.db(&db_name).await.unwrap();
+        assert!(db.chunk_summaries()?.is_empty());
+
+        let err = restarted
+            .drop_persisted_chunk(&db_name, &partition_key, snapshot.chunk_id())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DroppingChunk {
+                source: db::Error::UnknownPersistedChunk { .. },
+                ..
+            }
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn snapshot_keeps_earlier_chunks_unpersisted() -> Result {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(TestConnectionManager::new(), Arc::clone(&store));
+        server.set_id(1);
+        let rules = DatabaseRules {
+            wal_buffer_config: Some(WalBufferConfig {
+                buffer_size: 500,
+                segment_size: 10,
+                buffer_rollover: WalBufferRollover::ReturnError,
+                store_segments: true,
+                close_segment_after: None,
+            }),
+            ..Default::default()
+        };
+        server.create_database("my_db", rules).await?;
+        let db_name = DatabaseName::new("my_db").unwrap();
+        let db = server.db(&db_name).await.unwrap();
+
+        // two closed chunks, of which only the second is snapshotted
+        server
+            .write_lines("my_db", &parsed_lines("cpu bar=1 10"))
+            .await?;
+        let partition_key = db.partition_keys()?.remove(0);
+        db.rollover_partition(&partition_key).await?;
+        server
+            .write_lines("my_db", &parsed_lines("cpu bar=2 20"))
+            .await?;
+        let snapshot = server.snapshot_partition(&db_name, &partition_key).await?;
+        assert_eq!(snapshot.chunk_id(), 1);
+
+        let mut wal_path = store.new_path();
+        wal_path.push_all_dirs(&["1", "my_db", "wal"]);
+        for _ in 0..100 {
+            let segments: Vec<_> = store.list(Some(&wal_path)).await?.try_concat().await?;
+            if snapshot.finished() && segments.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        }
+        assert!(snapshot.finished());
+
+        // the snapshot doesn't mark the first chunk's writes as persisted
+        let catalog = db.catalog.state().await;
+        assert!(catalog.chunks[0].wal_positions.is_empty());
+
+        let restarted = Server::new(TestConnectionManager::new(), Arc::clone(&store));
+        restarted.set_id(1);
+        restarted.load_database_configs().await?;
+        let db = restarted.db(&db_name).await.unwrap();
+
+        let planner = SQLQueryPlanner::default();
+        let executor = restarted.executor();
+        let physical_plan = planner
+            .query(
+                db.as_ref(),
+                "select * from cpu where bar = 1",
+                executor.as_ref(),
+            )
+            .await?;
+
+        let batches = collect(physical_plan).await?;
+        let expected = vec![
+            "+-----+------+",
+            "| bar | time |",
+            "+-----+------+",
+            "| 1   | 10   |",
+            "+-----+------+",
+        ];
+        assert_table_eq!(expected, &batches);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replays_local_wal_on_restart() -> Result {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let dir = test_helpers::tmp_dir()?;
+        let server = Server::new(TestConnectionManager::new(), Arc::clone(&store))
+            .with_local_wal(dir.path());
+        server.set_id(1);
+        server
+            .create_database("my_db", DatabaseRules::new())
+            .await?;
+
+        // nothing is persisted to object storage besides the rules
+        server
+            .write_lines("my_db", &parsed_lines("cpu bar=1 10"))
+            .await?;
+        server
+            .write_lines("my_db", &parsed_lines("cpu bar=2 20"))
+            .await?;
+
+        let restarted = Server::new(TestConnectionManager::new(), Arc::clone(&store))
+            .with_local_wal(dir.path());
+        restarted.set_id(1);
+        restarted.load_database_configs().await?;
+        let db_name = DatabaseName::new("my_db").unwrap();
+        let db = restarted.db(&db_name).await.unwrap();
+
+        let planner = SQLQueryPlanner::default();
+        let executor = restarted.executor();
+        let physical_plan = planner
+            .query(
+                db.as_ref(),
+                "select * from cpu order by time",
+                executor.as_ref(),
+            )
+            .await?;
+
+        let batches = collect(physical_plan).await?;
+        let expected = vec![
+            "+-----+------+",
+            "| bar | time |",
+            "+-----+------+",
+            "| 1   | 10   |",
+            "| 2   | 20   |",
+            "+-----+------+",
+        ];
+        assert_table_eq!(expected, &batches);
+        assert_eq!(db.next_sequence(), 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejected_writes_are_not_replayed_on_restart() -> Result {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let dir = test_helpers::tmp_dir()?;
+        let server = Server::new(TestConnectionManager::new(), Arc::clone(&store))
+            .with_local_wal(dir.path());
+        server.set_id(1);
+        let rules = DatabaseRules {
+            mutable_buffer_config: Some(MutableBufferConfig {
+                buffer_size: 1,
+                reject_if_not_persisted: true,
+                persist_after_cold_seconds: None,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        server.create_database("my_db", rules).await?;
+
+        // the first write takes the buffer over size, so the second is rejected
+        server
+            .write_lines("my_db", &parsed_lines("cpu bar=1 10"))
+            .await?;
+        let err = server
+            .write_lines("my_db", &parsed_lines("cpu bar=2 20"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("over its limit"));
+
+        let restarted = Server::new(TestConnectionManager::new(), Arc::clone(&store))
+            .with_local_wal(dir.path());
+        restarted.set_id(1);
+        restarted.load_database_configs().await?;
+        let db_name = DatabaseName::new("my_db").unwrap();
+        let db = restarted.db(&db_name).await.unwrap();
+
+        let planner = SQLQueryPlanner::default();
+        let executor = restarted.executor();
+        let physical_plan = planner
+            .query(
+                db.as_ref(),
+                "select * from cpu order by time",
+                executor.as_ref(),
+            )
+            .await?;
+
+        let batches = collect(physical_plan).await?;
+        let expected = vec![
+            "+-----+------+",
+            "| bar | time |",
+            "+-----+------+",
+            "| 1   | 10   |",
+            "+-----+------+",
+        ];
+        assert_table_eq!(expected, &batches);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn starts_one_lifecycle_manager_per_db() -> Result {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let server = Server::new(TestConnectionManager::new(), store);
+        server.set_id(1);
+        server.create_database("foo", DatabaseRules::new()).await?;
+
+        server.start_lifecycle_managers()?;
+        let name = DatabaseName::new("foo").unwrap();
+        let managed = server.lifecycle_managers.lock()[&name].db().unwrap();
+        assert!(Arc::ptr_eq(&managed, &server.db(&name).await.unwrap()));
+
+        // the running manager isn't replaced
+        server.create_database("bar", DatabaseRules::new()).await?;
+        server.start_lifecycle_managers()?;
+        assert_eq!(server.lifecycle_managers.lock().len(), 2);
+        assert!(Arc::ptr_eq(
+            &server.lifecycle_managers.lock()[&name].db().unwrap(),
+            &managed
+        ));
+
+        // deleting the database stops its manager
+        server.delete_database(&name, false).await?;
+        assert!(!server.lifecycle_managers.lock().contains_key(&name));
+
+        Ok(())
+    }
+
+    #[derive(Snafu, Debug, Clone)]
+    enum TestClusterError {
+        #[snafu(display("Test cluster error:  {}", message))]
+        General { message: String },
+    }
+
+    #[derive(Debug)]
+    struct TestConnectionManager {
+        remotes: BTreeMap<String, Arc<TestRemoteServer>>,
+    }
+
+    impl TestConnectionManager {
+        fn new() -> Self {
+            Self {
+                remotes: BTreeMap::new(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ConnectionManager for TestConnectionManager {
+        type Error = TestClusterError;
+        type RemoteServer = TestRemoteServer;
+
+        async fn remote_server(&self, id: &str) -> Result<Arc<TestRemoteServer>, Self::Error> {
+            self.remotes
+                .get(id)
+                .cloned()
+                .ok_or_else(|| TestClusterError::General {
+                    message: format!("no remote server {}", id),
+                })
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct TestRemoteServer {
+        writes: Mutex<BTreeMap<String, Vec<ReplicatedWrite>>>,
+        unavailable: AtomicBool,
+    }
+
+    impl TestRemoteServer {
+        fn write_count(&self, db: &str) -> usize {
+            self.writes.lock().get(db).map_or(0, Vec::len)
+        }
+
+        fn set_unavailable(&self, unavailable: bool) {
+            self.unavailable.store(unavailable, Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl RemoteServer for TestRemoteServer {
+        type Error = TestClusterError;
+
+        async fn replicate(
+            &self,
+            db: &str,
+            replicated_write: &ReplicatedWrite,
+        ) -> Result<(), Self::Error> {
+            if self.unavailable.load(Ordering::SeqCst) {
+                return Err(TestClusterError::General {
+                    message: "remote server unavailable".to_string(),
+                });
+            }
+
+            let mut writes = self.writes.lock();
+            let entries = writes.entry(db.to_string()).or_insert_with(Vec::new);
+            entries.push(replicated_write.clone());
+
+            Ok(())
+        }
+    }
+
+    /// Connects servers in the same process. Replicated writes are decoded
+    /// and stored the way the write service of a remote server does.
+    #[derive(Debug, Default, Clone)]
+    struct LocalConnectionManager {
+        servers: Arc<Mutex<BTreeMap<String, Weak<Server<LocalConnectionManager>>>>>,
+    }
+
+    impl LocalConnectionManager {
+        fn add(&self, id: &str, server: &Arc<Server<Self>>) {
+            self.servers
+                .lock()
+                .insert(id.to_string(), Arc::downgrade(server));
+        }
+    }
+
+    #[async_trait]
+    impl ConnectionManager for LocalConnectionManager {
+        type Error = TestClusterError;
+        type RemoteServer = LocalRemoteServer;
+
+        async fn remote_server(&self, id: &str) -> Result<Arc<LocalRemoteServer>, Self::Error> {
+            let server = self
+                .servers
+                .lock()
+                .get(id)
+                .and_then(Weak::upgrade)
+                .ok_or_else(|| TestClusterError::General {
+                    message: format!("no remote server {}", id),
+                })?;
+            Ok(Arc::new(LocalRemoteServer { server }))
+        }
+    }
+
+    #[derive(Debug)]
+    struct LocalRemoteServer {
+        server: Arc<Server<LocalConnectionManager>>,
+    }
+
+    #[async_trait]
+    impl RemoteServer for LocalRemoteServer {
+        type Error = TestClusterError;
+
+        async fn replicate(
+            &self,
+            db: &str,
+            replicated_write: &ReplicatedWrite,
+        ) -> Result<(), Self::Error> {
+            let error = |message: String| TestClusterError::General { message };
+
+            let write = ReplicatedWrite::try_from(replicated_write.bytes().clone())
+                .map_err(|e| error(e.to_string()))?;
+            let db_name = DatabaseName::new(db).map_err(|e| error(e.to_string()))?;
+            let database = self
+                .server
+                .db(&db_name)
+                .await
+                .ok_or_else(|| error(format!("no database {}", db)))?;
+
+            self.server
+                .store_replicated_write(&db_name, &database, write)
+                .await
+                .map_err(|e| error(e.to_string()))?;
+
+            Ok(())
+        }
+    }
+
+    fn parsed_lines(lp: &str) -> Vec<ParsedLine<'_>> {
         parse_lines(lp).map(|l| l.unwrap()).collect()
     }
+
+    fn partition_keys(write: &ReplicatedWrite) -> Vec<String> {
+        write
+            .write_buffer_batch()
+            .and_then(|batch| batch.entries())
+            .map(|entries| {
+                entries
+                    .into_iter()
+                    .map(|e| e.partition_key().unwrap().to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
@@ -0,0 +1,499 @@
+//! This module contains the background task that manages the lifecycle of a
+//! database's chunks, as configured by its `MutableBufferConfig`. Once a
+//! partition has been cold for `persist_after_cold_seconds`, its open chunk is
+//! closed, moved into the read buffer and snapshotted to Parquet files in
+//! object storage, after which it is dropped from the mutable buffer. The
+//! mutable buffer is then kept under `buffer_size` by dropping partitions.
+//! If the database has a local WAL, the files holding the entries of the
+//! persisted writes are deleted from it. Each persisted partition and each
+//! snapshot is registered as a job with the server's job registry.
+
+use std::{
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
+
+use data_types::job::Job;
+use object_store::ObjectStore;
+use snafu::{OptionExt, ResultExt, Snafu};
+use tokio::sync::oneshot;
+use tracing::{error, info};
+
+use crate::{
+    db::{DBChunk, Db},
+    jobs::{JobProgress, JobRegistry, TrackedJob},
+    local_wal::{self, LocalWal},
+    snapshot,
+    tracker::TrackedFutureExt,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error closing open chunk of partition {}: {}", partition_key, source))]
+    ClosingChunk {
+        partition_key: String,
+        source: crate::db::Error,
+    },
+
+    #[snafu(display("Error moving chunk {} to the read buffer: {}", chunk_id, source))]
+    MovingChunk {
+        chunk_id: u32,
+        source: crate::db::Error,
+    },
+
+    #[snafu(display("Error starting snapshot of chunk {}: {}", chunk_id, source))]
+    StartingSnapshot {
+        chunk_id: u32,
+        source: snapshot::Error,
+    },
+
+    #[snafu(display("Snapshot of chunk {} in partition {} failed", chunk_id, partition_key))]
+    SnapshotFailed {
+        partition_key: String,
+        chunk_id: u32,
+    },
+
+    #[snafu(display("Error dropping persisted chunk {}: {}", chunk_id, source))]
+    DroppingChunk {
+        chunk_id: u32,
+        source: crate::db::Error,
+    },
+
+    #[snafu(display(
+        "Error truncating local WAL of partition {}: {}",
+        partition_key,
+        source
+    ))]
+    TruncatingLocalWal {
+        partition_key: String,
+        source: local_wal::Error,
+    },
+
+    #[snafu(display("Persisting partition {} was cancelled", partition_key))]
+    PersistCancelled { partition_key: String },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The handle of a running lifecycle manager
+#[derive(Debug)]
+pub(crate) struct LifecycleHandle {
+    db: Weak<Db>,
+    shutdown: oneshot::Sender<()>,
+    join: tokio::task::JoinHandle<()>,
+}
+
+impl LifecycleHandle {
+    /// Returns the managed database, unless it was dropped
+    pub(crate) fn db(&self) -> Option<Arc<Db>> {
+        self.db.upgrade()
+    }
+
+    /// Stops the manager once it has finished its current check for work and
+    /// waits for it to return
+    pub(crate) async fn stop(self) {
+        // the manager may already have returned
+        let _ = self.shutdown.send(());
+        if let Err(e) = self.join.await {
+            error!("lifecycle manager failed: {}", e);
+        }
+    }
+}
+
+/// How often the lifecycle manager checks its database for work
+const LIFECYCLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Manages the lifecycle of the chunks of a single database
+#[derive(Debug)]
+pub(crate) struct LifecycleManager {
+    db: Weak<Db>,
+    store: Arc<ObjectStore>,
+    db_path: object_store::path::Path,
+    local_wal: Option<Arc<LocalWal>>,
+    jobs: JobRegistry,
+}
+
+impl LifecycleManager {
+    /// Creates a manager for the database whose root path in object storage
+    /// is `db_path`. It holds a weak reference so that it stops once the
+    /// database is dropped.
+    pub(crate) fn new(
+        db: &Arc<Db>,
+        store: Arc<ObjectStore>,
+        db_path: object_store::path::Path,
+        local_wal: Option<Arc<LocalWal>>,
+        jobs: JobRegistry,
+    ) -> Self {
+        Self {
+            db: Arc::downgrade(db),
+            store,
+            db_path,
+            local_wal,
+            jobs,
+        }
+    }
+
+    /// Spawns a task that runs the manager, returning the handle that stops
+    /// it
+    pub(crate) fn spawn(self) -> LifecycleHandle {
+        let db = Weak::clone(&self.db);
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let join = tokio::task::spawn(self.run(shutdown_rx));
+
+        LifecycleHandle { db, shutdown, join }
+    }
+
+    /// Checks the database for work every `LIFECYCLE_INTERVAL` until the
+    /// database is dropped or `shutdown` fires
+    pub(crate) async fn run(self, mut shutdown: oneshot::Receiver<()>) {
+        let mut interval = tokio::time::interval(LIFECYCLE_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                // a dropped sender stops the manager too
+                _ = &mut shutdown => return,
+            }
+
+            let db = match self.db.upgrade() {
+                Some(db) => db,
+                None => return,
+            };
+
+            self.check_for_work(&db, Instant::now()).await;
+        }
+    }
+
+    /// Persists the partitions that have been cold since before `now` and
+    /// drops partitions if the mutable buffer is over size
+    pub(crate) async fn check_for_work(&self, db: &Db, now: Instant) {
+        let config = db.rules.read().mutable_buffer_config.clone();
+        let (mutable_buffer, config) = match (&db.mutable_buffer, config) {
+            (Some(mutable_buffer), Some(config)) => (mutable_buffer, config),
+            _ => return,
+        };
+
+        if let Some(cold_seconds) = config.persist_after_cold_seconds {
+            let cold_after = Duration::from_secs(cold_seconds.into());
+
+            for partition in mutable_buffer.partitions() {
+                let (partition_key, is_cold) = {
+                    let partition = partition.read().expect("mutex poisoned");
+                    let is_cold = !partition.is_empty()
+                        && now.saturating_duration_since(partition.last_write_at) >= cold_after;
+                    (partition.key().to_string(), is_cold)
+                };
+
+                if is_cold {
+                    let job = TrackedJob::new(Job::PersistPartition {
+                        db_name: db.rules.read().name.clone(),
+                        partition_key: partition_key.clone(),
+                    });
+                    let progress = Arc::clone(job.progress());
+
+                    let persisted = self
+                        .persist_partition(db, &partition_key, &progress)
+                        .track(&self.jobs, job)
+                        .await
+                        .unwrap_or_else(|_| {
+                            PersistCancelled {
+                                partition_key: &partition_key,
+                            }
+                            .fail()
+                        });
+                    if let Err(e) = persisted {
+                        error!(
+                            partition_key = partition_key.as_str(),
+                            "error persisting cold partition: {}", e
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = db.check_size_and_drop_partitions() {
+            error!("error dropping partitions from the mutable buffer: {}", e);
+        }
+    }
+
+    /// Closes the partition's open chunk, then moves each of its closed
+    /// chunks to the read buffer, snapshots them to object storage and drops
+    /// them from the mutable buffer. A chunk that fails to persist is left in
+    /// the mutable buffer to be retried, and the local WAL is only truncated
+    /// once all of them have been persisted. Each persisted chunk completes
+    /// one of the tasks of `progress`.
+    async fn persist_partition(
+        &self,
+        db: &Db,
+        partition_key: &str,
+        progress: &JobProgress,
+    ) -> Result<()> {
+        let mutable_buffer = match &db.mutable_buffer {
+            Some(mutable_buffer) => mutable_buffer,
+            None => return Ok(()),
+        };
+
+        // compactions of the partition wait until its chunks are in the
+        // catalog
+        let _partition_lock = db.lock_partition(partition_key).await;
+
+        // captured before the rollover so that the positions are covered by
+        // the snapshots of the closed chunks
+        let wal_positions = db.wal_positions();
+        let local_wal_position = match &self.local_wal {
+            Some(local_wal) => Some(local_wal.position().await),
+            None => None,
+        };
+
+        let partition = mutable_buffer
+            .partitions()
+            .into_iter()
+            .find(|p| p.read().expect("mutex poisoned").key() == partition_key);
+        let partition = match partition {
+            Some(partition) => partition,
+            None => return Ok(()),
+        };
+
+        let open_chunk_is_empty = partition
+            .read()
+            .expect("mutex poisoned")
+            .open_chunk_is_empty();
+        if !open_chunk_is_empty {
+            db.rollover_partition(partition_key)
+                .await
+                .context(ClosingChunk { partition_key })?;
+        }
+
+        let closed_chunks = partition.read().expect("mutex poisoned").closed_chunks();
+        progress.add_tasks(closed_chunks.len());
+        for chunk in closed_chunks {
+            let chunk_id = chunk.id();
+
+            // a chunk whose snapshot failed was already moved
+            if !db.read_buffer.chunk_ids(partition_key).contains(&chunk_id) {
+                db.load_chunk_to_read_buffer(partition_key, chunk_id)
+                    .await
+                    .context(MovingChunk { chunk_id })?;
+            }
+
+            // a chunk snapshotted on request is only dropped
+            let persisted = db.catalog.state().await.chunks.iter().any(|persisted| {
+                persisted.partition_key == partition_key && persisted.chunk_id == chunk_id
+            });
+            if persisted {
+                db.drop_mutable_buffer_chunk(partition_key, chunk_id)
+                    .await
+                    .context(DroppingChunk { chunk_id })?;
+                progress.complete_task();
+                continue;
+            }
+
+            let registration = self.jobs.register(TrackedJob::new(Job::SnapshotChunk {
+                db_name: db.rules.read().name.clone(),
+                partition_key: partition_key.to_string(),
+                chunk_id,
+            }));
+
+            // the positions are recorded by the last of the chunks to be
+            // persisted, as they cover the writes of all of them
+            let chunk_positions = db
+                .snapshot_wal_positions(partition_key, chunk_id, wal_positions.clone())
+                .await;
+
+            let (tx, rx) = oneshot::channel();
+            snapshot::snapshot_chunk(
+                &self.db_path,
+                Arc::clone(&self.store),
+                Arc::clone(&db.catalog),
+                partition_key,
+                DBChunk::new_mb(chunk),
+                chunk_positions,
+                Some(tx),
+                registration,
+            )
+            .context(StartingSnapshot { chunk_id })?;
+
+            // the snapshot only notifies once it has written everything
+            rx.await.ok().context(SnapshotFailed {
+                partition_key,
+                chunk_id,
+            })?;
+
+            db.drop_mutable_buffer_chunk(partition_key, chunk_id)
+                .await
+                .context(DroppingChunk { chunk_id })?;
+
+            info!(partition_key, chunk_id, "persisted cold chunk");
+            progress.complete_task();
+        }
+
+        if let (Some(local_wal), Some(position)) = (&self.local_wal, local_wal_position) {
+            local_wal
+                .partition_persisted(partition_key, position)
+                .context(TruncatingLocalWal { partition_key })?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recovery::rebuild_database;
+    use data_types::{
+        chunk::ChunkStorage,
+        data::lines_to_replicated_write,
+        database_rules::{DatabaseRules, MutableBufferConfig},
+    };
+    use influxdb_line_protocol::parse_lines;
+    use mutable_buffer::MutableBufferDb;
+    use object_store::{memory::InMemory, path::ObjectStorePath};
+    use query::{test::TestLPWriter, Database};
+
+    fn db(config: MutableBufferConfig) -> Arc<Db> {
+        let rules = DatabaseRules {
+            mutable_buffer_config: Some(config),
+            ..Default::default()
+        };
+
+        Arc::new(Db::new(
+            rules,
+            Some(MutableBufferDb::new("my_db")),
+            read_buffer::Database::new(),
+            None, // wal buffer
+        ))
+    }
+
+    fn manager(db: &Arc<Db>) -> (LifecycleManager, Arc<ObjectStore>) {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let mut db_path = store.new_path();
+        db_path.push_all_dirs(&["1", "my_db"]);
+
+        let manager =
+            LifecycleManager::new(db, Arc::clone(&store), db_path, None, Default::default());
+        (manager, store)
+    }
+
+    fn storage(db: &Db) -> Vec<(String, u32, ChunkStorage)> {
+        db.chunk_summaries()
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.partition_key, s.id, s.storage))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn persists_cold_partitions() {
+        let db = db(MutableBufferConfig {
+            persist_after_cold_seconds: Some(10),
+            ..Default::default()
+        });
+        let (manager, store) = manager(&db);
+
+        let mut writer = TestLPWriter::default();
+        writer
+            .write_lp_to_partition(db.as_ref(), "cpu bar=1 10", "p1")
+            .await;
+        let start = Instant::now();
+
+        // not cold yet
+        manager.check_for_work(&db, start).await;
+        assert_eq!(
+            storage(&db),
+            vec![("p1".into(), 0, ChunkStorage::OpenMutableBuffer)]
+        );
+
+        manager
+            .check_for_work(&db, start + Duration::from_secs(11))
+            .await;
+        assert_eq!(
+            storage(&db),
+            vec![
+                ("p1".into(), 0, ChunkStorage::ReadBuffer),
+                ("p1".into(), 1, ChunkStorage::OpenMutableBuffer),
+            ]
+        );
+
+        // the snapshot can be loaded by a restarted server
+        let restored = self::db(MutableBufferConfig::default());
+        let report = rebuild_database(&restored, 1, &manager.db_path, store, None)
+            .await
+            .unwrap();
+        assert_eq!(report.chunks_loaded, 1);
+        assert_eq!(restored.persisted_chunks("p1").len(), 1);
+
+        // an empty partition isn't persisted again
+        manager
+            .check_for_work(&db, start + Duration::from_secs(30))
+            .await;
+        assert_eq!(
+            storage(&db),
+            vec![
+                ("p1".into(), 0, ChunkStorage::ReadBuffer),
+                ("p1".into(), 1, ChunkStorage::OpenMutableBuffer),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn drops_only_persisted_partitions() {
+        let db = db(MutableBufferConfig::default());
+        let mut writer = TestLPWriter::default();
+        writer
+            .write_lp_to_partition(db.as_ref(), "cpu,adsf=jkl,foo=bar val=1 1", "p1")
+            .await;
+        writer
+            .write_lp_to_partition(db.as_ref(), "cpu,foo=bar val=1 1", "p2")
+            .await;
+        db.rollover_partition("p2").await.unwrap();
+        db.drop_mutable_buffer_chunk("p2", 0).await.unwrap();
+
+        // limit the buffer to less than what it holds
+        let size = db.mutable_buffer.as_ref().unwrap().size();
+        db.rules.write().mutable_buffer_config = Some(MutableBufferConfig {
+            buffer_size: size - 1,
+            reject_if_not_persisted: true,
+            persist_after_cold_seconds: None,
+            ..Default::default()
+        });
+        let (manager, _store) = manager(&db);
+
+        // only the partition without data is dropped, so the buffer stays
+        // over size and new writes are rejected
+        manager.check_for_work(&db, Instant::now()).await;
+        assert_eq!(db.partition_keys().unwrap(), vec!["p1"]);
+
+        let lines: Vec<_> = parse_lines("cpu,foo=bar val=2 2")
+            .map(|l| l.unwrap())
+            .collect();
+        let write = lines_to_replicated_write(1, 10, &lines, &db.rules.read());
+        let err = db.store_replicated_write(&write).await.unwrap_err();
+        assert!(matches!(
+            err,
+            crate::db::Error::MutableBufferFull { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn stops_when_db_dropped() {
+        let db = db(MutableBufferConfig::default());
+        let (manager, _store) = manager(&db);
+
+        drop(db);
+        // returns instead of checking the database forever
+        let (_shutdown, shutdown_rx) = oneshot::channel();
+        manager.run(shutdown_rx).await;
+    }
+
+    #[tokio::test]
+    async fn stops_when_shut_down() {
+        let db = db(MutableBufferConfig::default());
+        let (manager, _store) = manager(&db);
+
+        let handle = manager.spawn();
+        assert!(handle.db().is_some());
+        // returns while the database is still loaded
+        handle.stop().await;
+    }
+}
@@ -0,0 +1,316 @@
+//! This module connects the local-disk `wal` crate to the server, so that a
+//! standalone server without durable object storage doesn't lose its writes
+//! on a crash. Each database has its own WAL in a directory named after it.
+//! Writes are appended and synced to it before they are acknowledged, replayed
+//! into the mutable buffer on startup, and the WAL's files are deleted once
+//! the lifecycle manager has snapshotted the chunks holding their writes.
+
+use std::{cmp, collections::BTreeMap, convert::TryFrom, path::PathBuf, sync::Arc};
+
+use data_types::data::ReplicatedWrite;
+use parking_lot::Mutex;
+use snafu::{ResultExt, Snafu};
+use tokio::sync::{RwLock, RwLockReadGuard};
+use wal::{SequenceNumber, Wal, WalBuilder, WritePayload};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error creating local WAL directory {:?}: {}", path, source))]
+    CreatingDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Error opening local WAL in {:?}: {}", path, source))]
+    OpeningWal { path: PathBuf, source: wal::Error },
+
+    #[snafu(display("Error appending write to local WAL: {}", source))]
+    AppendingWrite { source: wal::Error },
+
+    #[snafu(display("Error reading local WAL entries: {}", source))]
+    ReadingEntries { source: wal::Error },
+
+    #[snafu(display("Invalid write in local WAL entry {}: {}", sequence, source))]
+    InvalidWrite {
+        sequence: SequenceNumber,
+        source: flatbuffers::InvalidFlatbuffer,
+    },
+
+    #[snafu(display("Error deleting persisted local WAL files: {}", source))]
+    DeletingFiles { source: wal::Error },
+
+    #[snafu(display("Error deleting local WAL directory {:?}: {}", path, source))]
+    DeletingDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Local WAL append task failed: {}", source))]
+    AppendTask { source: tokio::task::JoinError },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The local WALs of a server's databases, stored below a root directory
+#[derive(Debug)]
+pub(crate) struct LocalWals {
+    root: PathBuf,
+    file_rollover_size: u64,
+    wals: Mutex<BTreeMap<String, Arc<LocalWal>>>,
+}
+
+impl LocalWals {
+    pub(crate) fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            file_rollover_size: WalBuilder::DEFAULT_FILE_ROLLOVER_SIZE_BYTES,
+            wals: Default::default(),
+        }
+    }
+
+    /// Returns the database's WAL, opening it (and creating its directory)
+    /// the first time it is requested
+    pub(crate) fn open(&self, db_name: &str) -> Result<Arc<LocalWal>> {
+        let mut wals = self.wals.lock();
+        if let Some(local_wal) = wals.get(db_name) {
+            return Ok(Arc::clone(local_wal));
+        }
+
+        let path = self.root.join(db_name);
+        std::fs::create_dir_all(&path).context(CreatingDirectory { path: &path })?;
+
+        let builder = WalBuilder::new(&path).file_rollover_size(self.file_rollover_size);
+        let wal = builder.clone().wal().context(OpeningWal { path: &path })?;
+
+        let local_wal = Arc::new(LocalWal {
+            builder,
+            wal: Arc::new(Mutex::new(wal)),
+            appending: RwLock::new(()),
+            unpersisted: Default::default(),
+        });
+        wals.insert(db_name.to_string(), Arc::clone(&local_wal));
+
+        Ok(local_wal)
+    }
+
+    /// Closes the database's WAL so it is opened again the next time it is
+    /// requested, and deletes its directory if `delete_files` is set
+    pub(crate) fn remove(&self, db_name: &str, delete_files: bool) -> Result<()> {
+        self.wals.lock().remove(db_name);
+
+        if delete_files {
+            let path = self.root.join(db_name);
+            match std::fs::remove_dir_all(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e).context(DeletingDirectory { path: &path })
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The local-disk WAL of a single database
+#[derive(Debug)]
+pub(crate) struct LocalWal {
+    builder: WalBuilder,
+    wal: Arc<Mutex<Wal>>,
+    /// Held shared from appending a write until it has been stored in the
+    /// mutable buffer, and exclusively to read the WAL's position, so every
+    /// entry before a position has reached the mutable buffer
+    appending: RwLock<()>,
+    /// For each partition with writes that haven't been persisted yet, the
+    /// first and last of their entries
+    unpersisted: Mutex<BTreeMap<String, (SequenceNumber, SequenceNumber)>>,
+}
+
+impl LocalWal {
+    /// Appends the write to the WAL and syncs it to disk. The returned guard
+    /// must be held until the write has been stored in the mutable buffer.
+    pub(crate) async fn append(&self, write: &ReplicatedWrite) -> Result<RwLockReadGuard<'_, ()>> {
+        let guard = self.appending.read().await;
+
+        let wal = Arc::clone(&self.wal);
+        let data = write.bytes().clone();
+        let sequence = tokio::task::spawn_blocking(move || -> wal::Result<SequenceNumber> {
+            let mut wal = wal.lock();
+            let sequence = wal.append(WritePayload::new(data)?)?;
+            wal.sync_all()?;
+            Ok(sequence)
+        })
+        .await
+        .context(AppendTask)?
+        .context(AppendingWrite)?;
+
+        self.track(sequence, write);
+        Ok(guard)
+    }
+
+    /// Returns the entries in the WAL with the writes they hold, oldest first
+    pub(crate) fn entries(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<(SequenceNumber, ReplicatedWrite)>>> {
+        let entries = self.builder.clone().entries().context(ReadingEntries)?;
+
+        Ok(entries.map(|entry| {
+            let entry = entry.context(ReadingEntries)?;
+            let sequence = entry.sequence_number();
+            let write =
+                ReplicatedWrite::try_from(entry.into_data()).context(InvalidWrite { sequence })?;
+            Ok((sequence, write))
+        }))
+    }
+
+    /// Records that the entry holds data of the write's partitions that
+    /// hasn't been persisted. Called for each appended or replayed write.
+    pub(crate) fn track(&self, sequence: SequenceNumber, write: &ReplicatedWrite) {
+        let mut unpersisted = self.unpersisted.lock();
+
+        if let Some(entries) = write.write_buffer_batch().and_then(|b| b.entries()) {
+            for entry in entries {
+                let key = entry.partition_key().unwrap_or("").to_string();
+                let (first, last) = unpersisted.entry(key).or_insert((sequence, sequence));
+                *first = cmp::min(*first, sequence);
+                *last = cmp::max(*last, sequence);
+            }
+        }
+    }
+
+    /// Returns the sequence number of the next entry. All entries before it
+    /// have been stored in the mutable buffer.
+    pub(crate) async fn position(&self) -> SequenceNumber {
+        let _guard = self.appending.write().await;
+        self.wal.lock().next_sequence_number()
+    }
+
+    /// Records that the partition's writes in the entries before `position`
+    /// have been persisted, and deletes the WAL files that only hold entries
+    /// whose writes have all been persisted
+    pub(crate) fn partition_persisted(
+        &self,
+        partition_key: &str,
+        position: SequenceNumber,
+    ) -> Result<()> {
+        let first_needed = {
+            let mut unpersisted = self.unpersisted.lock();
+
+            if let Some((first, last)) = unpersisted.get_mut(partition_key) {
+                if *last < position {
+                    unpersisted.remove(partition_key);
+                } else {
+                    *first = cmp::max(*first, position);
+                }
+            }
+
+            unpersisted.values().map(|(first, _)| *first).min()
+        };
+
+        let wal = self.wal.lock();
+        let first_needed = first_needed.unwrap_or_else(|| wal.next_sequence_number());
+        wal.delete_up_to_entry(first_needed).context(DeletingFiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_types::{
+        data::lines_to_replicated_write,
+        database_rules::{DatabaseRules, PartitionTemplate, TemplatePart},
+    };
+    use influxdb_line_protocol::parse_lines;
+
+    type TestError = Box<dyn std::error::Error + Send + Sync + 'static>;
+    type Result<T = (), E = TestError> = std::result::Result<T, E>;
+
+    fn write(sequence: u64, lp: &str) -> ReplicatedWrite {
+        let rules = DatabaseRules {
+            partition_template: PartitionTemplate {
+                parts: vec![TemplatePart::Table],
+            },
+            ..Default::default()
+        };
+        let lines: Vec<_> = parse_lines(lp).map(|l| l.unwrap()).collect();
+        lines_to_replicated_write(1, sequence, &lines, &rules)
+    }
+
+    fn file_count(dir: &std::path::Path) -> usize {
+        std::fs::read_dir(dir.join("my_db")).unwrap().count()
+    }
+
+    #[tokio::test]
+    async fn appends_and_reopens() -> Result {
+        let dir = test_helpers::tmp_dir()?;
+        let w1 = write(1, "cpu bar=1 10");
+        let w2 = write(2, "mem foo=1 10");
+
+        {
+            let local_wal = LocalWals::new(dir.path()).open("my_db")?;
+            local_wal.append(&w1).await?;
+            local_wal.append(&w2).await?;
+            assert_eq!(local_wal.position().await, 2);
+        }
+
+        // a restarted server reads the synced entries back
+        let local_wal = LocalWals::new(dir.path()).open("my_db")?;
+        let entries = local_wal.entries()?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries, vec![(0, w1), (1, w2)]);
+        assert_eq!(local_wal.position().await, 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn removes_wal() -> Result {
+        let dir = test_helpers::tmp_dir()?;
+        let local_wals = LocalWals::new(dir.path());
+
+        local_wals
+            .open("my_db")?
+            .append(&write(1, "cpu bar=1 10"))
+            .await?;
+        local_wals.remove("my_db", false)?;
+        assert_eq!(local_wals.open("my_db")?.entries()?.count(), 1);
+
+        local_wals.remove("my_db", true)?;
+        assert!(!dir.path().join("my_db").exists());
+        // removing a WAL without files is fine
+        local_wals.remove("my_db", true)?;
+        assert_eq!(local_wals.open("my_db")?.entries()?.count(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn deletes_files_once_persisted() -> Result {
+        let dir = test_helpers::tmp_dir()?;
+        let mut local_wals = LocalWals::new(dir.path());
+        // every entry is written to a new file
+        local_wals.file_rollover_size = 1;
+        let local_wal = local_wals.open("my_db")?;
+
+        local_wal.append(&write(1, "cpu bar=1 10")).await?;
+        local_wal.append(&write(2, "mem foo=1 10")).await?;
+        let position = local_wal.position().await;
+        local_wal.append(&write(3, "cpu bar=2 20")).await?;
+        assert_eq!(file_count(dir.path()), 3);
+
+        // the entry of the mem write is still needed
+        local_wal.partition_persisted("cpu", position)?;
+        assert_eq!(file_count(dir.path()), 3);
+
+        // only the last cpu write is still needed, but the WAL conservatively
+        // keeps the file just before the one holding it
+        local_wal.partition_persisted("mem", position)?;
+        assert_eq!(file_count(dir.path()), 2);
+
+        local_wal.partition_persisted("cpu", local_wal.position().await)?;
+        assert_eq!(file_count(dir.path()), 1);
+        let entries = local_wal.entries()?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,579 @@
+//! This module contains the compiled form of a subscription `Matcher`, which
+//! selects the tables and rows of a replicated write that should be sent to a
+//! subscriber.
+//!
+//! A matcher's predicate is a SQL boolean expression, for example:
+//!
+//! ```text
+//! host = 'server01' AND (usage_user > 90.5 OR time >= 1600000000000000000)
+//! ```
+//!
+//! It is turned into a DataFusion `Expr` by DataFusion's SQL planner and kept
+//! in a `Predicate` together with the matcher's table, if it names one.
+//! Comparisons between a column and a literal value can be combined with `AND`,
+//! `OR`, `NOT` and parentheses, and `IS NULL` and `IS NOT NULL` test whether a
+//! row has a column. As with SQL's `NULL`, comparing a column the row doesn't
+//! have, or a value of a different type, is neither true nor false, and a row
+//! is only sent if the predicate is true for it.
+
+use std::{borrow::Cow, cmp::Ordering, collections::BTreeSet, sync::Arc};
+
+use arrow_deps::{
+    arrow::datatypes::{DataType, Field, Schema},
+    datafusion::{
+        datasource::MemTable,
+        error::DataFusionError,
+        execution::context::ExecutionContext,
+        logical_plan::{Expr, LogicalPlan, Operator},
+        scalar::ScalarValue,
+    },
+};
+use data_types::{
+    data::{filter_replicated_write, ReplicatedWrite},
+    database_rules::{MatchTables, Matcher},
+};
+use generated_types::wal as wb;
+use query::predicate::{Predicate, PredicateBuilder};
+use regex::Regex;
+use snafu::{ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Invalid table regex '{}': {}", regex, source))]
+    InvalidRegex { regex: String, source: regex::Error },
+
+    #[snafu(display("Invalid predicate '{}': {}", predicate, source))]
+    InvalidPredicate {
+        predicate: String,
+        source: DataFusionError,
+    },
+
+    #[snafu(display("Unsupported predicate '{}': {}", predicate, description))]
+    UnsupportedPredicate {
+        predicate: String,
+        description: String,
+    },
+}
+
+impl Error {
+    /// The field of the `Matcher` the error is about
+    pub fn field(&self) -> &'static str {
+        match self {
+            Self::InvalidRegex { .. } => "table_matcher.regex",
+            Self::InvalidPredicate { .. } | Self::UnsupportedPredicate { .. } => "predicate",
+        }
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A `Matcher` that has been validated and compiled so that it can be
+/// evaluated against the tables and rows of replicated writes.
+#[derive(Debug, Clone)]
+pub struct CompiledMatcher {
+    /// The matched table, if the matcher names one, and the row predicate
+    predicate: Predicate,
+    /// The regex table names must match, if any
+    table_regex: Option<Regex>,
+}
+
+impl CompiledMatcher {
+    pub fn new(matcher: &Matcher) -> Result<Self> {
+        let mut builder = PredicateBuilder::new();
+        let mut table_regex = None;
+
+        match &matcher.tables {
+            MatchTables::All => {}
+            MatchTables::Table(table) => builder = builder.table(table),
+            MatchTables::Regex(regex) => {
+                table_regex = Some(Regex::new(regex).context(InvalidRegex { regex })?)
+            }
+        }
+
+        if let Some(predicate) = &matcher.predicate {
+            builder = builder.add_expr(parse_predicate(predicate)?);
+        }
+
+        Ok(Self {
+            predicate: builder.build(),
+            table_regex,
+        })
+    }
+
+    /// Returns true if rows from the table should be sent to the subscriber
+    pub fn matches_table(&self, table_name: &str) -> bool {
+        self.predicate.should_include_table(table_name)
+            && self
+                .table_regex
+                .as_ref()
+                .map_or(true, |regex| regex.is_match(table_name))
+    }
+
+    /// Returns true if the matcher's predicate is true for the row
+    pub fn matches_row(&self, row: &wb::Row<'_>) -> bool {
+        self.predicate
+            .exprs
+            .iter()
+            .all(|expr| evaluate(expr, row) == Some(true))
+    }
+
+    /// Returns the part of the write that should be sent to the subscriber,
+    /// which keeps the writer id and sequence number of the original write.
+    /// The write itself is returned if the matcher matches everything and
+    /// `None` is returned if nothing in the write matches.
+    pub fn filter<'a>(&self, write: &'a ReplicatedWrite) -> Option<Cow<'a, ReplicatedWrite>> {
+        let matches_all = self.predicate.table_names.is_none()
+            && self.table_regex.is_none()
+            && !self.predicate.has_exprs();
+        if matches_all {
+            return Some(Cow::Borrowed(write));
+        }
+
+        filter_replicated_write(
+            write,
+            |table_name| self.matches_table(table_name),
+            |row| self.matches_row(row),
+        )
+        .map(Cow::Owned)
+    }
+}
+
+/// The table the predicate is planned against
+const PREDICATE_TABLE: &str = "predicate";
+
+/// Parses a predicate in the syntax described in the module docs
+pub fn parse_predicate(predicate: &str) -> Result<Expr> {
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table(
+        PREDICATE_TABLE,
+        Arc::new(predicate_table(predicate).context(InvalidPredicate { predicate })?),
+    );
+
+    // planning a whole query makes sure the predicate is a single expression:
+    // anything following it, such as a GROUP BY or LIMIT, adds to the plan
+    let query = format!("SELECT * FROM {} WHERE {}", PREDICATE_TABLE, predicate);
+    let plan = ctx
+        .create_logical_plan(&query)
+        .context(InvalidPredicate { predicate })?;
+
+    let expr = match &plan {
+        LogicalPlan::Projection { input, .. } => match input.as_ref() {
+            LogicalPlan::Filter {
+                predicate: expr,
+                input,
+            } => match input.as_ref() {
+                LogicalPlan::TableScan { .. } => Some(expr.clone()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let unsupported = |description: String| Error::UnsupportedPredicate {
+        predicate: predicate.to_string(),
+        description,
+    };
+
+    let expr = expr.ok_or_else(|| unsupported("expected a single expression".to_string()))?;
+    check_expr(&expr).map_err(unsupported)?;
+
+    Ok(expr)
+}
+
+/// Returns an empty table with a column for every word of the predicate that
+/// could name one, as the planner rejects columns its table doesn't have.
+/// Columns the predicate doesn't use, such as those for keywords, don't
+/// change the plan.
+fn predicate_table(predicate: &str) -> Result<MemTable, DataFusionError> {
+    let is_identifier_part = |c: char| c.is_alphanumeric() || "_@$#".contains(c);
+
+    let mut names = BTreeSet::new();
+    let mut chars = predicate.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // string literal, in which an escaped quote just ends and starts
+            // another literal
+            '\'' => while !matches!(chars.next(), Some('\'') | None) {},
+            '"' => {
+                names.insert(chars.by_ref().take_while(|&c| c != '"').collect::<String>());
+            }
+            c if c.is_numeric() => {
+                while matches!(chars.peek(), Some(&c) if c.is_numeric() || c == '.') {
+                    chars.next();
+                }
+            }
+            c if is_identifier_part(c) => {
+                let mut name = c.to_string();
+                while let Some(&c) = chars.peek().filter(|&&c| is_identifier_part(c)) {
+                    name.push(c);
+                    chars.next();
+                }
+                names.insert(name);
+            }
+            _ => {}
+        }
+    }
+
+    let fields = names
+        .into_iter()
+        .map(|name| Field::new(&name, DataType::Utf8, true))
+        .collect();
+
+    MemTable::try_new(Arc::new(Schema::new(fields)), vec![])
+}
+
+/// Checks that the planned expression only uses the parts of SQL described
+/// in the module docs, returning a description of the first part that doesn't
+fn check_expr(expr: &Expr) -> Result<(), String> {
+    match expr {
+        Expr::Column(_) => Ok(()),
+        Expr::Literal(ScalarValue::Int64(Some(_)))
+        | Expr::Literal(ScalarValue::Float64(Some(_)))
+        | Expr::Literal(ScalarValue::Utf8(Some(_)))
+        | Expr::Literal(ScalarValue::Boolean(Some(_))) => Ok(()),
+        Expr::Not(expr) => check_expr(expr),
+        Expr::IsNull(column) | Expr::IsNotNull(column) => match column.as_ref() {
+            Expr::Column(_) => Ok(()),
+            _ => Err(format!("'{:?}' isn't a column", column)),
+        },
+        Expr::BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        }
+        | Expr::BinaryExpr {
+            left,
+            op: Operator::Or,
+            right,
+        } => {
+            check_expr(left)?;
+            check_expr(right)
+        }
+        Expr::BinaryExpr { left, op, right } => {
+            match op {
+                Operator::Eq
+                | Operator::NotEq
+                | Operator::Lt
+                | Operator::LtEq
+                | Operator::Gt
+                | Operator::GtEq => {}
+                _ => return Err(format!("unsupported operator '{:?}'", op)),
+            }
+
+            match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(_), Expr::Literal(_)) | (Expr::Literal(_), Expr::Column(_)) => {}
+                _ => {
+                    return Err(format!(
+                        "'{:?}' doesn't compare a column with a value",
+                        expr
+                    ))
+                }
+            }
+
+            check_expr(left)?;
+            check_expr(right)
+        }
+        _ => Err(format!("unsupported expression '{:?}'", expr)),
+    }
+}
+
+/// Evaluates the expression for the row, returning `None` if its value is
+/// unknown
+fn evaluate(expr: &Expr, row: &wb::Row<'_>) -> Option<bool> {
+    match expr {
+        Expr::BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        } => match (evaluate(left, row), evaluate(right, row)) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        },
+        Expr::BinaryExpr {
+            left,
+            op: Operator::Or,
+            right,
+        } => match (evaluate(left, row), evaluate(right, row)) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        },
+        Expr::BinaryExpr { left, op, right } => {
+            let (column, op, literal) = match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(column), Expr::Literal(literal)) => (column, *op, literal),
+                (Expr::Literal(literal), Expr::Column(column)) => (column, reverse(*op), literal),
+                _ => return None,
+            };
+
+            let ordering = compare(&column_value(row, column)?, literal)?;
+            Some(match op {
+                Operator::Eq => ordering == Ordering::Equal,
+                Operator::NotEq => ordering != Ordering::Equal,
+                Operator::Lt => ordering == Ordering::Less,
+                Operator::LtEq => ordering != Ordering::Greater,
+                Operator::Gt => ordering == Ordering::Greater,
+                Operator::GtEq => ordering != Ordering::Less,
+                _ => return None,
+            })
+        }
+        Expr::Not(expr) => evaluate(expr, row).map(|b| !b),
+        Expr::IsNull(expr) => match expr.as_ref() {
+            Expr::Column(column) => Some(column_value(row, column).is_none()),
+            _ => None,
+        },
+        Expr::IsNotNull(expr) => match expr.as_ref() {
+            Expr::Column(column) => Some(column_value(row, column).is_some()),
+            _ => None,
+        },
+        Expr::Column(column) => {
+            let value = column_value(row, column)?;
+            match value.value_type() {
+                wb::ColumnValue::BoolValue => Some(value.value_as_bool_value()?.value()),
+                _ => None,
+            }
+        }
+        Expr::Literal(ScalarValue::Boolean(b)) => *b,
+        _ => None,
+    }
+}
+
+/// The operator that gives the same result with its operands swapped
+fn reverse(op: Operator) -> Operator {
+    match op {
+        Operator::Lt => Operator::Gt,
+        Operator::LtEq => Operator::GtEq,
+        Operator::Gt => Operator::Lt,
+        Operator::GtEq => Operator::LtEq,
+        op => op,
+    }
+}
+
+fn column_value<'a>(row: &wb::Row<'a>, column: &str) -> Option<wb::Value<'a>> {
+    row.values()
+        .into_iter()
+        .flatten()
+        .find(|value| value.column() == Some(column))
+}
+
+/// Compares the row's value with the literal, returning `None` if the types
+/// can't be compared
+fn compare(value: &wb::Value<'_>, literal: &ScalarValue) -> Option<Ordering> {
+    use wb::ColumnValue;
+
+    match (value.value_type(), literal) {
+        (ColumnValue::TagValue, ScalarValue::Utf8(Some(s))) => {
+            Some(value.value_as_tag_value()?.value()?.cmp(s.as_str()))
+        }
+        (ColumnValue::StringValue, ScalarValue::Utf8(Some(s))) => {
+            Some(value.value_as_string_value()?.value()?.cmp(s.as_str()))
+        }
+        (ColumnValue::BoolValue, ScalarValue::Boolean(Some(b))) => {
+            Some(value.value_as_bool_value()?.value().cmp(b))
+        }
+        (ColumnValue::I64Value, _) => {
+            compare_integer(i128::from(value.value_as_i64value()?.value()), literal)
+        }
+        (ColumnValue::U64Value, _) => {
+            compare_integer(i128::from(value.value_as_u64value()?.value()), literal)
+        }
+        (ColumnValue::F64Value, ScalarValue::Int64(Some(l))) => {
+            value.value_as_f64value()?.value().partial_cmp(&(*l as f64))
+        }
+        (ColumnValue::F64Value, ScalarValue::Float64(Some(l))) => {
+            value.value_as_f64value()?.value().partial_cmp(l)
+        }
+        _ => None,
+    }
+}
+
+// i64 and u64 values both fit into an i128, so integers compare exactly
+fn compare_integer(value: i128, literal: &ScalarValue) -> Option<Ordering> {
+    match literal {
+        ScalarValue::Int64(Some(l)) => Some(value.cmp(&i128::from(*l))),
+        ScalarValue::Float64(Some(l)) => (value as f64).partial_cmp(l),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_deps::datafusion::logical_plan::{col, lit};
+    use data_types::{data::lines_to_replicated_write, database_rules::DatabaseRules};
+    use influxdb_line_protocol::parse_lines;
+
+    fn write(lp: &str) -> ReplicatedWrite {
+        let lines: Vec<_> = parse_lines(lp).map(|l| l.unwrap()).collect();
+        lines_to_replicated_write(1, 42, &lines, &DatabaseRules::new())
+    }
+
+    fn matcher(tables: MatchTables, predicate: Option<&str>) -> CompiledMatcher {
+        CompiledMatcher::new(&Matcher {
+            tables,
+            predicate: predicate.map(ToString::to_string),
+        })
+        .unwrap()
+    }
+
+    fn filtered_lines(matcher: &CompiledMatcher, write: &ReplicatedWrite) -> Vec<String> {
+        match matcher.filter(write) {
+            Some(filtered) => {
+                assert_eq!(filtered.writer_and_sequence(), (1, 42));
+                filtered
+                    .to_string()
+                    .lines()
+                    .map(ToString::to_string)
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+
+    #[test]
+    fn parse_predicate_into_expr() {
+        let expr = parse_predicate(r#"host = 'a''b' AND ("usage user" >= -1.5 or NOT up = true)"#)
+            .unwrap();
+
+        assert_eq!(
+            expr,
+            col("host").eq(lit("a'b")).and(
+                col("usage user")
+                    .gt_eq(lit(-1.5))
+                    .or(Expr::Not(Box::new(col("up").eq(lit(true)))))
+            )
+        );
+
+        // the predicate doesn't have to be written the way it is printed
+        assert_eq!(
+            parse_predicate("host='a'and(count>=2)").unwrap(),
+            col("host").eq(lit("a")).and(col("count").gt_eq(lit(2)))
+        );
+    }
+
+    #[test]
+    fn parse_predicate_errors() {
+        let cases = vec![
+            ("host =", "predicate"),
+            ("host = 'a' GROUP BY host", "predicate"),
+            ("host = 'a' LIMIT 1", "predicate"),
+            ("host = 'a' ORDER BY host", "predicate"),
+            ("host = 'a'; DROP TABLE predicate", "predicate"),
+            ("host = region", "predicate"),
+            ("usage + 1 > 2", "predicate"),
+            ("host LIKE 'a%'", "predicate"),
+        ];
+
+        for (predicate, field) in cases {
+            let err = CompiledMatcher::new(&Matcher {
+                tables: MatchTables::All,
+                predicate: Some(predicate.to_string()),
+            })
+            .unwrap_err();
+
+            assert_eq!(err.field(), field, "{}", predicate);
+            assert!(err.to_string().contains(predicate), "{}", err);
+        }
+    }
+
+    #[test]
+    fn invalid_regex() {
+        let err = CompiledMatcher::new(&Matcher {
+            tables: MatchTables::Regex("cpu(".to_string()),
+            predicate: None,
+        })
+        .unwrap_err();
+
+        assert!(matches!(err, Error::InvalidRegex { .. }));
+        assert_eq!(err.field(), "table_matcher.regex");
+    }
+
+    #[test]
+    fn match_all_returns_write() {
+        let write = write("cpu bar=1 10\nmem bar=2 10");
+        let matcher = matcher(MatchTables::All, None);
+
+        assert!(matches!(matcher.filter(&write), Some(Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn filter_tables() {
+        let write = write("cpu bar=1 10\nmem bar=2 10\ndisk bar=3 10");
+
+        let table = matcher(MatchTables::Table("mem".to_string()), None);
+        let lines = filtered_lines(&table, &write);
+        assert!(lines.iter().any(|l| l.contains("mem")));
+        assert!(!lines
+            .iter()
+            .any(|l| l.contains("cpu") || l.contains("disk")));
+
+        let regex = matcher(MatchTables::Regex("^(cpu|disk)$".to_string()), None);
+        let lines = filtered_lines(&regex, &write);
+        assert!(lines.iter().any(|l| l.contains("cpu")));
+        assert!(lines.iter().any(|l| l.contains("disk")));
+        assert!(!lines.iter().any(|l| l.contains("mem")));
+
+        let none = matcher(MatchTables::Table("swap".to_string()), None);
+        assert!(none.filter(&write).is_none());
+    }
+
+    #[test]
+    fn filter_rows() {
+        let write = write(
+            "cpu,host=a usage=10i,up=true 10\n\
+             cpu,host=b usage=95i,up=true 20\n\
+             cpu,host=c usage=99.5,up=false,note=\"hot\" 30\n\
+             mem,host=b used=1i 40",
+        );
+
+        let cases = vec![
+            ("host = 'b'", vec!["host:b"]),
+            ("'b' = host", vec!["host:b"]),
+            ("host != 'b'", vec!["host:a", "host:c"]),
+            ("usage > 90", vec!["host:b", "host:c"]),
+            ("90 < usage", vec!["host:b", "host:c"]),
+            ("usage <= 10.0", vec!["host:a"]),
+            ("usage > -1", vec!["host:a", "host:b", "host:c"]),
+            ("up = false", vec!["host:c"]),
+            ("up", vec!["host:a", "host:b"]),
+            ("NOT up", vec!["host:c"]),
+            ("time >= 20 AND time < 30", vec!["host:b"]),
+            ("host = 'b' and usage = 95", vec!["host:b"]),
+            (
+                "host = 'a' OR (usage > 99 AND up = false)",
+                vec!["host:a", "host:c"],
+            ),
+            ("note IS NULL", vec!["host:a", "host:b"]),
+            ("note IS NOT NULL", vec!["host:c"]),
+            // a comparison with a missing column is unknown, and so is its
+            // negation
+            ("NOT note = 'hot'", vec![]),
+            ("note = 'hot' OR host = 'a'", vec!["host:a", "host:c"]),
+            // strings don't compare with numbers
+            ("host > 1", vec![]),
+        ];
+
+        for (predicate, expected) in cases {
+            let matcher = matcher(MatchTables::Table("cpu".to_string()), Some(predicate));
+            let lines = filtered_lines(&matcher, &write);
+
+            for host in &["host:a", "host:b", "host:c"] {
+                assert_eq!(
+                    lines.iter().any(|l| l.contains(host)),
+                    expected.contains(host),
+                    "predicate {} host {}: {:#?}",
+                    predicate,
+                    host,
+                    lines
+                );
+            }
+        }
+
+        // rows without the column never match
+        let matcher = matcher(MatchTables::All, Some("used = 1"));
+        let lines = filtered_lines(&matcher, &write);
+        assert!(lines.iter().any(|l| l.contains("mem")));
+        assert!(!lines.iter().any(|l| l.contains("cpu")));
+    }
+}
@@ -2,6 +2,7 @@ pub mod field_columns;
 pub mod read_filter;
 pub mod read_group;
 pub mod read_window_aggregate;
+pub mod series_cardinality;
 pub mod table_names;
 pub mod tag_keys;
 pub mod tag_values;
@@ -241,6 +241,64 @@ async fn test_grouped_series_set_plan_count() {
     );
 }
 
+#[tokio::test]
+async fn test_grouped_series_set_plan_sum_time_range() {
+    // a predicate the read buffer can evaluate, so that it can
+    // compute the sums of its chunks itself
+    let predicate = PredicateBuilder::default()
+        .timestamp_range(100, 1000)
+        .build();
+
+    let agg = Aggregate::Sum;
+    let group_columns = vec!["state"];
+
+    let expected_results = vec![
+        "+-------+-----------+----------+------+------+",
+        "| state | city      | humidity | temp | time |",
+        "+-------+-----------+----------+------+------+",
+        "| CA    | LA        | 21       | 181  | 1100 |",
+        "| MA    | Boston    |          | 141  | 700  |",
+        "| MA    | Cambridge |          | 163  | 300  |",
+        "+-------+-----------+----------+------+------+",
+    ];
+
+    run_read_group_test_case!(
+        AnotherMeasurementForAggs {},
+        predicate,
+        agg,
+        group_columns,
+        expected_results
+    );
+}
+
+#[tokio::test]
+async fn test_grouped_series_set_plan_count_time_range() {
+    let predicate = PredicateBuilder::default()
+        .timestamp_range(100, 1000)
+        .build();
+
+    let agg = Aggregate::Count;
+    let group_columns = vec!["state"];
+
+    let expected_results = vec![
+        "+-------+-----------+----------+------+------+",
+        "| state | city      | humidity | temp | time |",
+        "+-------+-----------+----------+------+------+",
+        "| CA    | LA        | 2        | 2    | 2    |",
+        "| MA    | Boston    | 0        | 2    | 2    |",
+        "| MA    | Cambridge | 0        | 2    | 2    |",
+        "+-------+-----------+----------+------+------+",
+    ];
+
+    run_read_group_test_case!(
+        AnotherMeasurementForAggs {},
+        predicate,
+        agg,
+        group_columns,
+        expected_results
+    );
+}
+
 #[tokio::test]
 async fn test_grouped_series_set_plan_mean() {
     let predicate = PredicateBuilder::default()
@@ -544,3 +602,60 @@ async fn test_grouped_series_set_plan_group_aggregate_none() {
         expected_results
     );
 }
+
+#[tokio::test]
+async fn test_read_group_keys_time_range() {
+    test_helpers::maybe_start_logging();
+    // only Boston has rows in the range
+    let predicate = PredicateBuilder::default()
+        .timestamp_range(250, 450)
+        .build();
+    let group_columns = vec!["city", "state"];
+
+    let expected_results = vec![
+        "+--------+-------+",
+        "| city   | state |",
+        "+--------+-------+",
+        "| Boston | MA    |",
+        "+--------+-------+",
+    ];
+
+    let scenarios = MeasurementForGroupKeys {}.make().await;
+    for scenario in scenarios {
+        let DBScenario {
+            scenario_name, db, ..
+        } = scenario;
+        println!("Running scenario '{}'", scenario_name);
+        let planner = InfluxRPCPlanner::new();
+        let executor = Executor::new();
+
+        let plans = planner
+            .read_group_keys(&db, predicate.clone(), &group_columns)
+            .await
+            .expect("built plan successfully");
+
+        let mut string_results = vec![];
+        for plan in plans.into_inner() {
+            assert_eq!(plan.num_prefix_tag_group_columns, Some(group_columns.len()));
+
+            let batches = executor
+                .run_logical_plan(plan.plan)
+                .await
+                .expect("ok running plan");
+
+            string_results.extend(
+                pretty_format_batches(&batches)
+                    .expect("formatting results")
+                    .trim()
+                    .split('\n')
+                    .map(|s| s.to_string()),
+            );
+        }
+
+        assert_eq!(
+            expected_results, string_results,
+            "Error in  scenario '{}'\n\nexpected:\n{:#?}\nactual:\n{:#?}",
+            scenario_name, expected_results, string_results
+        );
+    }
+}
@@ -146,6 +146,43 @@ async fn test_read_window_aggregate_nanoseconds() {
     );
 }
 
+#[tokio::test]
+async fn test_read_window_aggregate_nanoseconds_sum() {
+    // a predicate the read buffer can evaluate, so that it can
+    // compute the sums of its chunks itself
+    let predicate = PredicateBuilder::default()
+        .add_expr(col("state").eq(lit("MA")))
+        .timestamp_range(100, 450)
+        .build();
+
+    let agg = Aggregate::Sum;
+    let every = WindowDuration::from_nanoseconds(200);
+    let offset = WindowDuration::from_nanoseconds(0);
+
+    // the window of Cambridge ending at 400 has rows in both chunks
+    let expected_results = vec![
+        "+-----------+-------+------+------+",
+        "| city      | state | time | temp |",
+        "+-----------+-------+------+------+",
+        "| Boston    | MA    | 200  | 70   |",
+        "| Boston    | MA    | 400  | 143  |",
+        "| Boston    | MA    | 600  | 73   |",
+        "| Cambridge | MA    | 200  | 80   |",
+        "| Cambridge | MA    | 400  | 163  |",
+        "| Cambridge | MA    | 600  | 83   |",
+        "+-----------+-------+------+------+",
+    ];
+
+    run_read_window_aggregate_test_case!(
+        MeasurementForWindowAggregate {},
+        predicate,
+        agg,
+        every,
+        offset,
+        expected_results
+    );
+}
+
 struct MeasurementForWindowAggregateMonths {}
 #[async_trait]
 impl DBSetup for MeasurementForWindowAggregateMonths {
@@ -0,0 +1,130 @@
+use arrow_deps::datafusion::logical_plan::{col, lit};
+use query::{
+    exec::{tagset::SeriesCardinality, Executor},
+    frontend::influxrpc::InfluxRPCPlanner,
+    predicate::PredicateBuilder,
+};
+
+use crate::query_tests::scenarios::*;
+
+/// runs series_cardinality(predicate) and compares it to the expected
+/// number of series of each measurement
+macro_rules! run_series_cardinality_test_case {
+    ($DB_SETUP:expr, $PREDICATE:expr, $EXPECTED_CARDINALITY:expr) => {
+        test_helpers::maybe_start_logging();
+        let predicate = $PREDICATE;
+        let expected_cardinality = $EXPECTED_CARDINALITY;
+        for scenario in $DB_SETUP.make().await {
+            let DBScenario {
+                scenario_name, db, ..
+            } = scenario;
+            println!("Running scenario '{}'", scenario_name);
+            println!("Predicate: '{:#?}'", predicate);
+            let planner = InfluxRPCPlanner::new();
+            let executor = Executor::new();
+
+            let plan = planner
+                .series_cardinality(&db, predicate.clone())
+                .await
+                .expect("built plan successfully");
+            let cardinality = executor
+                .to_series_cardinality(plan)
+                .await
+                .expect("counted series successfully");
+
+            assert_eq!(
+                cardinality,
+                to_series_cardinality(&expected_cardinality),
+                "Error in  scenario '{}'\n\nexpected:\n{:?}\nactual:\n{:?}",
+                scenario_name,
+                expected_cardinality,
+                cardinality
+            );
+        }
+    };
+}
+
+#[tokio::test]
+async fn series_cardinality_no_predicate() {
+    let predicate = PredicateBuilder::default().build();
+    // o2 has the series (MA, Boston), (CA), (NY), (NY, NYC) and
+    // (NY, NYC, Brooklyn)
+    let expected_cardinality = vec![("h2o", 2), ("o2", 5)];
+    run_series_cardinality_test_case!(TwoMeasurementsManyNulls {}, predicate, expected_cardinality);
+}
+
+#[tokio::test]
+async fn series_cardinality_timestamp_predicate() {
+    let predicate = PredicateBuilder::default()
+        .timestamp_range(200, 401)
+        .build();
+    let expected_cardinality = vec![("h2o", 1), ("o2", 3)];
+    run_series_cardinality_test_case!(TwoMeasurementsManyNulls {}, predicate, expected_cardinality);
+}
+
+#[tokio::test]
+async fn series_cardinality_table_predicate() {
+    let predicate = PredicateBuilder::default().table("o2").build();
+    let expected_cardinality = vec![("o2", 5)];
+    run_series_cardinality_test_case!(TwoMeasurementsManyNulls {}, predicate, expected_cardinality);
+}
+
+#[tokio::test]
+async fn series_cardinality_state_pred() {
+    let predicate = PredicateBuilder::default()
+        .add_expr(col("state").eq(lit("NY")))
+        .build();
+    let expected_cardinality = vec![("o2", 3)];
+    run_series_cardinality_test_case!(TwoMeasurementsManyNulls {}, predicate, expected_cardinality);
+}
+
+#[tokio::test]
+async fn series_cardinality_timestamp_and_state_pred() {
+    let predicate = PredicateBuilder::default()
+        .timestamp_range(200, 500)
+        .add_expr(col("state").eq(lit("NY")))
+        .build();
+    let expected_cardinality = vec![("o2", 1)];
+    run_series_cardinality_test_case!(TwoMeasurementsManyNulls {}, predicate, expected_cardinality);
+}
+
+#[tokio::test]
+async fn series_cardinality_no_match() {
+    let predicate = PredicateBuilder::default()
+        .add_expr(col("state").eq(lit("TX")))
+        .build();
+    let expected_cardinality: Vec<(&str, u64)> = vec![];
+    run_series_cardinality_test_case!(TwoMeasurementsManyNulls {}, predicate, expected_cardinality);
+}
+
+#[tokio::test]
+async fn series_cardinality_field_pred() {
+    // only h2o has other_temp, in the series (MA, Boston) and (CA, Boston)
+    let predicate = PredicateBuilder::default()
+        .field_columns(vec!["other_temp"])
+        .build();
+    let expected_cardinality = vec![("h2o", 2)];
+    run_series_cardinality_test_case!(
+        TwoMeasurementsManyFields {},
+        predicate,
+        expected_cardinality
+    );
+
+    // the h2o series (CA, Boston) has no temp
+    let predicate = PredicateBuilder::default()
+        .field_columns(vec!["temp"])
+        .build();
+    let expected_cardinality = vec![("h2o", 1), ("o2", 2)];
+    run_series_cardinality_test_case!(
+        TwoMeasurementsManyFields {},
+        predicate,
+        expected_cardinality
+    );
+}
+
+fn to_series_cardinality(expected: &[(&str, u64)]) -> SeriesCardinality {
+    expected
+        .iter()
+        .map(|(table_name, count)| (table_name.to_string(), *count))
+        .collect()
+}
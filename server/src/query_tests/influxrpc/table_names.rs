@@ -1,4 +1,5 @@
 //! Tests for the Influx gRPC queries
+use arrow_deps::datafusion::logical_plan::{col, lit};
 use query::{
     exec::{
         stringset::{IntoStringSet, StringSetRef},
@@ -77,6 +78,46 @@ async fn list_table_names_data_pred_250_300() {
     run_table_names_test_case!(TwoMeasurements {}, tsp(250, 300), vec![]);
 }
 
+#[tokio::test]
+async fn list_table_names_tag_pred() {
+    let predicate = PredicateBuilder::default()
+        .add_expr(col("region").eq(lit("west")))
+        .build();
+    run_table_names_test_case!(TwoMeasurements {}, predicate, vec!["cpu"]);
+}
+
+#[tokio::test]
+async fn list_table_names_tag_pred_no_match() {
+    let predicate = PredicateBuilder::default()
+        .add_expr(col("region").eq(lit("north")))
+        .build();
+    run_table_names_test_case!(TwoMeasurements {}, predicate, vec![]);
+}
+
+#[tokio::test]
+async fn list_table_names_field_pred() {
+    let predicate = PredicateBuilder::default()
+        .add_expr(col("bytes").gt(lit(50)))
+        .build();
+    run_table_names_test_case!(TwoMeasurements {}, predicate, vec!["disk"]);
+}
+
+#[tokio::test]
+async fn list_table_names_timestamp_and_tag_pred() {
+    let predicate = PredicateBuilder::default()
+        .timestamp_range(120, 300)
+        .add_expr(col("region").eq(lit("west")))
+        .build();
+    run_table_names_test_case!(TwoMeasurements {}, predicate, vec!["cpu"]);
+
+    // the only west row in range is at time 150
+    let predicate = PredicateBuilder::default()
+        .timestamp_range(151, 300)
+        .add_expr(col("region").eq(lit("west")))
+        .build();
+    run_table_names_test_case!(TwoMeasurements {}, predicate, vec![]);
+}
+
 // make a single timestamp predicate between r1 and r2
 fn tsp(r1: i64, r2: i64) -> Predicate {
     PredicateBuilder::default().timestamp_range(r1, r2).build()
@@ -100,6 +100,23 @@ async fn sql_select_from_cpu_with_projection_and_pred() {
     );
 }
 
+#[tokio::test]
+async fn sql_select_from_cpu_with_tag_and_field_pred() {
+    // the integer literal is compared with a float field
+    let expected = vec![
+        "+--------+------+------+",
+        "| region | time | user |",
+        "+--------+------+------+",
+        "| west   | 100  | 23.2 |",
+        "+--------+------+------+",
+    ];
+    run_sql_test_case!(
+        TwoMeasurements {},
+        "SELECT * from cpu where region = 'west' and user > 22",
+        &expected
+    );
+}
+
 #[tokio::test]
 async fn sql_select_from_cpu_group() {
     let expected = vec![
@@ -161,3 +178,20 @@ async fn sql_select_with_schema_merge_subset() {
         &expected
     );
 }
+
+#[tokio::test]
+async fn sql_select_with_schema_merge_pred() {
+    // only one of the chunks has the host column
+    let expected = vec![
+        "+------+--------+--------+------+------+",
+        "| host | region | system | time | user |",
+        "+------+--------+--------+------+------+",
+        "| foo  | east   |        | 100  | 23.2 |",
+        "+------+--------+--------+------+------+",
+    ];
+    run_sql_test_case!(
+        MultiChunkSchemaMerge {},
+        "SELECT * from cpu where host = 'foo'",
+        &expected
+    );
+}
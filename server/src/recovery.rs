@@ -0,0 +1,764 @@
+//! This module rebuilds a database's state from object storage when the
+//! server starts. The chunks in the database's catalog of Parquet files are
+//! loaded as persisted chunks, then the writes in the persisted WAL segments
+//! that aren't covered by a snapshot are replayed into the mutable buffer,
+//! followed by the writes in the database's local WAL, if the server has one.
+
+use std::{borrow::Cow, collections::BTreeMap, sync::Arc};
+
+use bytes::BytesMut;
+use data_types::{
+    data::{replicated_write_from_entries, ReplicatedWrite},
+    database_rules::WriterId,
+};
+use futures::TryStreamExt;
+use object_store::{path::ObjectStorePath, ObjectStore, ObjectStoreApi};
+use query::Database;
+use snafu::{ResultExt, Snafu};
+use tracing::{error, info, warn};
+
+use crate::{
+    buffer::{object_store_path_for_segments, Segment},
+    catalog::{self, load_catalog},
+    db::{parquet_file::ParquetChunk, Db},
+    local_wal::LocalWal,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error listing object store path {}: {}", path, source))]
+    ListingObjectStore {
+        path: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display("Error loading catalog: {}", source))]
+    LoadingCatalog { source: catalog::Error },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The WAL positions covered by the persisted chunks of each partition
+pub(crate) type Watermarks = BTreeMap<String, BTreeMap<WriterId, u64>>;
+
+/// What was restored by `rebuild_database`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RecoveryReport {
+    /// The number of persisted chunks loaded from Parquet files
+    pub chunks_loaded: usize,
+    /// The number of WAL segments read
+    pub segments_replayed: usize,
+    /// The number of writes (or parts of writes) not covered by a snapshot
+    /// that were stored in the mutable buffer
+    pub writes_replayed: usize,
+    /// The number of files (catalog versions, chunks, segments or local WAL
+    /// entries) that could not be read or parsed
+    pub skipped_files: usize,
+}
+
+/// Rebuilds the database from the catalog and WAL segments persisted under
+/// `db_path` in object storage and from its local WAL. Files that can't be
+/// read are logged and skipped so that a single corrupt file doesn't keep the
+/// database from loading.
+pub(crate) async fn rebuild_database(
+    db: &Db,
+    writer_id: WriterId,
+    db_path: &object_store::path::Path,
+    store: Arc<ObjectStore>,
+    local_wal: Option<&LocalWal>,
+) -> Result<RecoveryReport> {
+    let mut report = RecoveryReport::default();
+
+    let watermarks = load_chunks(db, db_path, &store, &mut report).await?;
+    let segments = read_segments(db_path, &store, &mut report).await?;
+
+    let mut writer_sequences = BTreeMap::new();
+    let mut replayed = Watermarks::new();
+    for segment in &segments {
+        for write in &segment.writes {
+            record_sequence(&mut writer_sequences, write);
+            record_partition_sequences(&mut replayed, write);
+            if let Some(write) = unpersisted_parts(write, &watermarks) {
+                replay_write(db, &write, &mut report).await;
+            }
+        }
+
+        report.segments_replayed += 1;
+    }
+
+    if let Some(local_wal) = local_wal {
+        replay_local_wal(
+            db,
+            local_wal,
+            &watermarks,
+            &mut writer_sequences,
+            &mut replayed,
+            &mut report,
+        )
+        .await;
+    }
+
+    // the segments of persisted writes may have been garbage collected, so
+    // the persisted chunks' positions are needed to not reuse their sequences
+    for positions in watermarks.values() {
+        for (&writer, &sequence) in positions {
+            let position = writer_sequences.entry(writer).or_insert(sequence);
+            *position = (*position).max(sequence);
+        }
+    }
+
+    // new segments and writes continue after the replayed ones
+    if let Some(&sequence) = writer_sequences.get(&writer_id) {
+        db.restore_sequence(sequence);
+    }
+
+    if let Some(buffer) = db.wal_buffer.as_ref() {
+        let last_segment_id = segments.last().map_or(0, |segment| segment.id);
+        buffer.lock().restore(last_segment_id, &writer_sequences);
+    }
+
+    info!(
+        db_name = db.rules.read().name.as_str(),
+        chunks_loaded = report.chunks_loaded,
+        segments_replayed = report.segments_replayed,
+        writes_replayed = report.writes_replayed,
+        skipped_files = report.skipped_files,
+        "rebuilt database from object store"
+    );
+
+    Ok(report)
+}
+
+/// Loads the chunks listed by the newest version of the catalog that can be
+/// read, returning the WAL positions covered by the loaded chunks of each
+/// partition
+async fn load_chunks(
+    db: &Db,
+    db_path: &object_store::path::Path,
+    store: &Arc<ObjectStore>,
+    report: &mut RecoveryReport,
+) -> Result<Watermarks> {
+    let (state, skipped) = load_catalog(store, db_path).await.context(LoadingCatalog)?;
+    report.skipped_files += skipped;
+
+    // the writes of dropped chunks are not replayed either
+    let mut watermarks = state.dropped_wal_positions.clone();
+    for chunk in &state.chunks {
+        let partition_key = chunk.partition_key.as_str();
+
+        // new chunks of the partition must not reuse the persisted ids
+        if let Some(mutable_buffer) = &db.mutable_buffer {
+            mutable_buffer.reserve_chunk_ids(partition_key, chunk.chunk_id + 1);
+        }
+
+        match ParquetChunk::load(
+            partition_key,
+            chunk.chunk_id,
+            Arc::clone(store),
+            &chunk.data_path(db_path),
+            &chunk.files,
+        )
+        .await
+        {
+            Ok(parquet_chunk) => {
+                info!(
+                    partition_key,
+                    chunk_id = chunk.chunk_id,
+                    "loaded persisted chunk"
+                );
+                db.add_persisted_chunk(parquet_chunk);
+                report.chunks_loaded += 1;
+                chunk.merge_wal_positions(&mut watermarks);
+            }
+            Err(e) => {
+                // the chunk's writes will be replayed from the WAL instead
+                error!(
+                    "skipping chunk {} of partition {} that could not be loaded: {}",
+                    chunk.chunk_id, partition_key, e
+                );
+                report.skipped_files += 1;
+            }
+        }
+    }
+
+    // new snapshots are added to the version that was loaded
+    db.catalog.restore(state).await;
+
+    Ok(watermarks)
+}
+
+/// Reads the persisted WAL segments, ordered by id
+async fn read_segments(
+    db_path: &object_store::path::Path,
+    store: &Arc<ObjectStore>,
+    report: &mut RecoveryReport,
+) -> Result<Vec<Segment>> {
+    let segments_path = object_store_path_for_segments(db_path);
+    let locations: Vec<_> = store
+        .list(Some(&segments_path))
+        .await
+        .context(ListingObjectStore {
+            path: segments_path.display(),
+        })?
+        .try_concat()
+        .await
+        .context(ListingObjectStore {
+            path: segments_path.display(),
+        })?;
+
+    let mut segments = vec![];
+    for location in locations {
+        match read_bytes(store, &location)
+            .await
+            .and_then(|data| Segment::from_file_bytes(&data).map_err(|e| e.to_string()))
+        {
+            Ok(segment) => segments.push(segment),
+            Err(e) => {
+                warn!(
+                    "skipping WAL segment {} that could not be read: {}",
+                    location.display(),
+                    e
+                );
+                report.skipped_files += 1;
+            }
+        }
+    }
+    segments.sort_by_key(|segment| segment.id);
+
+    Ok(segments)
+}
+
+/// Replays the writes in the local WAL that come after both the persisted
+/// segments and the snapshot of their partition, and tracks the entries whose
+/// writes still have to be persisted. Reading stops at the first entry that
+/// can't be read, as that is where a crash interrupted an append.
+///
+/// Whether a write was already replayed is decided per partition: the parts
+/// of a write that was split up between the hosts of a host group share its
+/// writer and sequence, and more than one part can reach the same host.
+async fn replay_local_wal(
+    db: &Db,
+    local_wal: &LocalWal,
+    watermarks: &Watermarks,
+    writer_sequences: &mut BTreeMap<WriterId, u64>,
+    replayed: &mut Watermarks,
+    report: &mut RecoveryReport,
+) {
+    let entries = match local_wal.entries() {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("skipping local WAL that could not be read: {}", e);
+            report.skipped_files += 1;
+            return;
+        }
+    };
+
+    for entry in entries {
+        let (entry_sequence, write) = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!(
+                    "stopping local WAL replay at entry that could not be read: {}",
+                    e
+                );
+                report.skipped_files += 1;
+                return;
+            }
+        };
+
+        record_sequence(writer_sequences, &write);
+
+        if let Some(unpersisted) = unpersisted_parts(&write, watermarks) {
+            local_wal.track(entry_sequence, &unpersisted);
+            if let Some(unreplayed) = unpersisted_parts(&unpersisted, replayed) {
+                replay_write(db, &unreplayed, report).await;
+            }
+        }
+        record_partition_sequences(replayed, &write);
+    }
+}
+
+fn record_sequence(writer_sequences: &mut BTreeMap<WriterId, u64>, write: &ReplicatedWrite) {
+    let (writer, sequence) = write.writer_and_sequence();
+    let position = writer_sequences.entry(writer).or_insert(sequence);
+    *position = (*position).max(sequence);
+}
+
+/// Records the write's sequence for its writer in each of its partitions
+fn record_partition_sequences(positions: &mut Watermarks, write: &ReplicatedWrite) {
+    let (writer, sequence) = write.writer_and_sequence();
+
+    if let Some(entries) = write.write_buffer_batch().and_then(|b| b.entries()) {
+        for entry in entries {
+            let key = entry.partition_key().unwrap_or("").to_string();
+            let position = positions
+                .entry(key)
+                .or_default()
+                .entry(writer)
+                .or_insert(sequence);
+            *position = (*position).max(sequence);
+        }
+    }
+}
+
+/// Returns the parts of the write that come after the position of its writer
+/// in their partition, such as the snapshot of the partition, or `None` if
+/// the positions cover all of it
+pub(crate) fn unpersisted_parts<'a>(
+    write: &'a ReplicatedWrite,
+    watermarks: &Watermarks,
+) -> Option<Cow<'a, ReplicatedWrite>> {
+    let (writer, sequence) = write.writer_and_sequence();
+    let entries: Vec<_> = write.write_buffer_batch()?.entries()?.into_iter().collect();
+    let total_entries = entries.len();
+
+    let unpersisted: Vec<_> = entries
+        .into_iter()
+        .filter(|entry| {
+            let key = entry.partition_key().unwrap_or("");
+            watermarks
+                .get(key)
+                .and_then(|positions| positions.get(&writer))
+                .map_or(true, |&persisted| sequence > persisted)
+        })
+        .collect();
+
+    if unpersisted.is_empty() {
+        None
+    } else if unpersisted.len() == total_entries {
+        Some(Cow::Borrowed(write))
+    } else {
+        let write = replicated_write_from_entries(writer, sequence, unpersisted);
+        Some(Cow::Owned(write))
+    }
+}
+
+async fn replay_write(db: &Db, write: &ReplicatedWrite, report: &mut RecoveryReport) {
+    match db.store_replicated_write(write).await {
+        Ok(()) => report.writes_replayed += 1,
+        Err(e) => {
+            let (writer, sequence) = write.writer_and_sequence();
+            error!(writer, sequence, "error replaying write: {}", e)
+        }
+    }
+}
+
+pub(crate) async fn read_bytes(
+    store: &ObjectStore,
+    location: &object_store::path::Path,
+) -> Result<BytesMut, String> {
+    store
+        .get(location)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_ok(|b| BytesMut::from(&b[..]))
+        .try_concat()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        buffer::{object_store_path_for_segment, Buffer},
+        db::DBChunk,
+        jobs::{JobRegistry, TrackedJob},
+        local_wal::LocalWals,
+        query_tests::utils::make_db,
+        snapshot::snapshot_chunk,
+    };
+    use bytes::Bytes;
+    use data_types::{
+        chunk::ChunkStorage,
+        data::{lines_to_replicated_write, ReplicatedWrite},
+        database_rules::{DatabaseRules, PartitionTemplate, TemplatePart, WalBufferRollover},
+        job::Job,
+    };
+    use influxdb_line_protocol::parse_lines;
+    use mutable_buffer::MutableBufferDb;
+    use object_store::memory::InMemory;
+    use query::PartitionChunk;
+
+    fn write(sequence: u64, lp: &str) -> Arc<ReplicatedWrite> {
+        let rules = DatabaseRules {
+            partition_template: PartitionTemplate {
+                parts: vec![TemplatePart::Table],
+            },
+            ..Default::default()
+        };
+        let lines: Vec<_> = parse_lines(lp).map(|l| l.unwrap()).collect();
+        Arc::new(lines_to_replicated_write(1, sequence, &lines, &rules))
+    }
+
+    fn db_path(store: &ObjectStore) -> object_store::path::Path {
+        let mut path = store.new_path();
+        path.push_all_dirs(&["1", "my_db"]);
+        path
+    }
+
+    async fn put(store: &ObjectStore, location: &object_store::path::Path, data: Bytes) {
+        let len = data.len();
+        let stream = futures::stream::once(async move { Ok(data) });
+        store.put(location, stream, Some(len)).await.unwrap();
+    }
+
+    /// Persists each write in its own segment, as a buffer with a tiny
+    /// segment size would
+    async fn persist_segments(store: &ObjectStore, writes: &[Arc<ReplicatedWrite>]) {
+        let mut buffer = Buffer::new(1 << 20, 10, WalBufferRollover::ReturnError, false);
+        for write in writes {
+            let segment = buffer.append(Arc::clone(write)).unwrap().unwrap();
+            let location = object_store_path_for_segment(&db_path(store), segment.id).unwrap();
+            put(store, &location, segment.to_file_bytes(1).unwrap()).await;
+        }
+    }
+
+    async fn snapshot_partition(
+        store: &Arc<ObjectStore>,
+        partition_key: &str,
+        writes: &[Arc<ReplicatedWrite>],
+        wal_positions: BTreeMap<WriterId, u64>,
+    ) {
+        let db = make_db();
+        snapshot_writes(store, &db, partition_key, writes, wal_positions).await;
+    }
+
+    /// Stores the writes in the database, then snapshots the partition's
+    /// chunk that holds them
+    async fn snapshot_writes(
+        store: &Arc<ObjectStore>,
+        db: &Db,
+        partition_key: &str,
+        writes: &[Arc<ReplicatedWrite>],
+        wal_positions: BTreeMap<WriterId, u64>,
+    ) {
+        for write in writes {
+            db.store_replicated_write(write).await.unwrap();
+        }
+        let chunk = db.rollover_partition(partition_key).await.unwrap();
+
+        let registration = JobRegistry::new().register(TrackedJob::new(Job::SnapshotChunk {
+            db_name: "my_db".to_string(),
+            partition_key: partition_key.to_string(),
+            chunk_id: chunk.id(),
+        }));
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        snapshot_chunk(
+            &db_path(store),
+            Arc::clone(store),
+            Arc::clone(&db.catalog),
+            partition_key,
+            chunk,
+            wal_positions,
+            Some(tx),
+            registration,
+        )
+        .unwrap();
+        rx.await.unwrap();
+    }
+
+    fn db_with_wal_buffer() -> Db {
+        Db::new(
+            DatabaseRules::new(),
+            Some(MutableBufferDb::new("my_db")),
+            read_buffer::Database::new(),
+            Some(Buffer::new(
+                1 << 20,
+                1 << 10,
+                WalBufferRollover::ReturnError,
+                false,
+            )),
+        )
+    }
+
+    fn chunk_tables(chunk: &DBChunk) -> Vec<String> {
+        let mut tables: Vec<_> = chunk
+            .table_stats()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        tables.sort();
+        tables
+    }
+
+    #[tokio::test]
+    async fn rebuilds_snapshots_and_replays_later_writes() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let w1 = write(1, "cpu bar=1 10");
+        let w2 = write(2, "mem foo=1 10");
+        let w3 = write(3, "cpu bar=2 20");
+        persist_segments(&store, &[Arc::clone(&w1), Arc::clone(&w2), Arc::clone(&w3)]).await;
+
+        // the cpu partition was snapshotted after the first write
+        let positions = vec![(1, 1)].into_iter().collect();
+        snapshot_partition(&store, "cpu", &[w1], positions).await;
+
+        let db = db_with_wal_buffer();
+        let report = rebuild_database(&db, 1, &db_path(&store), Arc::clone(&store), None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            report,
+            RecoveryReport {
+                chunks_loaded: 1,
+                segments_replayed: 3,
+                writes_replayed: 2,
+                skipped_files: 0,
+            }
+        );
+
+        assert_eq!(db.persisted_chunks("cpu").len(), 1);
+        assert_eq!(db.catalog.state().await.version, 1);
+
+        let storage: Vec<_> = db
+            .chunk_summaries()
+            .unwrap()
+            .into_iter()
+            .map(|summary| (summary.partition_key, summary.id, summary.storage))
+            .collect();
+        assert_eq!(
+            storage,
+            vec![
+                ("cpu".to_string(), 0, ChunkStorage::ObjectStore),
+                ("cpu".to_string(), 1, ChunkStorage::OpenMutableBuffer),
+                ("mem".to_string(), 0, ChunkStorage::OpenMutableBuffer),
+            ]
+        );
+
+        // both the persisted and the replayed data are queryable
+        let chunks = db.chunks("cpu");
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk_tables(chunk) == vec!["cpu".to_string()]));
+
+        // new writes continue after the persisted ones
+        assert_eq!(db.next_sequence(), 4);
+        let buffer = db.wal_buffer.as_ref().unwrap().lock();
+        assert_eq!(
+            buffer.writer_sequences(),
+            vec![(1, 3)].into_iter().collect()
+        );
+    }
+
+    #[tokio::test]
+    async fn replays_local_wal_after_segments() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let w1 = write(1, "cpu bar=1 10");
+        let w2 = write(2, "mem foo=1 10");
+        let w3 = write(3, "cpu bar=2 20");
+        persist_segments(&store, &[Arc::clone(&w1), Arc::clone(&w2)]).await;
+
+        let positions = vec![(1, 1)].into_iter().collect();
+        snapshot_partition(&store, "cpu", &[Arc::clone(&w1)], positions).await;
+
+        // the local WAL also holds the last write, which never made it into a
+        // persisted segment
+        let dir = test_helpers::tmp_dir().unwrap();
+        let local_wal = LocalWals::new(dir.path()).open("my_db").unwrap();
+        for write in &[&w1, &w2, &w3] {
+            local_wal.append(write).await.unwrap();
+        }
+
+        let db = db_with_wal_buffer();
+        let report = rebuild_database(
+            &db,
+            1,
+            &db_path(&store),
+            Arc::clone(&store),
+            Some(&local_wal),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            report,
+            RecoveryReport {
+                chunks_loaded: 1,
+                segments_replayed: 2,
+                writes_replayed: 2,
+                skipped_files: 0,
+            }
+        );
+
+        // the mem write replayed from its segment isn't stored twice
+        let expected = db_with_wal_buffer();
+        expected.store_replicated_write(&w2).await.unwrap();
+        expected.store_replicated_write(&w3).await.unwrap();
+        assert_eq!(
+            db.mutable_buffer.as_ref().unwrap().size(),
+            expected.mutable_buffer.as_ref().unwrap().size()
+        );
+        assert_eq!(db.next_sequence(), 4);
+    }
+
+    #[tokio::test]
+    async fn replays_each_part_of_a_split_write() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+
+        // both parts of a write that was split up between the hosts of a host
+        // group reached this server, as one host couldn't be reached
+        let split = write(1, "cpu bar=1 10\nmem foo=1 10");
+        let parts: Vec<_> = split
+            .write_buffer_batch()
+            .unwrap()
+            .entries()
+            .unwrap()
+            .into_iter()
+            .map(|entry| Arc::new(replicated_write_from_entries(1, 1, vec![entry])))
+            .collect();
+        assert_eq!(parts.len(), 2);
+        persist_segments(&store, &parts[..1]).await;
+
+        let dir = test_helpers::tmp_dir().unwrap();
+        let local_wal = LocalWals::new(dir.path()).open("my_db").unwrap();
+        for part in &parts {
+            local_wal.append(part).await.unwrap();
+        }
+
+        let db = db_with_wal_buffer();
+        let report = rebuild_database(
+            &db,
+            1,
+            &db_path(&store),
+            Arc::clone(&store),
+            Some(&local_wal),
+        )
+        .await
+        .unwrap();
+
+        // the part in the segment is replayed once, and the other part with
+        // the same writer and sequence isn't mistaken for it
+        assert_eq!(report.writes_replayed, 2);
+        assert_eq!(db.chunks("cpu").len(), 1);
+        assert_eq!(db.chunks("mem").len(), 1);
+
+        let expected = db_with_wal_buffer();
+        for part in &parts {
+            expected.store_replicated_write(part).await.unwrap();
+        }
+        assert_eq!(
+            db.mutable_buffer.as_ref().unwrap().size(),
+            expected.mutable_buffer.as_ref().unwrap().size()
+        );
+        assert_eq!(db.next_sequence(), 2);
+    }
+
+    #[tokio::test]
+    async fn loads_every_snapshot_of_a_partition() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let w1 = write(1, "cpu bar=1 10");
+        let w2 = write(2, "cpu bar=2 20");
+        persist_segments(&store, &[Arc::clone(&w1), Arc::clone(&w2)]).await;
+
+        let db = make_db();
+        let positions = vec![(1, 1)].into_iter().collect();
+        snapshot_writes(&store, &db, "cpu", &[w1], positions).await;
+        let positions = vec![(1, 2)].into_iter().collect();
+        snapshot_writes(&store, &db, "cpu", &[w2], positions).await;
+
+        let restored = db_with_wal_buffer();
+        let report = rebuild_database(&restored, 1, &db_path(&store), Arc::clone(&store), None)
+            .await
+            .unwrap();
+
+        // the second snapshot didn't overwrite the first, and both cover all
+        // of the writes
+        assert_eq!(
+            report,
+            RecoveryReport {
+                chunks_loaded: 2,
+                segments_replayed: 2,
+                writes_replayed: 0,
+                skipped_files: 0,
+            }
+        );
+        assert_eq!(restored.persisted_chunks("cpu").len(), 2);
+        assert_eq!(restored.catalog.state().await.version, 2);
+    }
+
+    #[tokio::test]
+    async fn keeps_snapshots_from_before_a_restart() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let w1 = write(1, "cpu bar=1 10");
+        let w2 = write(2, "cpu bar=2 20");
+        persist_segments(&store, &[Arc::clone(&w1), Arc::clone(&w2)]).await;
+
+        let db = make_db();
+        let positions = vec![(1, 1)].into_iter().collect();
+        snapshot_writes(&store, &db, "cpu", &[w1], positions).await;
+
+        // after a restart the partition's new chunk doesn't reuse the id of
+        // the persisted one, and its snapshot is added next to it
+        let restarted = db_with_wal_buffer();
+        rebuild_database(&restarted, 1, &db_path(&store), Arc::clone(&store), None)
+            .await
+            .unwrap();
+        assert_eq!(restarted.mutable_buffer_chunks("cpu")[0].id(), 1);
+
+        let positions = vec![(1, 2)].into_iter().collect();
+        snapshot_writes(&store, &restarted, "cpu", &[], positions).await;
+
+        let restored = db_with_wal_buffer();
+        let report = rebuild_database(&restored, 1, &db_path(&store), Arc::clone(&store), None)
+            .await
+            .unwrap();
+
+        assert_eq!(report.chunks_loaded, 2);
+        let ids: Vec<_> = restored
+            .persisted_chunks("cpu")
+            .iter()
+            .map(|chunk| chunk.id())
+            .collect();
+        assert_eq!(ids, vec![0, 1]);
+        assert_eq!(restored.catalog.state().await.chunks.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn skips_corrupt_files() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+        let w1 = write(1, "cpu bar=1 10");
+        persist_segments(&store, &[Arc::clone(&w1)]).await;
+
+        let mut bad_catalog = catalog::catalog_path(&db_path(&store));
+        bad_catalog.set_file_name("00000000000000000001.json");
+        put(&store, &bad_catalog, Bytes::from("not json")).await;
+
+        let bad_segment = object_store_path_for_segment(&db_path(&store), 2).unwrap();
+        put(&store, &bad_segment, Bytes::from("not a segment")).await;
+
+        let db = db_with_wal_buffer();
+        let report = rebuild_database(&db, 1, &db_path(&store), Arc::clone(&store), None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            report,
+            RecoveryReport {
+                chunks_loaded: 0,
+                segments_replayed: 1,
+                writes_replayed: 1,
+                skipped_files: 2,
+            }
+        );
+        assert_eq!(db.chunks("cpu").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn empty_object_store() {
+        let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
+
+        let db = db_with_wal_buffer();
+        let report = rebuild_database(&db, 1, &db_path(&store), Arc::clone(&store), None)
+            .await
+            .unwrap();
+
+        assert_eq!(report, RecoveryReport::default());
+        assert!(db.chunk_summaries().unwrap().is_empty());
+        assert_eq!(db.next_sequence(), 1);
+    }
+}
@@ -0,0 +1,370 @@
+//! This module contains the in-memory queue of replicated writes that still
+//! need to be sent to one of a database's host groups. Writes end up in the
+//! queue once they have been acknowledged by `replication_count` host groups
+//! (so the write has already returned success) or when a host group couldn't
+//! be reached. The server retries the queued writes in the background.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use data_types::{
+    data::ReplicatedWrite,
+    database_rules::{HostGroupId, ReplicationQueueOverflow},
+};
+use tracing::warn;
+
+/// A write waiting to be replicated to a host group
+#[derive(Debug, Clone)]
+pub(crate) struct QueuedWrite {
+    /// Identifies the entry in the queue so it can be removed once it has been
+    /// sent, even if other writes were queued in the meantime
+    pub id: u64,
+    pub host_group_id: HostGroupId,
+    pub write: Arc<ReplicatedWrite>,
+    pub queued_at: Instant,
+}
+
+/// The bounded per-database queue of writes to replicate in the background.
+/// The queue holds at most `max_size` writes. What happens to a write pushed
+/// onto a full queue depends on `overflow`: it is either rejected, or the
+/// oldest write is dropped to make room, which keeps the replication lag
+/// bounded. A `max_size` of zero disables the queue, so every write that
+/// couldn't be replicated synchronously is dropped, whatever `overflow` is.
+#[derive(Debug)]
+pub(crate) struct ReplicationQueue {
+    max_size: usize,
+    overflow: ReplicationQueueOverflow,
+    next_id: u64,
+    writes: VecDeque<QueuedWrite>,
+    dropped_writes: u64,
+}
+
+impl ReplicationQueue {
+    pub(crate) fn new(max_size: usize, overflow: ReplicationQueueOverflow) -> Self {
+        Self {
+            max_size,
+            overflow,
+            next_id: 0,
+            writes: VecDeque::new(),
+            dropped_writes: 0,
+        }
+    }
+
+    pub(crate) fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.max_size > 0
+    }
+
+    /// Queues the write to be sent to the host group. Returns false if the
+    /// queue is full and rejects writes, in which case the write isn't
+    /// queued. A full queue that drops writes, or a disabled queue, always
+    /// returns true.
+    pub(crate) fn push(&mut self, host_group_id: HostGroupId, write: Arc<ReplicatedWrite>) -> bool {
+        if !self.is_enabled() {
+            let (writer, sequence) = write.writer_and_sequence();
+            warn!(
+                %host_group_id,
+                writer,
+                sequence,
+                "replication queue disabled, dropping write"
+            );
+            self.dropped_writes += 1;
+            return true;
+        }
+
+        if self.writes.len() >= self.max_size
+            && self.overflow == ReplicationQueueOverflow::ReturnError
+        {
+            return false;
+        }
+
+        while self.writes.len() >= self.max_size {
+            if let Some(dropped) = self.writes.pop_front() {
+                let (writer, sequence) = dropped.write.writer_and_sequence();
+                warn!(
+                    host_group_id = %dropped.host_group_id,
+                    writer,
+                    sequence,
+                    "replication queue full, dropping oldest write"
+                );
+                self.dropped_writes += 1;
+            }
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.writes.push_back(QueuedWrite {
+            id,
+            host_group_id,
+            write,
+            queued_at: Instant::now(),
+        });
+
+        true
+    }
+
+    /// Returns true if `count` more writes can be pushed without rejecting
+    /// any. A queue that drops its oldest writes, or a disabled queue, never
+    /// rejects writes.
+    pub(crate) fn has_room_for(&self, count: usize) -> bool {
+        !self.is_enabled()
+            || self.overflow == ReplicationQueueOverflow::DropOldest
+            || self.writes.len() + count <= self.max_size
+    }
+
+    /// Queues the write for each of the host groups, all at once. The write
+    /// has already been stored and acknowledged by then, so if the queue
+    /// rejects writes and doesn't have room for every group, the write is
+    /// dropped for all of them instead of returning an error.
+    pub(crate) fn push_all(
+        &mut self,
+        host_group_ids: Vec<HostGroupId>,
+        write: &Arc<ReplicatedWrite>,
+    ) {
+        if self.has_room_for(host_group_ids.len()) {
+            for host_group_id in host_group_ids {
+                self.push(host_group_id, Arc::clone(write));
+            }
+            return;
+        }
+
+        let (writer, sequence) = write.writer_and_sequence();
+        for host_group_id in host_group_ids {
+            warn!(
+                %host_group_id,
+                writer,
+                sequence,
+                "replication queue full, dropping write"
+            );
+            self.dropped_writes += 1;
+        }
+    }
+
+    /// Changes how many writes the queue holds and what happens once it is
+    /// full, dropping the oldest writes if it holds more than that
+    pub(crate) fn configure(&mut self, max_size: usize, overflow: ReplicationQueueOverflow) {
+        self.max_size = max_size;
+        self.overflow = overflow;
+
+        if self.writes.len() > max_size {
+            let dropped = self.writes.len() - max_size;
+            warn!(dropped, "replication queue shrunk, dropping oldest writes");
+            self.writes.drain(..dropped);
+            self.dropped_writes += dropped as u64;
+        }
+    }
+
+    /// Returns a copy of the queued writes, oldest first
+    pub(crate) fn pending(&self) -> Vec<QueuedWrite> {
+        self.writes.iter().cloned().collect()
+    }
+
+    /// Removes the write with the id from the queue once it was sent. It may
+    /// already have been dropped if the queue filled up in the meantime.
+    pub(crate) fn remove(&mut self, id: u64) {
+        if let Some(pos) = self.writes.iter().position(|w| w.id == id) {
+            self.writes.remove(pos);
+        }
+    }
+
+    /// Returns a summary of the queue's depth and lag
+    pub(crate) fn status(&self) -> ReplicationQueueStatus {
+        let now = Instant::now();
+        let age = |w: &QueuedWrite| now.saturating_duration_since(w.queued_at);
+
+        let mut host_groups: BTreeMap<HostGroupId, HostGroupQueueStatus> = BTreeMap::new();
+        for write in &self.writes {
+            let status = host_groups
+                .entry(write.host_group_id.clone())
+                .or_insert_with(|| HostGroupQueueStatus {
+                    queued_writes: 0,
+                    lag: age(write),
+                });
+            status.queued_writes += 1;
+        }
+
+        ReplicationQueueStatus {
+            max_size: self.max_size,
+            queued_writes: self.writes.len(),
+            dropped_writes: self.dropped_writes,
+            lag: self.writes.front().map(age).unwrap_or_default(),
+            host_groups,
+        }
+    }
+}
+
+/// The depth and lag of a database's replication queue
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicationQueueStatus {
+    /// The configured `replication_queue_max_size`
+    pub max_size: usize,
+    /// The number of writes currently waiting to be replicated
+    pub queued_writes: usize,
+    /// The number of writes dropped since the database was loaded, either
+    /// because the queue was full and drops writes, disabled or shrunk
+    pub dropped_writes: u64,
+    /// How long the oldest write has been waiting in the queue
+    pub lag: Duration,
+    /// The queued writes and lag for each host group with queued writes
+    pub host_groups: BTreeMap<HostGroupId, HostGroupQueueStatus>,
+}
+
+/// The queued writes for a single host group
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostGroupQueueStatus {
+    pub queued_writes: usize,
+    pub lag: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(sequence: u64) -> Arc<ReplicatedWrite> {
+        let lines: Vec<_> = influxdb_line_protocol::parse_lines("cpu bar=1 10")
+            .map(|l| l.unwrap())
+            .collect();
+        Arc::new(data_types::data::lines_to_replicated_write(
+            1,
+            sequence,
+            &lines,
+            &data_types::database_rules::DatabaseRules::new(),
+        ))
+    }
+
+    #[test]
+    fn disabled_queue_drops_writes() {
+        let mut queue = ReplicationQueue::new(0, ReplicationQueueOverflow::DropOldest);
+        assert!(queue.push("az1".to_string(), write(1)));
+        let status = queue.status();
+        assert_eq!(status.queued_writes, 0);
+        assert_eq!(status.dropped_writes, 1);
+    }
+
+    #[test]
+    fn disabled_queue_drops_writes_that_would_be_rejected() {
+        let mut queue = ReplicationQueue::new(0, ReplicationQueueOverflow::ReturnError);
+        assert!(queue.has_room_for(1));
+        assert!(queue.push("az1".to_string(), write(1)));
+        let status = queue.status();
+        assert_eq!(status.queued_writes, 0);
+        assert_eq!(status.dropped_writes, 1);
+    }
+
+    #[test]
+    fn shrinking_queue_drops_oldest() {
+        let mut queue = ReplicationQueue::new(3, ReplicationQueueOverflow::ReturnError);
+        for sequence in 1..=3 {
+            assert!(queue.push("az1".to_string(), write(sequence)));
+        }
+
+        queue.configure(1, ReplicationQueueOverflow::ReturnError);
+        let sequences: Vec<_> = queue
+            .pending()
+            .iter()
+            .map(|w| w.write.writer_and_sequence().1)
+            .collect();
+        assert_eq!(sequences, vec![3]);
+        assert_eq!(queue.status().dropped_writes, 2);
+
+        queue.configure(0, ReplicationQueueOverflow::DropOldest);
+        assert!(queue.push("az1".to_string(), write(4)));
+        let status = queue.status();
+        assert_eq!(status.queued_writes, 0);
+        assert_eq!(status.dropped_writes, 4);
+    }
+
+    #[test]
+    fn full_queue_drops_oldest() {
+        let mut queue = ReplicationQueue::new(2, ReplicationQueueOverflow::DropOldest);
+        assert!(queue.push("az1".to_string(), write(1)));
+        assert!(queue.push("az2".to_string(), write(2)));
+        assert!(queue.push("az1".to_string(), write(3)));
+
+        let sequences: Vec<_> = queue
+            .pending()
+            .iter()
+            .map(|w| w.write.writer_and_sequence().1)
+            .collect();
+        assert_eq!(sequences, vec![2, 3]);
+
+        let status = queue.status();
+        assert_eq!(status.queued_writes, 2);
+        assert_eq!(status.dropped_writes, 1);
+        assert_eq!(status.host_groups.len(), 2);
+        assert_eq!(status.host_groups["az1"].queued_writes, 1);
+    }
+
+    #[test]
+    fn full_queue_rejects_writes() {
+        let mut queue = ReplicationQueue::new(2, ReplicationQueueOverflow::ReturnError);
+        assert!(queue.push("az1".to_string(), write(1)));
+        assert!(queue.push("az2".to_string(), write(2)));
+        assert!(!queue.push("az1".to_string(), write(3)));
+
+        let sequences: Vec<_> = queue
+            .pending()
+            .iter()
+            .map(|w| w.write.writer_and_sequence().1)
+            .collect();
+        assert_eq!(sequences, vec![1, 2]);
+        assert_eq!(queue.status().dropped_writes, 0);
+
+        // there is room again once a write has been sent
+        let first = queue.pending()[0].id;
+        queue.remove(first);
+        assert!(queue.push("az1".to_string(), write(3)));
+    }
+
+    #[test]
+    fn push_all_or_nothing() {
+        let mut queue = ReplicationQueue::new(2, ReplicationQueueOverflow::ReturnError);
+        assert!(queue.has_room_for(2));
+        queue.push_all(vec!["az1".to_string(), "az2".to_string()], &write(1));
+        assert_eq!(queue.pending().len(), 2);
+
+        // a write that doesn't fit for every group isn't queued for any
+        queue.remove(queue.pending()[0].id);
+        assert!(queue.has_room_for(1));
+        assert!(!queue.has_room_for(2));
+        queue.push_all(vec!["az1".to_string(), "az3".to_string()], &write(2));
+
+        let sequences: Vec<_> = queue
+            .pending()
+            .iter()
+            .map(|w| w.write.writer_and_sequence().1)
+            .collect();
+        assert_eq!(sequences, vec![1]);
+        assert_eq!(queue.status().dropped_writes, 2);
+
+        // a queue that drops the oldest writes always takes new ones
+        let mut queue = ReplicationQueue::new(1, ReplicationQueueOverflow::DropOldest);
+        assert!(queue.has_room_for(2));
+        queue.push_all(vec!["az1".to_string(), "az2".to_string()], &write(1));
+        assert_eq!(queue.pending().len(), 1);
+        assert_eq!(queue.status().dropped_writes, 1);
+    }
+
+    #[test]
+    fn remove_sent_write() {
+        let mut queue = ReplicationQueue::new(10, ReplicationQueueOverflow::ReturnError);
+        queue.push("az1".to_string(), write(1));
+        queue.push("az1".to_string(), write(2));
+
+        let first = queue.pending()[0].id;
+        queue.remove(first);
+        // removing an unknown id is a no-op
+        queue.remove(first);
+
+        let pending = queue.pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].write.writer_and_sequence().1, 2);
+    }
+}
@@ -1,11 +1,11 @@
 //! This module contains code for snapshotting a database chunk to Parquet
 //! files in object storage.
 use arrow_deps::{
-    arrow::datatypes::SchemaRef,
-    datafusion::physical_plan::SendableRecordBatchStream,
+    arrow::{datatypes::SchemaRef, error::Result as ArrowResult, record_batch::RecordBatch},
     parquet::{self, arrow::ArrowWriter, file::writer::TryClone},
 };
 use data_types::{
+    database_rules::WriterId,
     partition_metadata::{PartitionSummary, TableSummary},
     selection::Selection,
 };
@@ -13,18 +13,25 @@ use object_store::{path::ObjectStorePath, ObjectStore, ObjectStoreApi};
 use query::{predicate::EMPTY_PREDICATE, PartitionChunk};
 
 use std::{
-    io::{Cursor, Seek, SeekFrom, Write},
+    collections::BTreeMap,
+    io::{Seek, SeekFrom, Write},
     sync::Arc,
 };
 
 use bytes::Bytes;
-use futures::StreamExt;
+use futures::{channel::mpsc, SinkExt, Stream, StreamExt};
 use parking_lot::Mutex;
 use snafu::{ResultExt, Snafu};
 use tokio::sync::oneshot;
 use tracing::{error, info};
 use uuid::Uuid;
 
+use crate::{
+    catalog::{Catalog, CatalogChunk, CatalogFile},
+    jobs::{JobProgress, TrackedJob},
+    tracker::{TrackerId, TrackerRegistration},
+};
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("Partition error creating snapshot: {}", source))]
@@ -40,16 +47,16 @@ pub enum Error {
     #[snafu(display("Table position out of bounds: {}", position))]
     TablePositionOutOfBounds { position: usize },
 
-    #[snafu(display("Error generating json response: {}", source))]
-    JsonGenerationError { source: serde_json::Error },
+    #[snafu(display("Error adding the snapshot to the catalog: {}", source))]
+    UpdatingCatalog { source: crate::catalog::Error },
 
     #[snafu(display("Error opening Parquet Writer: {}", source))]
     OpeningParquetWriter {
         source: parquet::errors::ParquetError,
     },
 
-    #[snafu(display("Error writing Parquet to memory: {}", source))]
-    WritingParquetToMemory {
+    #[snafu(display("Error writing Parquet: {}", source))]
+    WritingParquet {
         source: parquet::errors::ParquetError,
     },
 
@@ -61,6 +68,12 @@ pub enum Error {
     #[snafu(display("Error writing to object store: {}", source))]
     WritingToObjectStore { source: object_store::Error },
 
+    #[snafu(display("Error finishing the upload of a Parquet file: {}", source))]
+    FinishingUpload { source: std::io::Error },
+
+    #[snafu(display("Error running the Parquet writer: {}", message))]
+    RunningParquetWriter { message: String },
+
     #[snafu(display("Error reading batches while writing to '{}': {}", file_name, source))]
     ReadingBatches {
         file_name: String,
@@ -79,10 +92,14 @@ where
     T: Send + Sync + 'static + PartitionChunk,
 {
     pub id: Uuid,
+    /// The id of the operation running the snapshot
+    pub operation_id: TrackerId,
     pub partition_summary: PartitionSummary,
-    pub metadata_path: object_store::path::Path,
+    pub wal_positions: BTreeMap<WriterId, u64>,
     pub data_path: object_store::path::Path,
+    db_path: object_store::path::Path,
     store: Arc<ObjectStore>,
+    catalog: Arc<Catalog>,
     chunk: Arc<T>,
     status: Mutex<Status>,
 }
@@ -93,12 +110,15 @@ where
 {
     fn new(
         partition_key: impl Into<String>,
-        metadata_path: object_store::path::Path,
-        data_path: object_store::path::Path,
+        db_path: &object_store::path::Path,
         store: Arc<ObjectStore>,
+        catalog: Arc<Catalog>,
         partition: Arc<T>,
         tables: Vec<TableSummary>,
+        wal_positions: BTreeMap<WriterId, u64>,
+        operation_id: TrackerId,
     ) -> Self {
+        let partition_key = partition_key.into();
         let table_states = vec![TableState::NotStarted; tables.len()];
 
         let status = Status {
@@ -106,15 +126,20 @@ where
             ..Default::default()
         };
 
+        let id = Uuid::new_v4();
+
         Self {
-            id: Uuid::new_v4(),
+            id,
+            operation_id,
+            data_path: snapshot_data_path(db_path, &partition_key, id),
+            db_path: db_path.clone(),
             partition_summary: PartitionSummary {
-                key: partition_key.into(),
+                key: partition_key,
                 tables,
             },
-            metadata_path,
-            data_path,
+            wal_positions,
             store,
+            catalog,
             chunk: partition,
             status: Mutex::new(status),
         }
@@ -142,9 +167,14 @@ where
         }
     }
 
-    fn mark_meta_written(&self) {
+    fn mark_catalog_updated(&self) {
         let mut status = self.status.lock();
-        status.meta_written = true;
+        status.catalog_updated = true;
+    }
+
+    /// The id of the chunk being snapshotted
+    pub fn chunk_id(&self) -> u32 {
+        self.chunk.id()
     }
 
     pub fn finished(&self) -> bool {
@@ -161,7 +191,12 @@ where
         status.stop_on_next_update
     }
 
-    async fn run(&self, notify: Option<oneshot::Sender<()>>) -> Result<()> {
+    async fn run(
+        &self,
+        notify: Option<oneshot::Sender<()>>,
+        progress: Arc<JobProgress>,
+    ) -> Result<()> {
+        let mut files = vec![];
         while let Some((pos, table_name)) = self.next_table() {
             // get all the data in this chunk:
             let stream = self
@@ -172,36 +207,32 @@ where
                 .context(PartitionError)?;
 
             let schema = stream.schema();
-
-            let mut location = self.data_path.clone();
-            let file_name = format!("{}.parquet", table_name);
-            location.set_file_name(&file_name);
-            let data = Self::parquet_stream_to_bytes(stream, schema).await?;
-            self.write_to_object_store(data, &location).await?;
+            let table = self.partition_summary.tables[pos].clone();
+            let file =
+                write_table_file(&self.store, &self.data_path, table, schema, stream).await?;
+            files.push(file);
             self.mark_table_finished(pos);
+            progress.complete_task();
 
             if self.should_stop() {
                 return StoppedEarly.fail();
             }
         }
 
-        let mut partition_meta_path = self.metadata_path.clone();
-        let key = format!("{}.json", &self.partition_summary.key);
-        partition_meta_path.set_file_name(&key);
-        let json_data = serde_json::to_vec(&self.partition_summary).context(JsonGenerationError)?;
-        let data = Bytes::from(json_data);
-        let len = data.len();
-        let stream_data = std::io::Result::Ok(data);
-        self.store
-            .put(
-                &partition_meta_path,
-                futures::stream::once(async move { stream_data }),
-                Some(len),
-            )
+        // the files are only used once the catalog refers to them
+        let chunk = CatalogChunk {
+            partition_key: self.partition_summary.key.clone(),
+            chunk_id: self.chunk.id(),
+            snapshot_id: self.id,
+            wal_positions: self.wal_positions.clone(),
+            files,
+        };
+        self.catalog
+            .add_chunk(&self.store, &self.db_path, chunk)
             .await
-            .context(WritingToObjectStore)?;
+            .context(UpdatingCatalog)?;
 
-        self.mark_meta_written();
+        self.mark_catalog_updated();
 
         if let Some(notify) = notify {
             if let Err(e) = notify.send(()) {
@@ -212,49 +243,6 @@ where
         Ok(())
     }
 
-    /// Convert the record batches in stream to bytes in a parquet file stream
-    /// in memory
-    ///
-    /// TODO: connect the streams to avoid buffering into Vec<u8>
-    async fn parquet_stream_to_bytes(
-        mut stream: SendableRecordBatchStream,
-        schema: SchemaRef,
-    ) -> Result<Vec<u8>> {
-        let mem_writer = MemWriter::default();
-        {
-            let mut writer = ArrowWriter::try_new(mem_writer.clone(), schema, None)
-                .context(OpeningParquetWriter)?;
-            while let Some(batch) = stream.next().await {
-                let batch = batch.context(ReadingStream)?;
-                writer.write(&batch).context(WritingParquetToMemory)?;
-            }
-            writer.close().context(ClosingParquetWriter)?;
-        } // drop the reference to the MemWriter that the SerializedFileWriter has
-
-        Ok(mem_writer
-            .into_inner()
-            .expect("Nothing else should have a reference here"))
-    }
-
-    async fn write_to_object_store(
-        &self,
-        data: Vec<u8>,
-        file_name: &object_store::path::Path,
-    ) -> Result<()> {
-        let len = data.len();
-        let data = Bytes::from(data);
-        let stream_data = Result::Ok(data);
-
-        self.store
-            .put(
-                &file_name,
-                futures::stream::once(async move { stream_data }),
-                Some(len),
-            )
-            .await
-            .context(WritingToObjectStore)
-    }
-
     fn set_error(&self, e: Error) {
         let mut status = self.status.lock();
         status.error = Some(e);
@@ -271,18 +259,98 @@ pub enum TableState {
 #[derive(Debug, Default)]
 pub struct Status {
     table_states: Vec<TableState>,
-    meta_written: bool,
+    catalog_updated: bool,
     stop_on_next_update: bool,
     error: Option<Error>,
 }
 
+/// Returns the directory the Parquet files of a snapshot of one of a
+/// partition's chunks are written to, given the database's root path in
+/// object storage
+pub(crate) fn snapshot_data_path(
+    db_path: &object_store::path::Path,
+    partition_key: &str,
+    snapshot_id: Uuid,
+) -> object_store::path::Path {
+    let mut path = db_path.clone();
+    path.push_all_dirs(&["data", partition_key, &snapshot_id.to_string()]);
+    path
+}
+
+/// Writes the rows of one of a chunk's tables, which `table` summarizes, as a
+/// Parquet file named after the table to the chunk's data directory, and
+/// returns the catalog's entry for the file
+pub(crate) async fn write_table_file<S>(
+    store: &ObjectStore,
+    data_path: &object_store::path::Path,
+    table: TableSummary,
+    schema: SchemaRef,
+    stream: S,
+) -> Result<CatalogFile>
+where
+    S: Stream<Item = ArrowResult<RecordBatch>> + Unpin + Send + 'static,
+{
+    let mut location = data_path.clone();
+    let file_name = format!("{}.parquet", table.name);
+    location.set_file_name(&file_name);
+    let size = write_parquet_to_object_store(store, stream, schema, &location).await?;
+
+    Ok(CatalogFile {
+        file_name,
+        time_range: table.time_range(),
+        table,
+        size,
+    })
+}
+
+/// Writes the record batches in the stream as a Parquet file to the
+/// object store. The file is encoded on a blocking thread and uploaded
+/// as it is written, so only a bounded number of its parts are held in
+/// memory, however big the table is. Returns the size of the file.
+async fn write_parquet_to_object_store<S>(
+    store: &ObjectStore,
+    stream: S,
+    schema: SchemaRef,
+    location: &object_store::path::Path,
+) -> Result<u64>
+where
+    S: Stream<Item = ArrowResult<RecordBatch>> + Unpin + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(UPLOAD_QUEUE_LEN);
+    let writer = UploadWriter::new(sender);
+
+    let upload = store.put(location, receiver, None);
+    let write = tokio::task::spawn_blocking(move || write_parquet(stream, schema, writer));
+    let (upload, write) = futures::join!(upload, write);
+
+    // a failed write is also sent to the upload so that no truncated file
+    // is stored, and the error of a failed upload explains why the write
+    // stopped, so the upload's error is reported first
+    upload.context(WritingToObjectStore)?;
+    match write {
+        Ok(result) => result,
+        Err(e) => RunningParquetWriter {
+            message: e.to_string(),
+        }
+        .fail(),
+    }
+}
+
+/// Snapshots the chunk to the paths below the database's root path
+/// `db_path` in the background and adds it to the database's catalog. The
+/// snapshot runs as the registered job, whose progress counts the tables
+/// written so far, and `notify` is sent once all of them have been written
+/// and the catalog has been updated.
+#[allow(clippy::too_many_arguments)]
 pub fn snapshot_chunk<T>(
-    metadata_path: object_store::path::Path,
-    data_path: object_store::path::Path,
+    db_path: &object_store::path::Path,
     store: Arc<ObjectStore>,
+    catalog: Arc<Catalog>,
     partition_key: &str,
     chunk: Arc<T>,
+    wal_positions: BTreeMap<WriterId, u64>,
     notify: Option<oneshot::Sender<()>>,
+    registration: TrackerRegistration<TrackedJob>,
 ) -> Result<Arc<Snapshot<T>>>
 where
     T: Send + Sync + 'static + PartitionChunk,
@@ -292,72 +360,175 @@ where
         .map_err(|e| Box::new(e) as _)
         .context(PartitionError)?;
 
+    let progress = Arc::clone(registration.metadata().progress());
+    progress.add_tasks(table_stats.len());
+
     let snapshot = Snapshot::new(
         partition_key.to_string(),
-        metadata_path,
-        data_path,
+        db_path,
         store,
+        catalog,
         chunk,
         table_stats,
+        wal_positions,
+        registration.id(),
     );
     let snapshot = Arc::new(snapshot);
 
     let return_snapshot = Arc::clone(&snapshot);
 
-    tokio::spawn(async move {
+    tokio::spawn(registration.track(async move {
         info!(
             "starting snapshot of {} to {}",
             &snapshot.partition_summary.key,
             &snapshot.data_path.display()
         );
-        if let Err(e) = snapshot.run(notify).await {
+        if let Err(e) = snapshot.run(notify, progress).await {
             error!("error running snapshot: {:?}", e);
             snapshot.set_error(e);
         }
-    });
+    }));
 
     Ok(return_snapshot)
 }
 
-#[derive(Debug, Default, Clone)]
-struct MemWriter {
-    mem: Arc<Mutex<Cursor<Vec<u8>>>>,
+/// Writes the record batches in the stream as a Parquet file to the writer,
+/// and then finishes its upload, returning the size of the file.
+/// This blocks, so it must be run on a blocking thread.
+fn write_parquet<S>(stream: S, schema: SchemaRef, writer: UploadWriter) -> Result<u64>
+where
+    S: Stream<Item = ArrowResult<RecordBatch>> + Unpin,
+{
+    let result = write_parquet_batches(stream, schema, writer.clone())
+        .and_then(|()| writer.finish().context(FinishingUpload));
+
+    if let Err(e) = &result {
+        writer.abort(e);
+    }
+    result
+}
+
+fn write_parquet_batches<S>(mut stream: S, schema: SchemaRef, writer: UploadWriter) -> Result<()>
+where
+    S: Stream<Item = ArrowResult<RecordBatch>> + Unpin,
+{
+    let mut parquet_writer =
+        ArrowWriter::try_new(writer, schema, None).context(OpeningParquetWriter)?;
+    while let Some(batch) = futures::executor::block_on(stream.next()) {
+        let batch = batch.context(ReadingStream)?;
+        parquet_writer.write(&batch).context(WritingParquet)?;
+    }
+    parquet_writer.close().context(ClosingParquetWriter)?;
+    Ok(())
+}
+
+/// The number of bytes the Parquet writer's output is uploaded in
+const UPLOAD_PART_SIZE: usize = 1024 * 1024;
+
+/// The number of parts that can be waiting to be uploaded before the Parquet
+/// writer is blocked
+const UPLOAD_QUEUE_LEN: usize = 4;
+
+/// A `Write` for the Parquet writer that sends what is written to an object
+/// store upload in parts of `UPLOAD_PART_SIZE` bytes. Writing blocks while
+/// the upload is `UPLOAD_QUEUE_LEN` parts behind.
+///
+/// The Parquet writer only seeks to find out how much it has written, which
+/// is the only seek supported.
+#[derive(Debug, Clone)]
+struct UploadWriter {
+    inner: Arc<Mutex<UploadWriterState>>,
+}
+
+#[derive(Debug)]
+struct UploadWriterState {
+    sender: mpsc::Sender<std::io::Result<Bytes>>,
+    buffer: Vec<u8>,
+    position: u64,
 }
 
-impl MemWriter {
-    /// Returns the inner buffer as long as there are no other references to the
-    /// Arc.
-    pub fn into_inner(self) -> Option<Vec<u8>> {
-        Arc::try_unwrap(self.mem)
-            .ok()
-            .map(|mutex| mutex.into_inner().into_inner())
+impl UploadWriter {
+    fn new(sender: mpsc::Sender<std::io::Result<Bytes>>) -> Self {
+        let state = UploadWriterState {
+            sender,
+            buffer: Vec::with_capacity(UPLOAD_PART_SIZE),
+            position: 0,
+        };
+        Self {
+            inner: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Sends the last part of the file and ends the upload, returning the
+    /// size of what was written
+    fn finish(&self) -> std::io::Result<u64> {
+        let mut inner = self.inner.lock();
+        inner.send_part()?;
+        inner.sender.close_channel();
+        Ok(inner.position)
+    }
+
+    /// Fails the upload with the error, so that the file isn't stored
+    fn abort(&self, e: &Error) {
+        let mut inner = self.inner.lock();
+        let e = std::io::Error::new(std::io::ErrorKind::Other, e.to_string());
+        // the upload may have already stopped, in which case there is nothing
+        // left to fail
+        let _ = futures::executor::block_on(inner.sender.send(Err(e)));
+        inner.sender.close_channel();
     }
 }
 
-impl Write for MemWriter {
+impl UploadWriterState {
+    fn send_part(&mut self) -> std::io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let part = std::mem::replace(&mut self.buffer, Vec::with_capacity(UPLOAD_PART_SIZE));
+        futures::executor::block_on(self.sender.send(Ok(Bytes::from(part)))).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "the object store upload stopped",
+            )
+        })
+    }
+}
+
+impl Write for UploadWriter {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let mut inner = self.mem.lock();
-        inner.write(buf)
+        let mut inner = self.inner.lock();
+        inner.buffer.extend_from_slice(buf);
+        inner.position += buf.len() as u64;
+
+        if inner.buffer.len() >= UPLOAD_PART_SIZE {
+            inner.send_part()?;
+        }
+        Ok(buf.len())
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        let mut inner = self.mem.lock();
-        inner.flush()
+        // parts are only sent once full, to keep the number of them down
+        Ok(())
     }
 }
 
-impl Seek for MemWriter {
+impl Seek for UploadWriter {
     fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        let mut inner = self.mem.lock();
-        inner.seek(pos)
+        let inner = self.inner.lock();
+        match pos {
+            SeekFrom::Current(0) => Ok(inner.position),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "an object store upload can't seek",
+            )),
+        }
     }
 }
 
-impl TryClone for MemWriter {
+impl TryClone for UploadWriter {
     fn try_clone(&self) -> std::io::Result<Self> {
-        Ok(Self {
-            mem: Arc::clone(&self.mem),
-        })
+        Ok(self.clone())
     }
 }
 
@@ -367,7 +538,8 @@ mod tests {
     use read_buffer::Database as ReadBufferDb;
 
     use super::*;
-    use data_types::database_rules::DatabaseRules;
+    use crate::jobs::JobRegistry;
+    use data_types::{database_rules::DatabaseRules, job::Job};
     use futures::TryStreamExt;
     use mutable_buffer::{chunk::Chunk as ChunkWB, MutableBufferDb};
     use object_store::memory::InMemory;
@@ -388,40 +560,97 @@ mem,host=A,region=west used=45 1
 
         let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
         let (tx, rx) = tokio::sync::oneshot::channel();
-        let mut metadata_path = store.new_path();
-        metadata_path.push_dir("meta");
-
-        let mut data_path = store.new_path();
-        data_path.push_dir("data");
+        let db_path = store.new_path();
 
         let chunk = Arc::clone(&db.chunks("1970-01-01T00")[0]);
+        let jobs = JobRegistry::new();
+        let registration = jobs.register(TrackedJob::new(Job::SnapshotChunk {
+            db_name: "test_db".to_string(),
+            partition_key: "testaroo".to_string(),
+            chunk_id: chunk.id(),
+        }));
+        let job = registration.metadata();
+
+        let catalog = Arc::new(Catalog::default());
 
         let snapshot = snapshot_chunk(
-            metadata_path.clone(),
-            data_path,
+            &db_path,
             Arc::clone(&store),
+            Arc::clone(&catalog),
             "testaroo",
             chunk,
+            BTreeMap::new(),
             Some(tx),
+            registration,
         )
         .unwrap();
 
         rx.await.unwrap();
 
-        let mut location = metadata_path;
-        location.set_file_name("testaroo.json");
+        // both tables were written
+        assert_eq!(job.progress().tasks(), (2, 2));
+
+        // the catalog lists both files, which are stored with the recorded
+        // size
+        let state = catalog.state().await;
+        assert_eq!(state.version, 1);
+        assert_eq!(state.chunks.len(), 1);
+
+        let catalog_chunk = &state.chunks[0];
+        assert_eq!(catalog_chunk.partition_key, "testaroo");
+        assert_eq!(catalog_chunk.snapshot_id, snapshot.id);
+        assert_eq!(
+            catalog_chunk.table_summaries(),
+            snapshot.partition_summary.tables
+        );
+        assert_eq!(catalog_chunk.data_path(&db_path), snapshot.data_path);
 
-        let summary = store
-            .get(&location)
-            .await
-            .unwrap()
-            .map_ok(|b| bytes::BytesMut::from(&b[..]))
-            .try_concat()
-            .await
+        let cpu = catalog_chunk
+            .files
+            .iter()
+            .find(|file| file.file_name == "cpu.parquet")
             .unwrap();
+        assert_eq!(cpu.table.name, "cpu");
+        assert_eq!(cpu.time_range, Some((1, 10)));
+
+        for (file, location) in catalog_chunk
+            .files
+            .iter()
+            .zip(catalog_chunk.file_paths(&db_path))
+        {
+            let data = store
+                .get(&location)
+                .await
+                .unwrap()
+                .map_ok(|b| bytes::BytesMut::from(&b[..]))
+                .try_concat()
+                .await
+                .unwrap();
 
-        let meta: PartitionSummary = serde_json::from_slice(&*summary).unwrap();
-        assert_eq!(meta, snapshot.partition_summary);
+            assert_eq!(data.len() as u64, file.size);
+        }
+    }
+
+    #[test]
+    fn upload_writer_sends_parts() {
+        let (sender, receiver) = mpsc::channel(UPLOAD_QUEUE_LEN);
+        let mut writer = UploadWriter::new(sender);
+
+        let data = vec![1u8; UPLOAD_PART_SIZE];
+        writer.write_all(&data).unwrap();
+        writer.write_all(&data).unwrap();
+        writer.write_all(&data[..10]).unwrap();
+        assert_eq!(
+            writer.seek(SeekFrom::Current(0)).unwrap(),
+            (UPLOAD_PART_SIZE * 2 + 10) as u64
+        );
+        assert!(writer.seek(SeekFrom::Start(0)).is_err());
+        let size = writer.finish().unwrap();
+        assert_eq!(size, (UPLOAD_PART_SIZE * 2 + 10) as u64);
+
+        let parts: Vec<_> = futures::executor::block_on(receiver.collect());
+        let sizes: Vec<_> = parts.into_iter().map(|part| part.unwrap().len()).collect();
+        assert_eq!(sizes, vec![UPLOAD_PART_SIZE, UPLOAD_PART_SIZE, 10]);
     }
 
     #[test]
@@ -443,13 +672,18 @@ mem,host=A,region=west used=45 1
 
         let store = Arc::new(ObjectStore::new_in_memory(InMemory::new()));
         let chunk = DBChunk::new_mb(Arc::new(ChunkWB::new(11)));
-        let mut metadata_path = store.new_path();
-        metadata_path.push_dir("meta");
+        let db_path = store.new_path();
 
-        let mut data_path = store.new_path();
-        data_path.push_dir("data");
-
-        let snapshot = Snapshot::new("testaroo", metadata_path, data_path, store, chunk, tables);
+        let snapshot = Snapshot::new(
+            "testaroo",
+            &db_path,
+            store,
+            Default::default(),
+            chunk,
+            tables,
+            BTreeMap::new(),
+            TrackerId::from(0),
+        );
 
         let (pos, name) = snapshot.next_table().unwrap();
         assert_eq!(0, pos);
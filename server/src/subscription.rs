@@ -0,0 +1,251 @@
+//! This module contains the stream of replicated writes served to pull based
+//! subscribers from a database's WAL buffer. The stream starts with the writes
+//! in the buffer's segments that the subscriber hasn't seen yet, then tails the
+//! buffer as new writes are appended. The stream ends once the database has
+//! been deleted from the server and dropped.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Weak},
+};
+
+use data_types::{data::ReplicatedWrite, database_rules::WriterId};
+use futures::{stream, Stream};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::{buffer::WriterSequence, db::Db, matcher::CompiledMatcher};
+
+/// Returns a stream of the writes in the database's WAL buffer that come after
+/// the writer positions in `since`, followed by new writes as they are
+/// appended. Only the parts of each write that pass the matcher are returned.
+/// The stream doesn't keep the database alive, so it ends once the database is
+/// dropped. Returns `None` if the database has no WAL buffer.
+pub(crate) fn subscribe(
+    db: Arc<Db>,
+    since: Vec<WriterSequence>,
+    matcher: Option<CompiledMatcher>,
+) -> Option<impl Stream<Item = Arc<ReplicatedWrite>> + Send + 'static> {
+    let (pending, receiver) = read_buffer(&db, &since)?;
+    let positions = since.iter().map(|s| (s.id, s.sequence)).collect();
+
+    let subscription = Subscription {
+        db: Arc::downgrade(&db),
+        matcher,
+        positions,
+        pending: pending.into(),
+        receiver,
+    };
+
+    Some(stream::unfold(
+        subscription,
+        |mut subscription| async move {
+            let write = subscription.next().await?;
+            Some((write, subscription))
+        },
+    ))
+}
+
+#[derive(Debug)]
+struct Subscription {
+    /// Weak so that the stream doesn't keep the database, and thus the
+    /// sender of `receiver`, alive once the database is deleted
+    db: Weak<Db>,
+    matcher: Option<CompiledMatcher>,
+    /// The last write returned (or filtered out) for each writer
+    positions: BTreeMap<WriterId, u64>,
+    /// Buffered writes that haven't been returned yet
+    pending: VecDeque<Arc<ReplicatedWrite>>,
+    receiver: broadcast::Receiver<Arc<ReplicatedWrite>>,
+}
+
+impl Subscription {
+    async fn next(&mut self) -> Option<Arc<ReplicatedWrite>> {
+        loop {
+            let write = match self.pending.pop_front() {
+                Some(write) => write,
+                None => match self.receiver.recv().await {
+                    Ok(write) => write,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(
+                            skipped,
+                            "subscriber fell behind the WAL buffer, catching up from its segments"
+                        );
+                        self.catch_up()?;
+                        continue;
+                    }
+                    // the database has been dropped
+                    Err(RecvError::Closed) => return None,
+                },
+            };
+
+            let (writer, sequence) = write.writer_and_sequence();
+            self.positions.insert(writer, sequence);
+
+            if let Some(write) = self.filter(write) {
+                return Some(write);
+            }
+        }
+    }
+
+    /// Re-reads the writes after the current positions from the buffer's
+    /// segments, for when the subscriber missed writes while it was behind.
+    fn catch_up(&mut self) -> Option<()> {
+        let since: Vec<_> = self
+            .positions
+            .iter()
+            .map(|(&id, &sequence)| WriterSequence { id, sequence })
+            .collect();
+
+        let db = self.db.upgrade()?;
+        let (pending, receiver) = read_buffer(&db, &since)?;
+        self.pending = pending.into();
+        self.receiver = receiver;
+
+        Some(())
+    }
+
+    fn filter(&self, write: Arc<ReplicatedWrite>) -> Option<Arc<ReplicatedWrite>> {
+        let matcher = match &self.matcher {
+            Some(matcher) => matcher,
+            None => return Some(write),
+        };
+
+        let filtered = match matcher.filter(&write)? {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(filtered) => Some(filtered),
+        };
+
+        Some(filtered.map_or(write, Arc::new))
+    }
+}
+
+/// Reads the buffered writes after `since` and subscribes to the writes
+/// appended after them while holding the buffer's lock, so that no write is
+/// missed or returned twice.
+fn read_buffer(
+    db: &Db,
+    since: &[WriterSequence],
+) -> Option<(
+    Vec<Arc<ReplicatedWrite>>,
+    broadcast::Receiver<Arc<ReplicatedWrite>>,
+)> {
+    let buffer = db.wal_buffer.as_ref()?.lock();
+    Some((buffer.writes_since_each(since), buffer.subscribe()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+    use data_types::{
+        data::lines_to_replicated_write,
+        database_rules::{DatabaseRules, MatchTables, Matcher, WalBufferRollover},
+    };
+    use futures::StreamExt;
+    use influxdb_line_protocol::parse_lines;
+    use read_buffer::Database as ReadBufferDb;
+
+    fn write(writer: u32, sequence: u64, lp: &str) -> Arc<ReplicatedWrite> {
+        let lines: Vec<_> = parse_lines(lp).map(|l| l.unwrap()).collect();
+        Arc::new(lines_to_replicated_write(
+            writer,
+            sequence,
+            &lines,
+            &DatabaseRules::new(),
+        ))
+    }
+
+    fn db() -> Arc<Db> {
+        let buffer = Buffer::new(1 << 20, 1 << 10, WalBufferRollover::ReturnError, false);
+        Arc::new(Db::new(
+            DatabaseRules::new(),
+            None,
+            ReadBufferDb::new(),
+            Some(buffer),
+        ))
+    }
+
+    fn append(db: &Db, write: &Arc<ReplicatedWrite>) {
+        db.wal_buffer
+            .as_ref()
+            .unwrap()
+            .lock()
+            .append(Arc::clone(write))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn streams_buffered_then_new_writes() {
+        let db = db();
+        let w1 = write(1, 1, "cpu bar=1 10");
+        let w2 = write(2, 1, "cpu bar=2 10");
+        let w3 = write(1, 2, "cpu bar=3 10");
+        let w4 = write(2, 2, "cpu bar=4 10");
+        append(&db, &w1);
+        append(&db, &w2);
+        append(&db, &w3);
+
+        // writer 1 has seen its first write, writer 2 isn't known yet
+        let since = vec![WriterSequence { id: 1, sequence: 1 }];
+        let stream = subscribe(Arc::clone(&db), since, None).unwrap();
+        futures::pin_mut!(stream);
+
+        assert_eq!(stream.next().await.unwrap(), w2);
+        assert_eq!(stream.next().await.unwrap(), w3);
+
+        append(&db, &w4);
+        assert_eq!(stream.next().await.unwrap(), w4);
+    }
+
+    #[tokio::test]
+    async fn filters_writes_with_matcher() {
+        let db = db();
+        append(&db, &write(1, 1, "mem bar=1 10"));
+        append(
+            &db,
+            &write(1, 2, "cpu,host=a bar=2 10\ncpu,host=b bar=3 10"),
+        );
+
+        let matcher = CompiledMatcher::new(&Matcher {
+            tables: MatchTables::Table("cpu".to_string()),
+            predicate: Some("host = 'b'".to_string()),
+        })
+        .unwrap();
+        let stream = subscribe(Arc::clone(&db), vec![], Some(matcher)).unwrap();
+        futures::pin_mut!(stream);
+
+        let filtered = stream.next().await.unwrap();
+        assert_eq!(filtered.writer_and_sequence(), (1, 2));
+        let text = filtered.to_string();
+        assert!(text.contains("host:b"));
+        assert!(!text.contains("host:a"));
+    }
+
+    #[tokio::test]
+    async fn ends_when_db_is_dropped() {
+        let db = db();
+        let w1 = write(1, 1, "cpu bar=1 10");
+        append(&db, &w1);
+
+        let stream = subscribe(Arc::clone(&db), vec![], None).unwrap();
+        futures::pin_mut!(stream);
+        assert_eq!(stream.next().await.unwrap(), w1);
+
+        drop(db);
+        assert!(stream.next().await.is_none());
+    }
+
+    #[test]
+    fn no_wal_buffer() {
+        let db = Arc::new(Db::new(
+            DatabaseRules::new(),
+            None,
+            ReadBufferDb::new(),
+            None,
+        ));
+
+        assert!(subscribe(db, vec![], None).is_none());
+    }
+}
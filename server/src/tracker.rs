@@ -14,6 +14,24 @@ use pin_project::{pin_project, pinned_drop};
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TrackerId(usize);
 
+impl From<usize> for TrackerId {
+    fn from(id: usize) -> Self {
+        Self(id)
+    }
+}
+
+impl From<TrackerId> for usize {
+    fn from(id: TrackerId) -> Self {
+        id.0
+    }
+}
+
+impl std::fmt::Display for TrackerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 #[derive(Debug)]
 struct Tracker<T> {
     data: T,
@@ -66,7 +84,6 @@ impl<T> TrackerRegistry<T> {
     ///
     /// Note: If the future is currently executing, termination
     /// will only occur when the future yields (returns from poll)
-    #[allow(dead_code)]
     pub fn terminate(&self, id: TrackerId) -> bool {
         if let Some(meta) = self.inner.trackers.lock().get_mut(&id) {
             meta.abort.abort();
@@ -80,7 +97,11 @@ impl<T> TrackerRegistry<T> {
         self.inner.trackers.lock().remove(id);
     }
 
-    fn track(&self, metadata: T) -> (TrackerId, future::AbortRegistration) {
+    /// Registers the metadata of a future that is yet to be created, for
+    /// when the future needs to know its `TrackerId`. The future is tracked
+    /// once it is passed to `TrackerRegistration::track`, and the metadata
+    /// is unregistered if the registration is dropped before that.
+    pub fn register(&self, metadata: T) -> TrackerRegistration<T> {
         let id = TrackerId(self.inner.id.fetch_add(1, Ordering::Relaxed));
         let (abort_handle, abort_registration) = future::AbortHandle::new_pair();
 
@@ -92,14 +113,26 @@ impl<T> TrackerRegistry<T> {
             },
         );
 
-        (id, abort_registration)
+        TrackerRegistration {
+            reg: self.clone(),
+            id,
+            abort_registration: Some(abort_registration),
+        }
     }
 }
 
 impl<T: Clone> TrackerRegistry<T> {
+    /// Returns the metadata of the tracked future with the provided ID
+    pub fn get(&self, id: TrackerId) -> Option<T> {
+        self.inner
+            .trackers
+            .lock()
+            .get(&id)
+            .map(|tracker| tracker.data.clone())
+    }
+
     /// Returns a list of tracked futures, with their accompanying IDs and
     /// metadata
-    #[allow(dead_code)]
     pub fn tracked(&self) -> Vec<(TrackerId, T)> {
         // TODO: Improve this - (#711)
         self.inner
@@ -118,17 +151,59 @@ pub trait TrackedFutureExt: Future {
     where
         Self: Sized,
     {
-        let (id, registration) = reg.track(metadata);
+        reg.register(metadata).track(self)
+    }
+}
+
+impl<T: ?Sized> TrackedFutureExt for T where T: Future {}
+
+/// The registration of a future's metadata returned by
+/// `TrackerRegistry::register`
+#[derive(Debug)]
+pub struct TrackerRegistration<T> {
+    reg: TrackerRegistry<T>,
+    id: TrackerId,
+    abort_registration: Option<future::AbortRegistration>,
+}
+
+impl<T> TrackerRegistration<T> {
+    /// The ID the future will be tracked with
+    pub fn id(&self) -> TrackerId {
+        self.id
+    }
+
+    /// Tracks the future with the registered metadata
+    pub fn track<F: Future>(mut self, fut: F) -> TrackedFuture<F, T> {
+        let abort_registration = self
+            .abort_registration
+            .take()
+            .expect("future is only tracked once");
 
         TrackedFuture {
-            inner: future::Abortable::new(self, registration),
-            reg: reg.clone(),
-            id,
+            inner: future::Abortable::new(fut, abort_registration),
+            reg: self.reg.clone(),
+            id: self.id,
         }
     }
 }
 
-impl<T: ?Sized> TrackedFutureExt for T where T: Future {}
+impl<T: Clone> TrackerRegistration<T> {
+    /// Returns the registered metadata
+    pub fn metadata(&self) -> T {
+        self.reg
+            .get(self.id)
+            .expect("metadata stays registered until the future completes")
+    }
+}
+
+impl<T> Drop for TrackerRegistration<T> {
+    fn drop(&mut self) {
+        // the metadata of a future that was never created is unregistered
+        if self.abort_registration.is_some() {
+            self.reg.untrack(&self.id)
+        }
+    }
+}
 
 /// The `Future` returned by `TrackedFutureExt::track()`
 /// Unregisters the future from the registered `TrackerRegistry` on drop
@@ -223,6 +298,29 @@ mod tests {
         assert_eq!(reg.tracked().len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_register() {
+        let reg = TrackerRegistry::new();
+
+        let registration = reg.register(1);
+        let id = registration.id();
+        assert_eq!(reg.get(id), Some(1));
+
+        let (sender, receive) = oneshot::channel();
+        let task = tokio::spawn(registration.track(receive));
+        assert_eq!(reg.get(id), Some(1));
+
+        sender.send(()).unwrap();
+        task.await.unwrap().unwrap().unwrap();
+        assert_eq!(reg.get(id), None);
+
+        // dropping a registration before tracking a future unregisters it
+        let registration = reg.register(2);
+        assert_eq!(reg.tracked().len(), 1);
+        std::mem::drop(registration);
+        assert_eq!(reg.tracked().len(), 0);
+    }
+
     #[tokio::test]
     async fn test_terminate() {
         let reg = TrackerRegistry::new();
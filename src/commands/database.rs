@@ -1,3 +1,4 @@
+use generated_types::google::protobuf::Empty;
 use influxdb_iox_client::{
     connection::Builder,
     management::{generated_types::*, *},
@@ -13,9 +14,36 @@ pub enum Error {
     #[error("Error getting database: {0}")]
     GetDatabaseError(#[from] GetDatabaseError),
 
+    #[error("Error updating database: {0}")]
+    UpdateDatabaseError(#[from] UpdateDatabaseError),
+
+    #[error("Error deleting database: {0}")]
+    DeleteDatabaseError(#[from] DeleteDatabaseError),
+
     #[error("Error listing databases: {0}")]
     ListDatabaseError(#[from] ListDatabaseError),
 
+    #[error("Error listing partitions: {0}")]
+    ListPartitionsError(#[from] ListPartitionsError),
+
+    #[error("Error listing chunks: {0}")]
+    ListChunksError(#[from] ListChunksError),
+
+    #[error("Error rolling over partition: {0}")]
+    RolloverPartitionError(#[from] RolloverPartitionError),
+
+    #[error("Error loading chunk to the read buffer: {0}")]
+    LoadChunkError(#[from] LoadChunkError),
+
+    #[error("Error dropping chunk: {0}")]
+    DropChunkError(#[from] DropChunkError),
+
+    #[error("Error snapshotting partition: {0}")]
+    SnapshotPartitionError(#[from] SnapshotPartitionError),
+
+    #[error("Error compacting chunks: {0}")]
+    CompactChunksError(#[from] CompactChunksError),
+
     #[error("Error connecting to IOx: {0}")]
     ConnectionError(#[from] influxdb_iox_client::connection::Error),
 }
@@ -47,10 +75,172 @@ struct Get {
     name: Option<String>,
 }
 
+/// Change the rules of an existing database
+#[derive(Debug, StructOpt)]
+struct Update {
+    /// The name of the database
+    name: String,
+
+    /// Change the size in bytes of the database's mutable buffer
+    #[structopt(short, long)]
+    mutable_buffer: Option<u64>,
+
+    /// Replace the partition template with these parts: `table`,
+    /// `column:<name>` or `time:<strftime format>`
+    #[structopt(short, long, parse(try_from_str = parse_template_part))]
+    partition_template: Vec<partition_template::Part>,
+}
+
+/// Delete a database
+#[derive(Debug, StructOpt)]
+struct Delete {
+    /// The name of the database
+    name: String,
+
+    /// Also delete the database's data from object storage
+    #[structopt(long)]
+    delete_data: bool,
+}
+
+/// List the partitions of a database
+#[derive(Debug, StructOpt)]
+struct Partitions {
+    /// The name of the database
+    name: String,
+}
+
+/// List the chunks of a database with where they are stored, their size,
+/// row count and time range
+#[derive(Debug, StructOpt)]
+struct Chunks {
+    /// The name of the database
+    name: String,
+
+    /// Only list the chunks of this partition
+    #[structopt(short, long)]
+    partition: Option<String>,
+}
+
+/// Close the open mutable buffer chunk of a partition and start a new one
+#[derive(Debug, StructOpt)]
+struct Rollover {
+    /// The name of the database
+    name: String,
+
+    /// The key of the partition
+    partition: String,
+}
+
+/// Load a closed mutable buffer chunk into the read buffer
+#[derive(Debug, StructOpt)]
+struct Load {
+    /// The name of the database
+    name: String,
+
+    /// The key of the chunk's partition
+    partition: String,
+
+    /// The id of the chunk
+    chunk_id: u32,
+}
+
+/// Drop a chunk from the mutable buffer, the read buffer or object storage
+#[derive(Debug, StructOpt)]
+struct DropChunk {
+    /// The name of the database
+    name: String,
+
+    /// The key of the chunk's partition
+    partition: String,
+
+    /// The id of the chunk
+    chunk_id: u32,
+
+    /// Where to drop the chunk from: closed-mutable-buffer, read-buffer or
+    /// object-store
+    #[structopt(short, long, parse(try_from_str = parse_storage))]
+    storage: ChunkStorage,
+}
+
+/// Close the open mutable buffer chunk of a partition and write it to object
+/// storage in the background
+#[derive(Debug, StructOpt)]
+struct Snapshot {
+    /// The name of the database
+    name: String,
+
+    /// The key of the partition
+    partition: String,
+}
+
+/// Merge read buffer or persisted chunks of a partition into a single read
+/// buffer chunk
+#[derive(Debug, StructOpt)]
+struct Compact {
+    /// The name of the database
+    name: String,
+
+    /// The key of the chunks' partition
+    partition: String,
+
+    /// The ids of the chunks, at least two
+    chunk_ids: Vec<u32>,
+}
+
 #[derive(Debug, StructOpt)]
 enum Command {
     Create(Create),
     Get(Get),
+    Update(Update),
+    Delete(Delete),
+    Partitions(Partitions),
+    Chunks(Chunks),
+    Rollover(Rollover),
+    Load(Load),
+    DropChunk(DropChunk),
+    Snapshot(Snapshot),
+    Compact(Compact),
+}
+
+fn parse_storage(storage: &str) -> Result<ChunkStorage, String> {
+    match storage {
+        "open-mutable-buffer" => Ok(ChunkStorage::OpenMutableBuffer),
+        "closed-mutable-buffer" => Ok(ChunkStorage::ClosedMutableBuffer),
+        "read-buffer" => Ok(ChunkStorage::ReadBuffer),
+        "object-store" => Ok(ChunkStorage::ObjectStore),
+        _ => Err(format!("unknown chunk storage '{}'", storage)),
+    }
+}
+
+fn parse_template_part(part: &str) -> Result<partition_template::Part, String> {
+    use partition_template::part::Part;
+
+    let mut split = part.splitn(2, ':');
+    let part = match (split.next(), split.next()) {
+        (Some("table"), None) => Part::Table(Empty {}),
+        (Some("column"), Some(column)) => Part::Column(column.to_string()),
+        (Some("time"), Some(format)) => Part::Time(format.to_string()),
+        _ => return Err(format!("unknown partition template part '{}'", part)),
+    };
+    Ok(partition_template::Part { part: Some(part) })
+}
+
+/// Formats a chunk as a single line
+fn format_chunk(chunk: &Chunk) -> String {
+    let time_range = match &chunk.time_range {
+        Some(range) => format!("{}..={}", range.min, range.max),
+        None => "-".to_string(),
+    };
+
+    format!(
+        "{}\t{}\t{:?}\t{} bytes\t{} rows\t{}",
+        chunk.partition_key,
+        chunk.id,
+        chunk.storage(),
+        chunk.estimated_bytes,
+        chunk.row_count,
+        time_range
+    )
 }
 
 pub async fn command(url: String, config: Config) -> Result<()> {
@@ -83,6 +273,80 @@ pub async fn command(url: String, config: Config) -> Result<()> {
                 println!("{}", databases.join(", "))
             }
         }
+        Command::Update(update) => {
+            let mut rules = client.get_database(&update.name).await?;
+            if let Some(buffer_size) = update.mutable_buffer {
+                rules
+                    .mutable_buffer_config
+                    .get_or_insert_with(Default::default)
+                    .buffer_size = buffer_size;
+            }
+            if !update.partition_template.is_empty() {
+                rules.partition_template = Some(PartitionTemplate {
+                    parts: update.partition_template,
+                });
+            }
+            client.update_database(rules).await?;
+            println!("Ok");
+        }
+        Command::Delete(delete) => {
+            client
+                .delete_database(delete.name, delete.delete_data)
+                .await?;
+            println!("Ok");
+        }
+        Command::Partitions(partitions) => {
+            for partition in client.list_partitions(partitions.name).await? {
+                println!("{}", partition.key);
+            }
+        }
+        Command::Chunks(chunks) => {
+            let chunks = match chunks.partition {
+                Some(partition) => client.list_partition_chunks(chunks.name, partition).await?,
+                None => client.list_chunks(chunks.name).await?,
+            };
+            for chunk in &chunks {
+                println!("{}", format_chunk(chunk));
+            }
+        }
+        Command::Rollover(rollover) => {
+            let chunk = client
+                .rollover_partition(rollover.name, rollover.partition)
+                .await?;
+            println!("{}", format_chunk(&chunk));
+        }
+        Command::Load(load) => {
+            let chunk = client
+                .load_chunk_to_read_buffer(load.name, load.partition, load.chunk_id)
+                .await?;
+            println!("{}", format_chunk(&chunk));
+        }
+        Command::DropChunk(command) => {
+            client
+                .drop_chunk(
+                    command.name,
+                    command.partition,
+                    command.chunk_id,
+                    command.storage,
+                )
+                .await?;
+            println!("Ok");
+        }
+        Command::Snapshot(snapshot) => {
+            let response = client
+                .snapshot_partition(snapshot.name, snapshot.partition)
+                .await?;
+            println!(
+                "Started snapshot {} of chunk {} as operation {}",
+                response.snapshot_id, response.chunk_id, response.operation_id
+            );
+        }
+        Command::Compact(compact) => {
+            let chunk = client
+                .compact_chunks(compact.name, compact.partition, compact.chunk_ids)
+                .await?;
+            println!("{}", format_chunk(&chunk));
+        }
     }
 
     Ok(())
@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use influxdb_iox_client::{
+    connection::Builder,
+    operations::{generated_types::Operation, *},
+};
+use structopt::StructOpt;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Error listing operations: {0}")]
+    ListOperationsError(#[from] ListOperationsError),
+
+    #[error("Error getting operation: {0}")]
+    GetOperationError(#[from] GetOperationError),
+
+    #[error("Error cancelling operation: {0}")]
+    CancelOperationError(#[from] CancelOperationError),
+
+    #[error("Error connecting to IOx: {0}")]
+    ConnectionError(#[from] influxdb_iox_client::connection::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Manage the long-running operations of an IOx server, such as snapshots
+#[derive(Debug, StructOpt)]
+pub struct Config {
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+/// Get a running operation
+#[derive(Debug, StructOpt)]
+struct Get {
+    /// The id of the operation
+    id: u64,
+}
+
+/// Cancel a running operation
+#[derive(Debug, StructOpt)]
+struct Cancel {
+    /// The id of the operation
+    id: u64,
+}
+
+/// Print the progress of a running operation until it finishes
+#[derive(Debug, StructOpt)]
+struct Watch {
+    /// The id of the operation
+    id: u64,
+
+    /// How often to print the progress, in seconds
+    #[structopt(short, long, default_value = "1")]
+    interval: u64,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// List the running operations
+    List,
+    Get(Get),
+    Cancel(Cancel),
+    Watch(Watch),
+}
+
+/// Formats an operation as a single line
+fn format_operation(operation: &Operation) -> String {
+    let start_time = match &operation.start_time {
+        Some(time) => Utc.timestamp(time.seconds, time.nanos as u32).to_rfc3339(),
+        None => "-".to_string(),
+    };
+
+    format!(
+        "{}\t{}\t{}/{} tasks\tstarted {}",
+        operation.id,
+        operation.description,
+        operation.completed_tasks,
+        operation.total_tasks,
+        start_time
+    )
+}
+
+pub async fn command(url: String, config: Config) -> Result<()> {
+    let connection = Builder::default().build(url).await?;
+    let mut client = Client::new(connection);
+
+    match config.command {
+        Command::List => {
+            for operation in client.list_operations().await? {
+                println!("{}", format_operation(&operation));
+            }
+        }
+        Command::Get(get) => {
+            let operation = client.get_operation(get.id).await?;
+            println!("{}", format_operation(&operation));
+        }
+        Command::Cancel(cancel) => {
+            client.cancel_operation(cancel.id).await?;
+            println!("Ok");
+        }
+        Command::Watch(watch) => {
+            let mut interval = tokio::time::interval(Duration::from_secs(watch.interval));
+
+            // operations are only listed while they run
+            loop {
+                interval.tick().await;
+                match client.get_operation(watch.id).await {
+                    Ok(operation) => println!("{}", format_operation(&operation)),
+                    Err(GetOperationError::OperationNotFound) => break,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            println!("Operation {} is no longer running", watch.id);
+        }
+    }
+
+    Ok(())
+}
@@ -95,6 +95,37 @@ pub struct Config {
     #[structopt(long = "--data-dir", env = "INFLUXDB_IOX_DB_DIR")]
     pub database_directory: Option<PathBuf>,
 
+    /// Append every write to a local WAL under `--data-dir` before
+    /// acknowledging it. The WAL is replayed on startup and truncated once
+    /// its writes have been snapshotted, so that a standalone server doesn't
+    /// lose writes on a crash. Must also set `--data-dir`.
+    #[structopt(long = "--local-wal")]
+    pub local_wal: bool,
+
+    /// Garbage collect the object storage files of each database every this
+    /// many seconds: Parquet files and catalog versions that the database's
+    /// catalog no longer refers to, and WAL segments whose writes have all
+    /// been snapshotted. Garbage collection is off if not set.
+    #[structopt(
+        long = "--gc-interval-seconds",
+        env = "INFLUXDB_IOX_GC_INTERVAL_SECONDS"
+    )]
+    pub gc_interval_seconds: Option<u64>,
+
+    /// How many seconds an unreferenced file must be left alone before it is
+    /// garbage collected. It must be longer than a snapshot takes, as the
+    /// files of a running snapshot aren't referenced yet.
+    #[structopt(
+        long = "--gc-grace-period-seconds",
+        env = "INFLUXDB_IOX_GC_GRACE_PERIOD_SECONDS",
+        default_value = "86400"
+    )]
+    pub gc_grace_period_seconds: u64,
+
+    /// Only log the files garbage collection would delete.
+    #[structopt(long = "--gc-dry-run")]
+    pub gc_dry_run: bool,
+
     #[structopt(
         long = "--object-store",
         env = "INFLUXDB_IOX_OBJECT_STORE",
@@ -7,9 +7,9 @@ use object_store::{
     self, aws::AmazonS3, azure::MicrosoftAzure, gcp::GoogleCloudStorage, ObjectStore,
 };
 use panic_logging::SendPanicsToTracing;
-use server::{ConnectionManagerImpl as ConnectionManager, Server as AppServer};
-use snafu::{ResultExt, Snafu};
-use std::{convert::TryFrom, fs, net::SocketAddr, path::PathBuf, sync::Arc};
+use server::{gc::GcConfig, ConnectionManagerImpl as ConnectionManager, Server as AppServer};
+use snafu::{OptionExt, ResultExt, Snafu};
+use std::{convert::TryFrom, fs, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 use tracing::{error, info, warn};
 
 mod http;
@@ -64,6 +64,9 @@ pub enum Error {
     // don't return `Result`.
     #[snafu(display("Amazon S3 configuration was invalid: {}", source))]
     InvalidS3Config { source: object_store::aws::Error },
+
+    #[snafu(display("Using a local WAL requires setting --data-dir"))]
+    LocalWalWithoutDataDir,
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -104,8 +107,27 @@ pub async fn main(logging_level: LoggingLevel, config: Option<Box<Config>>) -> R
     let object_store = ObjectStore::try_from(&*config)?;
     let object_storage = Arc::new(object_store);
 
-    let connection_manager = ConnectionManager {};
-    let app_server = Arc::new(AppServer::new(connection_manager, object_storage));
+    let connection_manager = ConnectionManager::new();
+    let mut app_server = AppServer::new(connection_manager, object_storage);
+    if config.local_wal {
+        let data_dir = config
+            .database_directory
+            .as_ref()
+            .context(LocalWalWithoutDataDir)?;
+        let wal_dir = data_dir.join("wal");
+        info!(?wal_dir, "Using local WAL");
+        app_server = app_server.with_local_wal(wal_dir);
+    }
+    if let Some(interval) = config.gc_interval_seconds {
+        let gc_config = GcConfig {
+            interval: Duration::from_secs(interval),
+            grace_period: Duration::from_secs(config.gc_grace_period_seconds),
+            dry_run: config.gc_dry_run,
+        };
+        info!(?gc_config, "Garbage collecting object storage");
+        app_server = app_server.with_garbage_collection(gc_config);
+    }
+    let app_server = Arc::new(app_server);
 
     // if this ID isn't set the server won't be usable until this is set via an API
     // call
@@ -121,6 +143,10 @@ pub async fn main(logging_level: LoggingLevel, config: Option<Box<Config>>) -> R
         warn!("server ID not set. ID must be set via the INFLUXDB_IOX_ID config or API before writing or querying data.");
     }
 
+    // Retry queued replication in the background
+    let background_server = Arc::clone(&app_server);
+    tokio::spawn(async move { background_server.background_worker().await });
+
     // Construct and start up gRPC server
 
     let grpc_bind_addr = config.grpc_bind_address;
@@ -761,8 +761,6 @@ struct SnapshotInfo {
 async fn snapshot_partition<M: ConnectionManager + Send + Sync + Debug + 'static>(
     req: Request<Body>,
 ) -> Result<Response<Body>, ApplicationError> {
-    use object_store::path::ObjectStorePath;
-
     let server = Arc::clone(&req.data::<Arc<AppServer<M>>>().expect("server state"));
     let query = req.uri().query().context(ExpectedQueryString {})?;
 
@@ -773,32 +771,18 @@ async fn snapshot_partition<M: ConnectionManager + Send + Sync + Debug + 'static
     let db_name =
         org_and_bucket_to_database(&snapshot.org, &snapshot.bucket).context(BucketMappingError)?;
 
-    // TODO: refactor the rest of this out of the http route and into the server
-    // crate.
-    let db = server.db(&db_name).await.context(BucketNotFound {
+    server.db(&db_name).await.context(BucketNotFound {
         org: &snapshot.org,
         bucket: &snapshot.bucket,
     })?;
 
-    let store = Arc::clone(&server.store);
-
-    let mut metadata_path = store.new_path();
-    metadata_path.push_dir(&db_name.to_string());
-    let mut data_path = metadata_path.clone();
-    metadata_path.push_dir("meta");
-    data_path.push_all_dirs(&["data", &snapshot.partition]);
-
-    let partition_key = &snapshot.partition;
-    let chunk = db.rollover_partition(partition_key).await.unwrap();
-    let snapshot = server::snapshot::snapshot_chunk(
-        metadata_path,
-        data_path,
-        store,
-        partition_key,
-        chunk,
-        None,
-    )
-    .unwrap();
+    let snapshot = server
+        .snapshot_partition(&db_name, &snapshot.partition)
+        .await
+        .map_err(|e| Box::new(e) as _)
+        .context(DatabaseError {
+            database: db_name.as_str(),
+        })?;
 
     let ret = format!("{}", snapshot.id);
     Ok(Response::new(Body::from(ret)))
@@ -8,10 +8,14 @@ use tokio_stream::wrappers::TcpListenerStream;
 use data_types::error::ErrorLogger;
 use server::{ConnectionManager, Server};
 
+mod error;
 mod flight;
 mod management;
+mod operations;
 mod storage;
+mod subscription;
 mod testing;
+mod write;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -37,6 +41,8 @@ where
         generated_types::STORAGE_SERVICE,
         generated_types::IOX_TESTING_SERVICE,
         generated_types::ARROW_SERVICE,
+        generated_types::WRITE_SERVICE,
+        generated_types::SUBSCRIPTION_SERVICE,
     ];
 
     for service in &services {
@@ -50,7 +56,10 @@ where
         .add_service(testing::make_server())
         .add_service(storage::make_server(Arc::clone(&server)))
         .add_service(flight::make_server(Arc::clone(&server)))
-        .add_service(management::make_server(server))
+        .add_service(management::make_server(Arc::clone(&server)))
+        .add_service(operations::make_server(Arc::clone(&server)))
+        .add_service(write::make_server(Arc::clone(&server)))
+        .add_service(subscription::make_server(server))
         .serve_with_incoming(stream)
         .await
         .context(ServerError {})
@@ -0,0 +1,96 @@
+use generated_types::google::{FieldViolation, InternalError, NotFound, PreconditionViolation};
+use tracing::error;
+
+/// Converts a server error into the appropriate tonic status, logging
+/// anything that isn't a well-known condition.
+pub fn default_server_error_handler(error: server::Error) -> tonic::Status {
+    match error {
+        server::Error::IdNotSet => PreconditionViolation {
+            category: "Writer ID".to_string(),
+            subject: "influxdata.com/iox".to_string(),
+            description: "Writer ID must be set".to_string(),
+        }
+        .into(),
+        server::Error::DatabaseNotFound { db_name } => NotFound {
+            resource_type: "database".to_string(),
+            resource_name: db_name,
+            ..Default::default()
+        }
+        .into(),
+        server::Error::NoWalBuffer { db_name } => PreconditionViolation {
+            category: "Database".to_string(),
+            subject: db_name,
+            description: "Database must have a WAL buffer".to_string(),
+        }
+        .into(),
+        server::Error::InvalidRulesUpdate { db_name, source } => PreconditionViolation {
+            category: "Database".to_string(),
+            subject: db_name,
+            description: source.to_string(),
+        }
+        .into(),
+        server::Error::InvalidSubscription { index, source } => FieldViolation {
+            field: format!(
+                "rules.subscription_config.subscriptions.{}.matcher.{}",
+                index,
+                source.field()
+            ),
+            description: source.to_string(),
+        }
+        .into(),
+        server::Error::DroppingChunk { source, .. }
+        | server::Error::CompactingChunks { source, .. } => default_db_error_handler(source),
+        error @ server::Error::ReplicationQueueFull { .. } => {
+            tonic::Status::resource_exhausted(error.to_string())
+        }
+        error => {
+            error!(?error, "Unexpected error");
+            InternalError {}.into()
+        }
+    }
+}
+
+/// Converts a database error into the appropriate tonic status, logging
+/// anything that isn't a well-known condition.
+pub fn default_db_error_handler(error: server::db::Error) -> tonic::Status {
+    use server::db::Error;
+    match error {
+        Error::UnknownMutableBufferChunk { chunk_id }
+        | Error::UnknownPersistedChunk { chunk_id, .. }
+        | Error::UnknownCompactChunk { chunk_id, .. } => NotFound {
+            resource_type: "chunk".to_string(),
+            resource_name: chunk_id.to_string(),
+            ..Default::default()
+        }
+        .into(),
+        Error::MutableBufferDrop { .. } | Error::ReadBufferDrop { .. } => NotFound {
+            resource_type: "chunk".to_string(),
+            description: error.to_string(),
+            ..Default::default()
+        }
+        .into(),
+        Error::DatatbaseNotWriteable {} => PreconditionViolation {
+            category: "database".to_string(),
+            subject: "influxdata.com/iox".to_string(),
+            description: "Cannot write to database: no mutable buffer configured".to_string(),
+        }
+        .into(),
+        Error::CompactTooFewChunks { .. } => FieldViolation {
+            field: "chunk_ids".to_string(),
+            description: error.to_string(),
+        }
+        .into(),
+        Error::CompactMutableBufferChunk { .. } | Error::CompactMixedChunks { .. } => {
+            PreconditionViolation {
+                category: "chunk".to_string(),
+                subject: "influxdata.com/iox".to_string(),
+                description: error.to_string(),
+            }
+            .into()
+        }
+        error => {
+            error!(?error, "Unexpected error");
+            InternalError {}.into()
+        }
+    }
+}
@@ -1,22 +1,42 @@
-use std::{pin::Pin, sync::Arc};
+use std::{convert::TryFrom, fmt::Debug, pin::Pin, sync::Arc};
 
-use futures::Stream;
-use serde::Deserialize;
-use snafu::{OptionExt, ResultExt, Snafu};
+use futures::{Stream, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Streaming};
 use tracing::error;
 
 use arrow_deps::{
-    arrow,
+    arrow::{
+        self,
+        array::{Array, ArrayRef},
+        datatypes::{DataType, Field as ArrowField, Schema as ArrowSchema, SchemaRef},
+        ipc::{self, reader},
+        record_batch::RecordBatch,
+    },
     arrow_flight::{
         self,
+        flight_descriptor::DescriptorType,
         flight_service_server::{FlightService as Flight, FlightServiceServer as FlightServer},
-        Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
-        HandshakeRequest, HandshakeResponse, PutResult, SchemaResult, Ticket,
+        utils::flight_data_to_arrow_batch,
+        Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint,
+        FlightInfo, HandshakeRequest, HandshakeResponse, PutResult, SchemaResult, Ticket,
     },
-    datafusion::physical_plan::collect,
+    datafusion::physical_plan::ExecutionPlan,
 };
-use query::{frontend::sql::SQLQueryPlanner, DatabaseStore};
+use data_types::{
+    chunk::ChunkStorage,
+    job::Job,
+    schema::{InfluxColumnType, InfluxFieldType, Schema},
+    DatabaseName, DatabaseNameError,
+};
+use generated_types::google::FieldViolation;
+use query::{frontend::sql::SQLQueryPlanner, DatabaseStore, PartitionChunk};
+use server::{db::Db, jobs::TrackedJob, tracker::TrackedFutureExt, ConnectionManager, Server};
+
+use super::error::default_server_error_handler;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -31,6 +51,9 @@ pub enum Error {
         source: serde_json::Error,
     },
 
+    #[snafu(display("Invalid database name: {}", source))]
+    InvalidDatabaseName { source: DatabaseNameError },
+
     #[snafu(display("Database {} not found", database_name))]
     DatabaseNotFound { database_name: String },
 
@@ -49,6 +72,95 @@ pub enum Error {
         query: String,
         source: query::frontend::sql::Error,
     },
+
+    #[snafu(display(
+        "Unknown action '{}', supported actions are listed by ListActions",
+        action_type
+    ))]
+    UnknownAction { action_type: String },
+
+    #[snafu(display("Invalid body for action '{}': {}", action_type, source))]
+    InvalidActionBody {
+        action_type: String,
+        source: serde_json::Error,
+    },
+
+    #[snafu(display("Error running action '{}': {}", action_type, source))]
+    RunningAction {
+        action_type: String,
+        source: server::db::Error,
+    },
+
+    #[snafu(display("Error serializing action result: {}", source))]
+    SerializingActionResult { source: serde_json::Error },
+
+    #[snafu(display("FlightDescriptor must be a command containing a query"))]
+    NotQueryDescriptor,
+
+    #[snafu(display("DoPut stream must start with a FlightDescriptor"))]
+    MissingDescriptor,
+
+    #[snafu(display(
+        "Invalid FlightDescriptor path {:?}: expected a path of [database, table]",
+        path
+    ))]
+    InvalidDescriptor { path: Vec<String> },
+
+    #[snafu(display("Invalid schema in DoPut stream: {}", source))]
+    InvalidSchema { source: arrow::error::ArrowError },
+
+    #[snafu(display("Invalid IOx schema in DoPut stream: {}", source))]
+    InvalidIoxSchema { source: data_types::schema::Error },
+
+    #[snafu(display(
+        "Schema measurement '{}' doesn't match table '{}'",
+        measurement,
+        table_name
+    ))]
+    MeasurementMismatch {
+        measurement: String,
+        table_name: String,
+    },
+
+    #[snafu(display(
+        "Column '{}' has no IOx column type, it must be a tag, field or timestamp",
+        column_name
+    ))]
+    UntypedColumn { column_name: String },
+
+    #[snafu(display("Column '{}' has unsupported type {:?}", column_name, column_type))]
+    UnsupportedColumnType {
+        column_name: String,
+        column_type: InfluxColumnType,
+    },
+
+    #[snafu(display("Schema must have exactly one timestamp column, found {}", count))]
+    InvalidTimeColumn { count: usize },
+
+    #[snafu(display("Invalid message in DoPut stream: {}", message))]
+    InvalidPutMessage { message: String },
+
+    #[snafu(display("Invalid dictionary batch in DoPut stream: {}", source))]
+    InvalidDictionaryBatch { source: arrow::error::ArrowError },
+
+    #[snafu(display(
+        "Record batch in DoPut stream refers to a dictionary of column '{}' that wasn't sent",
+        column_name
+    ))]
+    MissingDictionary { column_name: String },
+
+    #[snafu(display("Invalid record batch in DoPut stream: {}", source))]
+    InvalidRecordBatch { source: arrow::error::ArrowError },
+
+    #[snafu(display("Row {} of the record batch has no field values", row))]
+    NoFieldValues { row: usize },
+
+    #[snafu(display(
+        "DoPut message of {} bytes exceeds the limit of {} bytes, split it into smaller batches",
+        size,
+        max_size
+    ))]
+    PutMessageTooLarge { size: usize, max_size: usize },
 }
 
 impl From<Error> for tonic::Status {
@@ -68,9 +180,40 @@ impl Error {
         match &self {
             Self::InvalidTicket { .. } => Status::invalid_argument(self.to_string()),
             Self::InvalidQuery { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidDatabaseName { .. } => Status::invalid_argument(self.to_string()),
             Self::DatabaseNotFound { .. } => Status::not_found(self.to_string()),
             Self::Query { .. } => Status::internal(self.to_string()),
             Self::PlanningSQLQuery { .. } => Status::invalid_argument(self.to_string()),
+            Self::UnknownAction { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidActionBody { .. } => Status::invalid_argument(self.to_string()),
+            Self::RunningAction { source, .. } => match source {
+                server::db::Error::UnknownMutableBufferChunk { .. }
+                | server::db::Error::MutableBufferDrop { .. }
+                | server::db::Error::ReadBufferDrop { .. }
+                | server::db::Error::UnknownPersistedChunk { .. } => {
+                    Status::not_found(self.to_string())
+                }
+                server::db::Error::DatatbaseNotWriteable { .. } => {
+                    Status::failed_precondition(self.to_string())
+                }
+                _ => Status::internal(self.to_string()),
+            },
+            Self::SerializingActionResult { .. } => Status::internal(self.to_string()),
+            Self::NotQueryDescriptor => Status::invalid_argument(self.to_string()),
+            Self::MissingDescriptor => Status::invalid_argument(self.to_string()),
+            Self::InvalidDescriptor { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidSchema { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidIoxSchema { .. } => Status::invalid_argument(self.to_string()),
+            Self::MeasurementMismatch { .. } => Status::invalid_argument(self.to_string()),
+            Self::UntypedColumn { .. } => Status::invalid_argument(self.to_string()),
+            Self::UnsupportedColumnType { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidTimeColumn { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidPutMessage { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidDictionaryBatch { .. } => Status::invalid_argument(self.to_string()),
+            Self::MissingDictionary { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidRecordBatch { .. } => Status::invalid_argument(self.to_string()),
+            Self::NoFieldValues { .. } => Status::invalid_argument(self.to_string()),
+            Self::PutMessageTooLarge { .. } => Status::resource_exhausted(self.to_string()),
         }
     }
 }
@@ -85,20 +228,265 @@ struct ReadInfo {
     sql_query: String,
 }
 
+/// The actions supported by `do_action`. The body of each action is a JSON
+/// object naming the `database_name` and the partition or chunk to act on.
+/// Each action returns one JSON result for every chunk it affected.
+const ROLLOVER_PARTITION: &str = "rollover_partition";
+const LOAD_CHUNK_TO_READ_BUFFER: &str = "load_chunk_to_read_buffer";
+const DROP_CHUNK: &str = "drop_chunk";
+const SNAPSHOT_PARTITION: &str = "snapshot_partition";
+const LIST_CHUNKS: &str = "list_chunks";
+
+/// The largest encoded record batch accepted in a DoPut stream
+const MAX_PUT_MESSAGE_SIZE: usize = 10 * 1024 * 1024;
+
+/// The action types and their descriptions returned by `list_actions`
+const ACTIONS: &[(&str, &str)] = &[
+    (
+        ROLLOVER_PARTITION,
+        "Closes the open mutable buffer chunk of a partition and starts a new one. \
+         Body: {\"database_name\", \"partition_key\"}",
+    ),
+    (
+        LOAD_CHUNK_TO_READ_BUFFER,
+        "Loads a closed mutable buffer chunk into the read buffer. \
+         Body: {\"database_name\", \"partition_key\", \"chunk_id\"}",
+    ),
+    (
+        DROP_CHUNK,
+        "Drops a chunk from the mutable buffer, the read buffer or object storage. \
+         Body: {\"database_name\", \"partition_key\", \"chunk_id\", \
+         \"storage\": \"closed_mutable_buffer\" | \"read_buffer\" | \"object_store\"}",
+    ),
+    (
+        SNAPSHOT_PARTITION,
+        "Closes the open mutable buffer chunk of a partition and writes it to object storage \
+         in the background. Body: {\"database_name\", \"partition_key\"}",
+    ),
+    (
+        LIST_CHUNKS,
+        "Lists the chunks of a database, optionally only those of one partition. \
+         Body: {\"database_name\", \"partition_key\"?}",
+    ),
+];
+
+#[derive(Deserialize, Debug)]
+/// Body of the partition actions
+struct PartitionAction {
+    database_name: String,
+    partition_key: String,
+}
+
+#[derive(Deserialize, Debug)]
+/// Body of the chunk actions; `storage` is only used by `drop_chunk`
+struct ChunkAction {
+    database_name: String,
+    partition_key: String,
+    chunk_id: u32,
+    storage: Option<ChunkStorage>,
+}
+
+#[derive(Deserialize, Debug)]
+/// Body of the `list_chunks` action
+struct ListChunksAction {
+    database_name: String,
+    partition_key: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+/// Result of the `snapshot_partition` action
+struct SnapshotResult {
+    snapshot_id: String,
+    partition_key: String,
+    operation_id: usize,
+}
+
 /// Concrete implementation of the gRPC Arrow Flight Service API
 #[derive(Debug)]
-struct FlightService<T: DatabaseStore> {
-    pub db_store: Arc<T>,
+struct FlightService<M: ConnectionManager> {
+    server: Arc<Server<M>>,
+}
+
+pub fn make_server<M>(server: Arc<Server<M>>) -> FlightServer<impl Flight>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    FlightServer::new(FlightService { server })
 }
 
-pub fn make_server<T: DatabaseStore + 'static>(db_store: Arc<T>) -> FlightServer<impl Flight> {
-    FlightServer::new(FlightService { db_store })
+impl<M> FlightService<M>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    /// Plans the SQL query against its database
+    async fn plan(&self, read_info: &ReadInfo) -> Result<(Arc<Db>, Arc<dyn ExecutionPlan>), Error> {
+        let database_name =
+            DatabaseName::new(&read_info.database_name).context(InvalidDatabaseName)?;
+        let db = self
+            .server
+            .db(&database_name)
+            .await
+            .context(DatabaseNotFound {
+                database_name: &read_info.database_name,
+            })?;
+
+        let planner = SQLQueryPlanner::default();
+        let executor = self.server.executor();
+
+        let physical_plan = planner
+            .query(&*db, &read_info.sql_query, &executor)
+            .await
+            .context(PlanningSQLQuery {
+                query: &read_info.sql_query,
+            })?;
+
+        Ok((db, physical_plan))
+    }
+
+    /// Runs the action with the JSON body, returning the JSON result bodies
+    async fn run_action(
+        &self,
+        action_type: &str,
+        body: &[u8],
+    ) -> Result<Vec<Vec<u8>>, tonic::Status> {
+        let results = match action_type {
+            ROLLOVER_PARTITION => {
+                let action: PartitionAction = parse_action_body(action_type, body)?;
+                let db = self.action_db(&action.database_name).await?;
+
+                let chunk = db
+                    .rollover_partition(&action.partition_key)
+                    .await
+                    .context(RunningAction { action_type })?;
+
+                let summary = db
+                    .chunk_summary(
+                        &action.partition_key,
+                        chunk.id(),
+                        ChunkStorage::ClosedMutableBuffer,
+                    )
+                    .context(RunningAction { action_type })?;
+
+                action_results(summary)?
+            }
+            LOAD_CHUNK_TO_READ_BUFFER => {
+                let action: ChunkAction = parse_action_body(action_type, body)?;
+                let db = self.action_db(&action.database_name).await?;
+
+                let job = TrackedJob::new(Job::LoadReadBuffer {
+                    db_name: action.database_name.clone(),
+                    partition_key: action.partition_key.clone(),
+                    chunk_id: action.chunk_id,
+                });
+                let chunk = db
+                    .load_chunk_to_read_buffer(&action.partition_key, action.chunk_id)
+                    .track(self.server.jobs(), job)
+                    .await
+                    .map_err(|_| tonic::Status::cancelled("loading the chunk was cancelled"))?
+                    .context(RunningAction { action_type })?;
+
+                let summary = db
+                    .chunk_summary(&action.partition_key, chunk.id(), ChunkStorage::ReadBuffer)
+                    .context(RunningAction { action_type })?;
+
+                action_results(summary)?
+            }
+            DROP_CHUNK => {
+                let action: ChunkAction = parse_action_body(action_type, body)?;
+                let storage = action
+                    .storage
+                    .ok_or_else(|| FieldViolation::required("storage"))?;
+                let db = self.action_db(&action.database_name).await?;
+
+                // summarized before the drop, which removes its statistics
+                let summary = db
+                    .partition_chunk_summaries(&action.partition_key)
+                    .context(RunningAction { action_type })?
+                    .into_iter()
+                    .find(|summary| {
+                        summary.id == action.chunk_id
+                            && (summary.storage == storage
+                                || (summary.storage.is_mutable_buffer()
+                                    && storage.is_mutable_buffer()))
+                    });
+
+                let dropped = match storage {
+                    ChunkStorage::OpenMutableBuffer | ChunkStorage::ClosedMutableBuffer => db
+                        .drop_mutable_buffer_chunk(&action.partition_key, action.chunk_id)
+                        .await
+                        .map(|_| ()),
+                    ChunkStorage::ReadBuffer => db
+                        .drop_read_buffer_chunk(&action.partition_key, action.chunk_id)
+                        .await
+                        .map(|_| ()),
+                    ChunkStorage::ObjectStore => {
+                        // the drop is recorded in the database's catalog
+                        let db_name = DatabaseName::new(&action.database_name)
+                            .context(InvalidDatabaseName)?;
+                        self.server
+                            .drop_persisted_chunk(&db_name, &action.partition_key, action.chunk_id)
+                            .await
+                            .map_err(default_server_error_handler)?;
+                        Ok(())
+                    }
+                };
+                dropped.context(RunningAction { action_type })?;
+
+                action_results(summary)?
+            }
+            SNAPSHOT_PARTITION => {
+                let action: PartitionAction = parse_action_body(action_type, body)?;
+                let db_name =
+                    DatabaseName::new(&action.database_name).context(InvalidDatabaseName)?;
+
+                let snapshot = self
+                    .server
+                    .snapshot_partition(&db_name, &action.partition_key)
+                    .await
+                    .map_err(default_server_error_handler)?;
+
+                action_results(vec![SnapshotResult {
+                    snapshot_id: snapshot.id.to_string(),
+                    partition_key: action.partition_key,
+                    operation_id: snapshot.operation_id.into(),
+                }])?
+            }
+            LIST_CHUNKS => {
+                let action: ListChunksAction = parse_action_body(action_type, body)?;
+                let db = self.action_db(&action.database_name).await?;
+
+                let chunks = db
+                    .chunk_summaries()
+                    .context(RunningAction { action_type })?
+                    .into_iter()
+                    .filter(|chunk| match &action.partition_key {
+                        Some(partition_key) => &chunk.partition_key == partition_key,
+                        None => true,
+                    });
+
+                action_results(chunks)?
+            }
+            _ => UnknownAction { action_type }.fail()?,
+        };
+
+        Ok(results)
+    }
+
+    /// Returns the database an action is run against
+    async fn action_db(&self, database_name: &str) -> Result<Arc<Db>, Error> {
+        let db_name = DatabaseName::new(database_name).context(InvalidDatabaseName)?;
+
+        self.server
+            .db(&db_name)
+            .await
+            .context(DatabaseNotFound { database_name })
+    }
 }
 
 #[tonic::async_trait]
-impl<T> Flight for FlightService<T>
+impl<M> Flight for FlightService<M>
 where
-    T: DatabaseStore + 'static,
+    M: ConnectionManager + Send + Sync + Debug + 'static,
 {
     type HandshakeStream = TonicStream<HandshakeResponse>;
     type ListFlightsStream = TonicStream<FlightInfo>;
@@ -108,82 +496,87 @@ where
     type ListActionsStream = TonicStream<ActionType>;
     type DoExchangeStream = TonicStream<FlightData>;
 
+    /// Returns the schema of the results of the SQL query in the command of
+    /// the descriptor, without running the query
     async fn get_schema(
         &self,
-        _request: Request<FlightDescriptor>,
+        request: Request<FlightDescriptor>,
     ) -> Result<Response<SchemaResult>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let read_info = descriptor_read_info(request.into_inner())?;
+        let (_, physical_plan) = self.plan(&read_info).await?;
+
+        let options = arrow::ipc::writer::IpcWriteOptions::default();
+        let schema_result = arrow_flight::utils::flight_schema_from_arrow_schema(
+            physical_plan.schema().as_ref(),
+            &options,
+        );
+
+        Ok(Response::new(schema_result))
     }
 
-    // TODO: Stream results back directly by using `execute` instead of `collect`
-    // https://docs.rs/datafusion/3.0.0/datafusion/physical_plan/trait.ExecutionPlan.html#tymethod.execute
+    /// Runs the SQL query in the ticket, streaming the record batches back as
+    /// they are produced. The first message contains the schema.
     async fn do_get(
         &self,
         request: Request<Ticket>,
     ) -> Result<Response<Self::DoGetStream>, tonic::Status> {
-        let ticket = request.into_inner();
-        let json_str = String::from_utf8(ticket.ticket.to_vec()).context(InvalidTicket {
-            ticket: ticket.ticket,
-        })?;
+        let read_info = read_info(request.into_inner().ticket)?;
+        let (_, physical_plan) = self.plan(&read_info).await?;
 
-        let read_info: ReadInfo =
-            serde_json::from_str(&json_str).context(InvalidQuery { query: &json_str })?;
-
-        let db = self
-            .db_store
-            .db(&read_info.database_name)
-            .await
-            .context(DatabaseNotFound {
-                database_name: &read_info.database_name,
-            })?;
-
-        let planner = SQLQueryPlanner::default();
-        let executor = self.db_store.executor();
-
-        let physical_plan = planner
-            .query(&*db, &read_info.sql_query, &executor)
-            .await
-            .context(PlanningSQLQuery {
-                query: &read_info.sql_query,
-            })?;
-
-        // execute the query
-        let results = collect(Arc::clone(&physical_plan))
+        let schema = physical_plan.schema();
+        let mut results = self
+            .server
+            .executor()
+            .new_context()
+            .execute(physical_plan)
             .await
             .map_err(|e| Box::new(e) as _)
             .context(Query {
                 database_name: &read_info.database_name,
             })?;
-        if results.is_empty() {
-            return Err(tonic::Status::internal("There were no results from ticket"));
-        }
 
-        let options = arrow::ipc::writer::IpcWriteOptions::default();
-        let schema = physical_plan.schema();
-        let schema_flight_data =
-            arrow_flight::utils::flight_data_from_arrow_schema(schema.as_ref(), &options);
+        let (tx, rx) = mpsc::channel(4);
 
-        let mut flights: Vec<Result<FlightData, tonic::Status>> = vec![Ok(schema_flight_data)];
+        tokio::spawn(async move {
+            let options = arrow::ipc::writer::IpcWriteOptions::default();
+            let schema_flight_data =
+                arrow_flight::utils::flight_data_from_arrow_schema(schema.as_ref(), &options);
+            if tx.send(Ok(schema_flight_data)).await.is_err() {
+                return;
+            }
+
+            while let Some(batch) = results.next().await {
+                let batch = match batch {
+                    Ok(batch) => batch,
+                    Err(e) => {
+                        let error = Error::Query {
+                            database_name: read_info.database_name,
+                            source: Box::new(e),
+                        };
+                        // the client may have gone away already
+                        let _ = tx.send(Err(error.into())).await;
+                        return;
+                    }
+                };
 
-        let mut batches: Vec<Result<FlightData, tonic::Status>> = results
-            .iter()
-            .flat_map(|batch| {
                 let (flight_dictionaries, flight_batch) =
-                    arrow_flight::utils::flight_data_from_arrow_batch(batch, &options);
-                flight_dictionaries
+                    arrow_flight::utils::flight_data_from_arrow_batch(&batch, &options);
+
+                for flight_data in flight_dictionaries
                     .into_iter()
                     .chain(std::iter::once(flight_batch))
-                    .map(Ok)
-            })
-            .collect();
+                {
+                    // stop running the query once the client has gone away
+                    if tx.send(Ok(flight_data)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
 
-        // append batch vector to schema vector, so that the first message sent is the
-        // schema
-        flights.append(&mut batches);
-
-        let output = futures::stream::iter(flights);
-
-        Ok(Response::new(Box::pin(output) as Self::DoGetStream))
+        Ok(Response::new(
+            Box::pin(ReceiverStream::new(rx)) as Self::DoGetStream
+        ))
     }
 
     async fn handshake(
@@ -200,32 +593,163 @@ where
         Err(tonic::Status::unimplemented("Not yet implemented"))
     }
 
+    /// Plans the SQL query in the command of the descriptor and returns its
+    /// schema, along with an estimate of the number of rows the query reads.
+    /// The query can be run with `do_get` using the ticket of the endpoint.
     async fn get_flight_info(
         &self,
-        _request: Request<FlightDescriptor>,
+        request: Request<FlightDescriptor>,
     ) -> Result<Response<FlightInfo>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let descriptor = request.into_inner();
+        let read_info = descriptor_read_info(descriptor.clone())?;
+        let (db, physical_plan) = self.plan(&read_info).await?;
+
+        let total_records = SQLQueryPlanner::default()
+            .estimate_rows(&*db, &read_info.sql_query)
+            .context(PlanningSQLQuery {
+                query: &read_info.sql_query,
+            })?
+            .map(|rows| rows as i64)
+            // -1 means unknown
+            .unwrap_or(-1);
+
+        let options = arrow::ipc::writer::IpcWriteOptions::default();
+        let schema = arrow_flight::utils::flight_schema_from_arrow_schema(
+            physical_plan.schema().as_ref(),
+            &options,
+        )
+        .schema;
+
+        let endpoint = FlightEndpoint {
+            ticket: Some(Ticket {
+                ticket: descriptor.cmd.clone(),
+            }),
+            location: vec![],
+        };
+
+        Ok(Response::new(FlightInfo {
+            schema,
+            flight_descriptor: Some(descriptor),
+            endpoint: vec![endpoint],
+            total_records,
+            total_bytes: -1,
+        }))
     }
 
+    /// Writes the record batches in the stream to the table named by the
+    /// path of the stream's `FlightDescriptor`, which must be `[database,
+    /// table]`. The columns of the batches must carry the IOx column type
+    /// metadata of a `data_types::schema::Schema`. The rows are converted
+    /// into a write straight from the columns, which is then handled like a
+    /// line protocol write, so it is partitioned, buffered and replicated
+    /// according to the database rules.
+    /// Columns can be dictionary encoded, with their dictionaries sent ahead
+    /// of the batches that refer to them. One `PutResult` is returned as soon
+    /// as each batch was written, and the stream ends at the first batch that
+    /// can't be written. Messages larger than `MAX_PUT_MESSAGE_SIZE` once
+    /// encoded are rejected.
     async fn do_put(
         &self,
-        _request: Request<Streaming<FlightData>>,
+        request: Request<Streaming<FlightData>>,
     ) -> Result<Response<Self::DoPutStream>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let mut stream = request.into_inner();
+
+        // The first message carries the descriptor and the schema
+        let first = stream.message().await?.context(MissingDescriptor)?;
+        let (db_name, table_name) = put_target(first.flight_descriptor.as_ref())?;
+        let mut decoder = PutDecoder::try_new(&table_name, &first)?;
+
+        let server = Arc::clone(&self.server);
+        let (tx, rx) = mpsc::channel(4);
+
+        // Write each batch as it arrives, returning one result per batch
+        tokio::spawn(async move {
+            loop {
+                let data = match stream.message().await {
+                    Ok(Some(data)) => data,
+                    Ok(None) => return,
+                    Err(status) => {
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                };
+
+                let result = async {
+                    let batch = match decoder.decode(&data)? {
+                        Some(batch) => batch,
+                        None => return Ok(None),
+                    };
+
+                    check_field_values(&decoder.columns, &batch)?;
+                    if batch.num_rows() > 0 {
+                        server
+                            .write_record_batch(&db_name, &table_name, &decoder.columns, &batch)
+                            .await
+                            .map_err(default_server_error_handler)?;
+                    }
+
+                    Ok::<_, tonic::Status>(Some(PutResult {
+                        app_metadata: vec![],
+                    }))
+                }
+                .await;
+
+                // no result is returned for dictionary batches
+                let result = match result {
+                    Ok(Some(put_result)) => Ok(put_result),
+                    Ok(None) => continue,
+                    Err(status) => Err(status),
+                };
+
+                // stop at the first error, or once the client has gone away
+                let failed = result.is_err();
+                if tx.send(result).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(
+            Box::pin(ReceiverStream::new(rx)) as Self::DoPutStream
+        ))
     }
 
+    /// Runs one of the chunk lifecycle actions listed by `list_actions`
     async fn do_action(
         &self,
-        _request: Request<Action>,
+        request: Request<Action>,
     ) -> Result<Response<Self::DoActionStream>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let action = request.into_inner();
+
+        let results: Vec<Result<arrow_flight::Result, tonic::Status>> = self
+            .run_action(&action.r#type, &action.body)
+            .await?
+            .into_iter()
+            .map(|body| Ok(arrow_flight::Result { body }))
+            .collect();
+
+        let output = futures::stream::iter(results);
+
+        Ok(Response::new(Box::pin(output) as Self::DoActionStream))
     }
 
     async fn list_actions(
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<Self::ListActionsStream>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let actions: Vec<Result<ActionType, tonic::Status>> = ACTIONS
+            .iter()
+            .map(|(action_type, description)| {
+                Ok(ActionType {
+                    r#type: action_type.to_string(),
+                    description: description.to_string(),
+                })
+            })
+            .collect();
+
+        let output = futures::stream::iter(actions);
+
+        Ok(Response::new(Box::pin(output) as Self::ListActionsStream))
     }
 
     async fn do_exchange(
@@ -235,3 +759,435 @@ where
         Err(tonic::Status::unimplemented("Not yet implemented"))
     }
 }
+
+/// Parses the JSON `ReadInfo` of a ticket
+fn read_info(ticket: Vec<u8>) -> Result<ReadInfo, Error> {
+    let json_str = String::from_utf8(ticket.clone()).context(InvalidTicket { ticket })?;
+
+    serde_json::from_str(&json_str).context(InvalidQuery { query: &json_str })
+}
+
+/// Parses the `ReadInfo` in the command of a descriptor, which has the same
+/// format as the tickets sent to `do_get`
+fn descriptor_read_info(descriptor: FlightDescriptor) -> Result<ReadInfo, Error> {
+    ensure!(
+        descriptor.r#type == DescriptorType::Cmd as i32,
+        NotQueryDescriptor
+    );
+
+    read_info(descriptor.cmd)
+}
+
+fn parse_action_body<T: DeserializeOwned>(action_type: &str, body: &[u8]) -> Result<T, Error> {
+    serde_json::from_slice(body).context(InvalidActionBody { action_type })
+}
+
+fn action_results<T: Serialize>(
+    results: impl IntoIterator<Item = T>,
+) -> Result<Vec<Vec<u8>>, Error> {
+    results
+        .into_iter()
+        .map(|result| serde_json::to_vec(&result).context(SerializingActionResult))
+        .collect()
+}
+
+/// Returns the database and table named by the descriptor of a DoPut stream
+fn put_target(
+    descriptor: Option<&FlightDescriptor>,
+) -> Result<(DatabaseName<'static>, String), Error> {
+    let descriptor = descriptor.context(MissingDescriptor)?;
+
+    let (db_name, table_name) = match descriptor.path.as_slice() {
+        [db_name, table_name] if descriptor.r#type == DescriptorType::Path as i32 => {
+            (db_name, table_name)
+        }
+        _ => {
+            return InvalidDescriptor {
+                path: descriptor.path.clone(),
+            }
+            .fail()
+        }
+    };
+
+    let db_name = DatabaseName::new(db_name.clone()).context(InvalidDatabaseName)?;
+
+    Ok((db_name, table_name.clone()))
+}
+
+/// Decodes the record batches of a DoPut stream, keeping the dictionaries
+/// sent ahead of the batches that refer to them
+struct PutDecoder {
+    /// The schema of the stream, which can have dictionary encoded columns
+    schema: SchemaRef,
+    /// The schema of the decoded batches, in which the dictionary encoded
+    /// columns hold their values instead
+    batch_schema: SchemaRef,
+    /// The name and IOx column type of each column of the decoded batches
+    columns: Vec<(String, InfluxColumnType)>,
+    /// The latest dictionary of each dictionary encoded column
+    dictionaries_by_field: Vec<Option<ArrayRef>>,
+}
+
+impl PutDecoder {
+    /// Creates a decoder for the schema in the first message of a DoPut
+    /// stream to the table
+    fn try_new(table_name: &str, first: &FlightData) -> Result<Self, Error> {
+        let schema = Arc::new(ArrowSchema::try_from(first).context(InvalidSchema)?);
+        let batch_schema = unpacked_schema(&schema);
+        let columns = put_columns(table_name, Arc::clone(&batch_schema))?;
+        let dictionaries_by_field = vec![None; schema.fields().len()];
+
+        Ok(Self {
+            schema,
+            batch_schema,
+            columns,
+            dictionaries_by_field,
+        })
+    }
+
+    /// Decodes the next message of the stream, returning `None` if it was a
+    /// dictionary batch
+    fn decode(&mut self, data: &FlightData) -> Result<Option<RecordBatch>, Error> {
+        check_put_size(data)?;
+
+        let message =
+            ipc::root_as_message(&data.data_header[..]).map_err(|e| Error::InvalidPutMessage {
+                message: e.to_string(),
+            })?;
+
+        if let Some(dictionary_batch) = message.header_as_dictionary_batch() {
+            reader::read_dictionary(
+                &data.data_body,
+                dictionary_batch,
+                &self.schema,
+                &mut self.dictionaries_by_field,
+            )
+            .context(InvalidDictionaryBatch)?;
+            return Ok(None);
+        }
+
+        for (field, dictionary) in self.schema.fields().iter().zip(&self.dictionaries_by_field) {
+            ensure!(
+                dictionary.is_some() || !matches!(field.data_type(), DataType::Dictionary(..)),
+                MissingDictionary {
+                    column_name: field.name()
+                }
+            );
+        }
+
+        let batch =
+            flight_data_to_arrow_batch(data, Arc::clone(&self.schema), &self.dictionaries_by_field)
+                .context(InvalidRecordBatch)?;
+
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|array| match array.data_type() {
+                DataType::Dictionary(_, value_type) => arrow::compute::cast(array, value_type),
+                _ => Ok(Arc::clone(array)),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .context(InvalidRecordBatch)?;
+
+        RecordBatch::try_new(Arc::clone(&self.batch_schema), columns)
+            .map(Some)
+            .context(InvalidRecordBatch)
+    }
+}
+
+/// Returns the schema with the dictionary encoded columns replaced by
+/// columns of their values, which is validated against the IOx data model
+fn unpacked_schema(schema: &ArrowSchema) -> SchemaRef {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| match field.data_type() {
+            DataType::Dictionary(_, value_type) => ArrowField::new(
+                field.name(),
+                value_type.as_ref().clone(),
+                field.is_nullable(),
+            ),
+            _ => field.clone(),
+        })
+        .collect();
+
+    Arc::new(ArrowSchema::new_with_metadata(
+        fields,
+        schema.metadata().clone(),
+    ))
+}
+
+/// Validates the schema of a DoPut stream against the IOx data model and
+/// returns the name and type of each column
+fn put_columns(
+    table_name: &str,
+    schema: arrow::datatypes::SchemaRef,
+) -> Result<Vec<(String, InfluxColumnType)>, Error> {
+    let schema = Schema::try_from(schema).context(InvalidIoxSchema)?;
+
+    if let Some(measurement) = schema.measurement() {
+        ensure!(
+            measurement == table_name,
+            MeasurementMismatch {
+                measurement,
+                table_name
+            }
+        );
+    }
+
+    let columns = schema
+        .iter()
+        .map(|(column_type, field)| match column_type {
+            // the mutable buffer has no unsigned integer columns
+            Some(column_type @ InfluxColumnType::Field(InfluxFieldType::UInteger)) => {
+                UnsupportedColumnType {
+                    column_name: field.name(),
+                    column_type,
+                }
+                .fail()
+            }
+            Some(column_type) => Ok((field.name().clone(), column_type)),
+            None => UntypedColumn {
+                column_name: field.name(),
+            }
+            .fail(),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let count = schema.time_iter().count();
+    ensure!(count == 1, InvalidTimeColumn { count });
+
+    Ok(columns)
+}
+
+/// Rejects DoPut messages larger than `MAX_PUT_MESSAGE_SIZE`, before they
+/// are decoded
+fn check_put_size(data: &FlightData) -> Result<(), Error> {
+    let size = data.data_header.len() + data.data_body.len();
+    ensure!(
+        size <= MAX_PUT_MESSAGE_SIZE,
+        PutMessageTooLarge {
+            size,
+            max_size: MAX_PUT_MESSAGE_SIZE
+        }
+    );
+    Ok(())
+}
+
+/// Rejects record batches with rows that have no field values, which can't
+/// be written
+fn check_field_values(
+    columns: &[(String, InfluxColumnType)],
+    batch: &RecordBatch,
+) -> Result<(), Error> {
+    let fields: Vec<_> = columns
+        .iter()
+        .zip(batch.columns())
+        .filter(|((_, column_type), _)| matches!(column_type, InfluxColumnType::Field(_)))
+        .map(|(_, array)| array)
+        .collect();
+
+    match (0..batch.num_rows()).find(|&row| fields.iter().all(|array| array.is_null(row))) {
+        Some(row) => NoFieldValues { row }.fail(),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_deps::{
+        arrow::{
+            array::{DictionaryArray, Float64Array, Int64Array, StringArray},
+            datatypes::Int32Type,
+            ipc::writer::IpcWriteOptions,
+            util::pretty::pretty_format_batches,
+        },
+        arrow_flight::utils::{flight_data_from_arrow_batch, flight_data_from_arrow_schema},
+    };
+    use data_types::schema::builder::SchemaBuilder;
+
+    fn schema() -> arrow::datatypes::SchemaRef {
+        SchemaBuilder::new()
+            .tag("host")
+            .field("usage", DataType::Float64)
+            .field("count", DataType::Int64)
+            .timestamp()
+            .build()
+            .unwrap()
+            .into()
+    }
+
+    fn batch(schema: arrow::datatypes::SchemaRef) -> RecordBatch {
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+                Arc::new(Float64Array::from(vec![Some(1.5), None])),
+                Arc::new(Int64Array::from(vec![None, Some(2)])),
+                Arc::new(Int64Array::from(vec![Some(10), Some(20)])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rejects_invalid_schemas() {
+        let untyped = Arc::new(ArrowSchema::new(vec![arrow::datatypes::Field::new(
+            "foo",
+            DataType::Int64,
+            true,
+        )]));
+        let err = put_columns("cpu", untyped).unwrap_err();
+        assert!(matches!(err, Error::UntypedColumn { .. }), "{}", err);
+
+        let no_time: arrow::datatypes::SchemaRef = SchemaBuilder::new()
+            .field("usage", DataType::Float64)
+            .build()
+            .unwrap()
+            .into();
+        let err = put_columns("cpu", no_time).unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidTimeColumn { count: 0 }),
+            "{}",
+            err
+        );
+
+        let other_table: arrow::datatypes::SchemaRef = SchemaBuilder::new()
+            .measurement("mem")
+            .field("usage", DataType::Float64)
+            .timestamp()
+            .build()
+            .unwrap()
+            .into();
+        let err = put_columns("cpu", other_table).unwrap_err();
+        assert!(matches!(err, Error::MeasurementMismatch { .. }), "{}", err);
+    }
+
+    #[test]
+    fn rejects_rows_without_fields() {
+        let schema = schema();
+        let columns = put_columns("cpu", Arc::clone(&schema)).unwrap();
+        let no_fields = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(StringArray::from(vec![Some("a")])),
+                Arc::new(Float64Array::from(vec![None])),
+                Arc::new(Int64Array::from(vec![None])),
+                Arc::new(Int64Array::from(vec![Some(10)])),
+            ],
+        )
+        .unwrap();
+
+        let err = check_field_values(&columns, &no_fields).unwrap_err();
+        assert!(matches!(err, Error::NoFieldValues { row: 0 }), "{}", err);
+
+        check_field_values(&columns, &batch(schema)).unwrap();
+    }
+
+    #[test]
+    fn decodes_dictionary_encoded_batches() {
+        let schema = schema();
+        let mut fields = schema.fields().clone();
+        fields[0] = ArrowField::new(
+            "host",
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+            true,
+        );
+        let dictionary_schema = Arc::new(ArrowSchema::new_with_metadata(
+            fields,
+            schema.metadata().clone(),
+        ));
+
+        let expected = batch(Arc::clone(&schema));
+        let hosts: DictionaryArray<Int32Type> = vec![Some("a"), None].into_iter().collect();
+        let mut columns = expected.columns().to_vec();
+        columns[0] = Arc::new(hosts);
+        let dictionary_batch = RecordBatch::try_new(dictionary_schema, columns).unwrap();
+
+        let options = IpcWriteOptions::default();
+        let first = flight_data_from_arrow_schema(&dictionary_batch.schema(), &options);
+        let mut decoder = PutDecoder::try_new("cpu", &first).unwrap();
+        assert_eq!(
+            decoder.columns,
+            put_columns("cpu", Arc::clone(&schema)).unwrap()
+        );
+
+        // the dictionary is sent ahead of the batch
+        let (dictionaries, data) = flight_data_from_arrow_batch(&dictionary_batch, &options);
+        assert_eq!(dictionaries.len(), 1);
+        assert!(decoder.decode(&dictionaries[0]).unwrap().is_none());
+
+        let decoded = decoder.decode(&data).unwrap().unwrap();
+        assert_eq!(decoded.schema().fields(), schema.fields());
+        assert_eq!(
+            pretty_format_batches(&[decoded]).unwrap(),
+            pretty_format_batches(&[expected]).unwrap()
+        );
+
+        // the dictionary must be sent first
+        let mut decoder = PutDecoder::try_new("cpu", &first).unwrap();
+        let err = decoder.decode(&data).unwrap_err();
+        assert!(matches!(err, Error::MissingDictionary { .. }), "{}", err);
+    }
+
+    #[test]
+    fn rejects_large_put_messages() {
+        let (_, flight_batch) =
+            flight_data_from_arrow_batch(&batch(schema()), &IpcWriteOptions::default());
+        check_put_size(&flight_batch).unwrap();
+
+        let too_large = FlightData {
+            data_body: vec![0; MAX_PUT_MESSAGE_SIZE + 1],
+            ..flight_batch
+        };
+        let err = check_put_size(&too_large).unwrap_err();
+        assert!(
+            matches!(err, Error::PutMessageTooLarge { size, .. } if size > MAX_PUT_MESSAGE_SIZE),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn read_info_from_descriptor() {
+        let cmd = br#"{"database_name": "my_db", "sql_query": "select * from cpu"}"#.to_vec();
+        let descriptor = FlightDescriptor {
+            r#type: DescriptorType::Cmd as i32,
+            cmd,
+            path: vec![],
+        };
+        let read_info = descriptor_read_info(descriptor.clone()).unwrap();
+        assert_eq!(read_info.database_name, "my_db");
+        assert_eq!(read_info.sql_query, "select * from cpu");
+
+        let descriptor = FlightDescriptor {
+            r#type: DescriptorType::Path as i32,
+            ..descriptor
+        };
+        let err = descriptor_read_info(descriptor).unwrap_err();
+        assert!(matches!(err, Error::NotQueryDescriptor), "{}", err);
+
+        let err = read_info(b"not json".to_vec()).unwrap_err();
+        assert!(matches!(err, Error::InvalidQuery { .. }), "{}", err);
+    }
+
+    #[test]
+    fn put_target_from_descriptor() {
+        let descriptor = FlightDescriptor {
+            r#type: DescriptorType::Path as i32,
+            cmd: vec![],
+            path: vec!["my_db".to_string(), "cpu".to_string()],
+        };
+        let (db_name, table_name) = put_target(Some(&descriptor)).unwrap();
+        assert_eq!(db_name.as_str(), "my_db");
+        assert_eq!(table_name, "cpu");
+
+        let descriptor = FlightDescriptor {
+            path: vec!["my_db".to_string()],
+            ..descriptor
+        };
+        let err = put_target(Some(&descriptor)).unwrap_err();
+        assert!(matches!(err, Error::InvalidDescriptor { .. }), "{}", err);
+    }
+}
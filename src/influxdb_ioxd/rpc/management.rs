@@ -1,36 +1,42 @@
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
 use std::sync::Arc;
 
 use tonic::{Request, Response, Status};
-use tracing::error;
 
+use data_types::chunk::ChunkStorage;
 use data_types::database_rules::DatabaseRules;
+use data_types::job::Job;
 use data_types::DatabaseName;
-use generated_types::google::{
-    AlreadyExists, FieldViolation, FieldViolationExt, InternalError, NotFound,
-    PreconditionViolation,
-};
+use generated_types::google::{AlreadyExists, FieldViolation, FieldViolationExt, NotFound};
 use generated_types::influxdata::iox::management::v1::*;
-use query::DatabaseStore;
-use server::{ConnectionManager, Error, Server};
+use query::{DatabaseStore, PartitionChunk};
+use server::{
+    db::Db, jobs::TrackedJob, tracker::TrackedFutureExt, ConnectionManager, Error, Server,
+};
+
+use super::error::{default_db_error_handler, default_server_error_handler};
 
 struct ManagementService<M: ConnectionManager> {
     server: Arc<Server<M>>,
 }
 
-fn default_error_handler(error: Error) -> tonic::Status {
-    match error {
-        Error::IdNotSet => PreconditionViolation {
-            category: "Writer ID".to_string(),
-            subject: "influxdata.com/iox".to_string(),
-            description: "Writer ID must be set".to_string(),
-        }
-        .into(),
-        error => {
-            error!(?error, "Unexpected error");
-            InternalError {}.into()
-        }
+impl<M> ManagementService<M>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    /// Returns the named database, or `NotFound` if the server doesn't have it
+    async fn db(&self, db_name: String) -> Result<Arc<Db>, Status> {
+        let name = DatabaseName::new(db_name).field("db_name")?;
+
+        self.server.db(&name).await.ok_or_else(|| {
+            NotFound {
+                resource_type: "database".to_string(),
+                resource_name: name.to_string(),
+                ..Default::default()
+            }
+            .into()
+        })
     }
 }
 
@@ -110,8 +116,310 @@ where
                 }
                 .into())
             }
-            Err(e) => Err(default_error_handler(e)),
+            Err(e) => Err(default_server_error_handler(e)),
+        }
+    }
+
+    async fn update_database(
+        &self,
+        request: Request<UpdateDatabaseRequest>,
+    ) -> Result<Response<UpdateDatabaseResponse>, Status> {
+        let rules: DatabaseRules = request
+            .into_inner()
+            .rules
+            .ok_or_else(|| FieldViolation::required(""))
+            .and_then(TryInto::try_into)
+            .map_err(|e| e.scope("rules"))?;
+
+        let name =
+            DatabaseName::new(rules.name.clone()).expect("protobuf mapping didn't validate name");
+
+        self.server
+            .update_database(&name, rules)
+            .await
+            .map_err(default_server_error_handler)?;
+
+        Ok(Response::new(UpdateDatabaseResponse {}))
+    }
+
+    async fn delete_database(
+        &self,
+        request: Request<DeleteDatabaseRequest>,
+    ) -> Result<Response<DeleteDatabaseResponse>, Status> {
+        let request = request.into_inner();
+        let name = DatabaseName::new(request.name).field("name")?;
+
+        self.server
+            .delete_database(&name, request.delete_data)
+            .await
+            .map_err(default_server_error_handler)?;
+
+        Ok(Response::new(DeleteDatabaseResponse {}))
+    }
+
+    async fn create_host_group(
+        &self,
+        request: Request<CreateHostGroupRequest>,
+    ) -> Result<Response<CreateHostGroupResponse>, Status> {
+        let host_group = request
+            .into_inner()
+            .host_group
+            .ok_or_else(|| FieldViolation::required("host_group"))?;
+
+        if host_group.id.is_empty() {
+            return Err(FieldViolation::required("host_group.id").into());
         }
+
+        self.server
+            .create_host_group(host_group.id, host_group.hosts)
+            .await
+            .map_err(default_server_error_handler)?;
+
+        Ok(Response::new(CreateHostGroupResponse {}))
+    }
+
+    async fn get_replication_status(
+        &self,
+        request: Request<GetReplicationStatusRequest>,
+    ) -> Result<Response<GetReplicationStatusResponse>, Status> {
+        let name = DatabaseName::new(request.into_inner().db_name).field("db_name")?;
+
+        let status = self
+            .server
+            .replication_status(&name)
+            .ok_or_else(|| NotFound {
+                resource_type: "database".to_string(),
+                resource_name: name.to_string(),
+                ..Default::default()
+            })?;
+
+        let host_groups = status
+            .host_groups
+            .into_iter()
+            .map(|(host_group_id, group)| HostGroupReplicationStatus {
+                host_group_id,
+                queued_writes: group.queued_writes as u64,
+                lag: Some(group.lag.into()),
+            })
+            .collect();
+
+        Ok(Response::new(GetReplicationStatusResponse {
+            queue_max_size: status.max_size as u64,
+            queued_writes: status.queued_writes as u64,
+            dropped_writes: status.dropped_writes,
+            lag: Some(status.lag.into()),
+            host_groups,
+        }))
+    }
+
+    async fn list_partitions(
+        &self,
+        request: Request<ListPartitionsRequest>,
+    ) -> Result<Response<ListPartitionsResponse>, Status> {
+        let db = self.db(request.into_inner().db_name).await?;
+
+        let partitions = db
+            .all_partition_keys()
+            .map_err(default_db_error_handler)?
+            .into_iter()
+            .map(|key| Partition { key })
+            .collect();
+
+        Ok(Response::new(ListPartitionsResponse { partitions }))
+    }
+
+    async fn list_chunks(
+        &self,
+        request: Request<ListChunksRequest>,
+    ) -> Result<Response<ListChunksResponse>, Status> {
+        let db = self.db(request.into_inner().db_name).await?;
+
+        let chunks = db
+            .chunk_summaries()
+            .map_err(default_db_error_handler)?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(Response::new(ListChunksResponse { chunks }))
+    }
+
+    async fn list_partition_chunks(
+        &self,
+        request: Request<ListPartitionChunksRequest>,
+    ) -> Result<Response<ListPartitionChunksResponse>, Status> {
+        let ListPartitionChunksRequest {
+            db_name,
+            partition_key,
+        } = request.into_inner();
+        let db = self.db(db_name).await?;
+
+        let chunks = db
+            .partition_chunk_summaries(&partition_key)
+            .map_err(default_db_error_handler)?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(Response::new(ListPartitionChunksResponse { chunks }))
+    }
+
+    async fn rollover_partition(
+        &self,
+        request: Request<RolloverPartitionRequest>,
+    ) -> Result<Response<RolloverPartitionResponse>, Status> {
+        let RolloverPartitionRequest {
+            db_name,
+            partition_key,
+        } = request.into_inner();
+        let db = self.db(db_name).await?;
+
+        let chunk = db
+            .rollover_partition(&partition_key)
+            .await
+            .map_err(default_db_error_handler)?;
+
+        let chunk = db
+            .chunk_summary(
+                &partition_key,
+                chunk.id(),
+                ChunkStorage::ClosedMutableBuffer,
+            )
+            .map_err(default_db_error_handler)?;
+
+        Ok(Response::new(RolloverPartitionResponse {
+            chunk: chunk.map(Into::into),
+        }))
+    }
+
+    async fn load_chunk_to_read_buffer(
+        &self,
+        request: Request<LoadChunkToReadBufferRequest>,
+    ) -> Result<Response<LoadChunkToReadBufferResponse>, Status> {
+        let LoadChunkToReadBufferRequest {
+            db_name,
+            partition_key,
+            chunk_id,
+        } = request.into_inner();
+        let db = self.db(db_name.clone()).await?;
+
+        let job = TrackedJob::new(Job::LoadReadBuffer {
+            db_name,
+            partition_key: partition_key.clone(),
+            chunk_id,
+        });
+        db.load_chunk_to_read_buffer(&partition_key, chunk_id)
+            .track(self.server.jobs(), job)
+            .await
+            .map_err(|_| Status::cancelled("loading the chunk was cancelled"))?
+            .map_err(default_db_error_handler)?;
+
+        let chunk = db
+            .chunk_summary(&partition_key, chunk_id, ChunkStorage::ReadBuffer)
+            .map_err(default_db_error_handler)?;
+
+        Ok(Response::new(LoadChunkToReadBufferResponse {
+            chunk: chunk.map(Into::into),
+        }))
+    }
+
+    async fn drop_chunk(
+        &self,
+        request: Request<DropChunkRequest>,
+    ) -> Result<Response<DropChunkResponse>, Status> {
+        let request = request.into_inner();
+        let storage = ChunkStorage::try_from(request.storage()).map_err(|e| e.scope("storage"))?;
+        let db = self.db(request.db_name.clone()).await?;
+
+        let partition_key = &request.partition_key;
+        let chunk_id = request.chunk_id;
+        let dropped = match storage {
+            ChunkStorage::OpenMutableBuffer => {
+                return Err(FieldViolation {
+                    field: "storage".to_string(),
+                    description: "The open chunk of a partition can't be dropped, \
+                                  roll the partition over first"
+                        .to_string(),
+                }
+                .into())
+            }
+            ChunkStorage::ClosedMutableBuffer => {
+                db.drop_mutable_buffer_chunk(partition_key, chunk_id).await
+            }
+            ChunkStorage::ReadBuffer => db.drop_read_buffer_chunk(partition_key, chunk_id).await,
+            ChunkStorage::ObjectStore => {
+                // the drop is recorded in the database's catalog
+                let name = DatabaseName::new(&request.db_name).field("db_name")?;
+                self.server
+                    .drop_persisted_chunk(&name, partition_key, chunk_id)
+                    .await
+                    .map_err(default_server_error_handler)?;
+                return Ok(Response::new(DropChunkResponse {}));
+            }
+        };
+        dropped.map_err(default_db_error_handler)?;
+
+        Ok(Response::new(DropChunkResponse {}))
+    }
+
+    async fn snapshot_partition(
+        &self,
+        request: Request<SnapshotPartitionRequest>,
+    ) -> Result<Response<SnapshotPartitionResponse>, Status> {
+        let SnapshotPartitionRequest {
+            db_name,
+            partition_key,
+        } = request.into_inner();
+        let name = DatabaseName::new(db_name).field("db_name")?;
+
+        let snapshot = self
+            .server
+            .snapshot_partition(&name, &partition_key)
+            .await
+            .map_err(default_server_error_handler)?;
+
+        Ok(Response::new(SnapshotPartitionResponse {
+            snapshot_id: snapshot.id.to_string(),
+            chunk_id: snapshot.chunk_id(),
+            operation_id: usize::from(snapshot.operation_id) as u64,
+        }))
+    }
+
+    async fn compact_chunks(
+        &self,
+        request: Request<CompactChunksRequest>,
+    ) -> Result<Response<CompactChunksResponse>, Status> {
+        let CompactChunksRequest {
+            db_name,
+            partition_key,
+            chunk_ids,
+        } = request.into_inner();
+        let name = DatabaseName::new(&db_name).field("db_name")?;
+        let db = self.db(db_name.clone()).await?;
+
+        let job = TrackedJob::new(Job::CompactChunks {
+            db_name: db_name.clone(),
+            partition_key: partition_key.clone(),
+            chunk_ids: chunk_ids.clone(),
+        });
+        let progress = Arc::clone(job.progress());
+
+        // persisted chunks are replaced in the database's catalog as well
+        let chunk = self
+            .server
+            .compact_chunks(&name, &partition_key, &chunk_ids, &progress)
+            .track(self.server.jobs(), job)
+            .await
+            .map_err(|_| Status::cancelled("compacting the chunks was cancelled"))?
+            .map_err(default_server_error_handler)?;
+
+        let chunk = db
+            .chunk_summary(&partition_key, chunk.id(), ChunkStorage::ReadBuffer)
+            .map_err(default_db_error_handler)?;
+
+        Ok(Response::new(CompactChunksResponse {
+            chunk: chunk.map(Into::into),
+        }))
     }
 }
 
@@ -0,0 +1,101 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use tonic::{Request, Response, Status};
+
+use generated_types::google::{protobuf::Timestamp, NotFound};
+use generated_types::influxdata::iox::management::v1::*;
+use server::{jobs::TrackedJob, tracker::TrackerId, ConnectionManager, Server};
+
+struct OperationsService<M: ConnectionManager> {
+    server: Arc<Server<M>>,
+}
+
+/// Converts a running job into its protobuf representation
+fn operation(id: TrackerId, tracked: TrackedJob) -> Operation {
+    let (total_tasks, completed_tasks) = tracked.progress().tasks();
+
+    Operation {
+        id: usize::from(id) as u64,
+        description: tracked.job.description(),
+        start_time: Some(Timestamp {
+            seconds: tracked.start_time.timestamp(),
+            nanos: tracked.start_time.timestamp_subsec_nanos() as i32,
+        }),
+        total_tasks: total_tasks as u64,
+        completed_tasks: completed_tasks as u64,
+        job: Some(tracked.job.into()),
+    }
+}
+
+fn operation_not_found(id: u64) -> Status {
+    NotFound {
+        resource_type: "operation".to_string(),
+        resource_name: id.to_string(),
+        ..Default::default()
+    }
+    .into()
+}
+
+#[tonic::async_trait]
+impl<M> operations_service_server::OperationsService for OperationsService<M>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    async fn list_operations(
+        &self,
+        _: Request<ListOperationsRequest>,
+    ) -> Result<Response<ListOperationsResponse>, Status> {
+        let mut tracked = self.server.jobs().tracked();
+        tracked.sort_unstable_by_key(|(id, _)| *id);
+
+        let operations = tracked
+            .into_iter()
+            .map(|(id, tracked)| operation(id, tracked))
+            .collect();
+
+        Ok(Response::new(ListOperationsResponse { operations }))
+    }
+
+    async fn get_operation(
+        &self,
+        request: Request<GetOperationRequest>,
+    ) -> Result<Response<GetOperationResponse>, Status> {
+        let id = request.into_inner().id;
+        let tracker_id = TrackerId::from(id as usize);
+
+        let tracked = self
+            .server
+            .jobs()
+            .get(tracker_id)
+            .ok_or_else(|| operation_not_found(id))?;
+
+        Ok(Response::new(GetOperationResponse {
+            operation: Some(operation(tracker_id, tracked)),
+        }))
+    }
+
+    async fn cancel_operation(
+        &self,
+        request: Request<CancelOperationRequest>,
+    ) -> Result<Response<CancelOperationResponse>, Status> {
+        let id = request.into_inner().id;
+
+        if !self.server.jobs().terminate(TrackerId::from(id as usize)) {
+            return Err(operation_not_found(id));
+        }
+
+        Ok(Response::new(CancelOperationResponse {}))
+    }
+}
+
+pub fn make_server<M>(
+    server: Arc<Server<M>>,
+) -> operations_service_server::OperationsServiceServer<
+    impl operations_service_server::OperationsService,
+>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    operations_service_server::OperationsServiceServer::new(OperationsService { server })
+}
@@ -16,6 +16,7 @@ use query::exec::{
 
 use generated_types::{
     measurement_fields_response::{FieldType, MessageField},
+    read_group_request::HintFlags,
     read_response::{
         frame::Data, BooleanPointsFrame, DataType, FloatPointsFrame, Frame, GroupFrame,
         IntegerPointsFrame, SeriesFrame, StringPointsFrame,
@@ -37,6 +38,49 @@ pub enum Error {
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// The `hints` of a read_group request, which ask for only part of
+/// the results to be sent
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ReadGroupHints {
+    /// Send the group and series frames, but no points
+    pub no_points: bool,
+    /// Send only the group frames
+    pub no_series: bool,
+    /// Find the groups and their series from all of the data, ignoring
+    /// the time range. Points are always restricted to the time range, so
+    /// this can only be set with `no_points` or `no_series`
+    pub schema_all_time: bool,
+}
+
+impl ReadGroupHints {
+    /// Decodes the `hints` bit flags of a read_group request, returning
+    /// `None` if any unknown flags are set, or if `HINT_SCHEMA_ALL_TIME`
+    /// is set without `HINT_NO_POINTS` or `HINT_NO_SERIES`
+    pub fn from_flags(hints: u32) -> Option<Self> {
+        let no_points = HintFlags::HintNoPoints as u32;
+        let no_series = HintFlags::HintNoSeries as u32;
+        let schema_all_time = HintFlags::HintSchemaAllTime as u32;
+
+        if hints & !(no_points | no_series | schema_all_time) != 0 {
+            return None;
+        }
+
+        let hints = Self {
+            no_points: hints & no_points != 0,
+            no_series: hints & no_series != 0,
+            schema_all_time: hints & schema_all_time != 0,
+        };
+
+        // the groups of the points in the time range can't be found from
+        // all of the data
+        if hints.schema_all_time && !(hints.no_points || hints.no_series) {
+            return None;
+        }
+
+        Some(hints)
+    }
+}
+
 /// Convert a set of tag_keys into a form suitable for gRPC transport,
 /// adding the special 0x00 (_m) and 0xff (_f) tag keys
 ///
@@ -53,10 +97,12 @@ pub fn tag_keys_to_byte_vecs(tag_keys: Arc<BTreeSet<String>>) -> Vec<Vec<u8>> {
     byte_vecs
 }
 
-fn series_set_to_frames(series_set: SeriesSet) -> Result<Vec<Frame>> {
+fn series_set_to_frames(series_set: SeriesSet, hints: ReadGroupHints) -> Result<Vec<Frame>> {
     let mut data_records = Vec::new();
-    for field_index in series_set.field_indexes.as_slice().iter() {
-        field_to_data(&mut data_records, &series_set, field_index)?
+    if !hints.no_series {
+        for field_index in series_set.field_indexes.as_slice().iter() {
+            field_to_data(&mut data_records, &series_set, field_index, hints)?
+        }
     }
 
     let frames = data_records
@@ -91,12 +137,18 @@ fn series_set_to_frames(series_set: SeriesSet) -> Result<Vec<Frame>> {
 /// ```
 ///
 /// The specific type of (*Points) depends on the type of field column.
-pub fn series_set_item_to_read_response(series_set_item: SeriesSetItem) -> Result<ReadResponse> {
+///
+/// The (*Points) frames are left out if `hints.no_points` is set, and
+/// only the (GroupFrame) is kept if `hints.no_series` is set.
+pub fn series_set_item_to_read_response(
+    series_set_item: SeriesSetItem,
+    hints: ReadGroupHints,
+) -> Result<ReadResponse> {
     let frames = match series_set_item {
         SeriesSetItem::GroupStart(group_description) => {
             group_description_to_frames(group_description)?
         }
-        SeriesSetItem::Data(series_set) => series_set_to_frames(series_set)?,
+        SeriesSetItem::Data(series_set) => series_set_to_frames(series_set, hints)?,
     };
     Ok(ReadResponse { frames })
 }
@@ -144,6 +196,7 @@ fn field_to_data(
     frames: &mut Vec<Data>,
     series_set: &SeriesSet,
     indexes: &FieldIndex,
+    hints: ReadGroupHints,
 ) -> Result<()> {
     let batch = &series_set.batch;
     let schema = batch.schema();
@@ -170,6 +223,10 @@ fn field_to_data(
     };
     frames.push(Data::Series(series_frame));
 
+    if hints.no_points {
+        return Ok(());
+    }
+
     let timestamps = batch
         .column(indexes.timestamp_index)
         .as_any()
@@ -357,7 +414,7 @@ mod tests {
     }
 
     fn series_set_to_read_response(series_set: SeriesSet) -> Result<ReadResponse> {
-        let frames = series_set_to_frames(series_set)?;
+        let frames = series_set_to_frames(series_set, ReadGroupHints::default())?;
         Ok(ReadResponse { frames })
     }
 
@@ -525,8 +582,9 @@ mod tests {
 
         let grouped_series_set_item = SeriesSetItem::GroupStart(group_description);
 
-        let response = series_set_item_to_read_response(grouped_series_set_item)
-            .expect("Correctly converted grouped_series_set_item");
+        let response =
+            series_set_item_to_read_response(grouped_series_set_item, ReadGroupHints::default())
+                .expect("Correctly converted grouped_series_set_item");
 
         let dumped_frames = response
             .frames
@@ -568,7 +626,7 @@ mod tests {
 
         let series_set_item = SeriesSetItem::Data(series_set);
 
-        let response = series_set_item_to_read_response(series_set_item)
+        let response = series_set_item_to_read_response(series_set_item, ReadGroupHints::default())
             .expect("Correctly converted series_set_item");
 
         let dumped_frames = response
@@ -589,6 +647,89 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_group_series_conversion_with_hints() {
+        let schema = Arc::new(Schema::new(vec![
+            ArrowField::new("float_field", ArrowDataType::Float64, true),
+            ArrowField::new("time", ArrowDataType::Int64, true),
+        ]));
+
+        let float_array: ArrayRef = Arc::new(Float64Array::from(vec![10.1, 20.1, 30.1, 40.1]));
+        let timestamp_array: ArrayRef = Arc::new(Int64Array::from(vec![1000, 2000, 3000, 4000]));
+
+        let batch = RecordBatch::try_new(schema, vec![float_array, timestamp_array])
+            .expect("created new record batch");
+
+        let dump_response = |hints| {
+            let series_set = SeriesSet {
+                table_name: Arc::new("the_table".into()),
+                tags: vec![(Arc::new("tag1".into()), Arc::new("val1".into()))],
+                field_indexes: FieldIndexes::from_timestamp_and_value_indexes(1, &[0]),
+                start_row: 1,
+                num_rows: 2,
+                batch: batch.clone(),
+            };
+
+            let series_set_item = SeriesSetItem::Data(series_set);
+            series_set_item_to_read_response(series_set_item, hints)
+                .expect("Correctly converted series_set_item")
+                .frames
+                .iter()
+                .map(|f| dump_frame(f))
+                .collect::<Vec<_>>()
+        };
+
+        let no_points = ReadGroupHints::from_flags(HintFlags::HintNoPoints as u32).unwrap();
+        let expected_frames =
+            vec!["SeriesFrame, tags: _field=float_field,_measurement=the_table,tag1=val1, type: 0"];
+        assert_eq!(dump_response(no_points), expected_frames);
+
+        let no_series = ReadGroupHints::from_flags(HintFlags::HintNoSeries as u32).unwrap();
+        assert!(dump_response(no_series).is_empty());
+
+        // group frames are always sent
+        let group_description = GroupDescription {
+            tags: vec![(Arc::new("tag1".into()), Arc::new("val1".into()))],
+        };
+        let response = series_set_item_to_read_response(
+            SeriesSetItem::GroupStart(group_description),
+            no_series,
+        )
+        .expect("Correctly converted grouped_series_set_item");
+        assert_eq!(response.frames.len(), 1);
+    }
+
+    #[test]
+    fn test_read_group_hints() {
+        assert_eq!(
+            ReadGroupHints::from_flags(0),
+            Some(ReadGroupHints::default())
+        );
+        assert_eq!(
+            ReadGroupHints::from_flags(0x07),
+            Some(ReadGroupHints {
+                no_points: true,
+                no_series: true,
+                schema_all_time: true,
+            })
+        );
+        assert_eq!(
+            ReadGroupHints::from_flags(
+                HintFlags::HintNoPoints as u32 | HintFlags::HintSchemaAllTime as u32
+            ),
+            Some(ReadGroupHints {
+                no_points: true,
+                schema_all_time: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            ReadGroupHints::from_flags(HintFlags::HintSchemaAllTime as u32),
+            None
+        );
+        assert_eq!(ReadGroupHints::from_flags(0x08), None);
+    }
+
     #[test]
     fn test_field_list_conversion() {
         let input = FieldList {
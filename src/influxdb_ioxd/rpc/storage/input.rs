@@ -3,7 +3,8 @@ use tonic::Status;
 use generated_types::{
     google::protobuf::Any, MeasurementFieldsRequest, MeasurementNamesRequest,
     MeasurementTagKeysRequest, MeasurementTagValuesRequest, ReadFilterRequest, ReadGroupRequest,
-    ReadSource, ReadWindowAggregateRequest, TagKeysRequest, TagValuesRequest,
+    ReadSeriesCardinalityRequest, ReadSource, ReadWindowAggregateRequest, TagKeysRequest,
+    TagValuesRequest,
 };
 
 use super::id::ID;
@@ -101,3 +102,9 @@ impl GrpcInputs for ReadWindowAggregateRequest {
         self.read_source.as_ref()
     }
 }
+
+impl GrpcInputs for ReadSeriesCardinalityRequest {
+    fn read_source_field(&self) -> Option<&Any> {
+        self.read_series_cardinality_source.as_ref()
+    }
+}
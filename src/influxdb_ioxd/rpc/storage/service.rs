@@ -5,7 +5,7 @@
 use super::{
     data::{
         fieldlist_to_measurement_fields_response, series_set_item_to_read_response,
-        tag_keys_to_byte_vecs,
+        tag_keys_to_byte_vecs, ReadGroupHints,
     },
     expr::{self, AddRPCNode, GroupByAndAggregate, Loggable, SpecialTagKeys},
     input::GrpcInputs,
@@ -93,6 +93,12 @@ pub enum Error {
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
+    #[snafu(display("Can not count series in database '{}': {}", db_name, source))]
+    CountingSeries {
+        db_name: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
     #[snafu(display("Error converting Predicate '{}: {}", rpc_predicate_string, source))]
     ConvertingPredicate {
         rpc_predicate_string: String,
@@ -145,14 +151,8 @@ pub enum Error {
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
-    #[snafu(display(
-        "Unexpected hint value on read_group request. Expected 0, got {}",
-        hints
-    ))]
-    InternalHintsFieldNotSupported { hints: u32 },
-
-    #[snafu(display("Operation not yet implemented:  {}", operation))]
-    NotYetImplemented { operation: String },
+    #[snafu(display("Unsupported hint flags on read_group request: {:#x}", hints))]
+    HintsFieldNotSupported { hints: u32 },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -186,6 +186,7 @@ impl Error {
             Self::FilteringSeries { .. } => Status::invalid_argument(self.to_string()),
             Self::GroupingSeries { .. } => Status::invalid_argument(self.to_string()),
             Self::ListingTagValues { .. } => Status::invalid_argument(self.to_string()),
+            Self::CountingSeries { .. } => Status::invalid_argument(self.to_string()),
             Self::ConvertingPredicate { .. } => Status::invalid_argument(self.to_string()),
             Self::ConvertingReadGroupAggregate { .. } => Status::invalid_argument(self.to_string()),
             Self::ConvertingReadGroupType { .. } => Status::invalid_argument(self.to_string()),
@@ -196,8 +197,7 @@ impl Error {
             Self::ConvertingSeriesSet { .. } => Status::invalid_argument(self.to_string()),
             Self::ConvertingFieldList { .. } => Status::invalid_argument(self.to_string()),
             Self::SendingResults { .. } => Status::internal(self.to_string()),
-            Self::InternalHintsFieldNotSupported { .. } => Status::internal(self.to_string()),
-            Self::NotYetImplemented { .. } => Status::internal(self.to_string()),
+            Self::HintsFieldNotSupported { .. } => Status::invalid_argument(self.to_string()),
         }
     }
 }
@@ -274,9 +274,7 @@ where
               predicate.loggable()
         );
 
-        if hints != 0 {
-            InternalHintsFieldNotSupported { hints }.fail()?
-        }
+        let hints = ReadGroupHints::from_flags(hints).context(HintsFieldNotSupported { hints })?;
 
         let aggregate_string = format!(
             "aggregate: {:?}, group: {:?}, group_keys: {:?}",
@@ -297,6 +295,7 @@ where
             range,
             predicate,
             gby_agg,
+            hints,
         )
         .await
         .map_err(|e| e.to_status())?;
@@ -347,6 +346,7 @@ where
             range,
             predicate,
             gby_agg,
+            ReadGroupHints::default(),
         )
         .await
         .map_err(|e| e.to_status())?;
@@ -428,11 +428,7 @@ where
                     predicate.loggable()
             );
 
-            if predicate.is_some() {
-                unimplemented!("tag_value for a measurement, with general predicate");
-            }
-
-            measurement_name_impl(Arc::clone(&self.db_store), db_name, range).await
+            measurement_name_impl(Arc::clone(&self.db_store), db_name, range, predicate).await
         } else if tag_key.is_field() {
             info!(
                 "tag_values with tag_key=[xff] (field name) for database {}, range: {:?}, predicate: {} --> returning fields",
@@ -487,9 +483,37 @@ where
 
     async fn read_series_cardinality(
         &self,
-        _req: tonic::Request<ReadSeriesCardinalityRequest>,
+        req: tonic::Request<ReadSeriesCardinalityRequest>,
     ) -> Result<tonic::Response<Self::ReadSeriesCardinalityStream>, Status> {
-        unimplemented!("read_series_cardinality not yet implemented");
+        let (tx, rx) = mpsc::channel(4);
+
+        let series_cardinality_request = req.into_inner();
+
+        let db_name = get_database_name(&series_cardinality_request)?;
+
+        let ReadSeriesCardinalityRequest {
+            read_series_cardinality_source: _read_series_cardinality_source,
+            range,
+            predicate,
+        } = series_cardinality_request;
+
+        info!(
+            "read_series_cardinality for database {}, range: {:?}, predicate: {}",
+            db_name,
+            range,
+            predicate.loggable()
+        );
+
+        let response =
+            series_cardinality_impl(Arc::clone(&self.db_store), db_name, range, predicate)
+                .await
+                .map_err(|e| e.to_status());
+
+        tx.send(response)
+            .await
+            .expect("sending read_series_cardinality response to server");
+
+        Ok(tonic::Response::new(ReceiverStream::new(rx)))
     }
 
     async fn capabilities(
@@ -546,17 +570,6 @@ where
             predicate,
         } = measurement_names_request;
 
-        if let Some(predicate) = predicate {
-            return NotYetImplemented {
-                operation: format!(
-                    "measurement_names request with a predicate: {:?}",
-                    predicate
-                ),
-            }
-            .fail()
-            .map_err(|e| e.to_status());
-        }
-
         info!(
             "measurement_names for database {}, range: {:?}, predicate: {}",
             db_name,
@@ -564,7 +577,7 @@ where
             predicate.loggable()
         );
 
-        let response = measurement_name_impl(Arc::clone(&self.db_store), db_name, range)
+        let response = measurement_name_impl(Arc::clone(&self.db_store), db_name, range, predicate)
             .await
             .map_err(|e| e.to_status());
 
@@ -743,16 +756,25 @@ fn get_database_name(input: &impl GrpcInputs) -> Result<DatabaseName<'static>, S
 // to the appropriate tonic Status
 
 /// Gathers all measurement names that have data in the specified
-/// (optional) range
+/// (optional) range and that match the (optional) predicate
 async fn measurement_name_impl<T>(
     db_store: Arc<T>,
     db_name: DatabaseName<'static>,
     range: Option<TimestampRange>,
+    rpc_predicate: Option<Predicate>,
 ) -> Result<StringValuesResponse>
 where
     T: DatabaseStore + 'static,
 {
-    let predicate = PredicateBuilder::default().set_range(range).build();
+    let rpc_predicate_string = format!("{:?}", rpc_predicate);
+
+    let predicate = PredicateBuilder::default()
+        .set_range(range)
+        .rpc_predicate(rpc_predicate)
+        .context(ConvertingPredicate {
+            rpc_predicate_string,
+        })?
+        .build();
     let db_name = db_name.as_ref();
 
     let db = db_store
@@ -901,6 +923,59 @@ where
     Ok(StringValuesResponse { values })
 }
 
+/// Return the number of distinct series, with optional timestamp and
+/// arbitrary predicates
+async fn series_cardinality_impl<T>(
+    db_store: Arc<T>,
+    db_name: DatabaseName<'static>,
+    range: Option<TimestampRange>,
+    rpc_predicate: Option<Predicate>,
+) -> Result<Int64ValuesResponse>
+where
+    T: DatabaseStore + 'static,
+{
+    let rpc_predicate_string = format!("{:?}", rpc_predicate);
+
+    let predicate = PredicateBuilder::default()
+        .set_range(range)
+        .rpc_predicate(rpc_predicate)
+        .context(ConvertingPredicate {
+            rpc_predicate_string,
+        })?
+        .build();
+
+    let db_name = db_name.as_str();
+
+    let db = db_store
+        .db(db_name)
+        .await
+        .context(DatabaseNotFound { db_name })?;
+
+    let planner = InfluxRPCPlanner::new();
+
+    let executor = db_store.executor();
+
+    let series_cardinality_plan = planner
+        .series_cardinality(db.as_ref(), predicate)
+        .await
+        .map_err(|e| Box::new(e) as _)
+        .context(CountingSeries { db_name })?;
+
+    let series_cardinality = executor
+        .to_series_cardinality(series_cardinality_plan)
+        .await
+        .map_err(|e| Box::new(e) as _)
+        .context(CountingSeries { db_name })?;
+
+    // The series of different measurements are always distinct, so
+    // the total is the sum of the counts of each measurement
+    let total: u64 = series_cardinality.values().sum();
+
+    Ok(Int64ValuesResponse {
+        values: vec![total as i64],
+    })
+}
+
 /// Launch async tasks that send the result of executing read_filter to `tx`
 async fn read_filter_impl<'a, T>(
     tx: mpsc::Sender<Result<ReadResponse, Status>>,
@@ -947,7 +1022,7 @@ where
     // client before we start sending result)
     let (tx_series, rx_series) = mpsc::channel(4);
     tokio::spawn(async move {
-        convert_series_set(rx_series, tx)
+        convert_series_set(rx_series, tx, ReadGroupHints::default())
             .await
             .log_if_error("Converting series set")
     });
@@ -972,12 +1047,19 @@ where
 async fn convert_series_set(
     mut rx: mpsc::Receiver<Result<SeriesSetItem, SeriesSetError>>,
     tx: mpsc::Sender<Result<ReadResponse, Status>>,
+    hints: ReadGroupHints,
 ) -> Result<()> {
     while let Some(series_set) = rx.recv().await {
+        // the plans for only the groups produce a series set without
+        // fields for each group, which has no frames to send
+        if hints.no_series && matches!(series_set, Ok(SeriesSetItem::Data(_))) {
+            continue;
+        }
+
         let response = series_set
             .context(ComputingSeriesSet)
             .and_then(|series_set| {
-                series_set_item_to_read_response(series_set).context(ConvertingSeriesSet)
+                series_set_item_to_read_response(series_set, hints).context(ConvertingSeriesSet)
             })
             .map_err(|e| Status::internal(e.to_string()));
 
@@ -997,12 +1079,17 @@ async fn query_group_impl<T>(
     range: Option<TimestampRange>,
     rpc_predicate: Option<Predicate>,
     gby_agg: GroupByAndAggregate,
+    hints: ReadGroupHints,
 ) -> Result<()>
 where
     T: DatabaseStore + 'static,
 {
     let rpc_predicate_string = format!("{:?}", rpc_predicate);
 
+    // The groups and series are found from all of the data if
+    // SCHEMA_ALL_TIME is set, which is only allowed if no points are sent
+    let range = if hints.schema_all_time { None } else { range };
+
     let predicate = PredicateBuilder::default()
         .set_range(range)
         .rpc_predicate(rpc_predicate)
@@ -1024,6 +1111,12 @@ where
     let planner = InfluxRPCPlanner::new();
 
     let grouped_series_set_plan = match gby_agg {
+        // no series are sent, so there is no need to read any points
+        GroupByAndAggregate::Columns { group_columns, .. } if hints.no_series => {
+            planner
+                .read_group_keys(db.as_ref(), predicate, &group_columns)
+                .await
+        }
         GroupByAndAggregate::Columns { agg, group_columns } => {
             planner
                 .read_group(db.as_ref(), predicate, agg, &group_columns)
@@ -1046,7 +1139,7 @@ where
     // client before we start sending result)
     let (tx_series, rx_series) = mpsc::channel(4);
     tokio::spawn(async move {
-        convert_series_set(rx_series, tx)
+        convert_series_set(rx_series, tx, hints)
             .await
             .log_if_error("Converting grouped series set")
     });
@@ -1216,7 +1309,7 @@ mod tests {
             end: 200,
         };
         let request = MeasurementNamesRequest {
-            source,
+            source: source.clone(),
             range: Some(range),
             predicate: None,
         };
@@ -1250,6 +1343,42 @@ mod tests {
             "\nActual: {:?}\nExpected: {:?}",
             actual_predicate, expected_predicate
         );
+
+        // --- General predicate
+        let request = MeasurementNamesRequest {
+            source,
+            range: make_timestamp_range(150, 200),
+            predicate: make_state_ma_predicate(),
+        };
+
+        let actual_measurements = fixture
+            .storage_client
+            .measurement_names(request)
+            .await
+            .unwrap();
+        let expected_measurements = to_string_vec(&["h2o", "o2"]);
+        assert_eq!(actual_measurements, expected_measurements);
+
+        let actual_predicate = fixture
+            .test_storage
+            .db_or_create(&db_info.db_name)
+            .await
+            .expect("getting db")
+            .get_chunk("my_partition_key", 0)
+            .and_then(|chunk| chunk.predicate());
+
+        let expected_predicate = Some(
+            PredicateBuilder::default()
+                .timestamp_range(150, 200)
+                .add_expr(make_state_ma_expr())
+                .build(),
+        );
+
+        assert_eq!(
+            actual_predicate, expected_predicate,
+            "\nActual: {:?}\nExpected: {:?}",
+            actual_predicate, expected_predicate
+        );
     }
 
     /// test the plumbing of the RPC layer for tag_keys -- specifically that
@@ -1517,6 +1646,82 @@ mod tests {
         assert_eq!(actual_tag_values, vec!["MA"]);
     }
 
+    /// test the plumbing of the RPC layer for read_series_cardinality
+    #[tokio::test]
+    async fn test_storage_rpc_read_series_cardinality() {
+        // Start a test gRPC server on a randomally allocated port
+        let mut fixture = Fixture::new().await.expect("Connecting to test server");
+
+        let db_info = OrgAndBucket::new(123, 456);
+        let partition_id = 1;
+
+        let chunk0 = TestChunk::new(0)
+            .with_time_column("TheMeasurement")
+            .with_tag_column("TheMeasurement", "state")
+            .with_one_row_of_null_data("TheMeasurement");
+
+        // the same series in another chunk is only counted once
+        let chunk1 = TestChunk::new(1)
+            .with_time_column("TheMeasurement")
+            .with_tag_column("TheMeasurement", "state")
+            .with_one_row_of_null_data("TheMeasurement");
+
+        let chunk2 = TestChunk::new(2)
+            .with_time_column("OtherMeasurement")
+            .with_tag_column("OtherMeasurement", "state")
+            .with_one_row_of_null_data("OtherMeasurement");
+
+        let db = fixture
+            .test_storage
+            .db_or_create(&db_info.db_name)
+            .await
+            .unwrap();
+        db.add_chunk("my_partition_key", Arc::new(chunk0));
+        db.add_chunk("my_partition_key", Arc::new(chunk1));
+        db.add_chunk("my_partition_key", Arc::new(chunk2));
+
+        let source = Some(StorageClientWrapper::read_source(
+            db_info.org_id,
+            db_info.bucket_id,
+            partition_id,
+        ));
+
+        let request = ReadSeriesCardinalityRequest {
+            read_series_cardinality_source: source.clone(),
+            range: make_timestamp_range(150, 2000),
+            predicate: make_state_ma_predicate(),
+        };
+
+        let actual_cardinality = fixture
+            .storage_client
+            .read_series_cardinality(request)
+            .await
+            .unwrap();
+        assert_eq!(actual_cardinality, vec![2]);
+
+        // and the predicate made it down to the chunk
+        let actual_predicate = fixture
+            .test_storage
+            .db_or_create(&db_info.db_name)
+            .await
+            .expect("getting db")
+            .get_chunk("my_partition_key", 0)
+            .and_then(|chunk| chunk.predicate());
+
+        let expected_predicate = Some(
+            PredicateBuilder::default()
+                .timestamp_range(150, 2000)
+                .add_expr(make_state_ma_expr())
+                .build(),
+        );
+
+        assert_eq!(
+            actual_predicate, expected_predicate,
+            "\nActual: {:?}\nExpected: {:?}",
+            actual_predicate, expected_predicate
+        );
+    }
+
     /// test the plumbing of the RPC layer for tag_values
     ///
     /// For the special case of
@@ -1955,6 +2160,110 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_read_group_hints() {
+        // Start a test gRPC server on a randomally allocated port
+        let mut fixture = Fixture::new().await.expect("Connecting to test server");
+
+        let db_info = OrgAndBucket::new(123, 456);
+        let partition_id = 1;
+
+        // a single row at time 1000
+        let chunk = TestChunk::new(0)
+            .with_time_column("TheMeasurement")
+            .with_tag_column("TheMeasurement", "state")
+            .with_int_field_column("TheMeasurement", "reading")
+            .with_one_row_of_null_data("TheMeasurement");
+
+        fixture
+            .test_storage
+            .db_or_create(&db_info.db_name)
+            .await
+            .unwrap()
+            .add_chunk("my_partition_key", Arc::new(chunk));
+
+        let source = Some(StorageClientWrapper::read_source(
+            db_info.org_id,
+            db_info.bucket_id,
+            partition_id,
+        ));
+
+        let group = generated_types::read_group_request::Group::By as i32;
+
+        use generated_types::read_group_request::HintFlags;
+        let cases = vec![
+            // group, series and points frames
+            (HintFlags::HintNone as u32, (0, 2000), "3 group frames"),
+            (HintFlags::HintNoPoints as u32, (0, 2000), "2 group frames"),
+            (HintFlags::HintNoSeries as u32, (0, 2000), "1 group frames"),
+            (
+                (HintFlags::HintNoPoints as u32) | (HintFlags::HintNoSeries as u32),
+                (0, 2000),
+                "1 group frames",
+            ),
+            // no rows are in the range
+            (HintFlags::HintNoSeries as u32, (0, 500), "0 group frames"),
+            (
+                (HintFlags::HintNoSeries as u32) | (HintFlags::HintSchemaAllTime as u32),
+                (0, 500),
+                "1 group frames",
+            ),
+            // the groups and series are found from all of the data
+            (
+                (HintFlags::HintNoPoints as u32) | (HintFlags::HintSchemaAllTime as u32),
+                (0, 500),
+                "2 group frames",
+            ),
+        ];
+
+        for (hints, (start, end), expected) in cases {
+            let request = ReadGroupRequest {
+                read_source: source.clone(),
+                range: make_timestamp_range(start, end),
+                predicate: None,
+                group_keys: vec!["state".into()],
+                group,
+                aggregate: Some(RPCAggregate {
+                    r#type: AggregateType::Sum as i32,
+                }),
+                hints,
+            };
+
+            let actual_frames = fixture.storage_client.read_group(request).await.unwrap();
+            let expected_frames: Vec<String> = vec![expected.into()];
+
+            assert_eq!(
+                actual_frames, expected_frames,
+                "unexpected frames returned by query_groups with hints {:#x}",
+                hints
+            );
+        }
+
+        // the points outside of the range can't be grouped by all of the data
+        let request = ReadGroupRequest {
+            read_source: source,
+            range: make_timestamp_range(0, 500),
+            predicate: None,
+            group_keys: vec!["state".into()],
+            group,
+            aggregate: Some(RPCAggregate {
+                r#type: AggregateType::Sum as i32,
+            }),
+            hints: HintFlags::HintSchemaAllTime as u32,
+        };
+
+        let response_string = fixture
+            .storage_client
+            .read_group(request)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_contains!(
+            response_string,
+            "Unsupported hint flags on read_group request: 0x4"
+        );
+    }
+
     #[tokio::test]
     async fn test_read_group_error() {
         // Start a test gRPC server on a randomally allocated port
@@ -2003,7 +2312,7 @@ mod tests {
             .to_string();
         assert_contains!(
             response_string,
-            "Unexpected hint value on read_group request. Expected 0, got 42"
+            "Unsupported hint flags on read_group request: 0x2a"
         );
 
         // ---
@@ -2473,6 +2782,23 @@ mod tests {
             Ok(self.to_string_vec(responses))
         }
 
+        /// Make a request to query::read_series_cardinality and do the
+        /// required async dance to flatten the resulting stream to counts
+        async fn read_series_cardinality(
+            &mut self,
+            request: ReadSeriesCardinalityRequest,
+        ) -> Result<Vec<i64>, tonic::Status> {
+            let responses: Vec<Int64ValuesResponse> = self
+                .inner
+                .read_series_cardinality(request)
+                .await?
+                .into_inner()
+                .try_collect()
+                .await?;
+
+            Ok(responses.into_iter().flat_map(|r| r.values).collect())
+        }
+
         /// Make a request to query::measurement_tag_keys and do the
         /// required async dance to flatten the resulting stream to Strings
         async fn measurement_tag_keys(
@@ -0,0 +1,101 @@
+use std::convert::TryInto;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use futures::StreamExt;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use data_types::{database_rules::Matcher, DatabaseName};
+use generated_types::google::{FieldViolation, FieldViolationExt};
+use generated_types::influxdata::iox::subscription::v1::*;
+use server::{buffer, matcher::CompiledMatcher, ConnectionManager, Server};
+
+use super::error::default_server_error_handler;
+
+struct SubscriptionService<M: ConnectionManager> {
+    server: Arc<Server<M>>,
+}
+
+#[tonic::async_trait]
+impl<M> subscription_service_server::SubscriptionService for SubscriptionService<M>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    type SubscribeStream = ReceiverStream<Result<SubscribeResponse, Status>>;
+
+    async fn subscribe(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let request = request.into_inner();
+        let db_name = DatabaseName::new(request.db_name).field("db_name")?;
+
+        let since = request
+            .since
+            .into_iter()
+            .map(|since| buffer::WriterSequence {
+                id: since.writer_id,
+                sequence: since.sequence,
+            })
+            .collect();
+
+        let matcher = request
+            .matcher
+            .map(|matcher| -> Result<_, FieldViolation> {
+                let matcher: Matcher = matcher.try_into()?;
+                CompiledMatcher::new(&matcher).map_err(|e| FieldViolation {
+                    field: e.field().to_string(),
+                    description: e.to_string(),
+                })
+            })
+            .transpose()
+            .map_err(|e| e.scope("matcher"))?;
+
+        let writes = self
+            .server
+            .subscribe(&db_name, since, matcher)
+            .map_err(default_server_error_handler)?;
+
+        let (tx, rx) = mpsc::channel(4);
+
+        tokio::spawn(async move {
+            futures::pin_mut!(writes);
+
+            loop {
+                let write = tokio::select! {
+                    write = writes.next() => write,
+                    // stop tailing the buffer once the subscriber has gone away
+                    _ = tx.closed() => break,
+                };
+
+                let write = match write {
+                    Some(write) => write,
+                    None => break,
+                };
+
+                let response = SubscribeResponse {
+                    payload: write.bytes().clone(),
+                };
+
+                if tx.send(Ok(response)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+pub fn make_server<M>(
+    server: Arc<Server<M>>,
+) -> subscription_service_server::SubscriptionServiceServer<
+    impl subscription_service_server::SubscriptionService,
+>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    subscription_service_server::SubscriptionServiceServer::new(SubscriptionService { server })
+}
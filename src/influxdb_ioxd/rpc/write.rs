@@ -0,0 +1,64 @@
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use tonic::{Request, Response, Status};
+
+use data_types::{data::ReplicatedWrite, DatabaseName};
+use generated_types::google::{FieldViolation, FieldViolationExt, NotFound};
+use generated_types::influxdata::iox::write::v1::*;
+use server::{ConnectionManager, Server};
+
+use super::error::default_server_error_handler;
+
+struct WriteService<M: ConnectionManager> {
+    server: Arc<Server<M>>,
+}
+
+#[tonic::async_trait]
+impl<M> write_service_server::WriteService for WriteService<M>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    async fn write_replicated(
+        &self,
+        request: Request<WriteReplicatedRequest>,
+    ) -> Result<Response<WriteReplicatedResponse>, Status> {
+        let request = request.into_inner();
+        let db_name = DatabaseName::new(request.db_name).field("db_name")?;
+
+        if request.payload.is_empty() {
+            return Err(FieldViolation::required("payload").into());
+        }
+
+        let db = match self.server.db(&db_name).await {
+            Some(db) => db,
+            None => {
+                return Err(NotFound {
+                    resource_type: "database".to_string(),
+                    resource_name: db_name.to_string(),
+                    ..Default::default()
+                }
+                .into())
+            }
+        };
+
+        let write = ReplicatedWrite::try_from(request.payload).field("payload")?;
+
+        self.server
+            .store_replicated_write(&db_name, &db, write)
+            .await
+            .map_err(default_server_error_handler)?;
+
+        Ok(Response::new(WriteReplicatedResponse {}))
+    }
+}
+
+pub fn make_server<M>(
+    server: Arc<Server<M>>,
+) -> write_service_server::WriteServiceServer<impl write_service_server::WriteService>
+where
+    M: ConnectionManager + Send + Sync + Debug + 'static,
+{
+    write_service_server::WriteServiceServer::new(WriteService { server })
+}
@@ -23,6 +23,7 @@ mod commands {
     mod input;
     pub mod logging;
     pub mod meta;
+    pub mod operations;
     pub mod server;
     pub mod stats;
     pub mod writer;
@@ -109,6 +110,7 @@ enum Command {
         input: String,
     },
     Database(commands::database::Config),
+    Operation(commands::operations::Config),
     Stats(commands::stats::Config),
     // Clippy recommended boxing this variant because it's much larger than the others
     Server(Box<commands::server::Config>),
@@ -176,6 +178,13 @@ fn main() -> Result<(), std::io::Error> {
                     std::process::exit(ReturnCode::Failure as _)
                 }
             }
+            Some(Command::Operation(config)) => {
+                logging_level.setup_basic_logging();
+                if let Err(e) = commands::operations::command(host, config).await {
+                    eprintln!("{}", e);
+                    std::process::exit(ReturnCode::Failure as _)
+                }
+            }
             Some(Command::Writer(config)) => {
                 logging_level.setup_basic_logging();
                 if let Err(e) = commands::writer::command(host, config).await {
@@ -76,6 +76,9 @@ async fn read_and_write_data() {
         .await
         .unwrap();
     let mut storage_client = StorageClient::new(grpc.clone());
+    let mut write_client = influxdb_iox_client::write::Client::new(grpc.clone());
+    let mut subscription_client = influxdb_iox_client::subscription::Client::new(grpc.clone());
+    let mut operations_client = influxdb_iox_client::operations::Client::new(grpc.clone());
     let mut management_client = influxdb_iox_client::management::Client::new(grpc);
 
     // These tests share data; TODO: a better way to indicate this
@@ -102,8 +105,23 @@ async fn read_and_write_data() {
         &mut storage_client,
     )
     .await;
+    write_api::test(&mut management_client, &mut write_client).await;
+    flight_api::test_write_record_batches(&mut management_client).await;
+    flight_api::test_actions(&mut management_client).await;
+    subscription_api::test(
+        &mut management_client,
+        &mut write_client,
+        &mut subscription_client,
+    )
+    .await;
     management_api::test(&mut management_client).await;
     management_cli::test(GRPC_URL_BASE).await;
+    operations_api::test(
+        &mut management_client,
+        &mut write_client,
+        &mut operations_client,
+    )
+    .await;
     test_http_error_messages(&influxdb2).await.unwrap();
 }
 
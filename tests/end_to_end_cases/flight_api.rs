@@ -1,6 +1,21 @@
-use crate::{Scenario, GRPC_URL_BASE};
-use arrow_deps::assert_table_eq;
-use influxdb_iox_client::{connection::Builder, flight::Client};
+use std::sync::Arc;
+
+use crate::{create_database, Scenario, GRPC_URL_BASE};
+use arrow_deps::{
+    arrow::{
+        array::{DictionaryArray, Float64Array, Int64Array, StringArray},
+        datatypes::{DataType, Field, Int32Type, Schema as ArrowSchema},
+        record_batch::RecordBatch,
+    },
+    assert_table_eq,
+};
+use data_types::schema::builder::SchemaBuilder;
+use influxdb_iox_client::{
+    connection::Builder,
+    flight::{Client, Error as FlightError},
+    management,
+};
+use serde_json::{json, Value};
 
 pub async fn test(scenario: &Scenario, sql_query: &str, expected_read_data: &[String]) {
     let connection = Builder::default().build(GRPC_URL_BASE).await.unwrap();
@@ -20,3 +35,277 @@ pub async fn test(scenario: &Scenario, sql_query: &str, expected_read_data: &[St
 
     assert_table_eq!(expected_read_data, &batches);
 }
+
+pub async fn test_write_record_batches(management_client: &mut management::Client) {
+    const DB_NAME: &str = "flight_do_put";
+    create_database(management_client, DB_NAME).await;
+
+    let connection = Builder::default().build(GRPC_URL_BASE).await.unwrap();
+    let mut client = Client::new(connection);
+
+    let schema = SchemaBuilder::new()
+        .tag("host")
+        .field("usage", DataType::Float64)
+        .timestamp()
+        .build()
+        .unwrap();
+    let batch = RecordBatch::try_new(
+        schema.into(),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "b"])),
+            Arc::new(Float64Array::from(vec![1.5, 2.0])),
+            Arc::new(Int64Array::from(vec![100, 200])),
+        ],
+    )
+    .unwrap();
+
+    client
+        .write_record_batches(DB_NAME, "cpu", &[batch.clone()])
+        .await
+        .expect("write record batches failed");
+
+    let mut query_results = client
+        .perform_query(DB_NAME, "select * from cpu order by time")
+        .await
+        .unwrap();
+
+    let mut batches = vec![];
+    while let Some(data) = query_results.next().await.unwrap() {
+        batches.push(data);
+    }
+
+    let expected = vec![
+        "+------+------+-------+",
+        "| host | time | usage |",
+        "+------+------+-------+",
+        "| a    | 100  | 1.5   |",
+        "| b    | 200  | 2     |",
+        "+------+------+-------+",
+    ];
+    assert_table_eq!(expected, &batches);
+
+    test_query_info(&mut client, DB_NAME).await;
+
+    // the dictionaries of dictionary encoded columns are sent ahead of the
+    // batches
+    let schema = batch.schema();
+    let mut fields = schema.fields().clone();
+    fields[0] = Field::new(
+        "host",
+        DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+        true,
+    );
+    let hosts: DictionaryArray<Int32Type> = vec!["c", "c"].into_iter().collect();
+    let mut columns = batch.columns().to_vec();
+    columns[0] = Arc::new(hosts);
+    let dictionary_batch = RecordBatch::try_new(
+        Arc::new(ArrowSchema::new_with_metadata(
+            fields,
+            schema.metadata().clone(),
+        )),
+        columns,
+    )
+    .unwrap();
+
+    client
+        .write_record_batches(DB_NAME, "mem", &[dictionary_batch])
+        .await
+        .expect("write dictionary encoded record batches failed");
+
+    let mut query_results = client
+        .perform_query(DB_NAME, "select * from mem order by time")
+        .await
+        .unwrap();
+
+    let mut batches = vec![];
+    while let Some(data) = query_results.next().await.unwrap() {
+        batches.push(data);
+    }
+
+    let expected = vec![
+        "+------+------+-------+",
+        "| host | time | usage |",
+        "+------+------+-------+",
+        "| c    | 100  | 1.5   |",
+        "| c    | 200  | 2     |",
+        "+------+------+-------+",
+    ];
+    assert_table_eq!(expected, &batches);
+
+    // columns without IOx column types are rejected
+    let untyped = RecordBatch::try_new(
+        Arc::new(arrow_deps::arrow::datatypes::Schema::new(vec![
+            arrow_deps::arrow::datatypes::Field::new("usage", DataType::Float64, false),
+        ])),
+        vec![Arc::new(Float64Array::from(vec![1.0]))],
+    )
+    .unwrap();
+
+    let err = client
+        .write_record_batches(DB_NAME, "cpu", &[untyped])
+        .await
+        .expect_err("expected write to fail");
+    assert!(err.to_string().contains("no IOx column type"), "{}", err);
+}
+
+async fn test_query_info(client: &mut Client, db_name: &str) {
+    let query = "select host, usage from cpu";
+
+    let schema = client.query_schema(db_name, query).await.unwrap();
+    let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(names, vec!["host", "usage"]);
+
+    let info = client.query_info(db_name, query).await.unwrap();
+    assert_eq!(info.schema, schema);
+    assert_eq!(info.estimated_rows, Some(2));
+
+    let query_results = client.perform_query(db_name, query).await.unwrap();
+    assert_eq!(query_results.schema(), schema);
+
+    let err = client
+        .query_info(db_name, "select * from does_not_exist")
+        .await
+        .expect_err("expected planning to fail");
+    assert!(err.to_string().contains("does_not_exist"), "{}", err);
+}
+
+pub async fn test_actions(management_client: &mut management::Client) {
+    const DB_NAME: &str = "flight_actions";
+    create_database(management_client, DB_NAME).await;
+
+    let connection = Builder::default().build(GRPC_URL_BASE).await.unwrap();
+    let mut client = Client::new(connection);
+
+    let actions: Vec<_> = client
+        .list_actions()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|action| action.r#type)
+        .collect();
+    assert_eq!(
+        actions,
+        vec![
+            "rollover_partition",
+            "load_chunk_to_read_buffer",
+            "drop_chunk",
+            "snapshot_partition",
+            "list_chunks"
+        ]
+    );
+
+    let schema = SchemaBuilder::new()
+        .field("usage", DataType::Float64)
+        .timestamp()
+        .build()
+        .unwrap();
+    let batch = RecordBatch::try_new(
+        schema.into(),
+        vec![
+            Arc::new(Float64Array::from(vec![1.0])),
+            Arc::new(Int64Array::from(vec![100])),
+        ],
+    )
+    .unwrap();
+    client
+        .write_record_batches(DB_NAME, "cpu", &[batch])
+        .await
+        .unwrap();
+
+    let chunks = run_action(
+        &mut client,
+        "list_chunks",
+        json!({ "database_name": DB_NAME }),
+    )
+    .await;
+    assert_eq!(chunks.len(), 1);
+    let partition_key = chunks[0]["partition_key"].as_str().unwrap().to_string();
+    let partition = json!({ "database_name": DB_NAME, "partition_key": partition_key });
+    let chunk = |id: u32, storage: &str| json!({ "partition_key": partition_key, "id": id, "storage": storage });
+    assert_eq!(
+        chunk_storage(&chunks),
+        vec![chunk(0, "open_mutable_buffer")]
+    );
+    assert_eq!(chunks[0]["row_count"], 1);
+    assert_eq!(chunks[0]["time_range"], json!([100, 100]));
+
+    let rolled = run_action(&mut client, "rollover_partition", partition.clone()).await;
+    assert_eq!(
+        chunk_storage(&rolled),
+        vec![chunk(0, "closed_mutable_buffer")]
+    );
+
+    let chunk_action = json!({
+        "database_name": DB_NAME,
+        "partition_key": partition_key,
+        "chunk_id": 0,
+        "storage": "mutable_buffer",
+    });
+    let loaded = run_action(
+        &mut client,
+        "load_chunk_to_read_buffer",
+        chunk_action.clone(),
+    )
+    .await;
+    assert_eq!(chunk_storage(&loaded), vec![chunk(0, "read_buffer")]);
+    assert_eq!(loaded[0]["row_count"], 1);
+
+    let dropped = run_action(&mut client, "drop_chunk", chunk_action).await;
+    assert_eq!(
+        chunk_storage(&dropped),
+        vec![chunk(0, "closed_mutable_buffer")]
+    );
+
+    let chunks = run_action(&mut client, "list_chunks", partition.clone()).await;
+    assert_eq!(
+        chunk_storage(&chunks),
+        vec![chunk(0, "read_buffer"), chunk(1, "open_mutable_buffer")]
+    );
+
+    let err = client
+        .do_action("does_not_exist", vec![])
+        .await
+        .expect_err("expected unknown action to fail");
+    assert!(err.to_string().contains("Unknown action"), "{}", err);
+
+    let err = client
+        .do_action(
+            "drop_chunk",
+            serde_json::to_vec(&json!({
+                "database_name": DB_NAME,
+                "partition_key": partition_key,
+                "chunk_id": 42,
+                "storage": "read_buffer",
+            }))
+            .unwrap(),
+        )
+        .await
+        .expect_err("expected dropping an unknown chunk to fail");
+    assert!(
+        matches!(err, FlightError::GrpcError(status) if status.code() == tonic::Code::NotFound)
+    );
+}
+
+/// Returns the partition key, id and storage of each chunk summary
+fn chunk_storage(summaries: &[Value]) -> Vec<Value> {
+    summaries
+        .iter()
+        .map(|summary| {
+            json!({
+                "partition_key": summary["partition_key"],
+                "id": summary["id"],
+                "storage": summary["storage"],
+            })
+        })
+        .collect()
+}
+
+async fn run_action(client: &mut Client, action_type: &str, body: Value) -> Vec<Value> {
+    client
+        .do_action(action_type, serde_json::to_vec(&body).unwrap())
+        .await
+        .unwrap()
+        .iter()
+        .map(|result| serde_json::from_slice(result).unwrap())
+        .collect()
+}
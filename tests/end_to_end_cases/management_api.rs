@@ -2,9 +2,20 @@ use std::num::NonZeroU32;
 
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 
+use data_types::data::lines_to_replicated_write;
 use generated_types::google::protobuf::Empty;
 use generated_types::{google::protobuf::Duration, influxdata::iox::management::v1::*};
-use influxdb_iox_client::management::{Client, CreateDatabaseError};
+use influxdb_iox_client::{
+    connection::Builder,
+    management::{
+        Client, CompactChunksError, CreateDatabaseError, CreateHostGroupError, DeleteDatabaseError,
+        DropChunkError, GetReplicationStatusError, ListChunksError, UpdateDatabaseError,
+    },
+    write,
+};
+use influxdb_line_protocol::parse_lines;
+
+use crate::{create_database, GRPC_URL_BASE};
 
 pub async fn test(client: &mut Client) {
     test_set_get_writer_id(client).await;
@@ -12,6 +23,11 @@ pub async fn test(client: &mut Client) {
     test_create_database_invalid_name(client).await;
     test_list_databases(client).await;
     test_create_get_database(client).await;
+    test_update_delete_database(client).await;
+    test_create_host_group(client).await;
+    test_get_replication_status(client).await;
+    test_chunk_lifecycle(client).await;
+    test_compact_chunks(client).await;
 }
 
 async fn test_set_get_writer_id(client: &mut Client) {
@@ -97,6 +113,7 @@ async fn test_create_get_database(client: &mut Client) {
             replications: vec!["cupcakes".to_string()],
             replication_count: 3,
             replication_queue_max_size: 20,
+            replication_queue_overflow: replication_config::Overflow::DropOldest as _,
         }),
         subscription_config: Some(SubscriptionConfig {
             subscriptions: vec![subscription_config::Subscription {
@@ -150,7 +167,326 @@ async fn test_create_get_database(client: &mut Client) {
     assert_eq!(response, rules);
 }
 
-fn rand_name() -> String {
+async fn test_update_delete_database(client: &mut Client) {
+    let db_name = rand_name();
+    create_database(client, &db_name).await;
+
+    let mut rules = client
+        .get_database(&db_name)
+        .await
+        .expect("get database failed");
+    rules.partition_template = Some(PartitionTemplate {
+        parts: vec![partition_template::Part {
+            part: Some(partition_template::part::Part::Column("host".to_string())),
+        }],
+    });
+    client
+        .update_database(rules.clone())
+        .await
+        .expect("update database failed");
+
+    let response = client
+        .get_database(&db_name)
+        .await
+        .expect("get database failed");
+    assert_eq!(response, rules);
+
+    // the mutable buffer of a loaded database can't be removed
+    let err = client
+        .update_database(DatabaseRules {
+            mutable_buffer_config: None,
+            ..rules.clone()
+        })
+        .await
+        .expect_err("expected request to fail");
+    assert!(matches!(
+        dbg!(err),
+        UpdateDatabaseError::FailedPrecondition(_)
+    ));
+
+    let err = client
+        .update_database(DatabaseRules {
+            name: rand_name(),
+            ..rules
+        })
+        .await
+        .expect_err("expected request to fail");
+    assert!(matches!(dbg!(err), UpdateDatabaseError::DatabaseNotFound));
+
+    client
+        .delete_database(&db_name, true)
+        .await
+        .expect("delete database failed");
+
+    let names = client
+        .list_databases()
+        .await
+        .expect("list databases failed");
+    assert!(!names.contains(&db_name));
+
+    let err = client
+        .delete_database(&db_name, true)
+        .await
+        .expect_err("expected request to fail");
+    assert!(matches!(dbg!(err), DeleteDatabaseError::DatabaseNotFound));
+
+    // the name can be used again
+    create_database(client, &db_name).await;
+}
+
+async fn test_create_host_group(client: &mut Client) {
+    client
+        .create_host_group(HostGroup {
+            id: rand_name(),
+            hosts: vec!["http://127.0.0.1:8082".to_string()],
+        })
+        .await
+        .expect("create host group failed");
+
+    let err = client
+        .create_host_group(HostGroup {
+            id: "".to_string(),
+            hosts: vec![],
+        })
+        .await
+        .expect_err("expected request to fail");
+
+    assert!(matches!(
+        dbg!(err),
+        CreateHostGroupError::InvalidArgument(_)
+    ));
+}
+
+async fn test_get_replication_status(client: &mut Client) {
+    let db_name = rand_name();
+
+    client
+        .create_database(DatabaseRules {
+            name: db_name.clone(),
+            replication_config: Some(ReplicationConfig {
+                replication_queue_max_size: 100,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .await
+        .expect("create database failed");
+
+    let status = client
+        .get_replication_status(&db_name)
+        .await
+        .expect("get replication status failed");
+
+    assert_eq!(status.queue_max_size, 100);
+    assert_eq!(status.queued_writes, 0);
+    assert!(status.host_groups.is_empty());
+
+    let err = client
+        .get_replication_status(rand_name())
+        .await
+        .expect_err("expected request to fail");
+
+    assert!(matches!(
+        dbg!(err),
+        GetReplicationStatusError::DatabaseNotFound
+    ));
+}
+
+async fn test_chunk_lifecycle(client: &mut Client) {
+    let db_name = rand_name();
+    create_database(client, &db_name).await;
+
+    let connection = Builder::default().build(GRPC_URL_BASE).await.unwrap();
+    let mut write_client = write::Client::new(connection);
+    let rules = data_types::database_rules::DatabaseRules::new();
+    let write_lp = |lp: &str, sequence| {
+        let lines: Vec<_> = parse_lines(lp).map(|l| l.unwrap()).collect();
+        lines_to_replicated_write(1, sequence, &lines, &rules).into_bytes()
+    };
+
+    write_client
+        .write_replicated(
+            &db_name,
+            write_lp(
+                "cpu,region=west user=23.2 100\ncpu,region=east user=21.0 150",
+                1,
+            ),
+        )
+        .await
+        .expect("write failed");
+
+    let partitions = client
+        .list_partitions(&db_name)
+        .await
+        .expect("list partitions failed");
+    assert_eq!(partitions.len(), 1);
+    let partition_key = partitions[0].key.clone();
+
+    let chunks = client
+        .list_chunks(&db_name)
+        .await
+        .expect("list chunks failed");
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].partition_key, partition_key);
+    assert_eq!(chunks[0].storage(), ChunkStorage::OpenMutableBuffer);
+    assert_eq!(chunks[0].row_count, 2);
+    assert_eq!(chunks[0].time_range, Some(TimeRange { min: 100, max: 150 }));
+    assert!(chunks[0].estimated_bytes > 0);
+
+    let chunk = client
+        .rollover_partition(&db_name, &partition_key)
+        .await
+        .expect("rollover partition failed");
+    assert_eq!(chunk.id, 0);
+    assert_eq!(chunk.storage(), ChunkStorage::ClosedMutableBuffer);
+
+    let chunk = client
+        .load_chunk_to_read_buffer(&db_name, &partition_key, 0)
+        .await
+        .expect("load chunk failed");
+    assert_eq!(chunk.id, 0);
+    assert_eq!(chunk.storage(), ChunkStorage::ReadBuffer);
+    assert_eq!(chunk.row_count, 2);
+
+    client
+        .drop_chunk(
+            &db_name,
+            &partition_key,
+            0,
+            ChunkStorage::ClosedMutableBuffer,
+        )
+        .await
+        .expect("drop chunk failed");
+
+    let chunks = client
+        .list_partition_chunks(&db_name, &partition_key)
+        .await
+        .expect("list partition chunks failed");
+    let storage: Vec<_> = chunks.iter().map(|c| (c.id, c.storage())).collect();
+    assert_eq!(
+        storage,
+        vec![
+            (0, ChunkStorage::ReadBuffer),
+            (1, ChunkStorage::OpenMutableBuffer)
+        ]
+    );
+
+    let err = client
+        .drop_chunk(&db_name, &partition_key, 1, ChunkStorage::OpenMutableBuffer)
+        .await
+        .expect_err("expected dropping the open chunk to fail");
+    assert!(matches!(dbg!(err), DropChunkError::InvalidArgument(_)));
+
+    let err = client
+        .drop_chunk(&db_name, &partition_key, 42, ChunkStorage::ReadBuffer)
+        .await
+        .expect_err("expected dropping an unknown chunk to fail");
+    assert!(matches!(dbg!(err), DropChunkError::NotFound(_)));
+
+    write_client
+        .write_replicated(&db_name, write_lp("cpu,region=west user=21.0 200", 2))
+        .await
+        .expect("write failed");
+
+    let snapshot = client
+        .snapshot_partition(&db_name, &partition_key)
+        .await
+        .expect("snapshot partition failed");
+    assert_eq!(snapshot.chunk_id, 1);
+    assert!(!snapshot.snapshot_id.is_empty());
+
+    let err = client
+        .list_chunks(rand_name())
+        .await
+        .expect_err("expected request to fail");
+    assert!(matches!(dbg!(err), ListChunksError::DatabaseNotFound));
+}
+
+async fn test_compact_chunks(client: &mut Client) {
+    let db_name = rand_name();
+    create_database(client, &db_name).await;
+
+    let connection = Builder::default().build(GRPC_URL_BASE).await.unwrap();
+    let mut write_client = write::Client::new(connection);
+    let rules = data_types::database_rules::DatabaseRules::new();
+
+    let mut partition_key = String::new();
+    for (sequence, lp) in [
+        "cpu,region=west user=23.2 100",
+        "cpu,region=east user=21.0 150",
+    ]
+    .iter()
+    .enumerate()
+    {
+        let lines: Vec<_> = parse_lines(lp).map(|l| l.unwrap()).collect();
+        let write = lines_to_replicated_write(1, sequence as u64 + 1, &lines, &rules);
+        write_client
+            .write_replicated(&db_name, write.into_bytes())
+            .await
+            .expect("write failed");
+
+        let partitions = client
+            .list_partitions(&db_name)
+            .await
+            .expect("list partitions failed");
+        assert_eq!(partitions.len(), 1);
+        partition_key = partitions[0].key.clone();
+
+        let chunk = client
+            .rollover_partition(&db_name, &partition_key)
+            .await
+            .expect("rollover partition failed");
+        client
+            .load_chunk_to_read_buffer(&db_name, &partition_key, chunk.id)
+            .await
+            .expect("load chunk failed");
+        client
+            .drop_chunk(
+                &db_name,
+                &partition_key,
+                chunk.id,
+                ChunkStorage::ClosedMutableBuffer,
+            )
+            .await
+            .expect("drop chunk failed");
+    }
+
+    let err = client
+        .compact_chunks(&db_name, &partition_key, vec![0])
+        .await
+        .expect_err("expected compacting a single chunk to fail");
+    assert!(matches!(dbg!(err), CompactChunksError::InvalidArgument(_)));
+
+    let err = client
+        .compact_chunks(&db_name, &partition_key, vec![0, 42])
+        .await
+        .expect_err("expected compacting an unknown chunk to fail");
+    assert!(matches!(dbg!(err), CompactChunksError::NotFound(_)));
+
+    let chunk = client
+        .compact_chunks(&db_name, &partition_key, vec![0, 1])
+        .await
+        .expect("compact chunks failed");
+    assert_eq!(chunk.id, 0);
+    assert_eq!(chunk.storage(), ChunkStorage::ReadBuffer);
+    assert_eq!(chunk.row_count, 2);
+    assert_eq!(chunk.time_range, Some(TimeRange { min: 100, max: 150 }));
+
+    let chunks = client
+        .list_partition_chunks(&db_name, &partition_key)
+        .await
+        .expect("list partition chunks failed");
+    let storage: Vec<_> = chunks.iter().map(|c| (c.id, c.storage())).collect();
+    assert_eq!(
+        storage,
+        vec![
+            (0, ChunkStorage::ReadBuffer),
+            (2, ChunkStorage::OpenMutableBuffer)
+        ]
+    );
+}
+
+pub fn rand_name() -> String {
     thread_rng()
         .sample_iter(&Alphanumeric)
         .take(10)
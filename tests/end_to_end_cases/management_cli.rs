@@ -4,6 +4,9 @@ use predicates::prelude::*;
 pub async fn test(addr: impl AsRef<str>) {
     test_writer_id(addr.as_ref()).await;
     test_create_database(addr.as_ref()).await;
+    test_update_delete_database(addr.as_ref()).await;
+    test_chunks(addr.as_ref()).await;
+    test_operations(addr.as_ref()).await;
 }
 
 async fn test_writer_id(addr: &str) {
@@ -75,3 +78,137 @@ async fn test_create_database(addr: &str) {
         .success()
         .stdout(predicate::str::contains(format!("name: \"{}\"", db)));
 }
+
+async fn test_update_delete_database(addr: &str) {
+    let db = "management-cli-update-test";
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("database")
+        .arg("create")
+        .arg(db)
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Ok"));
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("database")
+        .arg("update")
+        .arg(db)
+        .arg("--partition-template")
+        .arg("column:region")
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Ok"));
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("database")
+        .arg("get")
+        .arg(db)
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"region\""));
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("database")
+        .arg("delete")
+        .arg(db)
+        .arg("--delete-data")
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Ok"));
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("database")
+        .arg("get")
+        .arg(db)
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Database not found"));
+}
+
+async fn test_chunks(addr: &str) {
+    // created without a mutable buffer by test_create_database
+    let db = "management-cli-test";
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("database")
+        .arg("chunks")
+        .arg(db)
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("database")
+        .arg("rollover")
+        .arg(db)
+        .arg("p1")
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Database has no mutable buffer"));
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("database")
+        .arg("chunks")
+        .arg("does-not-exist")
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Database not found"));
+}
+
+async fn test_operations(addr: &str) {
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("operation")
+        .arg("list")
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .success();
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("operation")
+        .arg("get")
+        .arg("999999")
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Operation not found"));
+
+    Command::cargo_bin("influxdb_iox")
+        .unwrap()
+        .arg("operation")
+        .arg("cancel")
+        .arg("999999")
+        .arg("--host")
+        .arg(addr)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Operation not found"));
+}
@@ -1,5 +1,8 @@
 pub mod flight_api;
 pub mod management_api;
 pub mod management_cli;
+pub mod operations_api;
 pub mod read_api;
 pub mod storage_api;
+pub mod subscription_api;
+pub mod write_api;
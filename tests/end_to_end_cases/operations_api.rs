@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use data_types::data::lines_to_replicated_write;
+use influxdb_iox_client::{
+    management,
+    operations::{CancelOperationError, Client, GetOperationError},
+    write,
+};
+use influxdb_line_protocol::parse_lines;
+
+use crate::{create_database, end_to_end_cases::management_api::rand_name};
+
+pub async fn test(
+    management_client: &mut management::Client,
+    write_client: &mut write::Client,
+    client: &mut Client,
+) {
+    test_unknown_operation(client).await;
+    test_snapshot_operation(management_client, write_client, client).await;
+}
+
+async fn test_unknown_operation(client: &mut Client) {
+    client
+        .list_operations()
+        .await
+        .expect("list operations failed");
+
+    let err = client
+        .get_operation(u64::MAX)
+        .await
+        .expect_err("expected request to fail");
+    assert!(matches!(dbg!(err), GetOperationError::OperationNotFound));
+
+    let err = client
+        .cancel_operation(u64::MAX)
+        .await
+        .expect_err("expected request to fail");
+    assert!(matches!(dbg!(err), CancelOperationError::OperationNotFound));
+}
+
+async fn test_snapshot_operation(
+    management_client: &mut management::Client,
+    write_client: &mut write::Client,
+    client: &mut Client,
+) {
+    let db_name = rand_name();
+    create_database(management_client, &db_name).await;
+
+    let rules = data_types::database_rules::DatabaseRules::new();
+    let lines: Vec<_> = parse_lines("cpu,region=west user=23.2 100")
+        .map(|l| l.unwrap())
+        .collect();
+    write_client
+        .write_replicated(
+            &db_name,
+            lines_to_replicated_write(1, 1, &lines, &rules).into_bytes(),
+        )
+        .await
+        .expect("write failed");
+
+    let partitions = management_client
+        .list_partitions(&db_name)
+        .await
+        .expect("list partitions failed");
+    assert_eq!(partitions.len(), 1);
+
+    let snapshot = management_client
+        .snapshot_partition(&db_name, &partitions[0].key)
+        .await
+        .expect("snapshot partition failed");
+
+    // The snapshot is listed while it runs, and unregistered once it finishes
+    let mut finished = false;
+    for _ in 0..50 {
+        match client.get_operation(snapshot.operation_id).await {
+            Ok(operation) => {
+                assert_eq!(operation.id, snapshot.operation_id);
+                assert!(operation.description.contains(&db_name));
+                assert!(operation.completed_tasks <= operation.total_tasks);
+            }
+            Err(GetOperationError::OperationNotFound) => {
+                finished = true;
+                break;
+            }
+            Err(e) => panic!("get operation failed: {}", e),
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(finished, "snapshot did not finish");
+}
@@ -0,0 +1,122 @@
+use data_types::{
+    data::{lines_to_replicated_write, ReplicatedWrite},
+    database_rules::DatabaseRules,
+};
+use influxdb_iox_client::{
+    management::{self, generated_types as management_types},
+    subscription::{
+        self,
+        generated_types::{matcher::TableMatcher, Matcher, WriterSequence},
+        SubscribeError,
+    },
+    write,
+};
+use influxdb_line_protocol::parse_lines;
+use std::convert::TryFrom;
+
+pub async fn test(
+    management_client: &mut management::Client,
+    write_client: &mut write::Client,
+    subscription_client: &mut subscription::Client,
+) {
+    test_subscribe(management_client, write_client, subscription_client).await;
+    test_subscribe_unknown_database(subscription_client).await;
+}
+
+async fn test_subscribe(
+    management_client: &mut management::Client,
+    write_client: &mut write::Client,
+    subscription_client: &mut subscription::Client,
+) {
+    const DB_NAME: &str = "subscriptions";
+
+    management_client
+        .create_database(management_types::DatabaseRules {
+            name: DB_NAME.to_string(),
+            mutable_buffer_config: Some(Default::default()),
+            wal_buffer_config: Some(management_types::WalBufferConfig {
+                buffer_size: 1_000_000,
+                segment_size: 1_000,
+                buffer_rollover: management_types::wal_buffer_config::Rollover::DropOldSegment as _,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .await
+        .expect("create database failed");
+
+    let writes = vec![
+        replicated_write(7, 1, "cpu,region=west user=23.2 100"),
+        replicated_write(7, 2, "cpu,region=east user=21.0 150"),
+        replicated_write(7, 3, "mem,region=west free=1i 200"),
+        replicated_write(
+            7,
+            4,
+            "mem,region=east free=2i 250\ncpu,region=east user=19.5 250",
+        ),
+    ];
+
+    for write in &writes[..3] {
+        write_client
+            .write_replicated(DB_NAME, write.bytes().clone())
+            .await
+            .expect("write replicated failed");
+    }
+
+    // only the writes after the last one seen are streamed
+    let since = vec![WriterSequence {
+        writer_id: 7,
+        sequence: 1,
+    }];
+    let matcher = Matcher {
+        predicate: String::new(),
+        table_matcher: Some(TableMatcher::Table("cpu".to_string())),
+    };
+    let mut subscription = subscription_client
+        .subscribe(DB_NAME, since, Some(matcher))
+        .await
+        .expect("subscribe failed");
+
+    // the mem write doesn't match, so the next write streamed is the cpu one
+    let received = next_write(&mut subscription).await;
+    assert_eq!(received.writer_and_sequence(), (7, 2));
+    assert!(received
+        .to_string()
+        .contains("region:east user:21 time:150"));
+
+    // new writes are streamed as they arrive, with the unmatched rows removed
+    write_client
+        .write_replicated(DB_NAME, writes[3].bytes().clone())
+        .await
+        .expect("write replicated failed");
+
+    let received = next_write(&mut subscription).await;
+    assert_eq!(received.writer_and_sequence(), (7, 4));
+    let text = received.to_string();
+    assert!(text.contains("table:cpu"));
+    assert!(!text.contains("table:mem"));
+}
+
+async fn test_subscribe_unknown_database(subscription_client: &mut subscription::Client) {
+    let err = subscription_client
+        .subscribe("does_not_exist", vec![], None)
+        .await
+        .expect_err("expected subscribe to fail");
+
+    assert!(matches!(dbg!(err), SubscribeError::DatabaseNotFound));
+}
+
+fn replicated_write(writer: u32, sequence: u64, lp: &str) -> ReplicatedWrite {
+    let lines: Vec<_> = parse_lines(lp).map(|l| l.unwrap()).collect();
+    lines_to_replicated_write(writer, sequence, &lines, &DatabaseRules::new())
+}
+
+async fn next_write(subscription: &mut subscription::Subscription) -> ReplicatedWrite {
+    let payload = subscription
+        .next()
+        .await
+        .expect("error reading subscription")
+        .expect("subscription closed");
+
+    ReplicatedWrite::try_from(payload).expect("invalid write")
+}
@@ -0,0 +1,84 @@
+use crate::{create_database, GRPC_URL_BASE};
+use arrow_deps::assert_table_eq;
+use data_types::{data::lines_to_replicated_write, database_rules::DatabaseRules};
+use influxdb_iox_client::{
+    connection::Builder,
+    flight, management,
+    write::{self, WriteReplicatedError},
+};
+use influxdb_line_protocol::parse_lines;
+
+pub async fn test(management_client: &mut management::Client, write_client: &mut write::Client) {
+    test_write_replicated(management_client, write_client).await;
+    test_write_replicated_unknown_database(write_client).await;
+    test_write_replicated_invalid_payload(management_client, write_client).await;
+}
+
+async fn test_write_replicated(
+    management_client: &mut management::Client,
+    write_client: &mut write::Client,
+) {
+    const DB_NAME: &str = "replicated_writes";
+    create_database(management_client, DB_NAME).await;
+
+    let lp = "cpu,region=west user=23.2 100\ncpu,region=east user=21.0 150";
+    let lines: Vec<_> = parse_lines(lp).map(|l| l.unwrap()).collect();
+    let write = lines_to_replicated_write(7, 1, &lines, &DatabaseRules::new());
+
+    write_client
+        .write_replicated(DB_NAME, write.into_bytes())
+        .await
+        .expect("write replicated failed");
+
+    let connection = Builder::default().build(GRPC_URL_BASE).await.unwrap();
+    let mut flight_client = flight::Client::new(connection);
+    let mut query_results = flight_client
+        .perform_query(DB_NAME, "select * from cpu order by time")
+        .await
+        .unwrap();
+
+    let mut batches = vec![];
+    while let Some(data) = query_results.next().await.unwrap() {
+        batches.push(data);
+    }
+
+    let expected = vec![
+        "+--------+------+------+",
+        "| region | time | user |",
+        "+--------+------+------+",
+        "| west   | 100  | 23.2 |",
+        "| east   | 150  | 21   |",
+        "+--------+------+------+",
+    ];
+    assert_table_eq!(expected, &batches);
+}
+
+async fn test_write_replicated_unknown_database(write_client: &mut write::Client) {
+    let lines: Vec<_> = parse_lines("cpu user=1 10").map(|l| l.unwrap()).collect();
+    let write = lines_to_replicated_write(7, 1, &lines, &DatabaseRules::new());
+
+    let err = write_client
+        .write_replicated("does_not_exist", write.into_bytes())
+        .await
+        .expect_err("expected write to fail");
+
+    assert!(matches!(dbg!(err), WriteReplicatedError::DatabaseNotFound));
+}
+
+async fn test_write_replicated_invalid_payload(
+    management_client: &mut management::Client,
+    write_client: &mut write::Client,
+) {
+    const DB_NAME: &str = "replicated_writes_invalid";
+    create_database(management_client, DB_NAME).await;
+
+    let err = write_client
+        .write_replicated(DB_NAME, b"not a flatbuffer".to_vec())
+        .await
+        .expect_err("expected write to fail");
+
+    assert!(matches!(
+        dbg!(err),
+        WriteReplicatedError::InvalidArgument(_)
+    ));
+}
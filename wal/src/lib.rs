@@ -12,8 +12,9 @@
 //! This crate provides a local-disk based WAL tailored for InfluxDB
 //! IOx `Partition`s.
 //!
-//! It is not currently connected to anything, but the intent is to
-//! permit IOx running in standalone mode better durability.
+//! The server uses it to give IOx running in standalone mode better
+//! durability when it is started with `--local-wal`: each database's writes
+//! are appended to a WAL below `--data-dir` and replayed on startup.
 //!
 //! Work remaining:
 //!
@@ -297,6 +298,11 @@ impl Wal {
         Ok(sequence_number)
     }
 
+    /// The sequence number that will be assigned to the next appended entry
+    pub fn next_sequence_number(&self) -> SequenceNumber {
+        self.sequence_number
+    }
+
     /// Total size, in bytes, of all the data in all the files in the WAL. If
     /// files are deleted from disk without deleting them through the WAL,
     /// the size won't reflect that deletion until the WAL is recreated.